@@ -0,0 +1,340 @@
+use super::game::GridSize;
+
+#[derive(Clone)]
+pub struct BoardSettings {
+    size: GridSize,
+    num_bombs: usize,
+    pub screen_width: f32,
+    pub screen_height: f32,
+}
+
+impl BoardSettings {
+    pub fn builder() -> BoardSettingsBuilder {
+        BoardSettingsBuilder::default()
+    }
+
+    // only for the presets, which are known to be valid
+    const fn preset(
+        size: GridSize,
+        num_bombs: usize,
+        screen_width: f32,
+        screen_height: f32,
+    ) -> Self {
+        Self {
+            size,
+            num_bombs,
+            screen_width,
+            screen_height,
+        }
+    }
+
+    pub fn size(&self) -> GridSize {
+        self.size
+    }
+
+    pub fn num_bombs(&self) -> usize {
+        self.num_bombs
+    }
+
+    pub fn screen_dimensions(&self) -> (f32, f32) {
+        (self.screen_width, self.screen_height)
+    }
+
+    pub fn easy() -> Self {
+        EASY_BOARD.clone()
+    }
+
+    pub fn medium() -> Self {
+        MEDIUM_BOARD.clone()
+    }
+
+    pub fn hard() -> Self {
+        HARD_BOARD.clone()
+    }
+
+    pub fn expert_plus() -> Self {
+        EXPERT_PLUS_BOARD.clone()
+    }
+
+    pub fn insane() -> Self {
+        INSANE_BOARD.clone()
+    }
+
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name {
+            "easy" => Some(BoardSettings::easy()),
+            "medium" => Some(BoardSettings::medium()),
+            "hard" => Some(BoardSettings::hard()),
+            "expert+" => Some(BoardSettings::expert_plus()),
+            "insane" => Some(BoardSettings::insane()),
+            _ => None,
+        }
+    }
+
+    // the preset these settings match, ignoring the window size
+    pub fn preset_name(&self) -> Option<&'static str> {
+        PRESETS.into_iter().find(|name| {
+            let preset = BoardSettings::by_name(name).unwrap();
+            preset.size == self.size && preset.num_bombs == self.num_bombs
+        })
+    }
+}
+
+pub const MIN_SIDE: usize = 5;
+// tiles get too small to click well beyond this
+pub const MAX_SIDE: usize = 100;
+// the first click and its neighbours never hold a mine
+pub const SAFE_AREA: usize = 9;
+
+#[derive(Default)]
+pub struct BoardSettingsBuilder {
+    size: Option<(usize, usize)>,
+    mines: Option<usize>,
+}
+
+impl BoardSettingsBuilder {
+    pub fn size(mut self, w: usize, h: usize) -> Self {
+        self.size = Some((w, h));
+        self
+    }
+
+    pub fn mines(mut self, num_bombs: usize) -> Self {
+        self.mines = Some(num_bombs);
+        self
+    }
+
+    pub fn build(self) -> Result<BoardSettings, String> {
+        let (w, h) = self.size.ok_or("board size not set")?;
+        let num_bombs = self.mines.ok_or("mine count not set")?;
+        if w < MIN_SIDE || h < MIN_SIDE {
+            return Err(format!(
+                "{}x{} is smaller than {}x{}",
+                w, h, MIN_SIDE, MIN_SIDE
+            ));
+        }
+        if w > MAX_SIDE || h > MAX_SIDE {
+            return Err(format!(
+                "{}x{} is larger than {}x{}",
+                w, h, MAX_SIDE, MAX_SIDE
+            ));
+        }
+        // more than this and the mines can't all be placed around the first click
+        let max_bombs = w * h - SAFE_AREA;
+        if num_bombs == 0 || num_bombs > max_bombs {
+            return Err(format!(
+                "{} mines don't fit a {}x{} board, use 1-{}",
+                num_bombs, w, h, max_bombs
+            ));
+        }
+        // the window size of the closest preset
+        let (screen_width, screen_height) = match w.max(h) {
+            0..=8 => (EASY_SCREEN_WIDTH, EASY_SCREEN_HEIGHT),
+            9..=16 => (MEDIUM_SCREEN_WIDTH, MEDIUM_SCREEN_HEIGHT),
+            17..=30 => (HARD_SCREEN_WIDTH, HARD_SCREEN_HEIGHT),
+            31..=40 => (EXPERT_PLUS_SCREEN_WIDTH, EXPERT_PLUS_SCREEN_HEIGHT),
+            _ => (INSANE_SCREEN_WIDTH, INSANE_SCREEN_HEIGHT),
+        };
+        Ok(BoardSettings::preset(
+            GridSize::new(w, h),
+            num_bombs,
+            screen_width,
+            screen_height,
+        ))
+    }
+}
+
+pub const EASY_SCREEN_WIDTH: f32 = 800.0;
+pub const EASY_SCREEN_HEIGHT: f32 = 600.0;
+
+pub const MEDIUM_SCREEN_WIDTH: f32 = 1000.0;
+pub const MEDIUM_SCREEN_HEIGHT: f32 = 700.0;
+
+pub const HARD_SCREEN_WIDTH: f32 = 1200.0;
+pub const HARD_SCREEN_HEIGHT: f32 = 800.0;
+
+pub const EXPERT_PLUS_SCREEN_WIDTH: f32 = 1400.0;
+pub const EXPERT_PLUS_SCREEN_HEIGHT: f32 = 900.0;
+
+pub const INSANE_SCREEN_WIDTH: f32 = 1600.0;
+pub const INSANE_SCREEN_HEIGHT: f32 = 1000.0;
+
+// preset names, easiest first
+pub const PRESETS: [&str; 5] = ["easy", "medium", "hard", "expert+", "insane"];
+
+pub const EASY_BOARD: BoardSettings = BoardSettings::preset(
+    GridSize::new(8, 8),
+    10,
+    EASY_SCREEN_WIDTH,
+    EASY_SCREEN_HEIGHT,
+);
+pub const MEDIUM_BOARD: BoardSettings = BoardSettings::preset(
+    GridSize::new(16, 16),
+    40,
+    MEDIUM_SCREEN_WIDTH,
+    MEDIUM_SCREEN_HEIGHT,
+);
+pub const HARD_BOARD: BoardSettings = BoardSettings::preset(
+    GridSize::new(30, 16),
+    99,
+    HARD_SCREEN_WIDTH,
+    HARD_SCREEN_HEIGHT,
+);
+pub const EXPERT_PLUS_BOARD: BoardSettings = BoardSettings::preset(
+    GridSize::new(40, 25),
+    180,
+    EXPERT_PLUS_SCREEN_WIDTH,
+    EXPERT_PLUS_SCREEN_HEIGHT,
+);
+pub const INSANE_BOARD: BoardSettings = BoardSettings::preset(
+    GridSize::new(60, 35),
+    400,
+    INSANE_SCREEN_WIDTH,
+    INSANE_SCREEN_HEIGHT,
+);
+
+// every player gets the same board on the same calendar day
+pub fn daily_seed(day: i64) -> u64 {
+    (day as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+}
+
+pub const URL_PREFIX: &str = "rustsweeper://play?";
+
+// a board anyone can replay: "<width>x<height>-<mines>-<seed in hex>"
+#[derive(Clone)]
+pub struct BoardCode {
+    pub settings: BoardSettings,
+    pub seed: u64,
+}
+
+impl BoardCode {
+    pub fn encode(&self) -> String {
+        let size = self.settings.size();
+        format!(
+            "{}x{}-{}-{:016x}",
+            size.width,
+            size.height,
+            self.settings.num_bombs(),
+            self.seed
+        )
+    }
+
+    pub fn parse(code: &str) -> Result<Self, String> {
+        let invalid = || format!("'{}' is not a board code", code);
+        let fields = code.trim().split('-').collect::<Vec<_>>();
+        let [size, mines, seed] = fields.as_slice() else {
+            return Err(invalid());
+        };
+        let (width, height) = size.split_once('x').ok_or_else(invalid)?;
+        let settings = BoardSettings::builder()
+            .size(
+                width.parse().map_err(|_| invalid())?,
+                height.parse().map_err(|_| invalid())?,
+            )
+            .mines(mines.parse().map_err(|_| invalid())?)
+            .build()?;
+        let seed = u64::from_str_radix(seed, 16).map_err(|_| invalid())?;
+        Ok(Self { settings, seed })
+    }
+
+    // shared links look like "rustsweeper://play?code=16x16-40-00000000deadbeef"
+    pub fn from_url(url: &str) -> Result<Self, String> {
+        let query = url
+            .strip_prefix(URL_PREFIX)
+            .ok_or_else(|| format!("'{}' is not a rustsweeper link", url))?;
+        let code = query
+            .split('&')
+            .find_map(|param| param.strip_prefix("code="))
+            .ok_or("the link has no board code")?;
+        BoardCode::parse(code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets() {
+        for name in PRESETS {
+            assert_eq!(
+                BoardSettings::by_name(name).unwrap().preset_name(),
+                Some(name)
+            );
+        }
+        assert_eq!(BoardSettings::insane().size(), GridSize::new(60, 35));
+    }
+
+    #[test]
+    fn test_builder() {
+        let hard = BoardSettings::builder()
+            .size(30, 16)
+            .mines(99)
+            .build()
+            .unwrap();
+        assert_eq!(hard.preset_name(), Some("hard"));
+        assert_eq!(
+            hard.screen_dimensions(),
+            (HARD_SCREEN_WIDTH, HARD_SCREEN_HEIGHT)
+        );
+        let big = BoardSettings::builder()
+            .size(50, 30)
+            .mines(200)
+            .build()
+            .unwrap();
+        assert_eq!(
+            big.screen_dimensions(),
+            (INSANE_SCREEN_WIDTH, INSANE_SCREEN_HEIGHT)
+        );
+
+        assert!(BoardSettings::builder().size(8, 8).build().is_err());
+        assert!(BoardSettings::builder().mines(10).build().is_err());
+        assert!(BoardSettings::builder()
+            .size(4, 8)
+            .mines(3)
+            .build()
+            .is_err());
+        assert!(BoardSettings::builder()
+            .size(101, 8)
+            .mines(3)
+            .build()
+            .is_err());
+        assert!(BoardSettings::builder()
+            .size(8, 8)
+            .mines(0)
+            .build()
+            .is_err());
+        assert!(BoardSettings::builder()
+            .size(8, 8)
+            .mines(56)
+            .build()
+            .is_err());
+        assert!(BoardSettings::builder()
+            .size(8, 8)
+            .mines(55)
+            .build()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_board_codes() {
+        let code = BoardCode {
+            settings: BoardSettings::medium(),
+            seed: 0xdead_beef,
+        };
+        assert_eq!(code.encode(), "16x16-40-00000000deadbeef");
+
+        let parsed = BoardCode::parse(&code.encode()).unwrap();
+        assert_eq!(parsed.settings.preset_name(), Some("medium"));
+        assert_eq!(parsed.seed, 0xdead_beef);
+
+        let linked =
+            BoardCode::from_url("rustsweeper://play?v=1&code=30x16-99-00000000000000ff").unwrap();
+        assert_eq!(linked.settings.preset_name(), Some("hard"));
+        assert_eq!(linked.seed, 255);
+
+        assert!(BoardCode::parse("16x16-40").is_err());
+        assert!(BoardCode::parse("4x4-3-ff").is_err());
+        assert!(BoardCode::from_url("https://example.com/?code=16x16-40-ff").is_err());
+        assert!(BoardCode::from_url("rustsweeper://play?seed=ff").is_err());
+    }
+}
@@ -0,0 +1,1763 @@
+use super::solver;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::cmp::PartialEq;
+use std::ops::Add;
+use std::time::Duration;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Position(pub i32, pub i32);
+
+// board dimensions in tiles; floats only appear once tiles are turned into pixels
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct GridSize {
+    pub width: usize,
+    pub height: usize,
+}
+
+impl GridSize {
+    pub const fn new(width: usize, height: usize) -> Self {
+        Self { width, height }
+    }
+
+    pub fn tiles(&self) -> usize {
+        self.width * self.height
+    }
+
+    pub fn contains(&self, pos: Position) -> bool {
+        pos.0 >= 0 && pos.1 >= 0 && (pos.0 as usize) < self.width && (pos.1 as usize) < self.height
+    }
+}
+
+impl Position {
+    pub fn from_index(index: usize, board_width: usize) -> Self {
+        let x = index % board_width;
+        let y = index / board_width;
+        Position(x as i32, y as i32)
+    }
+
+    pub fn to_index(&self, board_width: usize) -> usize {
+        self.1 as usize * board_width + self.0 as usize
+    }
+}
+
+const ADJACENT_OFFSETS: [Position; 8] = [
+    Position(-1, -1),
+    Position(0, -1),
+    Position(1, -1),
+    Position(1, 0),
+    Position(1, 1),
+    Position(0, 1),
+    Position(-1, 1),
+    Position(-1, 0),
+];
+
+// how many tiles' worth of boards a no-guess layout draws before settling for the last
+// one: 200 expert boards, and fewer of the bigger ones so the first click stays quick.
+// A count rather than a clock, so a seed gives the same board on every machine
+const NO_GUESS_TILE_BUDGET: usize = 200 * 30 * 16;
+// past the largest preset the solver takes too long per board, and too few boards clear,
+// so bigger boards are laid out plainly
+const NO_GUESS_MAX_TILES: usize = 60 * 35;
+
+// every tile's in-bounds neighbours as indices, worked out once per board; tile i's
+// are indices[starts[i]..starts[i + 1]], in ADJACENT_OFFSETS order
+#[derive(Clone)]
+struct Neighbours {
+    starts: Vec<usize>,
+    indices: Vec<usize>,
+}
+
+impl Neighbours {
+    fn new(size: GridSize) -> Self {
+        let mut starts = Vec::with_capacity(size.tiles() + 1);
+        let mut indices = Vec::with_capacity(size.tiles() * ADJACENT_OFFSETS.len());
+        for i in 0..size.tiles() {
+            starts.push(indices.len());
+            let pos = Position::from_index(i, size.width);
+            for offset in ADJACENT_OFFSETS.iter() {
+                let adj = pos + *offset;
+                if size.contains(adj) {
+                    indices.push(adj.to_index(size.width));
+                }
+            }
+        }
+        starts.push(indices.len());
+        Self { starts, indices }
+    }
+
+    fn of(&self, index: usize) -> &[usize] {
+        &self.indices[self.starts[index]..self.starts[index + 1]]
+    }
+}
+
+impl Add for Position {
+    type Output = Position;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Position(self.0 + rhs.0, self.1 + rhs.1)
+    }
+}
+
+// what the player has put on a covered tile; right-clicking steps through them in order
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Mark {
+    Unmarked,
+    Flagged,
+    Questioned,
+}
+
+impl Mark {
+    pub fn next(self) -> Mark {
+        match self {
+            Mark::Unmarked => Mark::Flagged,
+            Mark::Flagged => Mark::Questioned,
+            Mark::Questioned => Mark::Unmarked,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum TileState {
+    Revealed,
+    Block(Mark),
+}
+#[derive(Clone, Debug)]
+pub struct Tile {
+    pub state: TileState,
+    has_bomb: bool,
+    adjacent_bombs: u32,
+}
+impl Tile {
+    pub fn is_bomb(&self) -> bool {
+        self.has_bomb
+    }
+
+    pub fn num_adjacent_bombs(&self) -> u32 {
+        self.adjacent_bombs
+    }
+
+    fn no_adjacent_bombs(&self) -> bool {
+        self.adjacent_bombs == 0
+    }
+
+    fn is_flagged(&self) -> bool {
+        match self.state {
+            TileState::Block(Mark::Flagged) => true,
+            _ => false,
+        }
+    }
+
+    pub fn is_revealed(&self) -> bool {
+        self.state == TileState::Revealed
+    }
+}
+
+// how mines are laid out once the first click is known; anything but plain is part of
+// what a seed means, so replays carry it
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Generation {
+    #[default]
+    Plain,
+    // mines stuck in a 50/50 no number can ever settle are moved elsewhere
+    AntiFiftyFifty,
+    // boards are drawn again until the solver clears one from the first click alone
+    NoGuess,
+    // no safe start: the first click is as likely to hit a mine as any other tile
+    Unprotected,
+}
+
+impl Generation {
+    pub const ALL: [Generation; 4] = [
+        Generation::Plain,
+        Generation::AntiFiftyFifty,
+        Generation::NoGuess,
+        Generation::Unprotected,
+    ];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            Generation::Plain => "plain",
+            Generation::AntiFiftyFifty => "anti-fifty-fifty",
+            Generation::NoGuess => "no-guess",
+            Generation::Unprotected => "unprotected",
+        }
+    }
+
+    pub fn next(self) -> Generation {
+        let i = Self::ALL.iter().position(|g| *g == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|generation| generation.key() == key)
+    }
+}
+
+#[derive(Clone)]
+struct Board {
+    size: GridSize,
+    tiles: Vec<Tile>,
+    tiles_left: usize,
+    num_bombs: i32,
+    seed: u64,
+    neighbours: Neighbours,
+    // every tile index the generator drew, kept or thrown back, in order
+    draws: Vec<usize>,
+    generation: Generation,
+}
+
+impl Board {
+    pub fn new(size: GridSize, num_bombs: usize, seed: u64, generation: Generation) -> Self {
+        let mut board = Self {
+            size,
+            tiles: Vec::with_capacity(size.tiles()),
+            tiles_left: size.tiles(),
+            num_bombs: num_bombs as i32,
+            seed,
+            neighbours: Neighbours::new(size),
+            draws: Vec::new(),
+            generation,
+        };
+        board.create_tiles();
+        board
+    }
+    fn create_tiles(&mut self) {
+        self.tiles = vec![
+            Tile {
+                state: TileState::Block(Mark::Unmarked),
+                has_bomb: false,
+                adjacent_bombs: 0
+            };
+            self.size.tiles()
+        ];
+    }
+
+    fn insert_bombs(&mut self, rng: &mut StdRng, safe_position: Position) {
+        let mut bombs_placed = 0;
+
+        let mut safe_idxs = Vec::new();
+        if self.generation != Generation::Unprotected {
+            let safe_idx = safe_position.to_index(self.size.width);
+            safe_idxs.push(safe_idx);
+            safe_idxs.extend_from_slice(self.neighbours.of(safe_idx));
+        }
+
+        // drawing for a mine that has nowhere to go would never end
+        assert!(
+            self.num_bombs as usize <= self.size.tiles() - safe_idxs.len(),
+            "{} mines don't fit a {}x{} board",
+            self.num_bombs,
+            self.size.width,
+            self.size.height
+        );
+        while bombs_placed < self.num_bombs {
+            let idx = rng.random_range(0..self.size.tiles());
+            self.draws.push(idx);
+            let tile = &mut self.tiles[idx];
+            if tile.has_bomb || safe_idxs.contains(&idx) {
+                continue;
+            }
+            tile.has_bomb = true;
+            bombs_placed += 1;
+        }
+
+        if self.generation == Generation::AntiFiftyFifty {
+            self.relocate_fifty_fifties(rng, &safe_idxs);
+        }
+    }
+
+    // the safe neighbours of a tile, leaving out one other tile
+    fn safe_around(&self, idx: usize, other: usize) -> Vec<usize> {
+        let mut safe = self
+            .neighbours
+            .of(idx)
+            .iter()
+            .copied()
+            .filter(|adj| *adj != other && !self.tiles[*adj].has_bomb)
+            .collect::<Vec<_>>();
+        safe.sort_unstable();
+        safe
+    }
+
+    // a mine and a covered safe neighbour touching the same safe tiles could swap
+    // without changing a number, so only a guess tells them apart; returns the mine
+    fn fifty_fifty(&self, safe_idxs: &[usize]) -> Option<usize> {
+        (0..self.tiles.len())
+            .filter(|idx| self.tiles[*idx].has_bomb)
+            .find(|mine| {
+                self.neighbours.of(*mine).iter().any(|safe| {
+                    !self.tiles[*safe].has_bomb
+                        && !safe_idxs.contains(safe)
+                        && self.safe_around(*mine, *safe) == self.safe_around(*safe, *mine)
+                })
+            })
+    }
+
+    // moves one mine of each 50/50 to a free tile away from it, drawn from the same
+    // generator; a move can make a new one, so the passes are capped at one per mine
+    fn relocate_fifty_fifties(&mut self, rng: &mut StdRng, safe_idxs: &[usize]) {
+        for _ in 0..self.num_bombs {
+            let Some(mine) = self.fifty_fifty(safe_idxs) else {
+                return;
+            };
+            let free = |board: &Board, idx: usize| {
+                !board.tiles[idx].has_bomb
+                    && !safe_idxs.contains(&idx)
+                    && idx != mine
+                    && !board.neighbours.of(mine).contains(&idx)
+            };
+            if !(0..self.tiles.len()).any(|idx| free(self, idx)) {
+                return;
+            }
+            loop {
+                let idx = rng.random_range(0..self.size.tiles());
+                self.draws.push(idx);
+                if free(self, idx) {
+                    self.tiles[mine].has_bomb = false;
+                    self.tiles[idx].has_bomb = true;
+                    break;
+                }
+            }
+        }
+    }
+
+    fn calculate_adjacent_bombs(&mut self) {
+        for idx in 0..self.tiles.len() {
+            if self.tiles[idx].has_bomb {
+                continue;
+            }
+
+            let count = self
+                .neighbours
+                .of(idx)
+                .iter()
+                .filter(|adj| self.tiles[**adj].has_bomb)
+                .count();
+
+            self.tiles[idx].adjacent_bombs = count as u32;
+        }
+    }
+
+    // returns the tiles it opened with the state each had before, breadth first from pos,
+    // which is the order a flood fill spreads in
+    pub fn reveal_tile(&mut self, pos: Position) -> Vec<(usize, TileState)> {
+        use std::collections::VecDeque;
+
+        let mut opened = Vec::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(pos.to_index(self.size.width));
+
+        while let Some(idx) = queue.pop_front() {
+            let tile = &mut self.tiles[idx];
+
+            if tile.is_revealed() || tile.is_flagged() {
+                continue;
+            }
+
+            opened.push((idx, tile.state.clone()));
+            tile.state = TileState::Revealed;
+            self.tiles_left -= 1;
+
+            if tile.no_adjacent_bombs() {
+                queue.extend(self.neighbours.of(idx));
+            }
+        }
+        opened
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MoveKind {
+    Reveal,
+    Flag,
+}
+
+// a single player action, timestamped with the game clock
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Move {
+    pub kind: MoveKind,
+    pub pos: Position,
+    pub time: Duration,
+}
+
+// player notes on unrevealed tiles, they have no meaning to the rules
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Annotation {
+    Red,
+    Green,
+    Blue,
+}
+
+impl Annotation {
+    // cycles red, green, blue, then back to no annotation
+    pub fn cycle(annotation: Option<Annotation>) -> Option<Annotation> {
+        match annotation {
+            None => Some(Annotation::Red),
+            Some(Annotation::Red) => Some(Annotation::Green),
+            Some(Annotation::Green) => Some(Annotation::Blue),
+            Some(Annotation::Blue) => None,
+        }
+    }
+}
+
+// the player's own clicks; the reveals a chord makes and the assists' moves aren't counted
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct Clicks {
+    pub reveals: usize,
+    pub flags: usize,
+    pub chords: usize,
+}
+
+impl Clicks {
+    pub fn total(&self) -> usize {
+        self.reveals + self.flags + self.chords
+    }
+}
+
+// time added to a result for each assist used
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Penalties {
+    pub per_hint: Duration,
+    pub per_undo: Duration,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Summary {
+    pub won: bool,
+    // on the game clock
+    pub time: Duration,
+    pub penalty: Duration,
+    // hints or undos were used; kept apart from clean runs in stats and leaderboards
+    pub assisted: bool,
+}
+
+impl Summary {
+    pub fn final_time(&self) -> Duration {
+        self.time + self.penalty
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GameState {
+    Lost,
+    Won,
+    InProgress,
+    FirstMove,
+}
+// what one move changed, kept so it can be taken back
+struct Delta {
+    // the tiles it changed, as they were before
+    tiles: Vec<(usize, TileState)>,
+    // whether the move's own tile was flagged by the assist
+    auto_flag: bool,
+    bombs_left: i32,
+    tiles_left: usize,
+    state: GameState,
+    // the first reveal also placed the mines, so the board can't go back past it
+    placed_mines: bool,
+}
+
+pub struct Engine {
+    board: Board,
+    state: GameState,
+    bombs_left: i32,
+    elapsed: Duration,
+    moves: Vec<Move>,
+    // one per move
+    deltas: Vec<Delta>,
+    annotations: Vec<Option<Annotation>>,
+    // fatal reveals taken back under casual rules
+    undos: u32,
+    // flags placed by the auto-flag assist, drawn differently from the player's
+    auto_flags: Vec<bool>,
+    hints: u32,
+    clicks: Clicks,
+    // the first click and how many tiles it opened
+    opening: Option<(Position, usize)>,
+    // whether flagging steps on to a question mark before clearing the tile
+    question_marks: bool,
+}
+
+impl Engine {
+    pub fn new(size: GridSize, num_bombs: usize) -> Self {
+        Engine::with_seed(size, num_bombs, rand::rng().random())
+    }
+
+    // the same seed and first click always produce the same board
+    pub fn with_seed(size: GridSize, num_bombs: usize, seed: u64) -> Self {
+        Engine::with_generation(size, num_bombs, seed, Generation::Plain)
+    }
+
+    // a board the solver can clear from the first click without a guess
+    pub fn new_no_guess(size: GridSize, num_bombs: usize) -> Self {
+        Engine::with_generation(size, num_bombs, rand::rng().random(), Generation::NoGuess)
+    }
+
+    pub fn with_generation(
+        size: GridSize,
+        num_bombs: usize,
+        seed: u64,
+        generation: Generation,
+    ) -> Self {
+        let mut board = Board::new(size, num_bombs, seed, generation);
+        board.create_tiles();
+
+        Self {
+            board,
+            state: GameState::FirstMove,
+            bombs_left: num_bombs as i32,
+            elapsed: Duration::ZERO,
+            moves: Vec::new(),
+            deltas: Vec::new(),
+            annotations: vec![None; size.tiles()],
+            undos: 0,
+            auto_flags: vec![false; size.tiles()],
+            hints: 0,
+            clicks: Clicks::default(),
+            opening: None,
+            question_marks: true,
+        }
+    }
+
+    // advances the game clock, which stops once the game is over
+    pub fn tick(&mut self, delta: Duration) {
+        if !self.is_lost() && !self.is_won() {
+            self.elapsed += delta;
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    // goes negative once more tiles are flagged than there are bombs
+    pub fn mines_left(&self) -> i32 {
+        self.bombs_left
+    }
+
+    pub fn bombs_left(&self) -> usize {
+        if self.bombs_left < 0 {
+            0
+        } else {
+            self.bombs_left as usize
+        }
+    }
+
+    pub fn is_lost(&self) -> bool {
+        self.state == GameState::Lost
+    }
+
+    pub fn is_won(&self) -> bool {
+        self.state == GameState::Won
+    }
+
+    pub fn is_in_progress(&self) -> bool {
+        self.state == GameState::InProgress
+    }
+
+    fn record(&mut self, kind: MoveKind, pos: Position) {
+        self.moves.push(Move {
+            kind,
+            pos,
+            time: self.elapsed,
+        });
+        self.deltas.push(Delta {
+            tiles: Vec::new(),
+            auto_flag: self.auto_flags[pos.to_index(self.board.size.width)],
+            bombs_left: self.bombs_left,
+            tiles_left: self.board.tiles_left,
+            state: self.state,
+            placed_mines: false,
+        });
+    }
+
+    // the tiles the move being made changes, as they are before it
+    fn record_tiles(&mut self, tiles: impl IntoIterator<Item = (usize, TileState)>) {
+        if let Some(delta) = self.deltas.last_mut() {
+            delta.tiles.extend(tiles);
+        }
+    }
+
+    // the tiles uncovered by the moves from this index on, in the order they were opened
+    pub fn opened_since(&self, moves: usize) -> Vec<usize> {
+        self.deltas
+            .get(moves..)
+            .unwrap_or(&[])
+            .iter()
+            .flat_map(|delta| &delta.tiles)
+            .filter(|(idx, state)| {
+                matches!(state, TileState::Block(_)) && self.board.tiles[*idx].is_revealed()
+            })
+            .map(|(idx, _)| *idx)
+            .collect()
+    }
+
+    // every move made so far, enough to replay the game from its seed
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    pub fn num_bombs(&self) -> usize {
+        self.board.num_bombs as usize
+    }
+
+    pub fn size(&self) -> GridSize {
+        self.board.size
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.board.seed
+    }
+
+    pub fn generation(&self) -> Generation {
+        self.board.generation
+    }
+
+    // the audit trail of mine placement, empty until the first reveal
+    pub fn generation_draws(&self) -> &[usize] {
+        &self.board.draws
+    }
+
+    pub fn flag(&mut self, pos: Position) {
+        if self.is_lost() || self.is_won() {
+            return;
+        }
+        self.clicks.flags += 1;
+        self.mark(pos);
+    }
+
+    fn mark(&mut self, pos: Position) {
+        if self.is_lost() || self.is_won() {
+            return;
+        }
+        self.record(MoveKind::Flag, pos);
+
+        let idx = pos.to_index(self.board.size.width);
+        self.record_tiles([(idx, self.board.tiles[idx].state.clone())]);
+        self.auto_flags[idx] = false;
+        let tile = &mut self.board.tiles[idx];
+
+        tile.state = match tile.state {
+            TileState::Block(mark) => {
+                match mark {
+                    Mark::Unmarked => self.bombs_left -= 1,
+                    Mark::Flagged => self.bombs_left += 1,
+                    Mark::Questioned => {}
+                }
+                match mark.next() {
+                    Mark::Questioned if !self.question_marks => TileState::Block(Mark::Unmarked),
+                    next => TileState::Block(next),
+                }
+            }
+            TileState::Revealed => TileState::Revealed,
+        };
+    }
+
+    // only changes what the next flag on a flagged tile does, so it can be set mid game
+    pub fn set_question_marks(&mut self, question_marks: bool) {
+        self.question_marks = question_marks;
+    }
+
+    // recorded like any other flag, so replays don't need the assist
+    pub fn auto_flag(&mut self, pos: Position) {
+        let tile = self.tile(pos);
+        if tile.is_revealed() || tile.is_flagged() {
+            return;
+        }
+        self.mark(pos);
+        self.auto_flags[pos.to_index(self.board.size.width)] = true;
+    }
+
+    pub fn is_auto_flagged(&self, index: usize) -> bool {
+        self.auto_flags[index]
+    }
+
+    // annotations aren't moves, so they are left out of replays
+    pub fn annotate(&mut self, pos: Position) {
+        let idx = pos.to_index(self.board.size.width);
+        if self.is_lost() || self.is_won() || self.board.tiles[idx].is_revealed() {
+            return;
+        }
+        self.annotations[idx] = Annotation::cycle(self.annotations[idx]);
+    }
+
+    pub fn annotation(&self, index: usize) -> Option<Annotation> {
+        self.annotations[index]
+    }
+
+    pub fn reveal(&mut self, pos: Position) {
+        if self.is_lost() || self.is_won() {
+            return;
+        }
+        self.clicks.reveals += 1;
+        self.open(pos);
+    }
+
+    fn open(&mut self, pos: Position) {
+        if self.is_lost() || self.is_won() {
+            return;
+        }
+        self.record(MoveKind::Reveal, pos);
+
+        // the first move is safe unless the board was generated without a safe start
+        if self.state == GameState::FirstMove {
+            self.place_mines(pos);
+            self.state = GameState::InProgress;
+            if let Some(delta) = self.deltas.last_mut() {
+                delta.placed_mines = true;
+            }
+        }
+
+        let idx = pos.to_index(self.board.size.width);
+        let tile = &self.board.tiles[idx];
+
+        if tile.is_revealed() || tile.is_flagged() {
+            return;
+        }
+
+        if tile.is_bomb() {
+            self.record_tiles([(idx, tile.state.clone())]);
+            self.board.tiles[idx].state = TileState::Revealed;
+            self.state = GameState::Lost;
+            return;
+        }
+
+        let opened = self.board.reveal_tile(pos);
+        self.record_tiles(opened);
+        if self.opening.is_none() {
+            self.opening = Some((pos, self.board.size.tiles() - self.board.tiles_left));
+        }
+
+        self.check_win_condition();
+    }
+
+    // no-guess boards are drawn again from the same generator until one clears from
+    // the first click alone; the last one drawn is kept if none does
+    fn place_mines(&mut self, first_click: Position) {
+        let mut rng = StdRng::seed_from_u64(self.board.seed);
+        let tiles = self.board.size.tiles();
+        let no_guess = self.board.generation == Generation::NoGuess && tiles <= NO_GUESS_MAX_TILES;
+        let max_boards = (NO_GUESS_TILE_BUDGET / tiles).max(1);
+        for boards in 1.. {
+            self.board.insert_bombs(&mut rng, first_click);
+            self.board.calculate_adjacent_bombs();
+            if !no_guess || boards == max_boards || self.clears_from(first_click) {
+                return;
+            }
+            // only the board that is kept is recorded
+            self.board.create_tiles();
+            self.board.draws.clear();
+        }
+    }
+
+    // plays a copy of the board with the solver alone
+    fn clears_from(&self, first_click: Position) -> bool {
+        let mut probe = Engine::with_generation(
+            self.board.size,
+            self.board.num_bombs as usize,
+            self.board.seed,
+            self.board.generation,
+        );
+        probe.board = self.board.clone();
+        probe.state = GameState::InProgress;
+        solver::clears(&mut probe, first_click)
+    }
+
+    fn tile(&self, pos: Position) -> &Tile {
+        &self.board.tiles[pos.to_index(self.board.size.width)]
+    }
+
+    pub fn neighbours(&self, pos: Position) -> Vec<Position> {
+        let width = self.board.size.width;
+        if !self.board.size.contains(pos) {
+            return Vec::new();
+        }
+        self.neighbour_indices(pos.to_index(width))
+            .iter()
+            .map(|adj| Position::from_index(*adj, width))
+            .collect()
+    }
+
+    // the same neighbours as tile indices, straight from the board's table
+    pub fn neighbour_indices(&self, index: usize) -> &[usize] {
+        self.board.neighbours.of(index)
+    }
+
+    // reveals the unflagged neighbours of a number once it has that many flags around it;
+    // each one is a normal reveal, so a wrong flag loses the game and replays see every tile
+    pub fn chord(&mut self, pos: Position) {
+        if self.is_lost() || self.is_won() {
+            return;
+        }
+        self.clicks.chords += 1;
+        self.open_around(pos);
+    }
+
+    fn open_around(&mut self, pos: Position) {
+        let tile = self.tile(pos);
+        if !tile.is_revealed() || tile.is_bomb() || tile.num_adjacent_bombs() == 0 {
+            return;
+        }
+        let number = tile.num_adjacent_bombs() as usize;
+        let flags = self
+            .neighbours(pos)
+            .iter()
+            .filter(|adj| self.tile(**adj).is_flagged())
+            .count();
+        if flags != number {
+            return;
+        }
+        for adj in self.chord_targets(pos) {
+            self.open(adj);
+        }
+    }
+
+    // the covered, unflagged neighbours a chord on this number would reveal
+    pub fn chord_targets(&self, pos: Position) -> Vec<Position> {
+        let tile = self.tile(pos);
+        if !tile.is_revealed() || tile.is_bomb() || tile.num_adjacent_bombs() == 0 {
+            return Vec::new();
+        }
+        self.neighbours(pos)
+            .into_iter()
+            .filter(|adj| {
+                let tile = self.tile(*adj);
+                !tile.is_revealed() && !tile.is_flagged()
+            })
+            .collect()
+    }
+
+    // the auto-chord assist: numbers next to a new flag chord themselves once it satisfies them
+    pub fn auto_chord(&mut self, flagged: Position) {
+        if !self.tile(flagged).is_flagged() {
+            return;
+        }
+        for adj in self.neighbours(flagged) {
+            self.open_around(adj);
+        }
+    }
+
+    // plays a recorded move again; one made at the same moment as the move before came
+    // from the same click, like the reveals of a chord or the assists' flags after it.
+    // A chord only leaves its reveals behind, so it is counted as a reveal
+    pub fn replay_move(&mut self, m: &Move) {
+        let same_click = self.moves.last().is_some_and(|last| last.time == m.time);
+        match (m.kind, same_click) {
+            (MoveKind::Reveal, false) => self.reveal(m.pos),
+            (MoveKind::Reveal, true) => self.open(m.pos),
+            (MoveKind::Flag, false) => self.flag(m.pos),
+            (MoveKind::Flag, true) => self.mark(m.pos),
+        }
+    }
+
+    // takes back the last move together with any made at the same moment, such as the
+    // reveals of a chord or the assists' flags after it; they are dropped from the moves
+    // so replays of the game stay valid. A win is final, and the reveal that placed the
+    // mines can't be taken back
+    pub fn undo(&mut self) -> bool {
+        if self.is_won() {
+            return false;
+        }
+        let Some(time) = self.moves.last().map(|m| m.time) else {
+            return false;
+        };
+        let mut undone = false;
+        while self.moves.last().is_some_and(|m| m.time == time) {
+            if self.deltas.last().is_some_and(|delta| delta.placed_mines) {
+                break;
+            }
+            let (Some(m), Some(delta)) = (self.moves.pop(), self.deltas.pop()) else {
+                break;
+            };
+            for (idx, state) in delta.tiles.into_iter().rev() {
+                self.board.tiles[idx].state = state;
+            }
+            self.auto_flags[m.pos.to_index(self.board.size.width)] = delta.auto_flag;
+            self.bombs_left = delta.bombs_left;
+            self.board.tiles_left = delta.tiles_left;
+            self.state = delta.state;
+            undone = true;
+        }
+        if undone {
+            self.undos += 1;
+        }
+        undone
+    }
+
+    // takes back the reveal that lost the game
+    pub fn undo_loss(&mut self) -> bool {
+        self.is_lost() && self.undo()
+    }
+
+    pub fn undos(&self) -> u32 {
+        self.undos
+    }
+
+    pub fn clicks(&self) -> Clicks {
+        self.clicks
+    }
+
+    // one click per opening, plus one for every number that isn't on an opening's edge;
+    // its length is the board's 3BV
+    pub fn optimal_clicks(&self) -> Vec<usize> {
+        let tiles = &self.board.tiles;
+        let is_zero = |i: usize| !tiles[i].is_bomb() && tiles[i].num_adjacent_bombs() == 0;
+        let mut cleared = vec![false; tiles.len()];
+        let mut clicks = Vec::new();
+        for start in 0..tiles.len() {
+            if cleared[start] || !is_zero(start) {
+                continue;
+            }
+            clicks.push(start);
+            cleared[start] = true;
+            let mut stack = vec![start];
+            while let Some(i) = stack.pop() {
+                for &adj in self.neighbour_indices(i) {
+                    if !cleared[adj] {
+                        cleared[adj] = true;
+                        if is_zero(adj) {
+                            stack.push(adj);
+                        }
+                    }
+                }
+            }
+        }
+        clicks.extend((0..tiles.len()).filter(|i| !cleared[*i] && !tiles[*i].is_bomb()));
+        clicks
+    }
+
+    // the fewest clicks that clear the board; mines are only placed on the first reveal,
+    // so it is 0 before then
+    pub fn board_3bv(&self) -> usize {
+        if self.state == GameState::FirstMove {
+            return 0;
+        }
+        self.optimal_clicks().len()
+    }
+
+    // the board's 3BV over the clicks used, as a percentage; only a won board was solved
+    pub fn efficiency(&self) -> Option<f32> {
+        self.is_won()
+            .then(|| self.board_3bv() as f32 * 100.0 / self.clicks.total().max(1) as f32)
+    }
+
+    // hints only count while there is a game to help with
+    pub fn record_hint(&mut self) -> bool {
+        if !self.is_in_progress() {
+            return false;
+        }
+        self.hints += 1;
+        true
+    }
+
+    // the result as it is recorded: assists add their penalties to the clock time
+    pub fn summary(&self, penalties: Penalties) -> Summary {
+        Summary {
+            won: self.is_won(),
+            time: self.elapsed,
+            penalty: penalties.per_hint * self.hints + penalties.per_undo * self.undos,
+            assisted: self.hints > 0 || self.undos > 0,
+        }
+    }
+
+    pub fn opening(&self) -> Option<(Position, usize)> {
+        self.opening
+    }
+
+    pub fn safe_tiles_left(&self) -> usize {
+        self.board.tiles_left - self.board.num_bombs as usize
+    }
+
+    // share of safe tiles revealed so far, from 0.0 to 1.0
+    pub fn progress(&self) -> f32 {
+        let total = self.board.size.tiles();
+        let safe_tiles = total - self.board.num_bombs as usize;
+        if safe_tiles == 0 {
+            return 1.0;
+        }
+        (total - self.board.tiles_left) as f32 / safe_tiles as f32
+    }
+
+    pub fn get_tiles(&self) -> &[Tile] {
+        &self.board.tiles
+    }
+
+    fn check_win_condition(&mut self) {
+        if self.board.tiles_left == self.board.num_bombs as usize {
+            self.state = GameState::Won;
+        }
+    }
+}
+
+// hooks for fuzz-style tests: random games plus the rules every state has to keep
+#[cfg(test)]
+impl Engine {
+    // random reveals, flags and chords until the game ends, with the invariants checked
+    // after every move
+    pub fn random_playout(&mut self, rng: &mut impl Rng) -> Result<(), String> {
+        let size = self.size();
+        // flags alone never end a game, so the number of moves is capped
+        for _ in 0..size.tiles() * 4 {
+            if self.is_won() || self.is_lost() {
+                break;
+            }
+            let pos = Position(
+                rng.random_range(0..size.width as i32),
+                rng.random_range(0..size.height as i32),
+            );
+            let flagged = (0..size.tiles())
+                .filter(|i| self.board.tiles[*i].is_flagged())
+                .collect::<Vec<_>>();
+
+            self.tick(Duration::from_millis(rng.random_range(0..2000)));
+            match rng.random_range(0..4) {
+                0 => self.flag(pos),
+                1 => self.chord(pos),
+                _ => self.reveal(pos),
+            }
+
+            if let Some(i) = flagged.iter().find(|i| self.board.tiles[**i].is_revealed()) {
+                return Err(format!("flagged tile {} was revealed by {:?}", i, pos));
+            }
+            self.check_invariants()?;
+        }
+        Ok(())
+    }
+
+    pub fn check_invariants(&self) -> Result<(), String> {
+        let tiles = &self.board.tiles;
+        let count = |f: &dyn Fn(&Tile) -> bool| tiles.iter().filter(|t| f(t)).count();
+        let revealed_safe = count(&|t| t.is_revealed() && !t.is_bomb());
+        let revealed_bombs = count(&|t| t.is_revealed() && t.is_bomb());
+        let flags = count(&|t| t.is_flagged());
+
+        if self.board.tiles_left != tiles.len() - revealed_safe {
+            return Err(format!(
+                "tiles_left is {} with {} safe tiles revealed",
+                self.board.tiles_left, revealed_safe
+            ));
+        }
+        if self.mines_left() != self.board.num_bombs - flags as i32 {
+            return Err(format!(
+                "{} mines left with {} flags placed",
+                self.mines_left(),
+                flags
+            ));
+        }
+
+        if self.state == GameState::FirstMove {
+            return match count(&|t| t.is_bomb() || t.is_revealed()) {
+                0 => Ok(()),
+                _ => Err("mines or revealed tiles before the first move".to_string()),
+            };
+        }
+        if count(&|t| t.is_bomb()) != self.num_bombs() {
+            return Err("wrong number of mines on the board".to_string());
+        }
+        for (i, tile) in tiles.iter().enumerate() {
+            let pos = Position::from_index(i, self.board.size.width);
+            let adjacent = self
+                .neighbours(pos)
+                .iter()
+                .filter(|adj| self.tile(**adj).is_bomb())
+                .count();
+            if !tile.is_bomb() && tile.num_adjacent_bombs() as usize != adjacent {
+                return Err(format!("tile {:?} counts the wrong number of mines", pos));
+            }
+        }
+
+        let expected_bombs = match self.state {
+            GameState::Lost => 1,
+            _ => 0,
+        };
+        if revealed_bombs != expected_bombs {
+            return Err(format!(
+                "{} mines revealed in state {:?}",
+                revealed_bombs, self.state
+            ));
+        }
+        // a win means every safe tile is open
+        if self.is_won() != (self.safe_tiles_left() == 0) {
+            return Err(format!(
+                "{} safe tiles left in state {:?}",
+                self.safe_tiles_left(),
+                self.state
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_neighbour_table_matches_offsets() {
+        let size = GridSize::new(5, 4);
+        let engine = Engine::new(size, 3);
+        assert_eq!(engine.neighbour_indices(0), [1, 6, 5]);
+        assert_eq!(engine.neighbour_indices(2).len(), 5);
+        assert_eq!(engine.neighbour_indices(6).len(), 8);
+        assert_eq!(engine.neighbour_indices(19), [13, 14, 18]);
+        for i in 0..size.tiles() {
+            let pos = Position::from_index(i, size.width);
+            let expected = ADJACENT_OFFSETS
+                .iter()
+                .map(|offset| pos + *offset)
+                .filter(|adj| size.contains(*adj))
+                .collect::<Vec<_>>();
+            assert_eq!(engine.neighbours(pos), expected);
+        }
+        assert!(engine.neighbours(Position(-1, 0)).is_empty());
+    }
+
+    #[test]
+    fn test_tile_count_matches_board_size() {
+        let board_width = 10;
+        let board_height = 15;
+        let num_bombs = 20;
+        let engine = Engine::new(GridSize::new(board_width, board_height), num_bombs);
+
+        let tile_count = engine.get_tiles().into_iter().count();
+        let expected_count = board_width * board_height;
+
+        assert_eq!(
+            tile_count, expected_count,
+            "Tile count ({}) does not match the expected board size ({})",
+            tile_count, expected_count
+        );
+    }
+
+    #[test]
+    fn test_bomb_count() {
+        let board_size = GridSize::new(8, 8);
+        let num_bombs = 10;
+        let mut engine = Engine::new(board_size, num_bombs);
+
+        engine.reveal(Position(2, 3));
+
+        let bomb_count = engine.get_tiles().iter().filter(|t| t.is_bomb()).count();
+
+        assert_eq!(
+            bomb_count, num_bombs,
+            "Bomb count ({}) does not match the expected number ({})",
+            bomb_count, num_bombs
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "don't fit")]
+    fn test_too_many_mines_fail_to_place() {
+        let mut engine = Engine::with_seed(GridSize::new(3, 3), 1, 7);
+        engine.reveal(Position(1, 1));
+    }
+
+    #[test]
+    fn test_same_seed_same_board() {
+        let mut first = Engine::with_seed(GridSize::new(16, 16), 40, 1234);
+        let mut second = Engine::with_seed(GridSize::new(16, 16), 40, 1234);
+
+        first.reveal(Position(4, 4));
+        second.reveal(Position(4, 4));
+
+        let bombs = |engine: &Engine| {
+            engine
+                .get_tiles()
+                .iter()
+                .map(|t| t.is_bomb())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            bombs(&first),
+            bombs(&second),
+            "Boards generated from the same seed should match"
+        );
+    }
+
+    #[test]
+    fn test_anti_fifty_fifty_moves_the_mines() {
+        let size = GridSize::new(9, 9);
+        let board = |seed, generation| {
+            let mut engine = Engine::with_generation(size, 15, seed, generation);
+            engine.reveal(Position(4, 4));
+            engine
+        };
+        let safe_idxs = |engine: &Engine| {
+            let mut safe = vec![40];
+            safe.extend_from_slice(engine.neighbour_indices(40));
+            safe
+        };
+        let seed = (0..)
+            .find(|seed| {
+                let plain = board(*seed, Generation::Plain);
+                plain.board.fifty_fifty(&safe_idxs(&plain)).is_some()
+            })
+            .unwrap();
+
+        let fixed = board(seed, Generation::AntiFiftyFifty);
+        assert_eq!(fixed.generation(), Generation::AntiFiftyFifty);
+        assert!(fixed.board.fifty_fifty(&safe_idxs(&fixed)).is_none());
+        assert_eq!(fixed.get_tiles().iter().filter(|t| t.is_bomb()).count(), 15);
+        assert!(fixed.is_in_progress());
+        assert!(
+            fixed.generation_draws().len()
+                > board(seed, Generation::Plain).generation_draws().len()
+        );
+
+        assert_eq!(
+            Generation::from_key("anti-fifty-fifty"),
+            Some(Generation::AntiFiftyFifty)
+        );
+        assert_eq!(Generation::from_key("lucky"), None);
+    }
+
+    #[test]
+    fn test_generation_draws_place_the_mines() {
+        let mut engine = Engine::with_seed(GridSize::new(9, 9), 10, 77);
+        assert!(engine.generation_draws().is_empty());
+        engine.reveal(Position(4, 4));
+
+        let draws = engine.generation_draws();
+        assert!(draws.len() >= 10);
+        // the last draw always places the last mine
+        assert!(engine.get_tiles()[*draws.last().unwrap()].is_bomb());
+        for (i, tile) in engine.get_tiles().iter().enumerate() {
+            assert_eq!(tile.is_bomb(), draws.contains(&i) && !tile.is_revealed());
+        }
+
+        // a no-guess board keeps only the draws of the board it settled on
+        let mut engine = Engine::with_generation(GridSize::new(30, 16), 99, 3, Generation::NoGuess);
+        engine.reveal(Position(15, 8));
+        let draws = engine.generation_draws();
+        for (i, tile) in engine.get_tiles().iter().enumerate() {
+            assert_eq!(tile.is_bomb(), draws.contains(&i) && !tile.is_revealed());
+        }
+    }
+
+    #[test]
+    fn test_no_guess_stops_at_the_largest_preset() {
+        let board = |generation| {
+            let mut engine = Engine::with_generation(GridSize::new(100, 100), 2000, 5, generation);
+            engine.reveal(Position(50, 50));
+            engine
+        };
+        let big = board(Generation::NoGuess);
+        assert_eq!(
+            big.generation_draws(),
+            board(Generation::Plain).generation_draws()
+        );
+    }
+
+    #[test]
+    fn test_initial_game_state() {
+        let engine = Engine::new(GridSize::new(5, 5), 5);
+        assert_eq!(
+            engine.state,
+            GameState::FirstMove,
+            "Initial game state should be InProgress"
+        );
+    }
+
+    #[test]
+    fn test_flag() {
+        let mut engine = Engine::new(GridSize::new(5, 5), 5);
+        let pos = Position(2, 2);
+
+        engine.flag(pos);
+        let tile = engine.get_tiles().iter().nth(pos.to_index(5)).unwrap();
+
+        assert!(tile.is_flagged(), "Tile should be flagged after flagging");
+    }
+
+    #[test]
+    fn test_flags_skip_question_marks_when_off() {
+        let mut engine = Engine::new(GridSize::new(5, 5), 5);
+        let pos = Position(2, 2);
+        engine.flag(pos);
+        engine.flag(pos);
+        assert_eq!(engine.tile(pos).state, TileState::Block(Mark::Questioned));
+
+        engine.set_question_marks(false);
+        engine.flag(pos);
+        engine.flag(pos);
+        engine.flag(pos);
+        assert_eq!(engine.tile(pos).state, TileState::Block(Mark::Unmarked));
+        assert_eq!(engine.mines_left(), 5);
+    }
+
+    #[test]
+    fn test_unprotected_first_click_can_hit_a_mine() {
+        let size = GridSize::new(5, 5);
+        let first = Position(2, 2);
+        let lost = (0..200).any(|seed| {
+            let mut engine = Engine::with_generation(size, 20, seed, Generation::Unprotected);
+            engine.reveal(first);
+            engine.is_lost()
+        });
+        assert!(lost);
+        assert_eq!(
+            Generation::from_key("unprotected"),
+            Some(Generation::Unprotected)
+        );
+    }
+
+    #[test]
+    fn test_reveal_safe_tile() {
+        let mut engine = Engine::new(GridSize::new(5, 5), 0);
+        let pos = Position(2, 2);
+
+        engine.reveal(pos);
+        let tile = engine.get_tiles().iter().nth(pos.to_index(5)).unwrap();
+
+        assert!(
+            tile.is_revealed(),
+            "Tile should be revealed after revealing a safe tile"
+        );
+        assert_eq!(engine.state, GameState::Won, "Game should be won");
+        assert_eq!(engine.opening(), Some((pos, 25)));
+    }
+
+    #[test]
+    fn test_reveal_bomb_tile() {
+        let board_size = GridSize::new(3, 3);
+        let num_bombs = 1;
+        let mut engine = Engine::new(board_size, num_bombs);
+
+        engine.state = GameState::InProgress;
+
+        let bomb_pos = Position(1, 1);
+        let bomb_idx = bomb_pos.to_index(board_size.width);
+
+        for tile in &mut engine.board.tiles {
+            tile.has_bomb = false;
+        }
+        engine.board.tiles[bomb_idx].has_bomb = true;
+
+        engine.board.calculate_adjacent_bombs();
+
+        assert!(
+            engine.get_tiles()[bomb_idx].is_bomb(),
+            "Expected to find a bomb at position ({}, {})",
+            bomb_pos.0,
+            bomb_pos.1
+        );
+
+        engine.reveal(Position(0, 0));
+
+        engine.reveal(bomb_pos);
+
+        // Verify the game is lost
+        assert_eq!(
+            engine.state,
+            GameState::Lost,
+            "Game should be lost after revealing a bomb"
+        );
+    }
+
+    #[test]
+    fn test_unflag_tile() {
+        let mut engine = Engine::new(GridSize::new(5, 5), 5);
+        let pos = Position(2, 2);
+
+        engine.flag(pos);
+        engine.flag(pos); // unflag
+        let tile = engine.get_tiles().iter().nth(pos.to_index(5)).unwrap();
+
+        assert!(
+            !tile.is_flagged(),
+            "Tile should not be flagged after unflagging"
+        );
+    }
+
+    #[test]
+    fn test_flags_cycle_through_question_marks() {
+        let mut engine = Engine::new(GridSize::new(5, 5), 5);
+        let pos = Position(2, 2);
+        let state = |engine: &Engine| engine.get_tiles()[pos.to_index(5)].state.clone();
+
+        engine.flag(pos);
+        assert_eq!(state(&engine), TileState::Block(Mark::Flagged));
+        assert_eq!(engine.mines_left(), 4);
+        engine.flag(pos);
+        assert_eq!(state(&engine), TileState::Block(Mark::Questioned));
+        assert_eq!(
+            engine.mines_left(),
+            5,
+            "a question mark isn't counted as a mine"
+        );
+        engine.flag(pos);
+        assert_eq!(state(&engine), TileState::Block(Mark::Unmarked));
+        assert_eq!(engine.mines_left(), 5);
+
+        // question marks don't stop a reveal
+        engine.flag(pos);
+        engine.flag(pos);
+        engine.reveal(pos);
+        assert!(engine.get_tiles()[pos.to_index(5)].is_revealed());
+    }
+
+    #[test]
+    fn test_reveal_adjacent_tiles() {
+        let mut engine = Engine::new(GridSize::new(5, 5), 0); // No bombs
+        let pos = Position(2, 2);
+
+        engine.reveal(pos);
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                if dx == 0 && dy == 0 {
+                    continue;
+                }
+                let adjacent_pos = Position(pos.0 + dx, pos.1 + dy);
+                let adjacent_tile = engine
+                    .get_tiles()
+                    .iter()
+                    .nth(adjacent_pos.to_index(5))
+                    .unwrap();
+                assert!(
+                    adjacent_tile.is_revealed(),
+                    "Adjacent tile at {:?} should be revealed",
+                    adjacent_pos
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_elapsed_time_stops_after_game_over() {
+        let mut engine = Engine::new(GridSize::new(5, 5), 0);
+
+        engine.tick(Duration::from_millis(1500));
+        engine.tick(Duration::from_secs(1000));
+        assert_eq!(engine.elapsed(), Duration::from_millis(1_001_500));
+
+        engine.reveal(Position(2, 2));
+        engine.tick(Duration::from_secs(5));
+
+        assert_eq!(
+            engine.elapsed(),
+            Duration::from_millis(1_001_500),
+            "Timer should not advance after the game is won"
+        );
+    }
+
+    #[test]
+    fn test_progress() {
+        let mut engine = Engine::new(GridSize::new(3, 3), 1);
+        assert_eq!(engine.progress(), 0.0);
+
+        engine.state = GameState::InProgress;
+        for tile in &mut engine.board.tiles {
+            tile.has_bomb = false;
+        }
+        engine.board.tiles[8].has_bomb = true;
+        engine.board.calculate_adjacent_bombs();
+
+        engine.reveal(Position(2, 1));
+        assert_eq!(engine.progress(), 1.0 / 8.0);
+
+        engine.reveal(Position(0, 0));
+        assert_eq!(engine.progress(), 1.0, "Every safe tile should be revealed");
+    }
+
+    #[test]
+    fn test_game_won() {
+        let board_size = GridSize::new(3, 3);
+        let num_bombs = 1;
+        let mut engine = Engine::new(board_size, num_bombs);
+
+        for x in 0..3 {
+            for y in 0..3 {
+                let pos = Position(x, y);
+                let tile = engine.get_tiles().iter().nth(pos.to_index(3)).unwrap();
+                if !tile.is_bomb() {
+                    engine.reveal(pos);
+                }
+            }
+        }
+
+        assert_eq!(
+            engine.state,
+            GameState::Won,
+            "Game should be won after revealing all non-bomb tiles"
+        );
+    }
+
+    #[test]
+    fn test_annotations_cycle_and_are_not_moves() {
+        let mut engine = Engine::with_seed(GridSize::new(8, 8), 10, 3);
+        let pos = Position(1, 2);
+        let idx = pos.to_index(8);
+
+        engine.annotate(pos);
+        assert_eq!(engine.annotation(idx), Some(Annotation::Red));
+        engine.annotate(pos);
+        engine.annotate(pos);
+        assert_eq!(engine.annotation(idx), Some(Annotation::Blue));
+        engine.annotate(pos);
+        assert_eq!(engine.annotation(idx), None);
+        assert!(engine.moves().is_empty());
+    }
+
+    #[test]
+    fn test_undo_loss() {
+        let mut engine = Engine::with_seed(GridSize::new(8, 8), 10, 3);
+        assert!(!engine.undo_loss());
+
+        engine.reveal(Position(0, 0));
+        let bomb = engine.get_tiles().iter().position(|t| t.is_bomb()).unwrap();
+        let moves = engine.moves().len();
+        engine.reveal(Position::from_index(bomb, 8));
+        assert!(engine.is_lost());
+
+        assert!(engine.undo_loss());
+        assert!(engine.is_in_progress());
+        assert!(!engine.get_tiles()[bomb].is_revealed());
+        assert_eq!(engine.moves().len(), moves);
+        assert_eq!(engine.undos(), 1);
+    }
+
+    #[test]
+    fn test_undo_puts_the_board_back() {
+        let mut engine = Engine::with_seed(GridSize::new(16, 16), 40, 8);
+        engine.flag(Position(15, 15));
+        engine.reveal(Position(8, 8));
+        // the first reveal placed the mines, and the flag before it stays with it
+        assert!(!engine.undo());
+
+        let mut rng = StdRng::seed_from_u64(8);
+        for step in 1..200u64 {
+            if engine.is_won() || engine.is_lost() {
+                break;
+            }
+            engine.tick(Duration::from_millis(step % 3));
+            let pos = Position(rng.random_range(0..16), rng.random_range(0..16));
+            match rng.random_range(0..3) {
+                0 => engine.flag(pos),
+                _ => engine.reveal(pos),
+            }
+            if step % 4 == 0 {
+                engine.undo();
+            }
+        }
+        if engine.is_lost() {
+            assert!(engine.undo_loss());
+            assert!(engine.is_in_progress());
+        }
+        engine.check_invariants().unwrap();
+
+        let mut replayed = Engine::with_seed(GridSize::new(16, 16), 40, 8);
+        for m in engine.moves() {
+            match m.kind {
+                MoveKind::Reveal => replayed.reveal(m.pos),
+                MoveKind::Flag => replayed.flag(m.pos),
+            }
+        }
+        let states = |e: &Engine| {
+            e.get_tiles()
+                .iter()
+                .map(|t| t.state.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(states(&replayed), states(&engine));
+        assert_eq!(replayed.mines_left(), engine.mines_left());
+        assert!(engine.undos() > 0);
+    }
+
+    #[test]
+    fn test_auto_chord_after_satisfying_flag() {
+        let mut engine = Engine::new(GridSize::new(3, 3), 2);
+        engine.state = GameState::InProgress;
+        for tile in &mut engine.board.tiles {
+            tile.has_bomb = false;
+        }
+        engine.board.tiles[Position(2, 0).to_index(3)].has_bomb = true;
+        engine.board.tiles[Position(2, 2).to_index(3)].has_bomb = true;
+        engine.board.calculate_adjacent_bombs();
+
+        engine.reveal(Position(1, 1));
+        engine.flag(Position(2, 0));
+        engine.auto_chord(Position(2, 0));
+        assert_eq!(engine.safe_tiles_left(), 6, "one flag doesn't satisfy a 2");
+
+        engine.flag(Position(2, 2));
+        engine.auto_chord(Position(2, 2));
+        assert!(engine.is_won());
+    }
+
+    #[test]
+    fn test_chord_needs_matching_flags() {
+        let mut engine = Engine::new(GridSize::new(3, 3), 1);
+        engine.state = GameState::InProgress;
+        for tile in &mut engine.board.tiles {
+            tile.has_bomb = false;
+        }
+        engine.board.tiles[Position(0, 0).to_index(3)].has_bomb = true;
+        engine.board.calculate_adjacent_bombs();
+
+        engine.reveal(Position(1, 1));
+        assert_eq!(engine.chord_targets(Position(1, 1)).len(), 8);
+        engine.chord(Position(1, 1));
+        assert_eq!(engine.safe_tiles_left(), 7, "no flag, no chord");
+
+        engine.flag(Position(0, 0));
+        assert_eq!(engine.chord_targets(Position(1, 1)).len(), 7);
+        engine.chord(Position(1, 1));
+        assert!(engine.is_won());
+        assert!(engine.chord_targets(Position(1, 1)).is_empty());
+    }
+
+    #[test]
+    fn test_board_3bv_counts_openings_and_lone_numbers() {
+        // a mine in the top right corner: every number is on the edge of one opening
+        let mut engine = Engine::new(GridSize::new(3, 3), 1);
+        assert_eq!(engine.board_3bv(), 0);
+        engine.state = GameState::InProgress;
+        for tile in &mut engine.board.tiles {
+            tile.has_bomb = false;
+        }
+        engine.board.tiles[Position(2, 0).to_index(3)].has_bomb = true;
+        engine.board.calculate_adjacent_bombs();
+        assert_eq!(engine.board_3bv(), 1);
+
+        // another in the bottom left splits it into two
+        engine.board.tiles[Position(0, 2).to_index(3)].has_bomb = true;
+        engine.board.calculate_adjacent_bombs();
+        assert_eq!(engine.board_3bv(), 2);
+
+        // a number between two mines borders no opening
+        let mut engine = Engine::new(GridSize::new(3, 1), 2);
+        engine.state = GameState::InProgress;
+        for (i, tile) in engine.board.tiles.iter_mut().enumerate() {
+            tile.has_bomb = i != 1;
+        }
+        engine.board.calculate_adjacent_bombs();
+        assert_eq!(engine.optimal_clicks(), vec![1]);
+    }
+
+    #[test]
+    fn test_clicks_count_a_chord_once() {
+        let board = || {
+            let mut engine = Engine::new(GridSize::new(3, 3), 1);
+            engine.state = GameState::InProgress;
+            for tile in &mut engine.board.tiles {
+                tile.has_bomb = false;
+            }
+            engine.board.tiles[Position(0, 0).to_index(3)].has_bomb = true;
+            engine.board.calculate_adjacent_bombs();
+            engine
+        };
+        let mut engine = board();
+
+        engine.reveal(Position(1, 1));
+        engine.tick(Duration::from_secs(1));
+        engine.flag(Position(0, 0));
+        engine.tick(Duration::from_secs(1));
+        engine.chord(Position(1, 1));
+        assert!(engine.is_won());
+        // the chord recorded a reveal for each tile it opened
+        assert!(engine.moves().len() > 3);
+        let clicks = Clicks {
+            reveals: 1,
+            flags: 1,
+            chords: 1,
+        };
+        assert_eq!(engine.clicks(), clicks);
+        assert_eq!(engine.efficiency(), Some(100.0 / 3.0));
+
+        // played again, the chord's reveals make one click
+        let mut replayed = board();
+        for m in engine.moves() {
+            replayed.tick(m.time - replayed.elapsed());
+            replayed.replay_move(m);
+        }
+        assert!(replayed.is_won());
+        assert_eq!(replayed.clicks().total(), 3);
+    }
+
+    #[test]
+    fn test_opened_since_follows_the_flood() {
+        let mut engine = Engine::new(GridSize::new(3, 3), 1);
+        engine.state = GameState::InProgress;
+        for tile in &mut engine.board.tiles {
+            tile.has_bomb = false;
+        }
+        engine.board.tiles[Position(2, 2).to_index(3)].has_bomb = true;
+        engine.board.calculate_adjacent_bombs();
+
+        engine.flag(Position(2, 2));
+        engine.reveal(Position(0, 0));
+        let opened = engine.opened_since(0);
+        assert_eq!(opened.len(), 8);
+        // the clicked tile first, its neighbours next, the far corner's neighbours last
+        assert_eq!(opened[0], 0);
+        assert!(opened[1..4].contains(&Position(1, 1).to_index(3)));
+        assert!(opened[4..].contains(&Position(2, 1).to_index(3)));
+        assert!(engine.opened_since(engine.moves().len()).is_empty());
+    }
+
+    #[test]
+    fn test_auto_flags_are_marked_until_removed() {
+        let mut engine = Engine::with_seed(GridSize::new(8, 8), 10, 3);
+        let pos = Position(1, 2);
+        let idx = pos.to_index(8);
+
+        engine.auto_flag(pos);
+        assert!(engine.get_tiles()[idx].is_flagged());
+        assert!(engine.is_auto_flagged(idx));
+        assert_eq!(engine.moves().len(), 1);
+
+        engine.auto_flag(pos);
+        assert!(engine.get_tiles()[idx].is_flagged());
+        engine.flag(pos);
+        assert!(!engine.is_auto_flagged(idx));
+    }
+
+    #[test]
+    fn test_summary_adds_penalties() {
+        let penalties = Penalties {
+            per_hint: Duration::from_secs(10),
+            per_undo: Duration::from_secs(30),
+        };
+        let mut engine = Engine::with_seed(GridSize::new(8, 8), 10, 3);
+        assert!(!engine.record_hint());
+        engine.reveal(Position(0, 0));
+        engine.tick(Duration::from_secs(5));
+        assert!(!engine.summary(penalties).assisted);
+
+        assert!(engine.record_hint());
+        let bomb = engine.get_tiles().iter().position(|t| t.is_bomb()).unwrap();
+        engine.reveal(Position::from_index(bomb, 8));
+        engine.undo_loss();
+
+        let summary = engine.summary(penalties);
+        assert!(summary.assisted);
+        assert_eq!(summary.penalty, Duration::from_secs(40));
+        assert_eq!(summary.final_time(), Duration::from_secs(45));
+    }
+
+    #[test]
+    fn test_random_playouts_keep_invariants() {
+        for (width, height, bombs) in [
+            (5, 5, 10),
+            (8, 8, 10),
+            (16, 16, 40),
+            (30, 16, 99),
+            (9, 5, 30),
+        ] {
+            for seed in 0..40 {
+                let mut rng = StdRng::seed_from_u64(seed);
+                let mut engine = Engine::with_seed(GridSize::new(width, height), bombs, seed);
+                if let Err(e) = engine.random_playout(&mut rng) {
+                    panic!(
+                        "{}x{} with {} mines, seed {}: {}",
+                        width, height, bombs, seed, e
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,5 @@
+// the rules and board setup without any window or rendering, for the game itself,
+// bots, tests and other frontends
+pub mod board;
+pub mod game;
+pub mod solver;
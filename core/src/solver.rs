@@ -0,0 +1,427 @@
+use super::game::{Engine, Mark, Position, TileState};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Certainty {
+    Safe,
+    Unknown,
+    Mine,
+}
+
+// what the revealed numbers alone prove about every tile; flags are ignored because
+// the player's can be wrong. Single numbers are read until they give nothing more,
+// then pairs of numbers, and back again until neither does
+pub fn analyze(engine: &Engine) -> Vec<Certainty> {
+    let mut known: Vec<Certainty> = engine
+        .get_tiles()
+        .iter()
+        .map(|tile| match tile.is_revealed() {
+            true if tile.is_bomb() => Certainty::Mine,
+            true => Certainty::Safe,
+            false => Certainty::Unknown,
+        })
+        .collect();
+
+    loop {
+        while single_numbers(engine, &mut known) {}
+        if !subsets(engine, &mut known) {
+            return known;
+        }
+    }
+}
+
+// a number with all its mines found clears the rest, one with as many covered tiles
+// as missing mines flags them all; returns whether anything was learned
+fn single_numbers(engine: &Engine, known: &mut [Certainty]) -> bool {
+    let mut changed = false;
+    for (i, tile) in engine.get_tiles().iter().enumerate() {
+        if !tile.is_revealed() || tile.is_bomb() {
+            continue;
+        }
+        let neighbours = engine.neighbour_indices(i);
+        let mines = neighbours
+            .iter()
+            .filter(|j| known[**j] == Certainty::Mine)
+            .count();
+        let unknown: Vec<usize> = neighbours
+            .iter()
+            .copied()
+            .filter(|j| known[*j] == Certainty::Unknown)
+            .collect();
+        if unknown.is_empty() {
+            continue;
+        }
+
+        let number = tile.num_adjacent_bombs() as usize;
+        let verdict = if mines == number {
+            Certainty::Safe
+        } else if mines + unknown.len() == number {
+            Certainty::Mine
+        } else {
+            continue;
+        };
+        for j in unknown {
+            known[j] = verdict;
+        }
+        changed = true;
+    }
+    changed
+}
+
+// the tiles within three of a centre tile, one bit each in a 7x7 window. Two numbers can
+// only share a tile when they are within two of each other, and then the tiles around
+// both fit the window around either, so each pair is compared a word at a time
+struct Window {
+    centre: Position,
+    width: usize,
+}
+
+impl Window {
+    const SIDE: i32 = 7;
+    const REACH: i32 = 3;
+
+    fn bit(&self, idx: usize) -> u64 {
+        let Position(x, y) = Position::from_index(idx, self.width);
+        let dx = x - self.centre.0 + Window::REACH;
+        let dy = y - self.centre.1 + Window::REACH;
+        1 << (dy * Window::SIDE + dx)
+    }
+
+    fn mask(&self, tiles: &[usize]) -> u64 {
+        tiles.iter().fold(0, |mask, idx| mask | self.bit(*idx))
+    }
+
+    fn tile(&self, bit: u32) -> usize {
+        let x = self.centre.0 + bit as i32 % Window::SIDE - Window::REACH;
+        let y = self.centre.1 + bit as i32 / Window::SIDE - Window::REACH;
+        Position(x, y).to_index(self.width)
+    }
+}
+
+// when every unknown tile around one number also touches another, the other's extra
+// tiles hold exactly the difference between their missing mines: none means all safe,
+// one per tile means all mines
+fn subsets(engine: &Engine, known: &mut [Certainty]) -> bool {
+    let tiles = engine.get_tiles();
+    let width = engine.size().width;
+    // each number's unknown neighbours and the mines still missing among them
+    let mut numbers: Vec<Option<(Vec<usize>, usize)>> = vec![None; tiles.len()];
+    for (i, tile) in tiles.iter().enumerate() {
+        if !tile.is_revealed() || tile.is_bomb() {
+            continue;
+        }
+        let neighbours = engine.neighbour_indices(i);
+        let unknown: Vec<usize> = neighbours
+            .iter()
+            .copied()
+            .filter(|j| known[*j] == Certainty::Unknown)
+            .collect();
+        if unknown.is_empty() {
+            continue;
+        }
+        let found = neighbours
+            .iter()
+            .filter(|j| known[**j] == Certainty::Mine)
+            .count();
+        let missing = (tile.num_adjacent_bombs() as usize).saturating_sub(found);
+        numbers[i] = Some((unknown, missing));
+    }
+
+    let mut changed = false;
+    let mut nearby = Vec::new();
+    for (i, number) in numbers.iter().enumerate() {
+        let Some((small, small_mines)) = number else {
+            continue;
+        };
+        let window = Window {
+            centre: Position::from_index(i, width),
+            width,
+        };
+        let small_mask = window.mask(small);
+
+        // the numbers within two tiles, each once
+        nearby.clear();
+        for n in engine.neighbour_indices(i) {
+            nearby.push(*n);
+            nearby.extend_from_slice(engine.neighbour_indices(*n));
+        }
+        nearby.sort_unstable();
+        nearby.dedup();
+
+        for j in nearby.iter().filter(|j| **j != i) {
+            let Some((big, big_mines)) = &numbers[*j] else {
+                continue;
+            };
+            let big_mask = window.mask(big);
+            if small_mask & !big_mask != 0 {
+                continue;
+            }
+            let extra = big_mask & !small_mask;
+            let extra_len = extra.count_ones() as usize;
+            let extra_mines = big_mines.saturating_sub(*small_mines);
+            // a pair that says nothing about the extra tiles
+            let verdict = if extra_len == 0 {
+                continue;
+            } else if extra_mines == 0 {
+                Certainty::Safe
+            } else if extra_mines == extra_len {
+                Certainty::Mine
+            } else {
+                continue;
+            };
+            for bit in (0..u64::BITS).filter(|bit| extra & (1 << bit) != 0) {
+                let tile = window.tile(bit);
+                if known[tile] == Certainty::Unknown {
+                    known[tile] = verdict;
+                    changed = true;
+                }
+            }
+        }
+    }
+    changed
+}
+
+// one tile worth playing: a covered safe tile to open, or failing that a proven mine
+// still to flag; flagged tiles are passed over either way
+pub fn next_step(engine: &Engine) -> Option<(usize, Certainty)> {
+    let known = analyze(engine);
+    let tiles = engine.get_tiles();
+    let open = |certainty: Certainty| {
+        (0..tiles.len()).find(|i| {
+            known[*i] == certainty
+                && !tiles[*i].is_revealed()
+                && tiles[*i].state != TileState::Block(Mark::Flagged)
+        })
+    };
+    open(Certainty::Safe)
+        .map(|i| (i, Certainty::Safe))
+        .or_else(|| open(Certainty::Mine).map(|i| (i, Certainty::Mine)))
+}
+
+// whether the game can only go on with a guess: no covered tile, flagged or not, is
+// proven safe. The mine count is left out, so a few endgames are called early
+pub fn guess_required(engine: &Engine) -> bool {
+    if !engine.is_in_progress() {
+        return false;
+    }
+    let tiles = engine.get_tiles();
+    !analyze(engine)
+        .iter()
+        .zip(tiles)
+        .any(|(certainty, tile)| *certainty == Certainty::Safe && !tile.is_revealed())
+}
+
+// whether reading the numbers alone clears the board from a first click: every tile
+// proven safe is revealed until the board is won or nothing more can be proven
+pub fn clears(engine: &mut Engine, start: Position) -> bool {
+    let width = engine.size().width;
+    engine.reveal(start);
+    while engine.is_in_progress() {
+        let tiles = engine.get_tiles();
+        let safe: Vec<usize> = analyze(engine)
+            .iter()
+            .enumerate()
+            .filter(|(i, certainty)| **certainty == Certainty::Safe && !tiles[*i].is_revealed())
+            .map(|(i, _)| i)
+            .collect();
+        if safe.is_empty() {
+            return false;
+        }
+        for i in safe {
+            engine.reveal(Position::from_index(i, width));
+        }
+    }
+    engine.is_won()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Generation, GridSize};
+
+    #[test]
+    fn test_deductions_are_sound() {
+        let mut engine = Engine::with_seed(GridSize::new(16, 16), 40, 11);
+        assert!(analyze(&engine).iter().all(|c| *c == Certainty::Unknown));
+
+        engine.reveal(Position(8, 8));
+        let known = analyze(&engine);
+        let tiles = engine.get_tiles();
+        assert!(known.contains(&Certainty::Mine));
+        for (certainty, tile) in known.iter().zip(tiles) {
+            match certainty {
+                Certainty::Mine => assert!(tile.is_bomb()),
+                Certainty::Safe => assert!(!tile.is_bomb()),
+                Certainty::Unknown => assert!(!tile.is_revealed()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_pairs_of_numbers_prove_more() {
+        let mut stronger = 0;
+        for seed in 0..20 {
+            let mut engine = Engine::with_seed(GridSize::new(30, 16), 99, seed);
+            engine.reveal(Position(15, 8));
+            let tiles = engine.get_tiles();
+
+            // what reading one number at a time gets to on its own
+            let mut single: Vec<Certainty> = tiles
+                .iter()
+                .map(|tile| match tile.is_revealed() {
+                    true => Certainty::Safe,
+                    false => Certainty::Unknown,
+                })
+                .collect();
+            while single_numbers(&engine, &mut single) {}
+
+            let known = analyze(&engine);
+            for (i, certainty) in known.iter().enumerate() {
+                match certainty {
+                    Certainty::Mine => assert!(tiles[i].is_bomb()),
+                    Certainty::Safe => assert!(!tiles[i].is_bomb()),
+                    Certainty::Unknown => assert_eq!(single[i], Certainty::Unknown),
+                }
+            }
+            if known != single {
+                stronger += 1;
+            }
+        }
+        assert!(stronger > 0);
+    }
+
+    // every number against every other, as a reference for the windowed pairs
+    fn all_pairs(engine: &Engine, known: &mut [Certainty]) -> bool {
+        let tiles = engine.get_tiles();
+        let numbers: Vec<(Vec<usize>, usize)> = (0..tiles.len())
+            .filter(|i| tiles[*i].is_revealed() && !tiles[*i].is_bomb())
+            .map(|i| {
+                let neighbours = engine.neighbour_indices(i);
+                let unknown: Vec<usize> = neighbours
+                    .iter()
+                    .copied()
+                    .filter(|j| known[*j] == Certainty::Unknown)
+                    .collect();
+                let found = neighbours
+                    .iter()
+                    .filter(|j| known[**j] == Certainty::Mine)
+                    .count();
+                let number = tiles[i].num_adjacent_bombs() as usize;
+                (unknown, number.saturating_sub(found))
+            })
+            .filter(|(unknown, _)| !unknown.is_empty())
+            .collect();
+        let mut changed = false;
+        for (small, small_mines) in &numbers {
+            for (big, big_mines) in &numbers {
+                if !small.iter().all(|j| big.contains(j)) {
+                    continue;
+                }
+                let extra: Vec<usize> =
+                    big.iter().copied().filter(|j| !small.contains(j)).collect();
+                let extra_mines = big_mines.saturating_sub(*small_mines);
+                let verdict = if extra.is_empty() {
+                    continue;
+                } else if extra_mines == 0 {
+                    Certainty::Safe
+                } else if extra_mines == extra.len() {
+                    Certainty::Mine
+                } else {
+                    continue;
+                };
+                for j in extra {
+                    if known[j] == Certainty::Unknown {
+                        known[j] = verdict;
+                        changed = true;
+                    }
+                }
+            }
+        }
+        changed
+    }
+
+    #[test]
+    fn test_nearby_pairs_find_what_all_pairs_do() {
+        for seed in 0..20 {
+            let mut engine = Engine::with_seed(GridSize::new(30, 16), 99, seed);
+            engine.reveal(Position(0, 0));
+            engine.reveal(Position(15, 8));
+            let start: Vec<Certainty> = engine
+                .get_tiles()
+                .iter()
+                .map(|tile| match tile.is_revealed() {
+                    true => Certainty::Safe,
+                    false => Certainty::Unknown,
+                })
+                .collect();
+            let (mut nearby, mut every) = (start.clone(), start);
+            assert_eq!(
+                subsets(&engine, &mut nearby),
+                all_pairs(&engine, &mut every)
+            );
+            assert_eq!(nearby, every);
+        }
+    }
+
+    #[test]
+    fn test_next_step_is_always_right() {
+        let mut engine = Engine::with_seed(GridSize::new(16, 16), 40, 11);
+        assert_eq!(next_step(&engine), None);
+
+        engine.reveal(Position(8, 8));
+        let mut steps = 0;
+        while engine.is_in_progress() {
+            let Some((i, certainty)) = next_step(&engine) else {
+                break;
+            };
+            let pos = Position::from_index(i, 16);
+            match certainty {
+                Certainty::Safe => {
+                    assert!(!engine.get_tiles()[i].is_bomb());
+                    engine.reveal(pos);
+                }
+                Certainty::Mine => {
+                    assert!(engine.get_tiles()[i].is_bomb());
+                    engine.flag(pos);
+                }
+                Certainty::Unknown => unreachable!(),
+            }
+            steps += 1;
+        }
+        assert!(!engine.is_lost());
+        assert!(steps > 1);
+    }
+
+    #[test]
+    fn test_guess_required_once_nothing_is_safe() {
+        let mut engine = Engine::with_seed(GridSize::new(16, 16), 40, 11);
+        assert!(!guess_required(&engine));
+        engine.reveal(Position(8, 8));
+        let (safe, certainty) = next_step(&engine).unwrap();
+        assert_eq!(certainty, Certainty::Safe);
+        assert!(!guess_required(&engine));
+
+        // a proven safe tile under a wrong flag still counts
+        engine.flag(Position::from_index(safe, 16));
+        assert!(!guess_required(&engine));
+    }
+
+    #[test]
+    fn test_no_guess_boards_clear_without_guessing() {
+        let size = GridSize::new(16, 16);
+        let mut guesses = 0;
+        for seed in 0..10 {
+            let mut plain = Engine::with_seed(size, 40, seed);
+            if !clears(&mut plain, Position(8, 8)) {
+                guesses += 1;
+            }
+
+            let mut no_guess = Engine::with_generation(size, 40, seed, Generation::NoGuess);
+            assert!(clears(&mut no_guess, Position(8, 8)));
+            assert_eq!(
+                no_guess.get_tiles().iter().filter(|t| t.is_bomb()).count(),
+                40
+            );
+        }
+        assert!(guesses > 0);
+    }
+}
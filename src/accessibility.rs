@@ -0,0 +1,245 @@
+use super::game::Engine;
+use std::io;
+use std::process::{Child, Command};
+use std::time::Duration;
+
+// the settings screen cycles the announcement interval through these, None being off
+const INTERVALS: [Option<Duration>; 4] = [
+    None,
+    Some(Duration::from_secs(15)),
+    Some(Duration::from_secs(30)),
+    Some(Duration::from_secs(60)),
+];
+
+// anything that can read a line aloud
+pub trait SpeechBackend {
+    fn speak(&mut self, text: &str) -> io::Result<()>;
+
+    // called every update, for backends that have to clean up after a finished line
+    fn reap(&mut self) {}
+}
+
+// the platform's own speech command, so no speech engine ships with the game
+pub struct SystemSpeech {
+    reading: Option<Child>,
+}
+
+impl SystemSpeech {
+    pub fn new() -> Self {
+        Self { reading: None }
+    }
+}
+
+impl SpeechBackend for SystemSpeech {
+    fn speak(&mut self, text: &str) -> io::Result<()> {
+        // a new announcement cuts off one still being read
+        if let Some(mut child) = self.reading.take() {
+            let _ = child.kill();
+            child.wait()?;
+        }
+        self.reading = Some(speech_command(text).spawn()?);
+        Ok(())
+    }
+
+    // a command that finished is waited on, so it doesn't linger as a zombie until the
+    // next announcement
+    fn reap(&mut self) {
+        if let Some(child) = &mut self.reading {
+            if !matches!(child.try_wait(), Ok(None)) {
+                self.reading = None;
+            }
+        }
+    }
+}
+
+// a line still being read when the game quits is cut off
+impl Drop for SystemSpeech {
+    fn drop(&mut self) {
+        if let Some(mut child) = self.reading.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn speech_command(text: &str) -> Command {
+    let mut command = Command::new("say");
+    command.arg(text);
+    command
+}
+
+#[cfg(target_os = "windows")]
+fn speech_command(text: &str) -> Command {
+    let script = format!(
+        "Add-Type -AssemblyName System.Speech; \
+         (New-Object System.Speech.Synthesis.SpeechSynthesizer).Speak('{}')",
+        text.replace('\'', "''")
+    );
+    let mut command = Command::new("powershell");
+    command.args(["-NoProfile", "-Command", &script]);
+    command
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn speech_command(text: &str) -> Command {
+    let mut command = Command::new("espeak");
+    command.arg(text);
+    command
+}
+
+pub fn next_interval(every: Option<Duration>) -> Option<Duration> {
+    let i = INTERVALS.iter().position(|i| *i == every).unwrap_or(0);
+    INTERVALS[(i + 1) % INTERVALS.len()]
+}
+
+// the counters as a sentence, minutes spelled out past the first
+pub fn announcement(mines_left: i32, elapsed: Duration) -> String {
+    let plural = |n: u64, unit: &str| format!("{} {}{}", n, unit, if n == 1 { "" } else { "s" });
+    let mines = format!("{} left", plural(mines_left.unsigned_abs() as u64, "mine"));
+    let mines = if mines_left < 0 {
+        format!("minus {}", mines)
+    } else {
+        mines
+    };
+    let secs = elapsed.as_secs();
+    let time = match secs / 60 {
+        0 => plural(secs, "second"),
+        minutes => format!(
+            "{} {}",
+            plural(minutes, "minute"),
+            plural(secs % 60, "second")
+        ),
+    };
+    format!("{}, {}", mines, time)
+}
+
+// reads the mines left and the time aloud, every so often while a game runs or when
+// asked with V
+pub struct Announcer {
+    backend: Option<Box<dyn SpeechBackend>>,
+    // game time since the last timed announcement
+    since_last: Duration,
+}
+
+impl Announcer {
+    pub fn new(backend: Box<dyn SpeechBackend>) -> Self {
+        Self {
+            backend: Some(backend),
+            since_last: Duration::ZERO,
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        dt: Duration,
+        every: Option<Duration>,
+        engine: &Engine,
+    ) -> io::Result<()> {
+        if let Some(backend) = &mut self.backend {
+            backend.reap();
+        }
+        let Some(every) = every.filter(|_| engine.is_in_progress()) else {
+            self.since_last = Duration::ZERO;
+            return Ok(());
+        };
+        self.since_last += dt;
+        if self.since_last < every {
+            return Ok(());
+        }
+        self.since_last = Duration::ZERO;
+        self.announce(engine)
+    }
+
+    // a backend that fails is dropped, so the failure is only reported once
+    pub fn announce(&mut self, engine: &Engine) -> io::Result<()> {
+        let Some(backend) = self.backend.as_mut() else {
+            return Ok(());
+        };
+        let spoken = backend.speak(&announcement(engine.mines_left(), engine.elapsed()));
+        if spoken.is_err() {
+            self.backend = None;
+        }
+        spoken
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{GridSize, Position};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    struct Recorder(Rc<RefCell<Vec<String>>>);
+
+    impl SpeechBackend for Recorder {
+        fn speak(&mut self, text: &str) -> io::Result<()> {
+            self.0.borrow_mut().push(text.to_string());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_announcement_reads_the_counters() {
+        assert_eq!(
+            announcement(10, Duration::from_secs(42)),
+            "10 mines left, 42 seconds"
+        );
+        assert_eq!(
+            announcement(1, Duration::from_secs(61)),
+            "1 mine left, 1 minute 1 second"
+        );
+        assert_eq!(
+            announcement(-2, Duration::from_secs(0)),
+            "minus 2 mines left, 0 seconds"
+        );
+    }
+
+    #[test]
+    fn test_announcer_speaks_on_the_interval_while_playing() {
+        let spoken = Rc::new(RefCell::new(Vec::new()));
+        let mut announcer = Announcer::new(Box::new(Recorder(spoken.clone())));
+        let every = Some(Duration::from_secs(15));
+        let mut engine = Engine::with_seed(GridSize::new(9, 9), 10, 3);
+
+        // nothing before the first click
+        announcer
+            .update(Duration::from_secs(20), every, &engine)
+            .unwrap();
+        assert!(spoken.borrow().is_empty());
+
+        engine.reveal(Position(4, 4));
+        for _ in 0..4 {
+            announcer
+                .update(Duration::from_secs(10), every, &engine)
+                .unwrap();
+        }
+        assert_eq!(spoken.borrow().len(), 2);
+        announcer
+            .update(Duration::from_secs(60), None, &engine)
+            .unwrap();
+        assert_eq!(spoken.borrow().len(), 2);
+        announcer.announce(&engine).unwrap();
+        assert_eq!(spoken.borrow().len(), 3);
+
+        assert_eq!(next_interval(None), every);
+        assert_eq!(next_interval(Some(Duration::from_secs(60))), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_finished_speech_is_reaped() {
+        let mut speech = SystemSpeech {
+            reading: Some(Command::new("true").spawn().unwrap()),
+        };
+        for _ in 0..100 {
+            speech.reap();
+            if speech.reading.is_none() {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        panic!("the finished command was never waited on");
+    }
+}
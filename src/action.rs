@@ -0,0 +1,306 @@
+use super::game::{MoveKind, Position};
+use super::hud::ModeEntry;
+use super::scene::{
+    CustomOption, GameOverOption, PauseOption, ProfileOption, Scene, SettingsOption, TitleOption,
+};
+use super::tabs::TabKey;
+use ggez::input::gamepad::gilrs::Button;
+use ggez::input::keyboard::KeyCode;
+
+// everything the player can ask for, whichever device it came from;
+// MainState::dispatch is the only place that acts on them
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Action {
+    Reveal(Position),
+    Flag(Position),
+    // reveals the neighbours of a number once its flags are all placed
+    Chord(Position),
+    Annotate(Position),
+    // the keyboard cursor: moved a tile at a time, then acted on where it rests
+    MoveCursor(i32, i32),
+    RevealAtCursor,
+    FlagAtCursor,
+    // the face button: the next round or stage, otherwise the same settings again
+    Restart,
+    NewGame(&'static str),
+    // an entry of the HUD's mode list
+    SelectMode(ModeEntry),
+    // picks up a board kept open in a HUD tab
+    SwitchTab(TabKey),
+    GoTo(Position),
+    // writes the current game to the profile's exports as a save or replay
+    Export,
+    // board codes through the system clipboard
+    CopyCode,
+    PasteCode,
+    // opens the timeline of a finished game
+    Analyze,
+    // steps the timeline by a number of moves, or jumps to a fraction of the game
+    Scrub(i32),
+    ScrubTo(f32),
+    // a move in the analysis, played on an alternate line from the moment shown
+    Branch(MoveKind, Position),
+    Pause,
+    Resume,
+    // escape in a game: the pause menu over the dimmed board
+    OpenPauseMenu,
+    SelectPause(PauseOption),
+    // a button on the panel shown when a game ends
+    SelectGameOver(GameOverOption),
+    OpenCommandBar,
+    // text entry in the command bar and profile name field
+    Type(char),
+    Erase,
+    Submit,
+    // leaves the top scene; on the title screen it quits
+    Back,
+    ConfirmQuit(bool),
+    AnswerUndo(bool),
+    // takes back the last move
+    Undo,
+    SelectTitle(TitleOption),
+    SelectProfile(ProfileOption),
+    SelectCustom(CustomOption),
+    SelectSettings(SettingsOption),
+    // an answer to the current first run question, by index
+    SelectOnboarding(usize),
+    SetDensity(u32),
+    // steps the custom density slider
+    AdjustDensity(i32),
+    PlayWeekly,
+    ToggleDiagnostics,
+    ToggleOpponentPanel,
+    // counts clicks against the board's 3BV, from the next board on
+    ToggleTrainer,
+    // tints the board by what the solver knows
+    ShowHint,
+    // marks one tile the solver can prove, from the HUD button
+    HintTile,
+    // reads the mines left and the time aloud
+    Announce,
+    DismissErrors,
+    // keyboard and gamepad navigation of menu buttons
+    MoveFocus(i32),
+    // presses the focused button
+    Activate,
+}
+
+// the default key bindings
+impl Action {
+    // a menu button pressed, which clicks on the UI bus
+    pub fn is_selection(&self) -> bool {
+        matches!(
+            self,
+            Action::SelectMode(_)
+                | Action::SelectPause(_)
+                | Action::SelectGameOver(_)
+                | Action::SelectTitle(_)
+                | Action::SelectProfile(_)
+                | Action::SelectCustom(_)
+                | Action::SelectSettings(_)
+                | Action::SelectOnboarding(_)
+                | Action::ConfirmQuit(_)
+                | Action::AnswerUndo(_)
+        )
+    }
+}
+
+pub fn key_action(scene: Scene, key: Option<KeyCode>) -> Option<Action> {
+    if key == Some(KeyCode::F10) {
+        return Some(Action::ToggleDiagnostics);
+    }
+    // any other key ends a pause
+    if scene == Scene::Paused {
+        return Some(Action::Resume);
+    }
+    let action = match (scene, key?) {
+        (Scene::Game, KeyCode::P) => Action::Pause,
+        (Scene::Game, KeyCode::Escape) => Action::OpenPauseMenu,
+        (Scene::Game, KeyCode::Tab) => Action::ToggleOpponentPanel,
+        (Scene::Game, KeyCode::H) => Action::ShowHint,
+        // A is a cursor key, so the timeline has T
+        (Scene::Game, KeyCode::T) => Action::Analyze,
+        (Scene::Game, KeyCode::Up | KeyCode::W) => Action::MoveCursor(0, -1),
+        (Scene::Game, KeyCode::Down | KeyCode::S) => Action::MoveCursor(0, 1),
+        (Scene::Game, KeyCode::Left | KeyCode::A) => Action::MoveCursor(-1, 0),
+        (Scene::Game, KeyCode::Right | KeyCode::D) => Action::MoveCursor(1, 0),
+        (Scene::Game, KeyCode::Space | KeyCode::Return) => Action::RevealAtCursor,
+        (Scene::Game, KeyCode::F) => Action::FlagAtCursor,
+        (Scene::Game, KeyCode::R) => Action::Restart,
+        (Scene::Game, KeyCode::V) => Action::Announce,
+        (Scene::Analysis, KeyCode::Left) => Action::Scrub(-1),
+        (Scene::Analysis, KeyCode::Right) => Action::Scrub(1),
+        (Scene::Analysis, KeyCode::Home) => Action::ScrubTo(0.0),
+        (Scene::Analysis, KeyCode::End) => Action::ScrubTo(1.0),
+        // back to the game as it was played
+        (Scene::Analysis, KeyCode::Back) => Action::Scrub(0),
+        (Scene::Command | Scene::Profiles | Scene::NameEntry, KeyCode::Return) => Action::Submit,
+        (Scene::Command | Scene::Profiles | Scene::NameEntry, KeyCode::Back) => Action::Erase,
+        (Scene::QuitConfirm, KeyCode::Y) => Action::ConfirmQuit(true),
+        (Scene::QuitConfirm, KeyCode::N) => Action::ConfirmQuit(false),
+        (Scene::UndoPrompt, KeyCode::Y) => Action::AnswerUndo(true),
+        (Scene::UndoPrompt, KeyCode::N) => Action::AnswerUndo(false),
+        (Scene::Custom, KeyCode::Left) => Action::AdjustDensity(-1),
+        (Scene::Custom, KeyCode::Right) => Action::AdjustDensity(1),
+        (scene, KeyCode::Tab | KeyCode::Down | KeyCode::Right) if scene.is_menu() => {
+            Action::MoveFocus(1)
+        }
+        (scene, KeyCode::Up | KeyCode::Left) if scene.is_menu() => Action::MoveFocus(-1),
+        (scene, KeyCode::Return) if scene.is_menu() => Action::Activate,
+        (_, KeyCode::Escape) => Action::Back,
+        _ => return None,
+    };
+    Some(action)
+}
+
+// shortcuts held with ctrl; anything else falls back to the plain key
+pub fn ctrl_key_action(scene: Scene, key: Option<KeyCode>) -> Option<Action> {
+    match (scene, key?) {
+        (Scene::Game, KeyCode::C) => Some(Action::CopyCode),
+        (Scene::Game, KeyCode::Z) => Some(Action::Undo),
+        (Scene::UndoPrompt, KeyCode::Z) => Some(Action::AnswerUndo(true)),
+        (Scene::Title | Scene::Calendar | Scene::Custom, KeyCode::V) => Some(Action::PasteCode),
+        // no pasting over a game in progress, nor reading the counters by accident
+        (Scene::Game, KeyCode::V) => None,
+        _ => key_action(scene, key),
+    }
+}
+
+pub fn button_action(scene: Scene, button: Button) -> Option<Action> {
+    if scene == Scene::Paused {
+        return Some(Action::Resume);
+    }
+    let action = match (scene, button) {
+        (Scene::Game, Button::Start) => Action::Pause,
+        // the name offered from the profile is kept as it is
+        (Scene::NameEntry, Button::South) => Action::Submit,
+        (Scene::Analysis, Button::DPadLeft) => Action::Scrub(-1),
+        (Scene::Analysis, Button::DPadRight) => Action::Scrub(1),
+        (Scene::Custom, Button::DPadLeft) => Action::AdjustDensity(-1),
+        (Scene::Custom, Button::DPadRight) => Action::AdjustDensity(1),
+        (scene, Button::DPadDown | Button::DPadRight) if scene.is_menu() => Action::MoveFocus(1),
+        (scene, Button::DPadUp | Button::DPadLeft) if scene.is_menu() => Action::MoveFocus(-1),
+        (scene, Button::South) if scene.is_menu() => Action::Activate,
+        (_, Button::East) => Action::Back,
+        _ => return None,
+    };
+    Some(action)
+}
+
+pub fn text_action(scene: Scene, character: char) -> Option<Action> {
+    match scene {
+        Scene::Game if character == ':' => Some(Action::OpenCommandBar),
+        Scene::Command | Scene::Profiles | Scene::NameEntry => Some(Action::Type(character)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_depend_on_scene() {
+        assert_eq!(
+            key_action(Scene::Game, Some(KeyCode::P)),
+            Some(Action::Pause)
+        );
+        assert_eq!(key_action(Scene::Title, Some(KeyCode::P)), None);
+        assert_eq!(
+            key_action(Scene::QuitConfirm, Some(KeyCode::Return)),
+            Some(Action::Activate)
+        );
+        assert_eq!(
+            key_action(Scene::QuitConfirm, Some(KeyCode::Y)),
+            Some(Action::ConfirmQuit(true))
+        );
+        assert_eq!(
+            key_action(Scene::Title, Some(KeyCode::Down)),
+            Some(Action::MoveFocus(1))
+        );
+        assert_eq!(
+            key_action(Scene::Custom, Some(KeyCode::Left)),
+            Some(Action::AdjustDensity(-1))
+        );
+        assert_eq!(
+            key_action(Scene::Analysis, Some(KeyCode::Left)),
+            Some(Action::Scrub(-1))
+        );
+        assert_eq!(
+            key_action(Scene::Profiles, Some(KeyCode::Return)),
+            Some(Action::Submit)
+        );
+        assert_eq!(
+            key_action(Scene::Calendar, Some(KeyCode::Escape)),
+            Some(Action::Back)
+        );
+        assert_eq!(key_action(Scene::Game, None), None);
+        assert_eq!(
+            key_action(Scene::Game, Some(KeyCode::Escape)),
+            Some(Action::OpenPauseMenu)
+        );
+        assert_eq!(
+            key_action(Scene::PauseMenu, Some(KeyCode::Escape)),
+            Some(Action::Back)
+        );
+        assert_eq!(
+            key_action(Scene::Game, Some(KeyCode::A)),
+            Some(Action::MoveCursor(-1, 0))
+        );
+        assert_eq!(
+            key_action(Scene::Game, Some(KeyCode::Space)),
+            Some(Action::RevealAtCursor)
+        );
+        assert_eq!(
+            key_action(Scene::Game, Some(KeyCode::T)),
+            Some(Action::Analyze)
+        );
+        assert_eq!(
+            ctrl_key_action(Scene::Custom, Some(KeyCode::V)),
+            Some(Action::PasteCode)
+        );
+        assert_eq!(ctrl_key_action(Scene::Game, Some(KeyCode::V)), None);
+        assert_eq!(
+            key_action(Scene::Game, Some(KeyCode::V)),
+            Some(Action::Announce)
+        );
+        assert_eq!(
+            ctrl_key_action(Scene::Game, Some(KeyCode::Z)),
+            Some(Action::Undo)
+        );
+        assert_eq!(
+            ctrl_key_action(Scene::Game, Some(KeyCode::P)),
+            Some(Action::Pause)
+        );
+        assert_eq!(key_action(Scene::Paused, None), Some(Action::Resume));
+        assert_eq!(
+            key_action(Scene::Paused, Some(KeyCode::F10)),
+            Some(Action::ToggleDiagnostics)
+        );
+    }
+
+    #[test]
+    fn gamepad_navigates_menus() {
+        assert_eq!(
+            button_action(Scene::Title, Button::DPadUp),
+            Some(Action::MoveFocus(-1))
+        );
+        assert_eq!(
+            button_action(Scene::Calendar, Button::South),
+            Some(Action::Activate)
+        );
+        assert_eq!(button_action(Scene::Game, Button::South), None);
+        assert_eq!(
+            button_action(Scene::Game, Button::Start),
+            Some(Action::Pause)
+        );
+    }
+
+    #[test]
+    fn colon_opens_command_bar_only_in_game() {
+        assert_eq!(text_action(Scene::Game, ':'), Some(Action::OpenCommandBar));
+        assert_eq!(text_action(Scene::Game, 'a'), None);
+        assert_eq!(text_action(Scene::Command, ':'), Some(Action::Type(':')));
+        assert_eq!(text_action(Scene::Title, ':'), None);
+    }
+}
@@ -0,0 +1,317 @@
+use super::game::{Engine, Mark, Move, MoveKind, Position, TileState};
+use super::replay::Replay;
+use super::solver::{self, Certainty};
+use std::cmp::{Ordering, Reverse};
+use std::time::Duration;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Verdict {
+    Better,
+    Even,
+    Worse,
+}
+
+impl Verdict {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Verdict::Better => "BETTER",
+            Verdict::Even => "EVEN",
+            Verdict::Worse => "WORSE",
+        }
+    }
+}
+
+// how a line of play went after the point it starts from, each move judged by what
+// the solver could prove on the board before it
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct LineReport {
+    // moves on tiles the numbers already proved
+    pub sound: usize,
+    pub guesses: usize,
+    pub won: bool,
+    pub lost: bool,
+}
+
+impl LineReport {
+    // rates the move, then plays it; unflagging and pressing open tiles aren't rated
+    fn play(&mut self, engine: &mut Engine, kind: MoveKind, pos: Position) {
+        if engine.is_won() || engine.is_lost() {
+            return;
+        }
+        let index = pos.to_index(engine.size().width);
+        if let TileState::Block(Mark::Unmarked | Mark::Questioned) = engine.get_tiles()[index].state
+        {
+            let proof = match kind {
+                MoveKind::Reveal => Certainty::Safe,
+                MoveKind::Flag => Certainty::Mine,
+            };
+            // the first reveal can never hit a mine
+            if engine.moves().is_empty() || solver::analyze(engine)[index] == proof {
+                self.sound += 1;
+            } else {
+                self.guesses += 1;
+            }
+        }
+        match kind {
+            MoveKind::Reveal => engine.reveal(pos),
+            MoveKind::Flag => engine.flag(pos),
+        }
+        self.won = engine.is_won();
+        self.lost = engine.is_lost();
+    }
+
+    // surviving beats losing, winning beats surviving, and then fewer guesses win
+    pub fn compare(&self, other: &LineReport) -> Verdict {
+        let rank = |line: &LineReport| (!line.lost, line.won, Reverse(line.guesses));
+        match rank(self).cmp(&rank(other)) {
+            Ordering::Greater => Verdict::Better,
+            Ordering::Equal => Verdict::Even,
+            Ordering::Less => Verdict::Worse,
+        }
+    }
+}
+
+// a different line taken over from some point of the game, measured against what was
+// really played from there
+pub struct Branch {
+    engine: Engine,
+    fork: usize,
+    report: LineReport,
+    original: LineReport,
+}
+
+impl Branch {
+    // the move the two lines part at
+    pub fn fork(&self) -> usize {
+        self.fork
+    }
+
+    pub fn report(&self) -> &LineReport {
+        &self.report
+    }
+
+    pub fn original(&self) -> &LineReport {
+        &self.original
+    }
+
+    pub fn verdict(&self) -> Verdict {
+        self.report.compare(&self.original)
+    }
+}
+
+// a finished game rebuilt from its move log, shown as it looked at any point in time
+pub struct Analysis {
+    replay: Replay,
+    engine: Engine,
+    // how many of the moves the board shows
+    position: usize,
+    branch: Option<Branch>,
+}
+
+impl Analysis {
+    // starts on the final board
+    pub fn new(engine: &Engine) -> Self {
+        let replay = Replay::from_engine(engine);
+        let mut analysis = Self {
+            engine: replay.fresh_engine(),
+            replay,
+            position: 0,
+            branch: None,
+        };
+        analysis.seek_time(analysis.duration());
+        analysis
+    }
+
+    // the alternate line while there is one
+    pub fn engine(&self) -> &Engine {
+        self.branch
+            .as_ref()
+            .map_or(&self.engine, |branch| &branch.engine)
+    }
+
+    pub fn branch(&self) -> Option<&Branch> {
+        self.branch.as_ref()
+    }
+
+    // the first move on the board shown takes over the game from there
+    pub fn play(&mut self, kind: MoveKind, pos: Position) {
+        let branch = self.branch.get_or_insert_with(|| {
+            let mut original = LineReport::default();
+            let mut engine = replayed(&self.replay, self.position);
+            for m in &self.replay.moves[self.position..] {
+                original.play(&mut engine, m.kind, m.pos);
+            }
+            let mut engine = replayed(&self.replay, self.position);
+            engine.tick(self.engine.elapsed().saturating_sub(engine.elapsed()));
+            Branch {
+                engine,
+                fork: self.position,
+                report: LineReport::default(),
+                original,
+            }
+        });
+        branch.report.play(&mut branch.engine, kind, pos);
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.replay.time
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn moves(&self) -> &[Move] {
+        &self.replay.moves
+    }
+
+    pub fn num_moves(&self) -> usize {
+        self.replay.moves.len()
+    }
+
+    // the scrubber's fill, 0.0 at the first move and 1.0 at the end of the game
+    pub fn progress(&self) -> f32 {
+        if self.duration().is_zero() {
+            return 1.0;
+        }
+        self.engine.elapsed().as_secs_f32() / self.duration().as_secs_f32()
+    }
+
+    // the point of the game a fraction of the way along the scrubber
+    pub fn time_at(&self, fraction: f32) -> Duration {
+        self.duration().mul_f32(fraction.clamp(0.0, 1.0))
+    }
+
+    // moves made on the same tick, like a chord, are only ever shown together here
+    pub fn seek_time(&mut self, time: Duration) {
+        let time = time.min(self.duration());
+        self.rebuild(self.replay.moves.partition_point(|m| m.time <= time));
+        self.engine.tick(time.saturating_sub(self.engine.elapsed()));
+    }
+
+    // one move back or forward, with the clock on that move; a step of 0 just leaves
+    // the alternate line
+    pub fn step(&mut self, step: i32) {
+        let position = self
+            .position
+            .saturating_add_signed(step as isize)
+            .min(self.num_moves());
+        self.rebuild(position);
+    }
+
+    // seeking always goes back to the line really played
+    fn rebuild(&mut self, position: usize) {
+        self.engine = replayed(&self.replay, position);
+        self.position = position;
+        self.branch = None;
+    }
+}
+
+// the engine can't take moves back, so every state is played again from the start
+pub fn replayed(replay: &Replay, position: usize) -> Engine {
+    let mut engine = replay.fresh_engine();
+    for m in &replay.moves[..position] {
+        engine.tick(m.time.saturating_sub(engine.elapsed()));
+        engine.replay_move(m);
+    }
+    engine
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{GridSize, Position};
+
+    fn finished_engine() -> Engine {
+        let mut engine = Engine::with_seed(GridSize::new(8, 8), 10, 42);
+        engine.tick(Duration::from_secs(1));
+        engine.reveal(Position(3, 3));
+        let bombs = (0..64)
+            .filter(|&i| engine.get_tiles()[i].is_bomb())
+            .collect::<Vec<_>>();
+        engine.tick(Duration::from_secs(2));
+        engine.flag(Position::from_index(bombs[0], 8));
+        engine.tick(Duration::from_secs(1));
+        engine.reveal(Position::from_index(bombs[1], 8));
+        engine
+    }
+
+    #[test]
+    fn test_scrubbing_rebuilds_each_state() {
+        let engine = finished_engine();
+        let mut analysis = Analysis::new(&engine);
+        assert_eq!(analysis.position(), 3);
+        assert!(analysis.engine().is_lost());
+        assert_eq!(analysis.engine().elapsed(), engine.elapsed());
+        assert_eq!(analysis.progress(), 1.0);
+
+        analysis.seek_time(analysis.time_at(0.625));
+        assert_eq!(analysis.position(), 1);
+        assert_eq!(analysis.engine().elapsed(), Duration::from_millis(2500));
+        assert!(analysis.engine().is_in_progress());
+        assert!(analysis.engine().get_tiles()[27].is_revealed());
+
+        analysis.step(1);
+        assert_eq!(analysis.position(), 2);
+        assert_eq!(analysis.engine().elapsed(), Duration::from_secs(3));
+        assert_eq!(analysis.engine().mines_left(), 9);
+
+        analysis.step(-5);
+        assert_eq!(analysis.position(), 0);
+        assert!(!analysis.engine().get_tiles()[27].is_revealed());
+        analysis.step(10);
+        assert_eq!(analysis.position(), analysis.num_moves());
+    }
+
+    #[test]
+    fn test_branch_is_judged_against_the_game() {
+        let mut analysis = Analysis::new(&finished_engine());
+        analysis.step(-1);
+        let safe = (0..64)
+            .find(|&i| {
+                let tile = &analysis.engine().get_tiles()[i];
+                !tile.is_revealed() && !tile.is_bomb()
+            })
+            .unwrap();
+        analysis.play(MoveKind::Reveal, Position::from_index(safe, 8));
+
+        let branch = analysis.branch().unwrap();
+        assert_eq!(branch.fork(), 2);
+        assert_eq!(branch.report().sound + branch.report().guesses, 1);
+        assert!(branch.original().lost);
+        assert_eq!(branch.original().guesses, 1);
+        // not stepping on the mine is already an improvement
+        assert_eq!(branch.verdict(), Verdict::Better);
+        assert!(analysis.engine().get_tiles()[safe].is_revealed());
+
+        analysis.step(0);
+        assert!(analysis.branch().is_none());
+        assert_eq!(analysis.position(), 2);
+        assert!(!analysis.engine().get_tiles()[safe].is_revealed());
+    }
+
+    #[test]
+    fn test_lines_rank_by_outcome_then_guesses() {
+        let won = LineReport {
+            won: true,
+            guesses: 3,
+            ..Default::default()
+        };
+        let careful = LineReport {
+            sound: 4,
+            ..Default::default()
+        };
+        let lucky = LineReport {
+            guesses: 2,
+            ..Default::default()
+        };
+        let lost = LineReport {
+            lost: true,
+            ..Default::default()
+        };
+        assert_eq!(won.compare(&careful), Verdict::Better);
+        assert_eq!(careful.compare(&lucky), Verdict::Better);
+        assert_eq!(lost.compare(&lucky), Verdict::Worse);
+        assert_eq!(careful.compare(&careful), Verdict::Even);
+    }
+}
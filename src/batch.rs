@@ -0,0 +1,156 @@
+use super::draw::{Viewport, TILE_PX};
+use super::game::Engine;
+use super::sprites::{SpriteKey, SpriteManager};
+use ggez::graphics::{Canvas, DrawParam, InstanceArray, Rect};
+use ggez::Context;
+
+// the board's tiles as one instance array over the sprite atlas, so a large board costs a
+// single draw call rather than one per tile; the array is only rebuilt when a tile
+// changes sprite or the board is scaled or scrolled
+pub struct TileBatch {
+    // the sprite of every tile at the last rebuild
+    keys: Vec<SpriteKey>,
+    // tile size and board origin at the last rebuild
+    placement: [f32; 4],
+    array: Option<InstanceArray>,
+    // tiles whose sprite failed to load, left for the caller to draw in safe mode
+    missing: Vec<usize>,
+}
+
+impl TileBatch {
+    pub fn new() -> Self {
+        Self {
+            keys: Vec::new(),
+            placement: [0.0; 4],
+            array: None,
+            missing: Vec::new(),
+        }
+    }
+
+    // returns how many draw calls it took
+    pub fn draw(
+        &mut self,
+        ctx: &mut Context,
+        canvas: &mut Canvas,
+        viewport: &Viewport,
+        engine: &Engine,
+        sprite_manager: &SpriteManager,
+    ) -> usize {
+        let keys = tile_keys(viewport, engine);
+        let placement = placement(viewport);
+        if self.is_stale(&keys, placement) {
+            self.rebuild(ctx, viewport, sprite_manager, keys, placement);
+        }
+        match &self.array {
+            Some(array) => {
+                canvas.draw(array, DrawParam::default());
+                1
+            }
+            None => 0,
+        }
+    }
+
+    pub fn missing(&self) -> &[usize] {
+        &self.missing
+    }
+
+    fn is_stale(&self, keys: &[SpriteKey], placement: [f32; 4]) -> bool {
+        self.keys != keys || self.placement != placement
+    }
+
+    fn rebuild(
+        &mut self,
+        ctx: &mut Context,
+        viewport: &Viewport,
+        sprite_manager: &SpriteManager,
+        keys: Vec<SpriteKey>,
+        placement: [f32; 4],
+    ) {
+        let (tile_w, tile_h) = viewport.tile_size;
+        let mut atlas = None;
+        let mut params = Vec::new();
+        self.missing.clear();
+        for (i, key) in keys.iter().enumerate() {
+            let Some((image, uv)) = sprite_manager.get_uv(*key) else {
+                self.missing.push(i);
+                continue;
+            };
+            let Rect { x, y, .. } = viewport.tile_rect(i);
+            params.push(DrawParam::new().src(uv).dest_rect(Rect::new(
+                x,
+                y,
+                tile_w / TILE_PX,
+                tile_h / TILE_PX,
+            )));
+            atlas.get_or_insert(image);
+        }
+
+        self.array = atlas.map(|atlas| {
+            let mut array = InstanceArray::new(ctx, atlas);
+            array.set(params);
+            array
+        });
+        self.keys = keys;
+        self.placement = placement;
+    }
+}
+
+fn tile_keys(viewport: &Viewport, engine: &Engine) -> Vec<SpriteKey> {
+    (0..engine.get_tiles().len())
+        .map(|i| viewport.tile_sprite(engine, i).into())
+        .collect()
+}
+
+fn placement(viewport: &Viewport) -> [f32; 4] {
+    [
+        viewport.tile_size.0,
+        viewport.tile_size.1,
+        viewport.origin.x,
+        viewport.origin.y,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{BoardSettings, ScreenPoint};
+    use crate::game::{GridSize, Position};
+
+    #[test]
+    fn test_batch_goes_stale_only_when_the_board_changes() {
+        let board_settings = BoardSettings::builder()
+            .size(9, 9)
+            .mines(10)
+            .build()
+            .unwrap();
+        let mut viewport = Viewport {
+            screen_dim: (288.0, 288.0),
+            hud_h: 0.0,
+            tile_size: (32.0, 32.0),
+            origin: ScreenPoint::new(0.0, 0.0),
+            board_settings: &board_settings,
+            pressed_tile: None,
+            chord_tiles: &[],
+            cursor: None,
+            cascading: &[],
+        };
+        let mut engine = Engine::with_seed(GridSize::new(9, 9), 10, 3);
+        let mut batch = TileBatch::new();
+        batch.keys = tile_keys(&viewport, &engine);
+        batch.placement = placement(&viewport);
+        assert!(!batch.is_stale(&tile_keys(&viewport, &engine), placement(&viewport)));
+
+        engine.reveal(Position(4, 4));
+        assert!(batch.is_stale(&tile_keys(&viewport, &engine), placement(&viewport)));
+        batch.keys = tile_keys(&viewport, &engine);
+
+        // holding a tile down or scrolling moves sprites without a move being made
+        viewport.pressed_tile = tile_keys(&viewport, &engine)
+            .iter()
+            .position(|key| *key == SpriteKey::Block);
+        assert!(batch.is_stale(&tile_keys(&viewport, &engine), placement(&viewport)));
+        viewport.pressed_tile = None;
+        viewport.origin = ScreenPoint::new(0.0, -32.0);
+        assert!(batch.is_stale(&tile_keys(&viewport, &engine), placement(&viewport)));
+    }
+}
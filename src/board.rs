@@ -1,137 +1,343 @@
-use super::game::Position;
+use super::config::HudScale;
+use super::game::{GridSize, Position};
 use ggez::graphics::Rect;
+pub use rust_sweeper_core::board::*;
 
-#[derive(Clone)]
-pub struct BoardSettings {
-    pub w: f32,
-    pub h: f32,
-    num_bombs: usize,
-    pub screen_width: f32,
-    pub screen_height: f32,
-}
-
-impl BoardSettings {
-    pub const fn new(
-        w: f32,
-        h: f32,
-        num_bombs: usize,
-        screen_width: f32,
-        screen_height: f32,
-    ) -> Self {
+// HUD height in pixels at a scale of 1.0, matches the easy board's original layout
+pub const HUD_BASE_HEIGHT: f32 = 90.0;
+// the HUD never takes more than this share of the window when auto-scaling
+pub const HUD_MAX_HEIGHT_PERCENT: f32 = 0.2;
+
+pub fn hud_height(screen_h: f32, hud_scale: &HudScale) -> f32 {
+    match hud_scale {
+        HudScale::Auto => HUD_BASE_HEIGHT.min(screen_h * HUD_MAX_HEIGHT_PERCENT),
+        HudScale::Fixed(scale) => (HUD_BASE_HEIGHT * scale).min(screen_h * 0.5),
+    }
+}
+
+// room for the row and column labels left of and above the board
+pub const COORDINATE_GUTTER: f32 = 24.0;
+
+// below this tiles can't be read; boards that don't fit at it scroll instead
+pub const MIN_TILE_SIZE: f32 = 16.0;
+
+pub fn calculate_tile_size(w: f32, h: f32, hud_h: f32, gutter: f32, size: GridSize) -> (f32, f32) {
+    let game_area_width = w - gutter;
+    let game_area_height = h - hud_h - gutter;
+    (
+        (game_area_width / size.width as f32).max(MIN_TILE_SIZE),
+        (game_area_height / size.height as f32).max(MIN_TILE_SIZE),
+    )
+}
+
+// how far the board runs past the window on each axis; nothing to scroll when it fits
+pub fn board_overflow(
+    w: f32,
+    h: f32,
+    hud_h: f32,
+    gutter: f32,
+    tile_size: (f32, f32),
+    size: GridSize,
+) -> (f32, f32) {
+    (
+        (size.width as f32 * tile_size.0 - (w - gutter)).max(0.0),
+        (size.height as f32 * tile_size.1 - (h - hud_h - gutter)).max(0.0),
+    )
+}
+
+// square tiles at the largest whole-number scale of the sprites that fits, so
+// every sprite pixel covers the same number of screen pixels; tiles smaller
+// than a sprite are only rounded to whole pixels
+pub fn pixel_perfect_tile_size(tile_size: (f32, f32), sprite_px: f32) -> (f32, f32) {
+    let fit = tile_size.0.min(tile_size.1);
+    let size = if fit >= sprite_px {
+        (fit / sprite_px).floor() * sprite_px
+    } else {
+        fit.floor().max(1.0)
+    };
+    (size, size)
+}
+
+// the opponent panel hangs off the right edge just below the HUD, over the board
+pub const OPPONENT_PANEL_WIDTH: f32 = 180.0;
+pub const OPPONENT_PANEL_HEIGHT: f32 = 110.0;
+// a collapsed panel leaves a tab to open it again
+pub const OPPONENT_TAB_WIDTH: f32 = 16.0;
+
+pub fn opponent_panel_rect(screen_dim: (f32, f32), hud_h: f32, collapsed: bool) -> Rect {
+    let scale = hud_h / HUD_BASE_HEIGHT;
+    let w = if collapsed {
+        OPPONENT_TAB_WIDTH
+    } else {
+        OPPONENT_PANEL_WIDTH
+    } * scale;
+    Rect::new(
+        screen_dim.0 - w,
+        hud_h + 8.0 * scale,
+        w,
+        OPPONENT_PANEL_HEIGHT * scale,
+    )
+}
+
+// the error banner spans the window just below the HUD, one line per error plus a hint
+pub const BANNER_LINE_HEIGHT: f32 = 22.0;
+
+pub fn error_banner_rect(screen_dim: (f32, f32), hud_h: f32, lines: usize) -> Rect {
+    let scale = hud_h / HUD_BASE_HEIGHT;
+    Rect::new(
+        0.0,
+        hud_h,
+        screen_dim.0,
+        BANNER_LINE_HEIGHT * scale * (lines + 1) as f32,
+    )
+}
+
+// the analysis timeline runs along the bottom of the window, over the last board rows
+pub const SCRUBBER_HEIGHT: f32 = 28.0;
+
+pub fn scrubber_rect(screen_dim: (f32, f32), hud_h: f32) -> Rect {
+    let scale = hud_h / HUD_BASE_HEIGHT;
+    let h = SCRUBBER_HEIGHT * scale;
+    Rect::new(h, screen_dim.1 - h * 1.5, screen_dim.0 - h * 2.0, h)
+}
+
+// how much bigger the tiles are under the magnifier
+pub const LENS_ZOOM: f32 = 2.5;
+// a scissor rect can only be a rectangle, so the round lens is drawn as this many slices
+const LENS_STRIPS: usize = 24;
+
+// the magnifier held over the board, centred on the pointer
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Lens {
+    pub center: ScreenPoint,
+    pub radius: f32,
+}
+
+impl Lens {
+    pub fn new(center: ScreenPoint, screen_dim: (f32, f32)) -> Self {
         Self {
-            w,
-            h,
-            num_bombs,
-            screen_width,
-            screen_height,
+            center,
+            radius: screen_dim.0.min(screen_dim.1) * 0.18,
         }
     }
 
-    pub fn dimensions(&self) -> (f32, f32) {
-        (self.w, self.h)
+    // where the board starts once zoomed, so the tile under the pointer stays under it
+    pub fn origin(&self, origin: ScreenPoint) -> ScreenPoint {
+        ScreenPoint::new(
+            self.center.x - (self.center.x - origin.x) * LENS_ZOOM,
+            self.center.y - (self.center.y - origin.y) * LENS_ZOOM,
+        )
     }
 
-    pub fn num_bombs(&self) -> usize {
-        self.num_bombs
+    // the circle cut into horizontal slices that stay inside the window
+    pub fn strips(&self, screen_dim: (f32, f32)) -> Vec<Rect> {
+        let h = self.radius * 2.0 / LENS_STRIPS as f32;
+        let mut strips = Vec::new();
+        for i in 0..LENS_STRIPS {
+            let top = self.center.y - self.radius + i as f32 * h;
+            let mid = top + h / 2.0 - self.center.y;
+            let half_w = (self.radius * self.radius - mid * mid).max(0.0).sqrt();
+            let left = (self.center.x - half_w).max(0.0);
+            let right = (self.center.x + half_w).min(screen_dim.0);
+            let (top, bottom) = (top.max(0.0), (top + h).min(screen_dim.1));
+            if right > left && bottom > top {
+                strips.push(Rect::new(left, top, right - left, bottom - top));
+            }
+        }
+        strips
     }
+}
 
-    pub fn screen_dimensions(&self) -> (f32, f32) {
-        (self.screen_width, self.screen_height)
-    }
+// a position in window pixels
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ScreenPoint {
+    pub x: f32,
+    pub y: f32,
+}
 
-    pub fn easy() -> Self {
-        EASY_BOARD.clone()
+impl ScreenPoint {
+    pub const fn new(x: f32, y: f32) -> Self {
+        Self { x, y }
     }
+}
 
-    pub fn medium() -> Self {
-        MEDIUM_BOARD.clone()
-    }
+// a tile, counted in columns and rows from the board's top left corner
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CellCoord {
+    pub col: usize,
+    pub row: usize,
+}
 
-    pub fn hard() -> Self {
-        HARD_BOARD.clone()
+impl CellCoord {
+    pub fn from_index(index: usize, board_width: usize) -> Self {
+        Self {
+            col: index % board_width,
+            row: index / board_width,
+        }
     }
-}
 
-pub const EASY_SCREEN_WIDTH: f32 = 800.0;
-pub const EASY_SCREEN_HEIGHT: f32 = 600.0;
+    pub fn to_index(self, board_width: usize) -> usize {
+        self.row * board_width + self.col
+    }
 
-pub const MEDIUM_SCREEN_WIDTH: f32 = 1000.0;
-pub const MEDIUM_SCREEN_HEIGHT: f32 = 700.0;
+    pub fn to_position(self) -> Position {
+        Position(self.col as i32, self.row as i32)
+    }
 
-pub const HARD_SCREEN_WIDTH: f32 = 1200.0;
-pub const HARD_SCREEN_HEIGHT: f32 = 800.0;
+    // the keyboard cursor's next tile; it stops at the edges rather than wrapping
+    pub fn step(&self, dx: i32, dy: i32, size: GridSize) -> Self {
+        let clamp = |value: usize, delta: i32, len: usize| {
+            (value as i64 + delta as i64).clamp(0, len as i64 - 1) as usize
+        };
+        Self {
+            col: clamp(self.col, dx, size.width),
+            row: clamp(self.row, dy, size.height),
+        }
+    }
+}
 
-pub const EASY_BOARD: BoardSettings =
-    BoardSettings::new(8.0, 8.0, 10, EASY_SCREEN_WIDTH, EASY_SCREEN_HEIGHT);
-pub const MEDIUM_BOARD: BoardSettings =
-    BoardSettings::new(16.0, 16.0, 40, MEDIUM_SCREEN_WIDTH, MEDIUM_SCREEN_HEIGHT);
-pub const HARD_BOARD: BoardSettings =
-    BoardSettings::new(30.0, 16.0, 99, HARD_SCREEN_WIDTH, HARD_SCREEN_HEIGHT);
+// origin is the screen position of the board's top left corner
+pub fn screen_to_cell(
+    point: ScreenPoint,
+    origin: ScreenPoint,
+    tile_size: (f32, f32),
+    size: GridSize,
+) -> Option<CellCoord> {
+    let adjusted_x = point.x - origin.x;
+    let adjusted_y = point.y - origin.y;
 
-pub const MENU_HEIGHT_PERCENT: f32 = 0.15;
+    if adjusted_x < 0.0 || adjusted_y < 0.0 {
+        return None;
+    }
 
-pub fn calculate_tile_size(w: f32, h: f32, (x, y): (f32, f32)) -> (f32, f32) {
-    let game_area_height = h * (1.0 - MENU_HEIGHT_PERCENT);
-    (w / x, game_area_height / y)
+    let cell = CellCoord {
+        col: (adjusted_x / tile_size.0) as usize,
+        row: (adjusted_y / tile_size.1) as usize,
+    };
+    (cell.col < size.width && cell.row < size.height).then_some(cell)
 }
 
 pub fn get_tile_index(
-    mouse_x: f32,
-    mouse_y: f32,
-    screen_dim: (f32, f32),
+    point: ScreenPoint,
+    origin: ScreenPoint,
     tile_size: (f32, f32),
     board_settings: &BoardSettings,
 ) -> Option<usize> {
-    let adjusted_y = mouse_y - (screen_dim.1 * MENU_HEIGHT_PERCENT);
+    let size = board_settings.size();
+    screen_to_cell(point, origin, tile_size, size).map(|cell| cell.to_index(size.width))
+}
 
-    if mouse_y < screen_dim.1 * MENU_HEIGHT_PERCENT {
-        return None;
+pub fn get_tile_rect(cell: CellCoord, tile_size: (f32, f32), origin: ScreenPoint) -> Rect {
+    let x = cell.col as f32 * tile_size.0 + origin.x;
+    let y = cell.row as f32 * tile_size.1 + origin.y;
+
+    Rect::new(x, y, tile_size.0, tile_size.1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_small_windows_scroll_instead_of_shrinking() {
+        let size = GridSize::new(30, 16);
+        let fits = calculate_tile_size(800.0, 600.0, 100.0, 0.0, size);
+        assert_eq!(fits, (800.0 / 30.0, 500.0 / 16.0));
+        assert_eq!(
+            board_overflow(800.0, 600.0, 100.0, 0.0, fits, size),
+            (0.0, 0.0)
+        );
+
+        let floored = calculate_tile_size(300.0, 600.0, 100.0, 0.0, size);
+        assert_eq!(floored.0, MIN_TILE_SIZE);
+        assert_eq!(
+            board_overflow(300.0, 600.0, 100.0, 0.0, floored, size),
+            (30.0 * MIN_TILE_SIZE - 300.0, 0.0)
+        );
     }
 
-    let (tile_x, tile_y) = tile_size;
+    #[test]
+    fn test_pixel_perfect_tile_size() {
+        assert_eq!(pixel_perfect_tile_size((75.0, 61.0), 24.0), (48.0, 48.0));
+        assert_eq!(pixel_perfect_tile_size((24.0, 30.0), 24.0), (24.0, 24.0));
+        assert_eq!(pixel_perfect_tile_size((30.0, 17.6), 24.0), (17.0, 17.0));
+    }
 
-    let board_x = (mouse_x / tile_x) as usize;
-    let board_y = (adjusted_y / tile_y) as usize;
+    #[test]
+    fn test_screen_to_cell() {
+        let origin = ScreenPoint::new(24.0, 114.0);
+        let size = GridSize::new(30, 16);
+        let cell = CellCoord { col: 29, row: 3 };
+        let rect = get_tile_rect(cell, (20.0, 25.0), origin);
+        let inside = ScreenPoint::new(rect.x + 1.0, rect.y + 1.0);
+        assert_eq!(
+            screen_to_cell(inside, origin, (20.0, 25.0), size),
+            Some(cell)
+        );
+        assert_eq!(CellCoord::from_index(cell.to_index(30), 30), cell);
 
-    if board_x < board_settings.w as usize && board_y < board_settings.h as usize {
-        let index = board_y * board_settings.w as usize + board_x;
-        Some(index)
-    } else {
-        None
+        let right_of_board = ScreenPoint::new(rect.right() + 1.0, rect.y);
+        assert_eq!(
+            screen_to_cell(right_of_board, origin, (20.0, 25.0), size),
+            None
+        );
+        assert_eq!(
+            screen_to_cell(ScreenPoint::new(0.0, 0.0), origin, (20.0, 25.0), size),
+            None
+        );
     }
-}
 
-pub fn get_tile_position(
-    mouse_x: f32,
-    mouse_y: f32,
-    screen_dim: (f32, f32),
-    tile_size: (f32, f32),
-    board_settings: &BoardSettings,
-) -> Option<Position> {
-    let adjusted_y = mouse_y - (screen_dim.1 * MENU_HEIGHT_PERCENT);
+    #[test]
+    fn test_cursor_stops_at_the_edges() {
+        let size = GridSize::new(9, 9);
+        let corner = CellCoord { col: 0, row: 0 };
+        assert_eq!(corner.step(-1, -1, size), corner);
+        assert_eq!(corner.step(1, 0, size), CellCoord { col: 1, row: 0 });
+        let last = CellCoord { col: 8, row: 8 };
+        assert_eq!(last.step(1, 1, size), last);
+        assert_eq!(last.step(0, -1, size), CellCoord { col: 8, row: 7 });
+    }
 
-    if mouse_y < screen_dim.1 * MENU_HEIGHT_PERCENT {
-        return None;
+    #[test]
+    fn test_opponent_panel_rect() {
+        let open = opponent_panel_rect((800.0, 600.0), HUD_BASE_HEIGHT, false);
+        assert_eq!(open.right(), 800.0);
+        assert_eq!(open.w, OPPONENT_PANEL_WIDTH);
+        assert!(open.y > HUD_BASE_HEIGHT);
+
+        let collapsed = opponent_panel_rect((800.0, 600.0), HUD_BASE_HEIGHT / 2.0, true);
+        assert_eq!(collapsed.w, OPPONENT_TAB_WIDTH / 2.0);
+        assert_eq!(collapsed.right(), 800.0);
     }
 
-    let (tile_x, tile_y) = tile_size;
-    let x = (mouse_x / tile_x) as i32;
-    let y = (adjusted_y / tile_y) as i32;
-    if x < 0 || x >= board_settings.w as i32 || y < 0 || y >= board_settings.h as i32 {
-        return None;
+    #[test]
+    fn test_error_banner_rect() {
+        let banner = error_banner_rect((800.0, 600.0), HUD_BASE_HEIGHT, 2);
+        assert_eq!(banner.y, HUD_BASE_HEIGHT);
+        assert_eq!(banner.w, 800.0);
+        assert_eq!(banner.h, BANNER_LINE_HEIGHT * 3.0);
     }
-    Some(Position(x, y))
-}
 
-pub fn get_tile_rect(
-    index: usize,
-    board_width: f32,
-    tile_size: (f32, f32),
-    screen_dim: (f32, f32),
-) -> Rect {
-    let x = (index % board_width as usize) as f32 * tile_size.0;
-    let y = ((index / board_width as usize) as f32 * tile_size.1)
-        + (screen_dim.1 * MENU_HEIGHT_PERCENT);
+    #[test]
+    fn test_lens_is_round_and_stays_in_the_window() {
+        let lens = Lens::new(ScreenPoint::new(100.0, 100.0), (1000.0, 500.0));
+        assert_eq!(lens.radius, 90.0);
+        let strips = lens.strips((1000.0, 500.0));
+        assert_eq!(strips.len(), LENS_STRIPS);
+        let middle = strips[LENS_STRIPS / 2].w;
+        assert!(strips[0].w < middle && strips[LENS_STRIPS - 1].w < middle);
+        assert!((middle - 180.0).abs() < 1.0);
+        let area: f32 = strips.iter().map(|r| r.w * r.h).sum();
+        assert!((area / (std::f32::consts::PI * 90.0 * 90.0) - 1.0).abs() < 0.05);
 
-    Rect::new(x, y, tile_size.0, tile_size.1)
+        // the tile under the pointer doesn't move when zoomed
+        let origin = lens.origin(ScreenPoint::new(40.0, 60.0));
+        assert_eq!(origin, ScreenPoint::new(-50.0, 0.0));
+
+        let corner = Lens::new(ScreenPoint::new(0.0, 0.0), (1000.0, 500.0));
+        assert!(corner
+            .strips((1000.0, 500.0))
+            .iter()
+            .all(|r| r.x >= 0.0 && r.y >= 0.0 && r.w > 0.0));
+    }
 }
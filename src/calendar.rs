@@ -0,0 +1,269 @@
+use super::storage;
+use chrono::{Datelike, Months, NaiveDate};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// one "<date>\t<updated unix ms>\t<time ms>" line per completed daily, merged per record by sync
+const DAILY_FILE_NAME: &str = "daily.records";
+
+pub const MONTH_NAMES: [&str; 12] = [
+    "JANUARY",
+    "FEBRUARY",
+    "MARCH",
+    "APRIL",
+    "MAY",
+    "JUNE",
+    "JULY",
+    "AUGUST",
+    "SEPTEMBER",
+    "OCTOBER",
+    "NOVEMBER",
+    "DECEMBER",
+];
+
+// day numbers count from the unix epoch, chrono's from the first day of the common era
+const EPOCH_DAYS_FROM_CE: i64 = 719_163;
+
+pub fn date(day: i64) -> NaiveDate {
+    i32::try_from(day + EPOCH_DAYS_FROM_CE)
+        .ok()
+        .and_then(NaiveDate::from_num_days_from_ce_opt)
+        .unwrap_or(NaiveDate::MIN)
+}
+
+pub fn day_number(date: NaiveDate) -> i64 {
+    date.num_days_from_ce() as i64 - EPOCH_DAYS_FROM_CE
+}
+
+// length of the month the date falls in
+pub fn days_in_month(date: NaiveDate) -> u32 {
+    let first = date.with_day(1).unwrap_or(date);
+    first.checked_add_months(Months::new(1)).map_or(31, |next| {
+        next.signed_duration_since(first).num_days() as u32
+    })
+}
+
+// 0 for monday through 6 for sunday
+pub fn weekday(day: i64) -> u32 {
+    date(day).weekday().num_days_from_monday()
+}
+
+// the day number in the player's time zone, so days roll over at local midnight
+pub fn local_day(unix_secs: i64, utc_offset_secs: i32) -> i64 {
+    (unix_secs + utc_offset_secs as i64).div_euclid(86_400)
+}
+
+pub fn local_hour(unix_secs: i64, utc_offset_secs: i32) -> u32 {
+    ((unix_secs + utc_offset_secs as i64).rem_euclid(86_400) / 3600) as u32
+}
+
+fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+fn utc_offset_secs() -> i32 {
+    chrono::Local::now().offset().local_minus_utc()
+}
+
+pub fn today() -> i64 {
+    local_day(now_secs(), utc_offset_secs())
+}
+
+pub fn current_hour() -> u32 {
+    local_hour(now_secs(), utc_offset_secs())
+}
+
+pub fn format_date(day: i64) -> String {
+    date(day).format("%Y-%m-%d").to_string()
+}
+
+fn parse_date(date: &str) -> Option<i64> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .ok()
+        .map(day_number)
+}
+
+// completed daily challenges of one profile, keyed by local day number
+pub struct DailyLog {
+    path: PathBuf,
+    pub completed: BTreeMap<i64, Duration>,
+    // unix ms each day's record last changed, so sync keeps the newer of two copies
+    updated: BTreeMap<i64, u64>,
+}
+
+impl DailyLog {
+    pub fn load(profile_dir: &Path) -> Self {
+        let path = profile_dir.join(DAILY_FILE_NAME);
+        let mut completed = BTreeMap::new();
+        let mut updated = BTreeMap::new();
+        for line in fs::read_to_string(&path).unwrap_or_default().lines() {
+            let fields = line.split('\t').collect::<Vec<_>>();
+            let [date, changed, time] = fields.as_slice() else {
+                continue;
+            };
+            if let (Some(day), Ok(ms)) = (parse_date(date), time.parse()) {
+                completed.insert(day, Duration::from_millis(ms));
+                updated.insert(day, changed.parse().unwrap_or(0));
+            }
+        }
+        Self {
+            path,
+            completed,
+            updated,
+        }
+    }
+
+    pub fn is_completed(&self, day: i64) -> bool {
+        self.completed.contains_key(&day)
+    }
+
+    // keeps the best time when a daily is won more than once; only the day whose record
+    // changed gets a new updated stamp
+    pub fn record(&mut self, day: i64, time: Duration) -> io::Result<()> {
+        if self.completed.get(&day).is_some_and(|best| *best <= time) {
+            return Ok(());
+        }
+        self.completed.insert(day, time);
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        self.updated.insert(day, now);
+        self.save()
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let mut text = String::new();
+        for (day, time) in &self.completed {
+            text.push_str(&format!(
+                "{}\t{}\t{}\n",
+                format_date(*day),
+                self.updated.get(day).copied().unwrap_or(0),
+                time.as_millis()
+            ));
+        }
+        storage::write_atomic(&self.path, text)
+    }
+
+    // consecutive days ending today, or yesterday while today's daily is still open
+    pub fn current_streak(&self, today: i64) -> u32 {
+        let mut day = if self.is_completed(today) {
+            today
+        } else {
+            today - 1
+        };
+        let mut streak = 0;
+        while self.is_completed(day) {
+            streak += 1;
+            day -= 1;
+        }
+        streak
+    }
+
+    pub fn longest_streak(&self) -> u32 {
+        let mut longest = 0;
+        let mut streak = 0;
+        let mut previous = None;
+        for day in self.completed.keys() {
+            streak = if previous == Some(day - 1) {
+                streak + 1
+            } else {
+                1
+            };
+            longest = longest.max(streak);
+            previous = Some(*day);
+        }
+        longest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn days_from_civil(year: i32, month: u32, day: u32) -> i64 {
+        day_number(NaiveDate::from_ymd_opt(year, month, day).unwrap())
+    }
+
+    #[test]
+    fn test_day_numbers() {
+        for day in [-1, 0, 11_016, 19_000, 20_742] {
+            assert_eq!(day_number(date(day)), day);
+            assert_eq!(parse_date(&format_date(day)), Some(day));
+        }
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(parse_date("2026-02-30"), None);
+        assert_eq!(days_in_month(date(days_from_civil(2024, 2, 10))), 29);
+        assert_eq!(days_in_month(date(days_from_civil(2023, 2, 1))), 28);
+        assert_eq!(days_in_month(date(days_from_civil(2026, 12, 31))), 31);
+        // 2026-10-16 is a friday
+        assert_eq!(weekday(days_from_civil(2026, 10, 16)), 4);
+    }
+
+    #[test]
+    fn test_local_day_rolls_over_at_local_midnight() {
+        // 2026-10-16 23:30 UTC
+        let secs = days_from_civil(2026, 10, 16) * 86_400 + 23 * 3600 + 1800;
+        assert_eq!(local_day(secs, 0), days_from_civil(2026, 10, 16));
+        assert_eq!(local_day(secs, 3600), days_from_civil(2026, 10, 17));
+        assert_eq!(
+            local_day(secs - 86_400 + 3600, -5 * 3600),
+            days_from_civil(2026, 10, 15)
+        );
+        assert_eq!(local_hour(secs, 0), 23);
+        assert_eq!(local_hour(secs, 3600), 0);
+        assert_eq!(local_hour(secs, -5 * 3600), 18);
+    }
+
+    #[test]
+    fn test_streaks() {
+        let mut log = DailyLog {
+            path: PathBuf::new(),
+            completed: BTreeMap::new(),
+            updated: BTreeMap::new(),
+        };
+        for day in [1, 2, 3, 4, 8, 9, 10] {
+            log.completed.insert(day, Duration::ZERO);
+        }
+
+        assert_eq!(log.current_streak(10), 3);
+        // today is still open, yesterday's streak holds
+        assert_eq!(log.current_streak(11), 3);
+        assert_eq!(log.current_streak(12), 0);
+        assert_eq!(log.longest_streak(), 4);
+    }
+
+    #[test]
+    fn test_daily_log_round_trip() {
+        let dir =
+            std::env::temp_dir().join(format!("rust_sweeper_test_daily_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut log = DailyLog::load(&dir);
+        log.record(20_742, Duration::from_millis(5000)).unwrap();
+        // a record synced in from elsewhere keeps its own stamp when another day changes
+        log.completed.insert(20_700, Duration::from_millis(7000));
+        log.updated.insert(20_700, 1);
+        log.record(20_743, Duration::from_millis(6000)).unwrap();
+        let stamped = log.updated[&20_742];
+        log.record(20_742, Duration::from_millis(9000)).unwrap();
+        assert_eq!(log.updated[&20_742], stamped);
+
+        let loaded = DailyLog::load(&dir);
+        assert_eq!(
+            loaded.completed.get(&20_742),
+            Some(&Duration::from_millis(5000))
+        );
+        assert_eq!(loaded.updated.get(&20_700), Some(&1));
+        assert_eq!(loaded.updated.get(&20_742), Some(&stamped));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
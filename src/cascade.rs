@@ -0,0 +1,88 @@
+use super::game::Engine;
+use std::collections::VecDeque;
+
+// a flood fill is uncovered over about this many ticks, however large it is
+const CASCADE_TICKS: usize = 12;
+const MIN_TILES_PER_TICK: usize = 2;
+
+// the tiles a reveal opened, uncovered on screen a few per tick outwards from the click;
+// the engine has already revealed them all, so this only changes what is drawn
+pub struct Cascade {
+    queue: VecDeque<usize>,
+    // by tile index, the tiles still drawn covered
+    covered: Vec<bool>,
+    per_tick: usize,
+    // the engine's move count when it was last followed
+    seen_moves: usize,
+}
+
+impl Cascade {
+    // nothing the engine opened before now is animated
+    pub fn new(engine: &Engine) -> Self {
+        Self {
+            queue: VecDeque::new(),
+            covered: vec![false; engine.get_tiles().len()],
+            per_tick: MIN_TILES_PER_TICK,
+            seen_moves: engine.moves().len(),
+        }
+    }
+
+    // picks up the tiles opened by the moves made since the last call
+    pub fn follow(&mut self, engine: &Engine) {
+        let moves = engine.moves().len();
+        if moves < self.seen_moves || self.covered.len() != engine.get_tiles().len() {
+            // an undo put tiles back, or another board was loaded
+            *self = Cascade::new(engine);
+            return;
+        }
+        for index in engine.opened_since(self.seen_moves) {
+            self.covered[index] = true;
+            self.queue.push_back(index);
+        }
+        self.seen_moves = moves;
+        self.per_tick = self
+            .queue
+            .len()
+            .div_ceil(CASCADE_TICKS)
+            .max(MIN_TILES_PER_TICK);
+    }
+
+    pub fn step(&mut self) {
+        for index in self.queue.drain(..self.per_tick.min(self.queue.len())) {
+            self.covered[index] = false;
+        }
+    }
+
+    pub fn covered(&self) -> &[bool] {
+        &self.covered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{GridSize, Position};
+
+    #[test]
+    fn test_cascade_uncovers_a_few_tiles_per_tick() {
+        let mut engine = Engine::with_seed(GridSize::new(30, 16), 10, 7);
+        let mut cascade = Cascade::new(&engine);
+        engine.reveal(Position(15, 8));
+        cascade.follow(&engine);
+
+        let opened = engine.opened_since(0);
+        assert!(opened.len() > MIN_TILES_PER_TICK * CASCADE_TICKS);
+        // the clicked tile comes out first
+        cascade.step();
+        assert!(!cascade.covered()[opened[0]]);
+        assert!(cascade.covered()[*opened.last().unwrap()]);
+        for _ in 0..CASCADE_TICKS {
+            cascade.step();
+        }
+        assert!(cascade.covered().iter().all(|covered| !covered));
+
+        // a board loaded mid game isn't animated
+        let cascade = Cascade::new(&engine);
+        assert!(cascade.covered().iter().all(|covered| !covered));
+    }
+}
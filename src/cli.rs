@@ -0,0 +1,74 @@
+use super::board::{BoardCode, URL_PREFIX};
+use super::files;
+use std::path::{Path, PathBuf};
+
+pub const USAGE: &str = "usage:
+  rust_sweeper
+  rust_sweeper <game.rsweep|replay.rsrep|theme.manifest>
+  rust_sweeper rustsweeper://play?code=<board code>
+  rust_sweeper tournament <bundle> <key>
+  rust_sweeper verify <replay-or-results> [<bundle> <key>]
+  rust_sweeper render <replay> <out.ppm>
+  rust_sweeper make-tournament <name> <start-unix-time> <easy|medium|hard|expert+|insane> <rounds> <key>";
+
+pub enum Command {
+    Play,
+    // launched with a save, replay or theme, e.g. by the file manager
+    Open(PathBuf),
+    // launched from a shared link
+    PlayCode(BoardCode),
+    Tournament {
+        bundle: PathBuf,
+        key: String,
+    },
+    Verify {
+        file: PathBuf,
+        bundle: Option<(PathBuf, String)>,
+    },
+    // draws the final board of a replay to an image, for checking the renderer by eye or diff
+    Render {
+        replay: PathBuf,
+        out: PathBuf,
+    },
+    MakeTournament {
+        name: String,
+        start: u64,
+        difficulty: String,
+        rounds: usize,
+        key: String,
+    },
+}
+
+// args excludes the program name
+pub fn parse_args(args: &[String]) -> Result<Command, String> {
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+    match args.as_slice() {
+        [] => Ok(Command::Play),
+        [url] if url.starts_with(URL_PREFIX) => BoardCode::from_url(url).map(Command::PlayCode),
+        [file] if files::can_open(Path::new(file)) => Ok(Command::Open(PathBuf::from(file))),
+        ["tournament", bundle, key] => Ok(Command::Tournament {
+            bundle: PathBuf::from(bundle),
+            key: key.to_string(),
+        }),
+        ["verify", file] => Ok(Command::Verify {
+            file: PathBuf::from(file),
+            bundle: None,
+        }),
+        ["verify", file, bundle, key] => Ok(Command::Verify {
+            file: PathBuf::from(file),
+            bundle: Some((PathBuf::from(bundle), key.to_string())),
+        }),
+        ["render", replay, out] => Ok(Command::Render {
+            replay: PathBuf::from(replay),
+            out: PathBuf::from(out),
+        }),
+        ["make-tournament", name, start, difficulty, rounds, key] => Ok(Command::MakeTournament {
+            name: name.to_string(),
+            start: start.parse().map_err(|_| "invalid start time")?,
+            difficulty: difficulty.to_string(),
+            rounds: rounds.parse().map_err(|_| "invalid round count")?,
+            key: key.to_string(),
+        }),
+        _ => Err(USAGE.to_string()),
+    }
+}
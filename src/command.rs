@@ -0,0 +1,135 @@
+use super::action::Action;
+use super::board::PRESETS;
+use super::game::{GridSize, Position};
+use std::time::Duration;
+
+// how long a tile found with `:g` keeps flashing
+pub const FLASH_DURATION: Duration = Duration::from_millis(1500);
+const FLASH_PERIOD_MS: u128 = 250;
+
+// the line typed after ':' while a game is shown
+pub struct CommandBar {
+    pub input: String,
+    pub error: Option<String>,
+}
+
+impl CommandBar {
+    pub fn new() -> Self {
+        Self {
+            input: String::new(),
+            error: None,
+        }
+    }
+
+    pub fn push(&mut self, character: char) {
+        if !character.is_control() {
+            self.input.push(character);
+            self.error = None;
+        }
+    }
+
+    pub fn pop(&mut self) {
+        self.input.pop();
+        self.error = None;
+    }
+}
+
+// coordinates are one-based, matching the labels drawn around the board
+pub fn parse(input: &str, board_size: GridSize) -> Result<Action, String> {
+    let mut words = input.split_whitespace();
+    let name = words.next().ok_or("empty command")?;
+    match name {
+        "g" | "goto" => {
+            let args = words.collect::<Vec<_>>().join("");
+            let (x, y) = args.split_once(',').ok_or("usage: g <column>,<row>")?;
+            let x = parse_coordinate(x, board_size.width)?;
+            let y = parse_coordinate(y, board_size.height)?;
+            Ok(Action::GoTo(Position(x - 1, y - 1)))
+        }
+        // the only way to reach presets without a HUD button
+        "n" | "new" => {
+            let preset = words.next().ok_or("usage: n <preset>")?;
+            PRESETS
+                .into_iter()
+                .find(|name| *name == preset)
+                .map(Action::NewGame)
+                .ok_or_else(|| format!("unknown preset '{}'", preset))
+        }
+        "e" | "export" => Ok(Action::Export),
+        "t" | "train" => Ok(Action::ToggleTrainer),
+        _ => Err(format!("unknown command '{}'", name)),
+    }
+}
+
+fn parse_coordinate(s: &str, max: usize) -> Result<i32, String> {
+    let n = s
+        .trim()
+        .parse::<usize>()
+        .map_err(|_| format!("'{}' is not a number", s.trim()))?;
+    if n == 0 || n > max {
+        return Err(format!("{} is outside 1-{}", n, max));
+    }
+    Ok(n as i32)
+}
+
+// a highlighted tile that blinks until its time runs out
+pub struct Flash {
+    pub index: usize,
+    left: Duration,
+}
+
+impl Flash {
+    pub fn new(index: usize) -> Self {
+        Self {
+            index,
+            left: FLASH_DURATION,
+        }
+    }
+
+    // returns false once the flash is over
+    pub fn update(&mut self, delta: Duration) -> bool {
+        self.left = self.left.saturating_sub(delta);
+        !self.left.is_zero()
+    }
+
+    pub fn is_lit(&self) -> bool {
+        (self.left.as_millis() / FLASH_PERIOD_MS) % 2 == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_goto() {
+        let size = GridSize::new(30, 16);
+        assert_eq!(parse("g 12,7", size), Ok(Action::GoTo(Position(11, 6))));
+        assert_eq!(parse("goto 1, 16", size), Ok(Action::GoTo(Position(0, 15))));
+        assert!(parse("g 31,1", size).is_err());
+        assert!(parse("g 0,1", size).is_err());
+        assert!(parse("g 3", size).is_err());
+        assert!(parse("x 1,1", size).is_err());
+        assert!(parse("", size).is_err());
+    }
+
+    #[test]
+    fn parses_new() {
+        let size = GridSize::new(8, 8);
+        assert_eq!(parse("n insane", size), Ok(Action::NewGame("insane")));
+        assert_eq!(parse("new expert+", size), Ok(Action::NewGame("expert+")));
+        assert!(parse("new", size).is_err());
+        assert!(parse("new huge", size).is_err());
+        assert_eq!(parse("export", size), Ok(Action::Export));
+        assert_eq!(parse("train", size), Ok(Action::ToggleTrainer));
+    }
+
+    #[test]
+    fn flash_runs_out() {
+        let mut flash = Flash::new(3);
+        assert!(flash.is_lit());
+        assert!(flash.update(Duration::from_millis(250)));
+        assert!(!flash.is_lit());
+        assert!(!flash.update(FLASH_DURATION));
+    }
+}
@@ -0,0 +1,1110 @@
+use super::board::PRESETS;
+use super::game::{Generation, Penalties};
+use super::mixer::Bus;
+use super::overlay::OverlayFormat;
+use super::storage;
+use super::sync::SyncBackend;
+use super::theme::{self, Theme};
+use ggez::input::mouse::MouseButton;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// the options the settings screen changes, kept in each profile's directory
+pub const CONFIG_FILE_NAME: &str = "settings.toml";
+// endpoints, paths and credentials belong to the machine, not the player, so they are kept
+// next to the profiles where sync never uploads or overwrites them
+pub const LOCAL_CONFIG_FILE_NAME: &str = "local.toml";
+
+fn is_local_key(key: &str) -> bool {
+    matches!(
+        key,
+        "overlay_path" | "overlay_format" | "ipc_enabled" | "metrics_port" | "webhook_url" | "sync"
+    ) || key.starts_with("sync_")
+}
+
+fn line_key(line: &str) -> &str {
+    line.split_once('=').map_or(line, |(key, _)| key).trim()
+}
+
+// a # outside quotes starts a comment
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            '#' if !quoted => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+// free text is written quoted with \ and " escaped; any other backslash is kept, so a
+// windows path typed in by hand reads as it looks
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn unquote(value: &str) -> Option<String> {
+    let Some(inner) = value.strip_prefix('"') else {
+        // a bare word reads as itself
+        return Some(value.to_string());
+    };
+    let inner = inner.strip_suffix('"')?;
+    let mut text = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(next @ ('\\' | '"')) => text.push(next),
+                Some(next) => {
+                    text.push(c);
+                    text.push(next);
+                }
+                None => return None,
+            },
+            '"' => return None,
+            c => text.push(c),
+        }
+    }
+    Some(text)
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TimerDisplay {
+    // clamps at 999 seconds like the original game
+    Classic,
+    // switches to mm:ss once 999 seconds is exceeded
+    Extended,
+}
+
+impl TimerDisplay {
+    pub const ALL: [TimerDisplay; 2] = [TimerDisplay::Classic, TimerDisplay::Extended];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            TimerDisplay::Classic => "classic",
+            TimerDisplay::Extended => "extended",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|display| display.key() == key)
+    }
+
+    pub fn format(&self, seconds: u64) -> String {
+        match self {
+            TimerDisplay::Classic => format!("{:03}", seconds.min(999)),
+            TimerDisplay::Extended if seconds > 999 => {
+                format!("{:02}:{:02}", seconds / 60, seconds % 60)
+            }
+            TimerDisplay::Extended => format!("{:03}", seconds),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum HudScale {
+    // keeps the base size, shrinking only when the window is too short
+    Auto,
+    Fixed(f32),
+}
+
+// the range a fixed scale may be set to in settings.toml
+const HUD_SCALE_RANGE: std::ops::RangeInclusive<f32> = 0.5..=3.0;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum NightMode {
+    Off,
+    // follows the operating system's dark setting where the platform reports one
+    System,
+    // dark from start_hour until end_hour local time, wrapping past midnight
+    Schedule { start_hour: u32, end_hour: u32 },
+}
+
+// used by System on platforms that can't report a theme
+const DEFAULT_NIGHT: NightMode = NightMode::Schedule {
+    start_hour: 20,
+    end_hour: 7,
+};
+
+impl NightMode {
+    // settings.toml spells these off, system or a range of hours such as 20-07
+    pub fn key(&self) -> String {
+        match self {
+            NightMode::Off => "off".to_string(),
+            NightMode::System => "system".to_string(),
+            NightMode::Schedule {
+                start_hour,
+                end_hour,
+            } => format!("{:02}-{:02}", start_hour, end_hour),
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "off" => Some(NightMode::Off),
+            "system" => Some(NightMode::System),
+            _ => {
+                let (start, end) = key.split_once('-')?;
+                let hour = |h: &str| h.parse::<u32>().ok().filter(|h| *h < 24);
+                Some(NightMode::Schedule {
+                    start_hour: hour(start)?,
+                    end_hour: hour(end)?,
+                })
+            }
+        }
+    }
+
+    pub fn is_night(&self, hour: u32, system_dark: Option<bool>) -> bool {
+        match *self {
+            NightMode::Off => false,
+            NightMode::System => system_dark.unwrap_or_else(|| DEFAULT_NIGHT.is_night(hour, None)),
+            NightMode::Schedule {
+                start_hour,
+                end_hour,
+            } if start_hour <= end_hour => hour >= start_hour && hour < end_hour,
+            NightMode::Schedule {
+                start_hour,
+                end_hour,
+            } => hour >= start_hour || hour < end_hour,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TextureFilter {
+    // keeps sprite pixels sharp
+    Nearest,
+    // smooths sprites when they are scaled by a non-integer factor
+    Linear,
+}
+
+impl TextureFilter {
+    pub const ALL: [TextureFilter; 2] = [TextureFilter::Nearest, TextureFilter::Linear];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            TextureFilter::Nearest => "nearest",
+            TextureFilter::Linear => "linear",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|filter| filter.key() == key)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LossPolicy {
+    // hitting a mine ends the game
+    Standard,
+    // free play offers to take the fatal reveal back; each undo shows next to the time
+    // and costs a penalty
+    Casual,
+}
+
+impl LossPolicy {
+    pub const ALL: [LossPolicy; 2] = [LossPolicy::Standard, LossPolicy::Casual];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            LossPolicy::Standard => "standard",
+            LossPolicy::Casual => "casual",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|policy| policy.key() == key)
+    }
+
+    pub fn next(self) -> LossPolicy {
+        match self {
+            LossPolicy::Standard => LossPolicy::Casual,
+            LossPolicy::Casual => LossPolicy::Standard,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RevealInput {
+    // a click reveals the tile under it
+    Direct,
+    // the first tap selects a tile and a second tap on it reveals it, for touchscreens
+    // and other coarse pointers
+    SelectThenConfirm,
+}
+
+impl RevealInput {
+    pub const ALL: [RevealInput; 2] = [RevealInput::Direct, RevealInput::SelectThenConfirm];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            RevealInput::Direct => "direct",
+            RevealInput::SelectThenConfirm => "select-then-confirm",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|input| input.key() == key)
+    }
+}
+
+// holds back clicks on tiles the solver proves are mines
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MisclickGuard {
+    Off,
+    // the same tile has to be clicked twice
+    Confirm,
+    // the pointer has to rest on the tile this long before the click
+    Dwell(Duration),
+}
+
+// how long the pointer rests on a proven mine before dwell lets a click through
+pub const MISCLICK_DWELL: Duration = Duration::from_millis(500);
+
+impl MisclickGuard {
+    pub const ALL: [MisclickGuard; 3] = [
+        MisclickGuard::Off,
+        MisclickGuard::Confirm,
+        MisclickGuard::Dwell(MISCLICK_DWELL),
+    ];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            MisclickGuard::Off => "off",
+            MisclickGuard::Confirm => "confirm",
+            MisclickGuard::Dwell(_) => "dwell",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|guard| guard.key() == key)
+    }
+
+    pub fn next(self) -> MisclickGuard {
+        let i = Self::ALL
+            .iter()
+            .position(|guard| guard.key() == self.key())
+            .unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+}
+
+#[derive(Clone)]
+pub struct HudConfig {
+    pub show_timer: bool,
+    pub show_bomb_counter: bool,
+    pub show_difficulty_buttons: bool,
+    pub show_progress_bar: bool,
+    pub show_hint_button: bool,
+    // a quiet note in the HUD when nothing on the board can be proven safe
+    pub show_guess_notice: bool,
+}
+
+// the parts of the HUD that can be hidden, each a show_<key> line in settings.toml
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum HudElement {
+    Timer,
+    BombCounter,
+    DifficultyButtons,
+    ProgressBar,
+    HintButton,
+    GuessNotice,
+}
+
+impl HudElement {
+    pub const ALL: [HudElement; 6] = [
+        HudElement::Timer,
+        HudElement::BombCounter,
+        HudElement::DifficultyButtons,
+        HudElement::ProgressBar,
+        HudElement::HintButton,
+        HudElement::GuessNotice,
+    ];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            HudElement::Timer => "timer",
+            HudElement::BombCounter => "bomb_counter",
+            HudElement::DifficultyButtons => "difficulty_buttons",
+            HudElement::ProgressBar => "progress_bar",
+            HudElement::HintButton => "hint_button",
+            HudElement::GuessNotice => "guess_notice",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<HudElement> {
+        HudElement::ALL
+            .into_iter()
+            .find(|element| element.key() == key)
+    }
+}
+
+impl HudConfig {
+    pub fn shows(&self, element: HudElement) -> bool {
+        match element {
+            HudElement::Timer => self.show_timer,
+            HudElement::BombCounter => self.show_bomb_counter,
+            HudElement::DifficultyButtons => self.show_difficulty_buttons,
+            HudElement::ProgressBar => self.show_progress_bar,
+            HudElement::HintButton => self.show_hint_button,
+            HudElement::GuessNotice => self.show_guess_notice,
+        }
+    }
+
+    pub fn set(&mut self, element: HudElement, show: bool) {
+        let field = match element {
+            HudElement::Timer => &mut self.show_timer,
+            HudElement::BombCounter => &mut self.show_bomb_counter,
+            HudElement::DifficultyButtons => &mut self.show_difficulty_buttons,
+            HudElement::ProgressBar => &mut self.show_progress_bar,
+            HudElement::HintButton => &mut self.show_hint_button,
+            HudElement::GuessNotice => &mut self.show_guess_notice,
+        };
+        *field = show;
+    }
+}
+
+impl Default for HudConfig {
+    fn default() -> Self {
+        Self {
+            show_timer: true,
+            show_bomb_counter: true,
+            show_difficulty_buttons: true,
+            show_progress_bar: false,
+            show_hint_button: true,
+            show_guess_notice: false,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Config {
+    pub timer_display: TimerDisplay,
+    pub hud_scale: HudScale,
+    pub confirm_quit: bool,
+    pub hud: HudConfig,
+    // when set, live stats are written here for streaming overlays
+    pub overlay_path: Option<PathBuf>,
+    pub overlay_format: OverlayFormat,
+    // exposes the game state to local tools over a socket
+    pub ipc_enabled: bool,
+    // serves counters for monitoring on this localhost port, None serves nothing
+    pub metrics_port: Option<u16>,
+    // remote copy of the profile data, synced on startup and exit
+    pub sync: Option<SyncBackend>,
+    // every finished game is posted here as json, for bots and home automation
+    pub webhook_url: Option<String>,
+    // plays back the personal best on the same board while you play
+    pub show_ghost: bool,
+    // pauses a running game after this long without input, None never pauses
+    pub idle_pause_after: Option<Duration>,
+    // counts down before dailies, marathon stages and tournament rounds
+    pub countdown: bool,
+    // row and column labels around the board, plus the hovered tile in the HUD
+    pub show_coordinates: bool,
+    // how tile sprites are sampled when scaled
+    pub texture_filter: TextureFilter,
+    // rounds tiles down to a whole multiple of the sprite size
+    pub pixel_perfect: bool,
+    // background, HUD and text colors currently in use, see MainState::update_night_mode
+    pub theme: Theme,
+    // when to switch to the night theme
+    pub night_mode: NightMode,
+    // beeps when more tiles are flagged than there are mines
+    pub counter_warning_sound: bool,
+    pub loss_policy: LossPolicy,
+    // how new free play boards lay out their mines; shared boards are always plain
+    pub generation: Generation,
+    // Ctrl+Z takes back moves in free play, each undo costing the undo penalty;
+    // strict players turn it off
+    pub allow_undo: bool,
+    // numbers reveal their other neighbours as soon as a flag satisfies them
+    pub auto_chord: bool,
+    // flags every tile the solver proves is a mine after each move
+    pub auto_flag: bool,
+    // added to the recorded time for each hint and undo
+    pub penalties: Penalties,
+    pub misclick_guard: MisclickGuard,
+    pub reveal_input: RevealInput,
+    // a press released sooner than this doesn't reveal, None reveals on any click
+    pub min_press: Option<Duration>,
+    // off silences everything, whatever the single sounds are set to
+    pub sounds: bool,
+    // percent, one per bus in Bus::ALL order
+    pub volumes: [u8; 3],
+    // ticks as the ghost clears the board, higher when the player is ahead of it
+    pub pace_cue: bool,
+    // how often the mines left and the time are read aloud during a game, None never
+    pub announce_every: Option<Duration>,
+    // flagging a flagged tile puts a question mark on it before clearing it
+    pub question_marks: bool,
+    // swaps the buttons on the board: right reveals and left flags
+    pub left_handed: bool,
+    // the preset the day theme manifest starts from
+    pub base_theme: &'static str,
+    // the preset the game starts on
+    pub difficulty: &'static str,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            timer_display: TimerDisplay::Classic,
+            hud_scale: HudScale::Auto,
+            confirm_quit: true,
+            hud: HudConfig::default(),
+            overlay_path: None,
+            overlay_format: OverlayFormat::Text,
+            ipc_enabled: false,
+            metrics_port: None,
+            sync: None,
+            webhook_url: None,
+            show_ghost: true,
+            idle_pause_after: Some(Duration::from_secs(60)),
+            countdown: true,
+            show_coordinates: false,
+            texture_filter: TextureFilter::Linear,
+            pixel_perfect: false,
+            theme: Theme::classic(),
+            night_mode: NightMode::Off,
+            counter_warning_sound: false,
+            loss_policy: LossPolicy::Standard,
+            generation: Generation::Plain,
+            allow_undo: true,
+            auto_chord: false,
+            auto_flag: false,
+            penalties: Penalties {
+                per_hint: Duration::from_secs(10),
+                per_undo: Duration::from_secs(30),
+            },
+            misclick_guard: MisclickGuard::Off,
+            reveal_input: RevealInput::Direct,
+            min_press: None,
+            sounds: true,
+            volumes: [100, 100, 60],
+            pace_cue: false,
+            announce_every: None,
+            question_marks: true,
+            left_handed: false,
+            base_theme: "classic",
+            difficulty: "easy",
+        }
+    }
+}
+
+impl Config {
+    // a safe start is a way of generating boards, so it lives in generation
+    pub fn safe_first_click(&self) -> bool {
+        self.generation != Generation::Unprotected
+    }
+
+    pub fn set_safe_first_click(&mut self, safe: bool) {
+        self.generation = match (safe, self.generation) {
+            (false, _) => Generation::Unprotected,
+            (true, Generation::Unprotected) => Generation::Plain,
+            (true, generation) => generation,
+        };
+    }
+
+    pub fn volume(&self, bus: Bus) -> f32 {
+        self.volumes[bus as usize] as f32 / 100.0
+    }
+
+    // the button as the board reads it
+    pub fn board_button(&self, button: MouseButton) -> MouseButton {
+        match button {
+            MouseButton::Left if self.left_handed => MouseButton::Right,
+            MouseButton::Right if self.left_handed => MouseButton::Left,
+            button => button,
+        }
+    }
+
+    // no settings file yet means the game has never been set up
+    pub fn is_first_run(dir: &Path) -> bool {
+        !dir.join(CONFIG_FILE_NAME).exists()
+    }
+
+    // the whole text at once, stopping at the first line that can't be read; keys left out
+    // keep the value from base
+    #[cfg(test)]
+    pub fn parse(text: &str, base: Config) -> Result<Config, String> {
+        let mut config = base;
+        for (i, line) in text.lines().enumerate() {
+            config.parse_line(i, line)?;
+        }
+        config.check()?;
+        Ok(config)
+    }
+
+    // a small subset of toml: one `key = value` line of a boolean, a number or a quoted
+    // string, with `#` comments; i counts from 0
+    fn parse_line(&mut self, i: usize, line: &str) -> Result<(), String> {
+        let config = self;
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            return Ok(());
+        }
+        let (key, value) = line
+            .split_once('=')
+            .map(|(key, value)| (key.trim(), value.trim()))
+            .ok_or_else(|| format!("line {}: expected key = value", i + 1))?;
+        let flag = || {
+            value
+                .parse::<bool>()
+                .map_err(|_| format!("line {}: '{}' is not true or false", i + 1, value))
+        };
+        // keywords are quoted, or not
+        let name = value.trim_matches('"');
+        let text = || {
+            unquote(value)
+                .ok_or_else(|| format!("line {}: {} is not a quoted string", i + 1, value))
+        };
+        if let Some(element) = key.strip_prefix("show_").and_then(HudElement::from_key) {
+            config.hud.set(element, flag()?);
+            return Ok(());
+        }
+        match key {
+            "timer_display" => {
+                config.timer_display = TimerDisplay::from_key(name)
+                    .ok_or_else(|| format!("line {}: unknown timer display '{}'", i + 1, name))?;
+            }
+            // "auto" or a factor of the base size
+            "hud_scale" => {
+                config.hud_scale = if name == "auto" {
+                    HudScale::Auto
+                } else {
+                    value
+                        .parse::<f32>()
+                        .ok()
+                        .filter(|scale| HUD_SCALE_RANGE.contains(scale))
+                        .map(HudScale::Fixed)
+                        .ok_or_else(|| {
+                            format!("line {}: '{}' is not auto or 0.5 to 3", i + 1, value)
+                        })?
+                };
+            }
+            "night_mode" => {
+                config.night_mode = NightMode::from_key(name).ok_or_else(|| {
+                    format!("line {}: '{}' is not off, system or HH-HH", i + 1, name)
+                })?;
+            }
+            "sounds" => config.sounds = flag()?,
+            "pace_cue" => config.pace_cue = flag()?,
+            // in seconds, 0 being off
+            "announce_every" => {
+                let secs = value
+                    .parse::<u64>()
+                    .map_err(|_| format!("line {}: '{}' is not a number", i + 1, value))?;
+                config.announce_every = (secs > 0).then(|| Duration::from_secs(secs));
+            }
+            // empty being off
+            "overlay_path" => {
+                let path = text()?;
+                config.overlay_path = (!path.is_empty()).then(|| PathBuf::from(path));
+            }
+            "overlay_format" => {
+                config.overlay_format = OverlayFormat::from_key(name)
+                    .ok_or_else(|| format!("line {}: '{}' is not text or json", i + 1, name))?;
+            }
+            "ipc_enabled" => config.ipc_enabled = flag()?,
+            // 0 being off
+            "metrics_port" => {
+                let port = value
+                    .parse::<u16>()
+                    .map_err(|_| format!("line {}: '{}' is not a port", i + 1, value))?;
+                config.metrics_port = (port > 0).then_some(port);
+            }
+            // empty being off
+            "webhook_url" => {
+                let url = text()?;
+                if !(url.is_empty() || url.starts_with("http://") || url.starts_with("https://")) {
+                    return Err(format!("line {}: '{}' is not an http url", i + 1, url));
+                }
+                config.webhook_url = (!url.is_empty()).then_some(url);
+            }
+            // in seconds, 0 being off
+            "idle_pause_after" => {
+                let secs = value
+                    .parse::<u64>()
+                    .map_err(|_| format!("line {}: '{}' is not a number", i + 1, value))?;
+                config.idle_pause_after = (secs > 0).then(|| Duration::from_secs(secs));
+            }
+            "show_coordinates" => config.show_coordinates = flag()?,
+            "texture_filter" => {
+                config.texture_filter = TextureFilter::from_key(name)
+                    .ok_or_else(|| format!("line {}: unknown texture filter '{}'", i + 1, name))?;
+            }
+            "pixel_perfect" => config.pixel_perfect = flag()?,
+            "counter_warning_sound" => config.counter_warning_sound = flag()?,
+            "loss_policy" => {
+                config.loss_policy = LossPolicy::from_key(name)
+                    .ok_or_else(|| format!("line {}: unknown loss policy '{}'", i + 1, name))?;
+            }
+            // whole seconds, 0 being no penalty
+            "hint_penalty_secs" | "undo_penalty_secs" => {
+                let penalty = value
+                    .parse::<u64>()
+                    .map(Duration::from_secs)
+                    .map_err(|_| format!("line {}: '{}' is not a number", i + 1, value))?;
+                if key == "hint_penalty_secs" {
+                    config.penalties.per_hint = penalty;
+                } else {
+                    config.penalties.per_undo = penalty;
+                }
+            }
+            "misclick_guard" => {
+                config.misclick_guard = MisclickGuard::from_key(name).ok_or_else(|| {
+                    format!("line {}: '{}' is not off, confirm or dwell", i + 1, name)
+                })?;
+            }
+            "auto_chord" => config.auto_chord = flag()?,
+            "auto_flag" => config.auto_flag = flag()?,
+            // in milliseconds, 0 being off
+            "min_press_ms" => {
+                let ms = value
+                    .parse::<u64>()
+                    .map_err(|_| format!("line {}: '{}' is not a number", i + 1, value))?;
+                config.min_press = (ms > 0).then(|| Duration::from_millis(ms));
+            }
+            "question_marks" => config.question_marks = flag()?,
+            // safe_first_click, when it comes later, can still turn this unprotected
+            "generation" => {
+                config.generation = Generation::from_key(name)
+                    .ok_or_else(|| format!("line {}: unknown generation '{}'", i + 1, name))?;
+            }
+            "safe_first_click" => config.set_safe_first_click(flag()?),
+            "left_handed" => config.left_handed = flag()?,
+            "theme" => {
+                config.base_theme = theme::PRESETS
+                    .into_iter()
+                    .find(|preset| *preset == name)
+                    .ok_or_else(|| format!("line {}: unknown theme '{}'", i + 1, name))?;
+            }
+            "reveal_input" => {
+                config.reveal_input = RevealInput::from_key(name)
+                    .ok_or_else(|| format!("line {}: unknown reveal input '{}'", i + 1, name))?;
+            }
+            "difficulty" => {
+                config.difficulty = PRESETS
+                    .into_iter()
+                    .find(|preset| *preset == name)
+                    .ok_or_else(|| format!("line {}: unknown difficulty '{}'", i + 1, name))?;
+            }
+            "sync" => {
+                config.sync = match name {
+                    "off" => None,
+                    _ => Some(SyncBackend::from_key(name).ok_or_else(|| {
+                        format!("line {}: '{}' is not off, webdav or s3", i + 1, name)
+                    })?),
+                };
+            }
+            // the fields of the backend chosen by sync, which has to come first
+            _ if key.starts_with("sync_") => {
+                let field = config
+                    .sync
+                    .as_mut()
+                    .and_then(|backend| backend.field_mut(&key["sync_".len()..]))
+                    .ok_or_else(|| format!("line {}: unknown key '{}'", i + 1, key))?;
+                *field = text()?;
+            }
+            _ if key.ends_with("_volume") => {
+                let bus = Bus::from_key(key.trim_end_matches("_volume"))
+                    .ok_or_else(|| format!("line {}: unknown key '{}'", i + 1, key))?;
+                config.volumes[bus as usize] = value
+                    .parse::<u8>()
+                    .ok()
+                    .filter(|volume| *volume <= 100)
+                    .ok_or_else(|| format!("line {}: '{}' is not 0 to 100", i + 1, value))?;
+            }
+            _ => return Err(format!("line {}: unknown key '{}'", i + 1, key)),
+        }
+        Ok(())
+    }
+
+    // what only the whole file can tell
+    fn check(&self) -> Result<(), String> {
+        match self.sync.as_ref().and_then(SyncBackend::missing_field) {
+            Some(field) => Err(format!("sync needs sync_{}", field)),
+            None => Ok(()),
+        }
+    }
+
+    pub fn to_toml(&self) -> String {
+        self.toml_lines(false)
+    }
+
+    pub fn local_toml(&self) -> String {
+        self.toml_lines(true)
+    }
+
+    fn toml_lines(&self, local: bool) -> String {
+        [
+            format!("timer_display = \"{}\"", self.timer_display.key()),
+            match self.hud_scale {
+                HudScale::Auto => "hud_scale = \"auto\"".to_string(),
+                HudScale::Fixed(scale) => format!("hud_scale = {}", scale),
+            },
+            format!("night_mode = \"{}\"", self.night_mode.key()),
+            format!("sounds = {}", self.sounds),
+            format!("pace_cue = {}", self.pace_cue),
+            format!(
+                "announce_every = {}",
+                self.announce_every.map_or(0, |every| every.as_secs())
+            ),
+            format!(
+                "overlay_path = {}",
+                quote(
+                    &self
+                        .overlay_path
+                        .as_deref()
+                        .map_or(String::new(), |path| path.display().to_string())
+                )
+            ),
+            format!("overlay_format = \"{}\"", self.overlay_format.key()),
+            format!("ipc_enabled = {}", self.ipc_enabled),
+            format!("metrics_port = {}", self.metrics_port.unwrap_or(0)),
+            format!(
+                "webhook_url = {}",
+                quote(self.webhook_url.as_deref().unwrap_or(""))
+            ),
+            format!(
+                "sync = \"{}\"",
+                self.sync.as_ref().map_or("off", SyncBackend::key)
+            ),
+            format!(
+                "idle_pause_after = {}",
+                self.idle_pause_after.map_or(0, |after| after.as_secs())
+            ),
+            format!("show_coordinates = {}", self.show_coordinates),
+            format!("texture_filter = \"{}\"", self.texture_filter.key()),
+            format!("pixel_perfect = {}", self.pixel_perfect),
+            format!("counter_warning_sound = {}", self.counter_warning_sound),
+            format!("loss_policy = \"{}\"", self.loss_policy.key()),
+            format!("hint_penalty_secs = {}", self.penalties.per_hint.as_secs()),
+            format!("undo_penalty_secs = {}", self.penalties.per_undo.as_secs()),
+            format!("misclick_guard = \"{}\"", self.misclick_guard.key()),
+            format!("auto_chord = {}", self.auto_chord),
+            format!("auto_flag = {}", self.auto_flag),
+            format!("question_marks = {}", self.question_marks),
+            format!("generation = \"{}\"", self.generation.key()),
+            format!("safe_first_click = {}", self.safe_first_click()),
+            format!("left_handed = {}", self.left_handed),
+            format!("theme = \"{}\"", self.base_theme),
+            format!("reveal_input = \"{}\"", self.reveal_input.key()),
+            format!(
+                "min_press_ms = {}",
+                self.min_press.map_or(0, |min| min.as_millis())
+            ),
+            format!("difficulty = \"{}\"", self.difficulty),
+        ]
+        .into_iter()
+        .chain(self.sync.iter().flat_map(|backend| {
+            backend
+                .fields()
+                .into_iter()
+                .map(|(field, value)| format!("sync_{} = {}", field, quote(value)))
+        }))
+        .chain(
+            HudElement::ALL
+                .map(|element| format!("show_{} = {}", element.key(), self.hud.shows(element))),
+        )
+        .chain(Bus::ALL.map(|bus| format!("{}_volume = {}", bus.key(), self.volumes[bus as usize])))
+        .filter(|line| is_local_key(line_key(line)) == local)
+        .map(|line| line + "\n")
+        .collect()
+    }
+
+    // a missing file is the defaults. A line that can't be read is reported and skipped,
+    // so a key from a newer version costs only itself; the profile's file is synced, so
+    // machine keys found in it are skipped too
+    pub fn load(profile_dir: &Path, data_dir: &Path) -> Self {
+        let config = Config::load_file(profile_dir, CONFIG_FILE_NAME, false, Config::default());
+        Config::load_file(data_dir, LOCAL_CONFIG_FILE_NAME, true, config)
+    }
+
+    fn load_file(dir: &Path, file_name: &str, local: bool, base: Config) -> Config {
+        let Ok(text) = fs::read_to_string(dir.join(file_name)) else {
+            return base;
+        };
+        let mut config = base;
+        for (i, line) in text.lines().enumerate() {
+            let key = line_key(strip_comment(line));
+            let result = if !key.is_empty() && is_local_key(key) != local {
+                Err(format!("line {}: '{}' doesn't belong here", i + 1, key))
+            } else {
+                config.parse_line(i, line)
+            };
+            if let Err(e) = result {
+                eprintln!("{}: ignoring {}", file_name, e);
+            }
+        }
+        if let Err(e) = config.check() {
+            eprintln!("{}: ignoring sync, {}", file_name, e);
+            config.sync = None;
+        }
+        config
+    }
+
+    pub fn save(&self, profile_dir: &Path, data_dir: &Path) -> io::Result<()> {
+        storage::write_atomic(&profile_dir.join(CONFIG_FILE_NAME), self.to_toml())?;
+        storage::write_atomic(&data_dir.join(LOCAL_CONFIG_FILE_NAME), self.local_toml())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timer_display_overflow() {
+        assert_eq!(TimerDisplay::Classic.format(42), "042");
+        assert_eq!(TimerDisplay::Classic.format(1234), "999");
+        assert_eq!(TimerDisplay::Extended.format(999), "999");
+        assert_eq!(TimerDisplay::Extended.format(1000), "16:40");
+        assert_eq!(TimerDisplay::Extended.format(6000), "100:00");
+    }
+
+    #[test]
+    fn test_night_mode() {
+        let evening = NightMode::Schedule {
+            start_hour: 20,
+            end_hour: 7,
+        };
+        assert!(evening.is_night(23, None));
+        assert!(evening.is_night(3, None));
+        assert!(!evening.is_night(7, None));
+        assert!(!evening.is_night(12, None));
+
+        let afternoon = NightMode::Schedule {
+            start_hour: 13,
+            end_hour: 15,
+        };
+        assert!(afternoon.is_night(14, None));
+        assert!(!afternoon.is_night(15, None));
+
+        assert!(NightMode::System.is_night(12, Some(true)));
+        assert!(!NightMode::System.is_night(23, Some(false)));
+        assert!(NightMode::System.is_night(23, None));
+        assert!(!NightMode::Off.is_night(23, Some(true)));
+    }
+
+    #[test]
+    fn test_bad_lines_cost_only_themselves() {
+        let dir = std::env::temp_dir().join(format!("rust_sweeper_load_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join(CONFIG_FILE_NAME),
+            "sounds = false # muted\nfrom_the_future = 3\nleft_handed = maybe\npace_cue = true\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join(LOCAL_CONFIG_FILE_NAME),
+            "webhook_url = \"https://example.com/#hook\"\nsync = \"s3\"\n",
+        )
+        .unwrap();
+
+        let loaded = Config::load(&dir, &dir);
+        assert!(!loaded.sounds);
+        assert!(loaded.pace_cue);
+        assert!(!loaded.left_handed);
+        assert_eq!(
+            loaded.webhook_url.as_deref(),
+            Some("https://example.com/#hook")
+        );
+        // a backend missing what it needs is left off
+        assert_eq!(loaded.sync, None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_machine_keys_stay_out_of_the_profile() {
+        let config = Config {
+            sounds: false,
+            webhook_url: Some("https://example.com/hook".to_string()),
+            sync: Some(SyncBackend::WebDav {
+                url: "https://dav.example.com".to_string(),
+                username: "me".to_string(),
+                password: "hunter2".to_string(),
+            }),
+            ..Default::default()
+        };
+        assert!(!config.to_toml().contains("hunter2"));
+        assert!(!config.to_toml().contains("webhook_url"));
+        assert!(!config.local_toml().contains("sounds"));
+
+        let dir = std::env::temp_dir().join(format!("rust_sweeper_config_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let profile_dir = dir.join("profiles").join("player");
+        fs::create_dir_all(&profile_dir).unwrap();
+        config.save(&profile_dir, &dir).unwrap();
+        // a synced settings.toml can't point the game somewhere else
+        let mut synced = fs::read_to_string(profile_dir.join(CONFIG_FILE_NAME)).unwrap();
+        synced.push_str("webhook_url = \"https://evil.example.com\"\nsync = \"off\"\n");
+        fs::write(profile_dir.join(CONFIG_FILE_NAME), synced).unwrap();
+
+        let loaded = Config::load(&profile_dir, &dir);
+        assert!(!loaded.sounds);
+        assert_eq!(loaded.webhook_url, config.webhook_url);
+        assert_eq!(loaded.sync, config.sync);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_settings_round_trip() {
+        let config = Config {
+            sounds: false,
+            pace_cue: true,
+            announce_every: Some(Duration::from_secs(30)),
+            left_handed: true,
+            generation: Generation::Unprotected,
+            base_theme: "dark",
+            reveal_input: RevealInput::SelectThenConfirm,
+            difficulty: "hard",
+            volumes: [100, 100, 0],
+            webhook_url: Some("https://example.com/hook".to_string()),
+            metrics_port: Some(9100),
+            ipc_enabled: true,
+            overlay_format: OverlayFormat::Json,
+            idle_pause_after: None,
+            show_coordinates: true,
+            texture_filter: TextureFilter::Nearest,
+            pixel_perfect: true,
+            counter_warning_sound: true,
+            timer_display: TimerDisplay::Extended,
+            hud_scale: HudScale::Fixed(1.25),
+            hud: HudConfig {
+                show_timer: false,
+                show_guess_notice: true,
+                ..HudConfig::default()
+            },
+            night_mode: NightMode::Schedule {
+                start_hour: 21,
+                end_hour: 6,
+            },
+            loss_policy: LossPolicy::Casual,
+            auto_chord: true,
+            auto_flag: true,
+            penalties: Penalties {
+                per_hint: Duration::ZERO,
+                per_undo: Duration::from_secs(45),
+            },
+            misclick_guard: MisclickGuard::Confirm,
+            min_press: Some(Duration::from_millis(120)),
+            sync: Some(SyncBackend::WebDav {
+                url: "https://dav.example.com/sweeper".to_string(),
+                username: "me".to_string(),
+                password: "\"hunter#2\\\"".to_string(),
+            }),
+            overlay_path: Some(PathBuf::from("C:\\Users\\me\\overlay.txt")),
+            ..Config::default()
+        };
+        let parsed = Config::parse(&config.to_toml(), Config::default()).unwrap();
+        let parsed = Config::parse(&config.local_toml(), parsed).unwrap();
+        assert!(!parsed.sounds);
+        assert!(parsed.pace_cue);
+        assert_eq!(parsed.announce_every, Some(Duration::from_secs(30)));
+        assert!(parsed.question_marks);
+        assert!(parsed.left_handed);
+        assert_eq!(parsed.generation, Generation::Unprotected);
+        assert_eq!(parsed.base_theme, "dark");
+        assert_eq!(parsed.reveal_input, RevealInput::SelectThenConfirm);
+        assert_eq!(parsed.difficulty, "hard");
+        assert_eq!(parsed.volume(Bus::Music), 0.0);
+        assert_eq!(parsed.volume(Bus::Game), 1.0);
+        assert_eq!(
+            parsed.webhook_url.as_deref(),
+            Some("https://example.com/hook")
+        );
+        assert_eq!(parsed.metrics_port, Some(9100));
+        assert!(parsed.ipc_enabled);
+        assert_eq!(
+            parsed.overlay_path.as_deref(),
+            Some(Path::new("C:\\Users\\me\\overlay.txt"))
+        );
+        assert_eq!(parsed.overlay_format, OverlayFormat::Json);
+        assert_eq!(parsed.idle_pause_after, None);
+        assert!(parsed.show_coordinates);
+        assert_eq!(parsed.texture_filter, TextureFilter::Nearest);
+        assert!(parsed.pixel_perfect);
+        assert!(parsed.counter_warning_sound);
+        assert_eq!(parsed.timer_display, TimerDisplay::Extended);
+        assert_eq!(parsed.hud_scale, HudScale::Fixed(1.25));
+        assert!(!parsed.hud.show_timer);
+        assert!(parsed.hud.show_bomb_counter);
+        assert!(parsed.hud.show_guess_notice);
+        assert_eq!(parsed.loss_policy, LossPolicy::Casual);
+        assert_eq!(parsed.sync, config.sync);
+        assert!(parsed.auto_chord);
+        assert!(parsed.auto_flag);
+        assert_eq!(parsed.penalties.per_hint, Duration::ZERO);
+        assert_eq!(parsed.penalties.per_undo, Duration::from_secs(45));
+        assert_eq!(parsed.misclick_guard, MisclickGuard::Confirm);
+        assert_eq!(parsed.min_press, Some(Duration::from_millis(120)));
+        assert_eq!(
+            MisclickGuard::Confirm.next(),
+            MisclickGuard::Dwell(MISCLICK_DWELL)
+        );
+        assert_eq!(
+            MisclickGuard::Dwell(MISCLICK_DWELL).next(),
+            MisclickGuard::Off
+        );
+        assert_eq!(
+            parsed.night_mode,
+            NightMode::Schedule {
+                start_hour: 21,
+                end_hour: 6
+            }
+        );
+        assert_eq!(parsed.board_button(MouseButton::Left), MouseButton::Right);
+
+        let parsed = Config::parse("# mine\nsafe_first_click = true\n", parsed).unwrap();
+        assert_eq!(parsed.generation, Generation::Plain);
+        let config = Config {
+            generation: Generation::NoGuess,
+            ..Config::default()
+        };
+        let parsed = Config::parse(&config.to_toml(), Config::default()).unwrap();
+        assert_eq!(parsed.generation, Generation::NoGuess);
+        assert!(Config::parse("generation = \"lucky\"", Config::default()).is_err());
+        assert!(Config::parse("texture_filter = \"cubic\"", Config::default()).is_err());
+        assert!(Config::parse("timer_display = \"hours\"", Config::default()).is_err());
+        assert!(Config::parse("hud_scale = 10", Config::default()).is_err());
+        assert!(Config::parse("hint_penalty_secs = -5", Config::default()).is_err());
+        assert!(Config::parse("show_face = true", Config::default()).is_err());
+        assert!(Config::parse("night_mode = \"20-25\"", Config::default()).is_err());
+        let parsed = Config::parse("night_mode = \"system\"", parsed).unwrap();
+        assert_eq!(parsed.night_mode, NightMode::System);
+        let parsed = Config::parse("hud_scale = \"auto\"", parsed).unwrap();
+        assert_eq!(parsed.hud_scale, HudScale::Auto);
+        assert!(Config::parse("sounds = loud", Config::default()).is_err());
+        assert!(Config::parse("theme = \"neon\"", Config::default()).is_err());
+        assert!(Config::parse("volume = 3", Config::default()).is_err());
+        assert!(Config::parse("ui_volume = 101", Config::default()).is_err());
+        assert!(Config::parse("voice_volume = 50", Config::default()).is_err());
+        assert!(Config::parse("difficulty = \"brutal\"", Config::default()).is_err());
+        assert!(Config::parse("sounds", Config::default()).is_err());
+        assert!(Config::parse("metrics_port = 70000", Config::default()).is_err());
+        assert!(Config::parse("webhook_url = \"ftp://x\"", Config::default()).is_err());
+        let parsed = Config::parse("webhook_url = \"\"", parsed).unwrap();
+        assert_eq!(parsed.webhook_url, None);
+        let parsed = Config::parse("overlay_path = \"\"", parsed).unwrap();
+        assert_eq!(parsed.overlay_path, None);
+        assert!(Config::parse("overlay_format = \"xml\"", Config::default()).is_err());
+        assert!(Config::parse("sync = \"ftp\"", Config::default()).is_err());
+        assert!(Config::parse("sync_url = \"https://x\"", Config::default()).is_err());
+        assert!(Config::parse("sync = \"s3\"\nsync_bucket = \"b\"", Config::default()).is_err());
+        assert!(
+            Config::parse("sync = \"webdav\"\nsync_bucket = \"b\"", Config::default()).is_err()
+        );
+        let parsed = Config::parse("sync = \"off\"", parsed).unwrap();
+        assert_eq!(parsed.sync, None);
+    }
+}
@@ -0,0 +1,95 @@
+use sha2::{Digest, Sha256};
+
+const BLOCK_SIZE: usize = 64;
+
+pub fn sha256(parts: &[&[u8]]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    let mut out = [0; 32];
+    out.copy_from_slice(&hasher.finalize()[..]);
+    out
+}
+
+// RFC 2104 HMAC, so appending moves to a checksummed replay invalidates the checksum
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut block_key = [0u8; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        block_key[..32].copy_from_slice(&sha256(&[key]));
+    } else {
+        block_key[..key.len()].copy_from_slice(key);
+    }
+
+    let inner_pad = block_key.map(|b| b ^ 0x36);
+    let outer_pad = block_key.map(|b| b ^ 0x5c);
+
+    let inner = sha256(&[&inner_pad, message]);
+    sha256(&[&outer_pad, &inner])
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+pub fn to_base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hmac_sha256_rfc4231() {
+        // test case 2 from RFC 4231
+        let mac = hmac_sha256(b"Jefe", b"what do ya want for nothing?");
+        assert_eq!(
+            to_hex(&mac),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(to_base64(b"user:pass"), "dXNlcjpwYXNz");
+        assert_eq!(to_base64(b"ab"), "YWI=");
+        assert_eq!(to_base64(b""), "");
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        let bytes = vec![0x00, 0x7f, 0xff, 0x10];
+        assert_eq!(from_hex(&to_hex(&bytes)), Some(bytes));
+        assert_eq!(from_hex("abc"), None);
+        assert_eq!(from_hex("zz"), None);
+    }
+}
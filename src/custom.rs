@@ -0,0 +1,208 @@
+use super::board::{BoardSettings, MIN_SIDE, SAFE_AREA};
+use super::storage;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// the insane preset is the largest board the window layouts are made for
+pub const MAX_WIDTH: usize = 60;
+pub const MAX_HEIGHT: usize = 35;
+// mines as a percentage of all tiles
+pub const MIN_DENSITY: u32 = 10;
+pub const MAX_DENSITY: u32 = 30;
+// custom boards offered again in the HUD's mode list, newest first
+const RECENT_FILE_NAME: &str = "recent_custom.txt";
+const MAX_RECENT: usize = 3;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Rating {
+    Easy,
+    Medium,
+    Hard,
+    Extreme,
+}
+
+impl Rating {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Rating::Easy => "EASY",
+            Rating::Medium => "MEDIUM",
+            Rating::Hard => "HARD",
+            Rating::Extreme => "EXTREME",
+        }
+    }
+}
+
+// the board picked in the custom dialog; the mine count follows from the density
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct CustomBoard {
+    pub width: usize,
+    pub height: usize,
+    pub density: u32,
+}
+
+impl CustomBoard {
+    pub fn new() -> Self {
+        Self {
+            width: 16,
+            height: 16,
+            density: 15,
+        }
+    }
+
+    pub fn resize(&mut self, dw: i32, dh: i32) {
+        self.width = (self.width as i32 + dw).clamp(MIN_SIDE as i32, MAX_WIDTH as i32) as usize;
+        self.height = (self.height as i32 + dh).clamp(MIN_SIDE as i32, MAX_HEIGHT as i32) as usize;
+    }
+
+    pub fn set_density(&mut self, density: u32) {
+        self.density = density.clamp(MIN_DENSITY, MAX_DENSITY);
+    }
+
+    // rounded to the nearest mine, but always leaving the first click's area free
+    pub fn num_bombs(&self) -> usize {
+        let tiles = self.width * self.height;
+        let bombs = (tiles as f32 * self.density as f32 / 100.0).round() as usize;
+        bombs.clamp(1, tiles.saturating_sub(SAFE_AREA).max(1))
+    }
+
+    // denser boards are harder, and bigger ones give more chances to guess wrong;
+    // the presets come out as easy, medium and hard
+    pub fn rating(&self) -> Rating {
+        let tiles = (self.width * self.height) as f32;
+        let density = self.num_bombs() as f32 / tiles * 100.0;
+        let score = density * tiles.log10();
+        if score < 32.0 {
+            Rating::Easy
+        } else if score < 45.0 {
+            Rating::Medium
+        } else if score < 65.0 {
+            Rating::Hard
+        } else {
+            Rating::Extreme
+        }
+    }
+
+    pub fn settings(&self) -> Result<BoardSettings, String> {
+        BoardSettings::builder()
+            .size(self.width, self.height)
+            .mines(self.num_bombs())
+            .build()
+    }
+}
+
+// the custom boards started most recently, one "<width> <height> <density>" line each
+pub struct RecentBoards {
+    path: PathBuf,
+    boards: Vec<CustomBoard>,
+}
+
+impl RecentBoards {
+    pub fn load(profile_dir: &Path) -> Self {
+        let path = profile_dir.join(RECENT_FILE_NAME);
+        let boards = fs::read_to_string(&path)
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split(' ').map(str::parse::<u32>);
+                let (Some(Ok(width)), Some(Ok(height)), Some(Ok(density))) =
+                    (fields.next(), fields.next(), fields.next())
+                else {
+                    return None;
+                };
+                let mut board = CustomBoard::new();
+                board.resize(
+                    width as i32 - board.width as i32,
+                    height as i32 - board.height as i32,
+                );
+                board.set_density(density);
+                Some(board)
+            })
+            .take(MAX_RECENT)
+            .collect();
+        Self { path, boards }
+    }
+
+    pub fn boards(&self) -> &[CustomBoard] {
+        &self.boards
+    }
+
+    // starting a board already on the list moves it to the front
+    pub fn record(&mut self, board: CustomBoard) -> io::Result<()> {
+        self.boards.retain(|recent| *recent != board);
+        self.boards.insert(0, board);
+        self.boards.truncate(MAX_RECENT);
+        let text = self
+            .boards
+            .iter()
+            .map(|board| format!("{} {} {}\n", board.width, board.height, board.density))
+            .collect::<String>();
+        storage::write_atomic(&self.path, text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mines_follow_density() {
+        let mut board = CustomBoard::new();
+        assert_eq!(board.num_bombs(), 38);
+        board.set_density(50);
+        assert_eq!(board.density, MAX_DENSITY);
+        assert_eq!(board.num_bombs(), 77);
+
+        board.resize(-100, -100);
+        assert_eq!((board.width, board.height), (MIN_SIDE, MIN_SIDE));
+        assert!(board.num_bombs() <= MIN_SIDE * MIN_SIDE - SAFE_AREA);
+        assert!(board.settings().is_ok());
+        board.resize(100, 100);
+        assert_eq!((board.width, board.height), (MAX_WIDTH, MAX_HEIGHT));
+        assert!(board.settings().is_ok());
+    }
+
+    #[test]
+    fn test_rating_matches_presets() {
+        let rate = |width, height, bombs: usize| {
+            let density = (bombs * 100 / (width * height)) as u32;
+            CustomBoard {
+                width,
+                height,
+                density,
+            }
+            .rating()
+        };
+        assert_eq!(rate(8, 8, 10), Rating::Easy);
+        assert_eq!(rate(16, 16, 40), Rating::Medium);
+        assert_eq!(rate(30, 16, 99), Rating::Hard);
+        assert_eq!(rate(60, 35, 630), Rating::Extreme);
+    }
+
+    #[test]
+    fn test_recent_boards_round_trip() {
+        let dir = std::env::temp_dir().join(format!(
+            "rust_sweeper_test_recent_custom_{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut recent = RecentBoards::load(&dir);
+        let board = |width, height| CustomBoard {
+            width,
+            height,
+            density: 20,
+        };
+        for width in [10, 20, 30, 40, 20] {
+            recent.record(board(width, 12)).unwrap();
+        }
+        let loaded = RecentBoards::load(&dir);
+        assert_eq!(
+            loaded.boards(),
+            &[board(20, 12), board(40, 12), board(30, 12)]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,147 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+// frames kept for the frame time graph
+pub const HISTORY_LEN: usize = 120;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+// the system allocator, counting calls so the overlay can show allocations per frame
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+// allocations and reallocations since startup, on every thread
+pub fn allocation_count() -> usize {
+    ALLOCATIONS.load(Ordering::Relaxed)
+}
+
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub struct FrameStats {
+    pub frame_time: Duration,
+    pub update_time: Duration,
+    pub draw_time: Duration,
+    pub tiles_drawn: usize,
+    pub allocations: usize,
+}
+
+pub struct Diagnostics {
+    pub visible: bool,
+    // filled in as the frame is updated and drawn
+    pub current: FrameStats,
+    frames: VecDeque<FrameStats>,
+    allocations_at_last_frame: usize,
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            current: FrameStats::default(),
+            frames: VecDeque::with_capacity(HISTORY_LEN),
+            allocations_at_last_frame: allocation_count(),
+        }
+    }
+
+    pub fn finish_frame(&mut self, frame_time: Duration, draw_time: Duration) {
+        let allocations = allocation_count();
+        self.current.frame_time = frame_time;
+        self.current.draw_time = draw_time;
+        self.current.allocations = allocations.saturating_sub(self.allocations_at_last_frame);
+        self.allocations_at_last_frame = allocations;
+
+        if self.frames.len() == HISTORY_LEN {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(std::mem::take(&mut self.current));
+    }
+
+    // oldest first
+    pub fn frames(&self) -> impl Iterator<Item = &FrameStats> {
+        self.frames.iter()
+    }
+
+    pub fn latest(&self) -> Option<&FrameStats> {
+        self.frames.back()
+    }
+
+    pub fn average_frame_time(&self) -> Duration {
+        if self.frames.is_empty() {
+            return Duration::ZERO;
+        }
+        let total = self.frames.iter().map(|f| f.frame_time).sum::<Duration>();
+        total / self.frames.len() as u32
+    }
+
+    // the frame time this share of the recent frames came in under, by nearest rank
+    pub fn frame_time_percentile(&self, quantile: f32) -> Duration {
+        let mut times = self.frames.iter().map(|f| f.frame_time).collect::<Vec<_>>();
+        if times.is_empty() {
+            return Duration::ZERO;
+        }
+        times.sort();
+        let rank = (times.len() as f32 * quantile).ceil() as usize;
+        times[rank.clamp(1, times.len()) - 1]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_a_bounded_history() {
+        let mut diagnostics = Diagnostics::new();
+        for i in 0..HISTORY_LEN + 10 {
+            diagnostics.current.tiles_drawn = i;
+            diagnostics.finish_frame(Duration::from_millis(10), Duration::from_millis(2));
+        }
+        assert_eq!(diagnostics.frames().count(), HISTORY_LEN);
+        assert_eq!(diagnostics.frames().next().unwrap().tiles_drawn, 10);
+        assert_eq!(diagnostics.latest().unwrap().tiles_drawn, HISTORY_LEN + 9);
+        assert_eq!(diagnostics.average_frame_time(), Duration::from_millis(10));
+        // the stats for the next frame start out empty
+        assert_eq!(diagnostics.current, FrameStats::default());
+    }
+
+    #[test]
+    fn frame_time_percentiles() {
+        let mut diagnostics = Diagnostics::new();
+        assert_eq!(diagnostics.frame_time_percentile(0.5), Duration::ZERO);
+        for ms in (1..=100).rev() {
+            diagnostics.finish_frame(Duration::from_millis(ms), Duration::ZERO);
+        }
+        assert_eq!(
+            diagnostics.frame_time_percentile(0.5),
+            Duration::from_millis(50)
+        );
+        assert_eq!(
+            diagnostics.frame_time_percentile(0.99),
+            Duration::from_millis(99)
+        );
+        assert_eq!(
+            diagnostics.frame_time_percentile(1.0),
+            Duration::from_millis(100)
+        );
+        assert_eq!(
+            diagnostics.frame_time_percentile(0.0),
+            Duration::from_millis(1)
+        );
+    }
+}
@@ -1,351 +1,2934 @@
+use crate::analysis::Analysis;
+use crate::batch::TileBatch;
+use crate::calendar::{self, DailyLog};
+use crate::command::CommandBar;
+use crate::config::{Config, TextureFilter};
+use crate::custom::{CustomBoard, MAX_DENSITY, MIN_DENSITY};
+use crate::diagnostics::Diagnostics;
+use crate::errors::Subsystem;
+#[cfg(feature = "debug")]
+use crate::generation::GenerationStats;
+use crate::hud::{self, CounterState, HudLayout, ModeEntry, HUD_MARGIN};
+use crate::leaderboard::Category;
+use crate::losses::LossCause;
+use crate::openings::{RegionStats, REGIONS};
+use crate::opponent::Opponent;
+use crate::preview;
+use crate::profile::Profiles;
+use crate::scene::{
+    CalendarState, ConfirmState, CustomOption, CustomState, GameOverOption, GameOverState,
+    OnboardingState, OnboardingStep, PauseOption, PauseState, ProfileOption, ProfileState,
+    ScoresState, SettingsOption, SettingsState, StatsState, TitleOption, TitleState,
+};
+use crate::scores::{Column, NameEntry, PLACES};
+use crate::sketch::Sketch;
+use crate::solver::{Certainty, HeatHint, TileHint};
 use crate::sprites::GameMode;
+use crate::tabs::TabKey;
+use crate::theme::{self, Presets, Theme};
+use crate::toast::Toast;
+use crate::trainer::Report;
 use crate::{sprites, MenuState};
 
-use super::board::{BoardSettings, MENU_HEIGHT_PERCENT};
-use super::game::{Engine, TileState};
+use super::board::{get_tile_rect, BoardSettings, CellCoord, ScreenPoint, HUD_BASE_HEIGHT};
+use super::board::{Lens, LENS_ZOOM};
+use super::game::{Annotation, Engine, Generation, GridSize, Mark, TileState};
 use super::sprites::{BlockKind, BombKind, FaceKind, Number, Sprite, SpriteManager};
+use chrono::Datelike;
 use ggez::graphics::{Canvas, Color, DrawMode, DrawParam, Mesh, PxScale, Rect, Text};
-use ggez::graphics::{Drawable, TextFragment};
+use ggez::graphics::{Drawable, Image, Sampler, TextFragment};
 use ggez::Context;
+use std::time::Duration;
 
-const TILE_PX: f32 = 24.0;
+// size of a tile sprite in the sprite sheet
+pub const TILE_PX: f32 = 24.0;
+
+// where the HUD and board sit on screen, built once per frame in MainState::draw
+pub struct Viewport<'a> {
+    pub screen_dim: (f32, f32),
+    pub hud_h: f32,
+    pub tile_size: (f32, f32),
+    // the board's top left corner, past the HUD and label gutter
+    pub origin: ScreenPoint,
+    pub board_settings: &'a BoardSettings,
+    // the tile held down by the left mouse button
+    pub pressed_tile: Option<usize>,
+    // the tiles a held chord would reveal
+    pub chord_tiles: &'a [usize],
+    // the keyboard cursor, once an arrow key has been pressed
+    pub cursor: Option<usize>,
+    // by tile index, opened tiles the reveal animation hasn't reached yet
+    pub cascading: &'a [bool],
+}
+
+impl Viewport<'_> {
+    pub fn tile_rect(&self, index: usize) -> Rect {
+        let cell = CellCoord::from_index(index, self.board_settings.size().width);
+        get_tile_rect(cell, self.tile_size, self.origin)
+    }
+
+    pub fn is_pressed(&self, index: usize) -> bool {
+        self.pressed_tile == Some(index) || self.chord_tiles.contains(&index)
+    }
+
+    pub fn tile_sprite(&self, engine: &Engine, index: usize) -> Sprite {
+        if self.cascading.get(index) == Some(&true) {
+            return Sprite::Block(BlockKind::Solid);
+        }
+        tile_sprite(engine, index, self.is_pressed(index))
+    }
+}
 
 pub fn draw_tiles(
-    _ctx: &mut Context,
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    viewport: &Viewport,
+    engine: &Engine,
+    sprite_manager: &SpriteManager,
+    texture_filter: TextureFilter,
+    batch: &mut TileBatch,
+) -> usize {
+    set_tile_sampler(canvas, texture_filter);
+    let mut drawn = batch.draw(ctx, canvas, viewport, engine, sprite_manager);
+    for i in batch.missing() {
+        let sprite = viewport.tile_sprite(engine, *i);
+        draw_safe_mode_tile(ctx, canvas, sprite, viewport.tile_rect(*i));
+        drawn += 1;
+    }
+    for i in 0..engine.get_tiles().len() {
+        drawn += draw_tile_marks(ctx, canvas, viewport, engine, i);
+    }
+    // the HUD and text keep the default sampler
+    canvas.set_sampler(Sampler::linear_clamp());
+    drawn
+}
+
+// the board again, zoomed around the pointer and cut to a circle one scissored slice at
+// a time; only the tiles under the lens are drawn
+#[allow(clippy::too_many_arguments)]
+pub fn draw_lens(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    viewport: &Viewport,
+    lens: Lens,
+    engine: &Engine,
+    sprite_manager: &SpriteManager,
+    texture_filter: TextureFilter,
+    theme: &Theme,
+) -> usize {
+    let zoomed = Viewport {
+        tile_size: (
+            viewport.tile_size.0 * LENS_ZOOM,
+            viewport.tile_size.1 * LENS_ZOOM,
+        ),
+        origin: lens.origin(viewport.origin),
+        ..*viewport
+    };
+    let bounds = Rect::new(
+        lens.center.x - lens.radius,
+        lens.center.y - lens.radius,
+        lens.radius * 2.0,
+        lens.radius * 2.0,
+    );
+    let under_lens = (0..engine.get_tiles().len())
+        .filter(|i| zoomed.tile_rect(*i).overlaps(&bounds))
+        .collect::<Vec<_>>();
+    let center = [lens.center.x, lens.center.y];
+    let backdrop = Mesh::new_circle(
+        ctx,
+        DrawMode::fill(),
+        center,
+        lens.radius,
+        0.5,
+        theme.background,
+    )
+    .unwrap();
+    canvas.draw(&backdrop, DrawParam::default());
+
+    set_tile_sampler(canvas, texture_filter);
+    let mut drawn = 0;
+    for strip in lens.strips(viewport.screen_dim) {
+        if canvas.set_scissor_rect(strip).is_err() {
+            continue;
+        }
+        for i in &under_lens {
+            drawn += draw_tile(ctx, canvas, &zoomed, engine, sprite_manager, *i);
+        }
+    }
+    canvas.set_default_scissor_rect();
+    canvas.set_sampler(Sampler::linear_clamp());
+
+    let width = (lens.radius * 0.03).max(2.0);
+    let rim = Mesh::new_circle(
+        ctx,
+        DrawMode::stroke(width),
+        center,
+        lens.radius,
+        0.5,
+        theme.accent,
+    )
+    .unwrap();
+    canvas.draw(&rim, DrawParam::default());
+    drawn
+}
+
+fn set_tile_sampler(canvas: &mut Canvas, texture_filter: TextureFilter) {
+    canvas.set_sampler(match texture_filter {
+        TextureFilter::Nearest => Sampler::nearest_clamp(),
+        TextureFilter::Linear => Sampler::linear_clamp(),
+    });
+}
+
+// one tile and its marks; returns how many things were drawn
+fn draw_tile(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    viewport: &Viewport,
+    engine: &Engine,
+    sprite_manager: &SpriteManager,
+    i: usize,
+) -> usize {
+    let tile_size = viewport.tile_size;
+    let Rect { x, y, .. } = viewport.tile_rect(i);
+    let sprite = viewport.tile_sprite(engine, i);
+    match sprite_manager.get_uv(sprite) {
+        Some((atlas, uv)) => atlas.draw(
+            canvas,
+            DrawParam::new().src(uv).dest_rect(Rect::new(
+                x,
+                y,
+                tile_size.0 / TILE_PX,
+                tile_size.1 / TILE_PX,
+            )),
+        ),
+        None => draw_safe_mode_tile(ctx, canvas, sprite, viewport.tile_rect(i)),
+    }
+    1 + draw_tile_marks(ctx, canvas, viewport, engine, i)
+}
+
+// annotations, auto-flag marks and the cursor over a tile's sprite
+fn draw_tile_marks(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    viewport: &Viewport,
+    engine: &Engine,
+    i: usize,
+) -> usize {
+    let tile_size = viewport.tile_size;
+    let tile = &engine.get_tiles()[i];
+    let Rect { x, y, .. } = viewport.tile_rect(i);
+    let rect = Rect::new(x, y, tile_size.0, tile_size.1);
+    let mut drawn = 0;
+
+    if tile.state == TileState::Block(Mark::Unmarked) {
+        if let Some(annotation) = engine.annotation(i) {
+            draw_annotation(ctx, canvas, annotation, rect);
+            drawn += 1;
+        }
+    }
+
+    if tile.state == TileState::Block(Mark::Flagged) && engine.is_auto_flagged(i) {
+        draw_auto_flag_mark(ctx, canvas, rect);
+        drawn += 1;
+    }
+
+    if viewport.cursor == Some(i) {
+        let width = (tile_size.0.min(tile_size.1) * 0.12).max(2.0);
+        let outline = Mesh::new_rectangle(
+            ctx,
+            DrawMode::stroke(width),
+            rect,
+            Color::from_rgb(255, 200, 0),
+        )
+        .unwrap();
+        canvas.draw(&outline, DrawParam::default());
+        drawn += 1;
+    }
+    drawn
+}
+
+// what a tile is drawn as; kept apart from the drawing so boards can be checked without a window
+pub fn tile_sprite(engine: &Engine, index: usize, pressed: bool) -> Sprite {
+    let tile = &engine.get_tiles()[index];
+    match tile.state {
+        TileState::Block(Mark::Unmarked | Mark::Questioned) if tile.is_bomb() && pressed => {
+            Sprite::Block(BlockKind::Revealed)
+        }
+
+        TileState::Block(Mark::Unmarked | Mark::Questioned)
+            if engine.is_lost() && tile.is_bomb() =>
+        {
+            Sprite::Bomb(BombKind::NotRevealed)
+        }
+
+        TileState::Block(Mark::Flagged) if engine.is_lost() && !tile.is_bomb() => {
+            Sprite::Bomb(BombKind::FlaggedWrong)
+        }
+
+        TileState::Block(_) if pressed => Sprite::Block(BlockKind::Revealed),
+
+        // Normal block - solid, flagged or question marked
+        TileState::Block(mark) => Sprite::Block(match mark {
+            Mark::Unmarked => BlockKind::Solid,
+            Mark::Flagged => BlockKind::Flagged,
+            Mark::Questioned => BlockKind::Questioned,
+        }),
+
+        TileState::Revealed if tile.num_adjacent_bombs() > 0 && !tile.is_bomb() => {
+            Sprite::Digit(Number(tile.num_adjacent_bombs() as u8))
+        }
+
+        TileState::Revealed if tile.is_bomb() => Sprite::Bomb(BombKind::Clicked),
+
+        TileState::Revealed => Sprite::Block(BlockKind::Revealed),
+    }
+}
+
+// stands in for a tile sprite that failed to load
+fn draw_safe_mode_tile(ctx: &mut Context, canvas: &mut Canvas, sprite: Sprite, rect: Rect) {
+    let (fill, label) = match sprite {
+        Sprite::Block(BlockKind::Solid) => (Color::from_rgb(120, 120, 120), String::new()),
+        Sprite::Block(BlockKind::Flagged) => (Color::from_rgb(120, 120, 120), "F".to_string()),
+        Sprite::Block(BlockKind::Questioned) => (Color::from_rgb(120, 120, 120), "?".to_string()),
+        Sprite::Digit(n) => (Color::from_rgb(190, 190, 190), n.0.to_string()),
+        Sprite::Bomb(BombKind::Clicked) => (Color::RED, "*".to_string()),
+        Sprite::Bomb(BombKind::FlaggedWrong) => (Color::from_rgb(190, 190, 190), "X".to_string()),
+        Sprite::Bomb(_) => (Color::from_rgb(190, 190, 190), "*".to_string()),
+        _ => (Color::from_rgb(190, 190, 190), String::new()),
+    };
+    let tile = Mesh::new_rectangle(ctx, DrawMode::fill(), rect, fill).unwrap();
+    let border = Mesh::new_rectangle(ctx, DrawMode::stroke(1.0), rect, Color::BLACK).unwrap();
+    canvas.draw(&tile, DrawParam::default());
+    canvas.draw(&border, DrawParam::default());
+    if label.is_empty() {
+        return;
+    }
+    let text = Text::new(
+        TextFragment::new(label)
+            .color(Color::BLACK)
+            .font("pressStart2P")
+            .scale(PxScale::from(rect.h * 0.5)),
+    );
+    let text_dimensions = text.dimensions(ctx).unwrap();
+    canvas.draw(
+        &text,
+        DrawParam::new().dest([
+            rect.x + (rect.w - text_dimensions.w) / 2.0,
+            rect.y + (rect.h - text_dimensions.h) / 2.0,
+        ]),
+    );
+}
+
+// labels are one-based, the way players count tiles
+pub fn draw_coordinates(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    viewport: &Viewport,
+    hovered_tile_idx: Option<usize>,
+    theme: &Theme,
+) {
+    let Viewport {
+        screen_dim,
+        hud_h: menu_h,
+        tile_size,
+        origin,
+        ..
+    } = *viewport;
+    let GridSize {
+        width: w,
+        height: h,
+    } = viewport.board_settings.size();
+    let hovered = hovered_tile_idx.map(|i| CellCoord::from_index(i, w));
+    let label_color = |highlighted: bool| {
+        if highlighted {
+            theme.accent
+        } else {
+            theme.text
+        }
+    };
+
+    // two digit column labels have to fit in one tile
+    let column_scale = (origin.x * 0.5).min(tile_size.0 * 0.4);
+    for x in 0..w {
+        let text = Text::new(
+            TextFragment::new((x + 1).to_string())
+                .color(label_color(hovered.is_some_and(|cell| cell.col == x)))
+                .font("pressStart2P")
+                .scale(PxScale::from(column_scale)),
+        );
+        let text_dimensions = text.dimensions(ctx).unwrap();
+        canvas.draw(
+            &text,
+            DrawParam::new().dest([
+                origin.x + x as f32 * tile_size.0 + (tile_size.0 - text_dimensions.w) / 2.0,
+                menu_h + (origin.x - text_dimensions.h) / 2.0,
+            ]),
+        );
+    }
+
+    let row_scale = (origin.x * 0.4).min(tile_size.1 * 0.6);
+    for y in 0..h {
+        let text = Text::new(
+            TextFragment::new((y + 1).to_string())
+                .color(label_color(hovered.is_some_and(|cell| cell.row == y)))
+                .font("pressStart2P")
+                .scale(PxScale::from(row_scale)),
+        );
+        let text_dimensions = text.dimensions(ctx).unwrap();
+        canvas.draw(
+            &text,
+            DrawParam::new().dest([
+                (origin.x - text_dimensions.w) / 2.0,
+                origin.y + y as f32 * tile_size.1 + (tile_size.1 - text_dimensions.h) / 2.0,
+            ]),
+        );
+    }
+
+    let Some(cell) = hovered else {
+        return;
+    };
+    // centered in the HUD gap right of the face
+    let (gap_left, gap_right) = HudLayout::new(screen_dim.0, menu_h).gap;
+    let text = Text::new(
+        TextFragment::new(format!("{},{}", cell.col + 1, cell.row + 1))
+            .color(theme.text)
+            .font("pressStart2P")
+            .scale(PxScale::from(menu_h * 0.2)),
+    );
+    let text_dimensions = text.dimensions(ctx).unwrap();
+    if text_dimensions.w > gap_right - gap_left {
+        return;
+    }
+    canvas.draw(
+        &text,
+        DrawParam::new().dest([
+            gap_left + (gap_right - gap_left - text_dimensions.w) / 2.0,
+            (menu_h - text_dimensions.h) / 2.0,
+        ]),
+    );
+}
+
+// the leaderboard the game counts towards, along the top of the gap the coordinates use
+pub fn draw_category(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    viewport: &Viewport,
+    category: &Category,
+    theme: &Theme,
+) {
+    let (screen_dim, menu_h) = (viewport.screen_dim, viewport.hud_h);
+    let margin = HUD_MARGIN * menu_h / HUD_BASE_HEIGHT;
+    let (gap_left, gap_right) = HudLayout::new(screen_dim.0, menu_h).gap;
+    let text = Text::new(
+        TextFragment::new(category.key().to_uppercase())
+            .color(theme.text)
+            .font("pressStart2P")
+            .scale(PxScale::from(menu_h * 0.12)),
+    );
+    let text_dimensions = text.dimensions(ctx).unwrap();
+    if text_dimensions.w > gap_right - gap_left {
+        return;
+    }
+    canvas.draw(
+        &text,
+        DrawParam::new().dest([
+            gap_left + (gap_right - gap_left - text_dimensions.w) / 2.0,
+            margin,
+        ]),
+    );
+}
+
+// along the bottom of the same gap, dim so it reads as a note rather than a warning
+pub fn draw_guess_notice(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    viewport: &Viewport,
+    theme: &Theme,
+) {
+    let (screen_dim, menu_h) = (viewport.screen_dim, viewport.hud_h);
+    let margin = HUD_MARGIN * menu_h / HUD_BASE_HEIGHT;
+    let (gap_left, gap_right) = HudLayout::new(screen_dim.0, menu_h).gap;
+    let mut color = theme.text;
+    color.a = 0.6;
+    let text = Text::new(
+        TextFragment::new("GUESS REQUIRED")
+            .color(color)
+            .font("pressStart2P")
+            .scale(PxScale::from(menu_h * 0.12)),
+    );
+    let text_dimensions = text.dimensions(ctx).unwrap();
+    if text_dimensions.w > gap_right - gap_left {
+        return;
+    }
+    canvas.draw(
+        &text,
+        DrawParam::new().dest([
+            gap_left + (gap_right - gap_left - text_dimensions.w) / 2.0,
+            menu_h - margin - text_dimensions.h,
+        ]),
+    );
+}
+
+// drawn above the tiles, the strokes never take part in hit-testing
+pub fn draw_sketch(ctx: &mut Context, canvas: &mut Canvas, viewport: &Viewport, sketch: &Sketch) {
+    let Viewport {
+        tile_size, origin, ..
+    } = *viewport;
+    for stroke in sketch.strokes() {
+        if stroke.len() < 2 {
+            continue;
+        }
+        let points = stroke
+            .iter()
+            .map(|[x, y]| [x * tile_size.0 + origin.x, y * tile_size.1 + origin.y])
+            .collect::<Vec<_>>();
+        let line = Mesh::new_line(
+            ctx,
+            &points,
+            tile_size.0.min(tile_size.1) * 0.1,
+            Color::new(1.0, 0.2, 0.8, 0.8),
+        )
+        .unwrap();
+        canvas.draw(&line, DrawParam::default());
+    }
+}
+
+fn draw_annotation(ctx: &mut Context, canvas: &mut Canvas, annotation: Annotation, tile: Rect) {
+    let color = match annotation {
+        Annotation::Red => Color::from_rgb(220, 40, 40),
+        Annotation::Green => Color::from_rgb(40, 180, 40),
+        Annotation::Blue => Color::from_rgb(40, 80, 220),
+    };
+    let dot = Mesh::new_circle(
+        ctx,
+        DrawMode::fill(),
+        [tile.x + tile.w / 2.0, tile.y + tile.h / 2.0],
+        tile.w.min(tile.h) * 0.18,
+        0.5,
+        color,
+    )
+    .unwrap();
+    canvas.draw(&dot, DrawParam::default());
+}
+
+// a small corner badge telling the assist's flags apart from the player's
+fn draw_auto_flag_mark(ctx: &mut Context, canvas: &mut Canvas, tile: Rect) {
+    let size = tile.w.min(tile.h) * 0.3;
+    let badge = Rect::new(tile.right() - size * 1.2, tile.y + size * 0.2, size, size);
+    let mark =
+        Mesh::new_rectangle(ctx, DrawMode::fill(), badge, Color::from_rgb(40, 80, 220)).unwrap();
+    canvas.draw(&mark, DrawParam::default());
+}
+
+// shades the tiles the personal best had already opened at this point of the game
+pub fn draw_ghost(
+    ctx: &mut Context,
     canvas: &mut Canvas,
+    viewport: &Viewport,
     engine: &Engine,
+    ghost_engine: &Engine,
+) {
+    let Viewport {
+        tile_size, origin, ..
+    } = *viewport;
+    let ghost_tiles = ghost_engine.get_tiles();
+    for (i, tile) in engine.get_tiles().iter().enumerate() {
+        if tile.is_revealed() || !ghost_tiles[i].is_revealed() {
+            continue;
+        }
+        let rect = viewport.tile_rect(i);
+        let shade =
+            Mesh::new_rectangle(ctx, DrawMode::fill(), rect, Color::new(0.6, 0.8, 1.0, 0.35))
+                .unwrap();
+        canvas.draw(&shade, DrawParam::default());
+    }
+
+    let (label, color) = if engine.progress() >= ghost_engine.progress() {
+        ("AHEAD", Color::from_rgb(0, 255, 0))
+    } else {
+        ("BEHIND", Color::from_rgb(255, 0, 0))
+    };
+    let text = Text::new(
+        TextFragment::new(format!("GHOST: {}", label))
+            .color(color)
+            .font("pressStart2P")
+            .scale(PxScale::from(tile_size.1 * 0.4)),
+    );
+    let text_dimensions = text.dimensions(ctx).unwrap();
+    let padding = tile_size.1 * 0.1;
+    let text_bg = Mesh::new_rectangle(
+        ctx,
+        DrawMode::fill(),
+        Rect::new(
+            origin.x,
+            origin.y,
+            text_dimensions.w + padding * 2.0,
+            text_dimensions.h + padding * 2.0,
+        ),
+        Color::new(0.0, 0.0, 0.0, 0.6),
+    )
+    .unwrap();
+    canvas.draw(&text_bg, DrawParam::default());
+    canvas.draw(
+        &text,
+        DrawParam::new().dest([origin.x + padding, origin.y + padding]),
+    );
+}
+
+// name, face, mines left and progress of the other player; collapsed it's just a tab
+pub fn draw_opponent_panel(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
     sprite_manager: &SpriteManager,
-    tile_size: (f32, f32),
-    board_settings: &BoardSettings,
+    rect: Rect,
+    collapsed: bool,
+    opponent: &Opponent,
+    theme: &Theme,
+) {
+    let panel = Mesh::new_rectangle(ctx, DrawMode::fill(), rect, theme.panel).unwrap();
+    canvas.draw(&panel, DrawParam::default());
+
+    if collapsed {
+        let text = Text::new(
+            TextFragment::new("<")
+                .color(theme.panel_text)
+                .font("pressStart2P")
+                .scale(PxScale::from(rect.w * 0.8)),
+        );
+        let text_dimensions = text.dimensions(ctx).unwrap();
+        canvas.draw(
+            &text,
+            DrawParam::new().dest([
+                rect.x + (rect.w - text_dimensions.w) / 2.0,
+                rect.y + (rect.h - text_dimensions.h) / 2.0,
+            ]),
+        );
+        return;
+    }
+
+    let padding = rect.h * 0.08;
+    let face_size = rect.h * 0.45;
+    if let Some((atlas, uv)) = sprite_manager.get_uv(Sprite::Face(opponent.face)) {
+        atlas.draw(
+            canvas,
+            DrawParam::new().src(uv).dest_rect(Rect::new(
+                rect.x + padding,
+                rect.y + padding,
+                face_size / TILE_PX,
+                face_size / TILE_PX,
+            )),
+        );
+    }
+
+    let text_x = rect.x + padding * 2.0 + face_size;
+    let name = Text::new(
+        TextFragment::new(opponent.name.clone())
+            .color(theme.panel_text)
+            .font("pressStart2P")
+            .scale(PxScale::from(face_size * 0.35)),
+    );
+    canvas.draw(&name, DrawParam::new().dest([text_x, rect.y + padding]));
+
+    let mines = Text::new(
+        TextFragment::new(format!("{:03}", opponent.mines_left))
+            .color(theme.alert)
+            .font("pressStart2P")
+            .scale(PxScale::from(face_size * 0.35)),
+    );
+    canvas.draw(
+        &mines,
+        DrawParam::new().dest([text_x, rect.y + padding + face_size * 0.55]),
+    );
+
+    let bar_h = rect.h * 0.15;
+    let bar = Rect::new(
+        rect.x + padding,
+        rect.bottom() - padding - bar_h,
+        rect.w - padding * 2.0,
+        bar_h,
+    );
+    let bar_bg = Mesh::new_rectangle(ctx, DrawMode::stroke(1.0), bar, theme.panel_text).unwrap();
+    canvas.draw(&bar_bg, DrawParam::default());
+    if opponent.progress > 0.0 {
+        let fill = Rect::new(bar.x, bar.y, bar.w * opponent.progress.min(1.0), bar.h);
+        let bar_fill =
+            Mesh::new_rectangle(ctx, DrawMode::fill(), fill, Color::from_rgb(0, 200, 0)).unwrap();
+        canvas.draw(&bar_fill, DrawParam::default());
+    }
+}
+
+pub fn draw_flash(ctx: &mut Context, canvas: &mut Canvas, viewport: &Viewport, index: usize) {
+    let rect = viewport.tile_rect(index);
+    let width = (rect.w.min(rect.h) * 0.15).max(2.0);
+    let outline = Mesh::new_rectangle(
+        ctx,
+        DrawMode::stroke(width),
+        rect,
+        Color::from_rgb(255, 255, 0),
+    )
+    .unwrap();
+    canvas.draw(&outline, DrawParam::default());
+}
+
+// the tile a second tap will reveal
+pub fn draw_selection(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    viewport: &Viewport,
+    index: usize,
+    theme: &Theme,
+) {
+    let rect = viewport.tile_rect(index);
+    let width = (rect.w.min(rect.h) * 0.1).max(2.0);
+    let outline = Mesh::new_rectangle(ctx, DrawMode::stroke(width), rect, theme.accent).unwrap();
+    canvas.draw(&outline, DrawParam::default());
+}
+
+// covered tiles tinted green, yellow or red by how sure the solver is about them
+pub fn draw_heat_hint(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    viewport: &Viewport,
+    engine: &Engine,
+    hint: &HeatHint,
+) {
+    for (i, tile) in engine.get_tiles().iter().enumerate() {
+        if tile.is_revealed() {
+            continue;
+        }
+        let color = match hint.certainty(i) {
+            Certainty::Safe => Color::from_rgba(40, 200, 40, 90),
+            Certainty::Unknown => Color::from_rgba(230, 200, 0, 70),
+            Certainty::Mine => Color::from_rgba(220, 40, 40, 90),
+        };
+        let tint =
+            Mesh::new_rectangle(ctx, DrawMode::fill(), viewport.tile_rect(i), color).unwrap();
+        canvas.draw(&tint, DrawParam::default());
+    }
+}
+
+// the tile the hint button found: green to open, red to flag
+pub fn draw_tile_hint(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    viewport: &Viewport,
+    hint: &TileHint,
+) {
+    let rect = viewport.tile_rect(hint.index);
+    let color = match hint.certainty {
+        Certainty::Mine => Color::from_rgb(220, 40, 40),
+        _ => Color::from_rgb(40, 200, 40),
+    };
+    let width = (rect.w.min(rect.h) * 0.15).max(2.0);
+    let outline = Mesh::new_rectangle(ctx, DrawMode::stroke(width), rect, color).unwrap();
+    let tint = Mesh::new_rectangle(
+        ctx,
+        DrawMode::fill(),
+        rect,
+        Color::new(color.r, color.g, color.b, 0.35),
+    )
+    .unwrap();
+    canvas.draw(&tint, DrawParam::default());
+    canvas.draw(&outline, DrawParam::default());
+}
+
+// after a won board in the trainer: a dot on each tile of the shortest solve, and the
+// clicks used against it along the bottom of the board
+pub fn draw_trainer_report(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    viewport: &Viewport,
+    report: &Report,
+    theme: &Theme,
+) {
+    for index in &report.optimal {
+        let rect = viewport.tile_rect(*index);
+        let dot = Mesh::new_circle(
+            ctx,
+            DrawMode::fill(),
+            [rect.x + rect.w / 2.0, rect.y + rect.h / 2.0],
+            rect.w.min(rect.h) * 0.15,
+            0.5,
+            theme.accent,
+        )
+        .unwrap();
+        canvas.draw(&dot, DrawParam::default());
+    }
+
+    let lines = [
+        format!(
+            "CLICKS {}  3BV {}  {:.0}%",
+            report.clicks,
+            report.optimal.len(),
+            report.efficiency() * 100.0
+        ),
+        format!(
+            "WASTED FLAGS {}  CHORDS {}",
+            report.wasted_flags, report.wasted_chords
+        ),
+    ];
+    let size = viewport.tile_size.1 * 0.35;
+    let padding = size * 0.5;
+    let board_h = viewport.board_settings.size().height as f32 * viewport.tile_size.1;
+    let panel_h = size * 2.0 + padding * 3.0;
+    let panel = Rect::new(
+        viewport.origin.x,
+        viewport.origin.y + board_h - panel_h,
+        viewport.board_settings.size().width as f32 * viewport.tile_size.0,
+        panel_h,
+    );
+    let background = Mesh::new_rectangle(
+        ctx,
+        DrawMode::fill(),
+        panel,
+        Color::new(0.0, 0.0, 0.0, 0.75),
+    )
+    .unwrap();
+    canvas.draw(&background, DrawParam::default());
+    for (i, line) in lines.into_iter().enumerate() {
+        let text = Text::new(
+            TextFragment::new(line)
+                .color(Color::WHITE)
+                .font("pressStart2P")
+                .scale(PxScale::from(size)),
+        );
+        let text_dimensions = text.dimensions(ctx).unwrap();
+        canvas.draw(
+            &text,
+            DrawParam::new().dest([
+                panel.x + (panel.w - text_dimensions.w) / 2.0,
+                panel.y + padding + i as f32 * (size + padding),
+            ]),
+        );
+    }
+}
+
+// a strip along the bottom of the window, like a terminal prompt
+pub fn draw_command_bar(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
     screen_dim: (f32, f32),
-    current_tile_idx: Option<usize>,
+    command_bar: &CommandBar,
 ) {
-    let img_w = tile_size.0 / TILE_PX;
-    let img_h = tile_size.1 / TILE_PX;
+    let (screen_w, screen_h) = screen_dim;
+    let bar_h = screen_h * 0.06;
+    let bar = Mesh::new_rectangle(
+        ctx,
+        DrawMode::fill(),
+        Rect::new(0.0, screen_h - bar_h, screen_w, bar_h),
+        Color::new(0.0, 0.0, 0.0, 0.8),
+    )
+    .unwrap();
+    canvas.draw(&bar, DrawParam::default());
+
+    let (line, color) = match &command_bar.error {
+        Some(error) => (error.to_uppercase(), Color::from_rgb(255, 0, 0)),
+        None => (format!(":{}_", command_bar.input), Color::WHITE),
+    };
+    let text = Text::new(
+        TextFragment::new(line)
+            .color(color)
+            .font("pressStart2P")
+            .scale(PxScale::from(bar_h * 0.4)),
+    );
+    let text_dimensions = text.dimensions(ctx).unwrap();
+    canvas.draw(
+        &text,
+        DrawParam::new().dest([bar_h * 0.3, screen_h - (bar_h + text_dimensions.h) / 2.0]),
+    );
+}
+
+// centered along the bottom edge, clear of the HUD and the error banner
+pub fn draw_toast(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    screen_dim: (f32, f32),
+    toast: &Toast,
+    theme: &Theme,
+) {
+    let text = Text::new(
+        TextFragment::new(toast.message.as_str())
+            .color(theme.panel_text)
+            .font("pressStart2P")
+            .scale(PxScale::from(16.0)),
+    );
+    let text_dimensions = text.dimensions(ctx).unwrap();
+    let padding = 8.0;
+    let rect = Rect::new(
+        (screen_dim.0 - text_dimensions.w) / 2.0 - padding,
+        screen_dim.1 - text_dimensions.h - 3.0 * padding,
+        text_dimensions.w + 2.0 * padding,
+        text_dimensions.h + 2.0 * padding,
+    );
+    let background = Mesh::new_rectangle(ctx, DrawMode::fill(), rect, theme.panel).unwrap();
+    canvas.draw(&background, DrawParam::default());
+    canvas.draw(
+        &text,
+        DrawParam::new().dest([rect.x + padding, rect.y + padding]),
+    );
+}
+
+// under the pointer, kept inside the window
+pub fn draw_tooltip(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    screen_dim: (f32, f32),
+    anchor: [f32; 2],
+    lines: &[String],
+    theme: &Theme,
+) {
+    let text = Text::new(
+        TextFragment::new(lines.join("\n"))
+            .color(theme.panel_text)
+            .font("pressStart2P")
+            .scale(PxScale::from(12.0)),
+    );
+    let text_dimensions = text.dimensions(ctx).unwrap();
+    let padding = 6.0;
+    let (w, h) = (
+        text_dimensions.w + 2.0 * padding,
+        text_dimensions.h + 2.0 * padding,
+    );
+    let rect = Rect::new(
+        anchor[0].min(screen_dim.0 - w).max(0.0),
+        (anchor[1] + 20.0).min(screen_dim.1 - h).max(0.0),
+        w,
+        h,
+    );
+    let background = Mesh::new_rectangle(ctx, DrawMode::fill(), rect, theme.panel).unwrap();
+    let border = Mesh::new_rectangle(ctx, DrawMode::stroke(1.0), rect, theme.panel_text).unwrap();
+    canvas.draw(&background, DrawParam::default());
+    canvas.draw(&border, DrawParam::default());
+    canvas.draw(
+        &text,
+        DrawParam::new().dest([rect.x + padding, rect.y + padding]),
+    );
+}
+
+// the analysis timeline: a tick per move, filled up to the moment on the board
+pub fn draw_scrubber(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    rect: Rect,
+    analysis: &Analysis,
+    theme: &Theme,
+) {
+    let track = Mesh::new_rectangle(ctx, DrawMode::fill(), rect, theme.panel).unwrap();
+    canvas.draw(&track, DrawParam::default());
+
+    let x_at = |fraction: f32| rect.x + rect.w * fraction;
+    let duration = analysis.duration().as_secs_f32();
+    let fill = Rect::new(rect.x, rect.y, rect.w * analysis.progress(), rect.h);
+    if fill.w > 0.0 {
+        let fill = Mesh::new_rectangle(ctx, DrawMode::fill(), fill, theme.accent).unwrap();
+        canvas.draw(&fill, DrawParam::default());
+    }
+    for m in analysis.moves() {
+        let fraction = if duration > 0.0 {
+            m.time.as_secs_f32() / duration
+        } else {
+            1.0
+        };
+        let tick = Rect::new(x_at(fraction), rect.y + rect.h * 0.7, 1.0, rect.h * 0.3);
+        let tick = Mesh::new_rectangle(ctx, DrawMode::fill(), tick, theme.panel_text).unwrap();
+        canvas.draw(&tick, DrawParam::default());
+    }
+
+    let label = match analysis.branch() {
+        Some(branch) => format!(
+            "WHAT-IF FROM MOVE {}: {}  GUESSES {} VS {}",
+            branch.fork(),
+            branch.verdict().label(),
+            branch.report().guesses,
+            branch.original().guesses
+        ),
+        None => format!(
+            "{:.1}s  MOVE {}/{}",
+            analysis.engine().elapsed().as_secs_f32(),
+            analysis.position(),
+            analysis.num_moves()
+        ),
+    };
+    let label = Text::new(
+        TextFragment::new(label)
+            .color(theme.panel_text)
+            .font("pressStart2P")
+            .scale(PxScale::from(rect.h * 0.4)),
+    );
+    canvas.draw(
+        &label,
+        DrawParam::new().dest([rect.x + rect.h * 0.3, rect.y + rect.h * 0.15]),
+    );
+}
+
+pub fn draw_error_banner(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    rect: Rect,
+    errors: &[(Subsystem, String)],
+    theme: &Theme,
+) {
+    let banner = Mesh::new_rectangle(ctx, DrawMode::fill(), rect, theme.panel).unwrap();
+    canvas.draw(&banner, DrawParam::default());
+
+    let line_h = rect.h / (errors.len() + 1) as f32;
+    let lines = errors
+        .iter()
+        .map(|(subsystem, message)| {
+            (
+                format!("{}: {}", subsystem.label(), message.to_uppercase()),
+                theme.alert,
+            )
+        })
+        .chain(std::iter::once((
+            "CLICK TO DISMISS".to_string(),
+            theme.disabled_text,
+        )));
+    for (i, (line, color)) in lines.enumerate() {
+        let text = Text::new(
+            TextFragment::new(line)
+                .color(color)
+                .font("pressStart2P")
+                .scale(PxScale::from(line_h * 0.5)),
+        );
+        let text_dimensions = text.dimensions(ctx).unwrap();
+        canvas.draw(
+            &text,
+            DrawParam::new().dest([
+                line_h * 0.3,
+                rect.y + i as f32 * line_h + (line_h - text_dimensions.h) / 2.0,
+            ]),
+        );
+    }
+}
+
+fn draw_face(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    button: Rect,
+    sprite_manager: &SpriteManager,
+    menu_state: &mut MenuState,
+) {
+    menu_state.face_rect = Some(button);
+
+    match sprite_manager.get_uv(Sprite::Face(menu_state.face_kind)) {
+        Some((atlas, uv)) => {
+            let scale = button.w / TILE_PX;
+            let face_rect = Rect::new(button.x, button.y, scale, scale);
+            atlas.draw(canvas, DrawParam::new().src(uv).dest_rect(face_rect));
+        }
+        // still a button to restart with
+        None => {
+            let plain = Mesh::new_rectangle(ctx, DrawMode::fill(), button, Color::YELLOW).unwrap();
+            canvas.draw(&plain, DrawParam::default());
+        }
+    }
+}
+
+fn draw_timer(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    timer_rect: Rect,
+    engine: &Engine,
+    menu_state: &mut MenuState,
+    config: &Config,
+) {
+    let Rect {
+        x: timer_x,
+        w: timer_w,
+        h: timer_h,
+        ..
+    } = timer_rect;
+    menu_state.timer_rect = Some(timer_rect);
+    let timer_bg =
+        Mesh::new_rectangle(ctx, DrawMode::fill(), timer_rect, config.theme.panel).unwrap();
+
+    canvas.draw(&timer_bg, DrawParam::default());
+
+    // penalties are added as they are taken, so the clock always shows the time to beat
+    let time = engine.summary(config.penalties).final_time();
+    let mut time_text = config.timer_display.format(time.as_secs());
+    // casual undos stay next to the time, right through to the final result
+    if engine.undos() > 0 {
+        time_text.push_str(&format!("+{}", engine.undos()));
+    }
+
+    let text_scale = PxScale {
+        x: timer_w / time_text.len() as f32,
+        y: timer_h,
+    };
+
+    let text_fragment = TextFragment::new(time_text)
+        .color(config.theme.accent)
+        .font("pressStart2P")
+        .scale(text_scale);
+
+    let text = Text::new(text_fragment);
+
+    let text_dimensions = text.dimensions(ctx).unwrap();
+    let text_x = ((timer_w - text_dimensions.w) / 2.0) + timer_x;
+    let text_y = counter_text_y(timer_rect, text_dimensions.h);
+
+    // Draw text
+    canvas.draw(&text, DrawParam::new().dest([text_x, text_y]));
+}
+
+// the font sits high in its box, so the digits go a little below the middle
+fn counter_text_y(rect: Rect, text_h: f32) -> f32 {
+    rect.y + (rect.h - text_h) / 2.0 + rect.h / 18.0
+}
+
+fn draw_bombs_left(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    counter_rect: Rect,
+    engine: &Engine,
+    menu_state: &mut MenuState,
+    theme: &Theme,
+) {
+    let Rect {
+        x: counter_x,
+        w: counter_w,
+        h: counter_h,
+        ..
+    } = counter_rect;
+    menu_state.counter_rect = Some(counter_rect);
+    let counter_bg = Mesh::new_rectangle(ctx, DrawMode::fill(), counter_rect, theme.panel).unwrap();
+
+    canvas.draw(&counter_bg, DrawParam::default());
+
+    let bombs_left = engine.mines_left();
+    let bombs_text = format!("{:03}", bombs_left);
+
+    let text_scale = PxScale {
+        x: counter_w / bombs_text.len() as f32,
+        y: counter_h,
+    };
+
+    let color = match menu_state.counter_state {
+        CounterState::Normal => theme.alert,
+        CounterState::Low => theme.accent,
+        CounterState::Over { lit: true } => theme.alert,
+        CounterState::Over { lit: false } => theme.panel,
+    };
+
+    let text_fragment = TextFragment::new(bombs_text)
+        .color(color)
+        .font("pressStart2P")
+        .scale(text_scale);
+
+    let text = Text::new(text_fragment);
+
+    let text_dimensions = text.dimensions(ctx).unwrap();
+    let text_x = ((counter_w - text_dimensions.w) / 2.0) + counter_x;
+    let text_y = counter_text_y(counter_rect, text_dimensions.h);
+
+    // Draw text
+    canvas.draw(&text, DrawParam::new().dest([text_x, text_y]));
+}
+
+fn draw_mode_button(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    rect: Rect,
+    label: &str,
+    theme: &Theme,
+) {
+    let bg = Mesh::new_rectangle(ctx, DrawMode::fill(), rect, theme.panel).unwrap();
+    canvas.draw(&bg, DrawParam::default());
+    let text = Text::new(
+        TextFragment::new(label)
+            .color(theme.panel_text)
+            .font("pressStart2P")
+            .scale(PxScale {
+                x: rect.w / label.len() as f32,
+                y: rect.h,
+            }),
+    );
+    let text_dimensions = text.dimensions(ctx).unwrap();
+    canvas.draw(
+        &text,
+        DrawParam::new().dest([
+            rect.x + (rect.w - text_dimensions.w) / 2.0,
+            rect.y + (rect.h - text_dimensions.h) / 2.0,
+        ]),
+    );
+}
+
+// the open mode list, drawn last so it hangs over the rest of the HUD and the board
+fn draw_mode_list(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    screen_w: f32,
+    modes: &[ModeEntry],
+    menu_state: &mut MenuState,
+    theme: &Theme,
+) {
+    menu_state.mode_items.clear();
+    let Some(button) = menu_state
+        .mode_button_rect
+        .filter(|_| menu_state.mode_list_open)
+    else {
+        return;
+    };
+    let rects = hud::mode_list_rects(button, modes.len(), screen_w);
+    for (entry, rect) in modes.iter().zip(rects) {
+        draw_mode_button(ctx, canvas, rect, &entry.label(), theme);
+        menu_state.mode_items.push((*entry, rect));
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn draw_menu(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    viewport: &Viewport,
+    engine: &Engine,
+    sprite_manager: &SpriteManager,
+    menu_state: &mut MenuState,
+    tabs: &[(TabKey, u64, bool)],
+    modes: &[ModeEntry],
+    config: &Config,
+) {
+    let layout = HudLayout::new(viewport.screen_dim.0, viewport.hud_h);
+    draw_face(ctx, canvas, layout.face, sprite_manager, menu_state);
+    draw_tabs(ctx, canvas, viewport, tabs, menu_state, &config.theme);
+
+    // hidden counters get no tooltips
+    menu_state.timer_rect = None;
+    menu_state.counter_rect = None;
+
+    if config.hud.show_timer {
+        draw_timer(ctx, canvas, layout.timer, engine, menu_state, config);
+    }
+
+    if config.hud.show_bomb_counter {
+        draw_bombs_left(
+            ctx,
+            canvas,
+            layout.counter,
+            engine,
+            menu_state,
+            &config.theme,
+        );
+    }
+
+    // a hidden button must not react to clicks, nor leave its list open
+    menu_state.mode_button_rect = None;
+    if config.hud.show_difficulty_buttons {
+        draw_mode_button(ctx, canvas, layout.mode, "MODE", &config.theme);
+        menu_state.mode_button_rect = Some(layout.mode);
+    } else {
+        menu_state.mode_list_open = false;
+    }
+
+    menu_state.hint_button_rect = None;
+    if config.hud.show_hint_button {
+        draw_hint_button(ctx, canvas, viewport, &layout, menu_state, config);
+    }
+
+    if config.hud.show_progress_bar {
+        draw_progress_bar(ctx, canvas, viewport, engine, &config.theme);
+    }
+
+    draw_mode_list(
+        ctx,
+        canvas,
+        viewport.screen_dim.0,
+        modes,
+        menu_state,
+        &config.theme,
+    );
+}
+
+// just left of the face; left out when the buttons beside the counter leave no room for it
+fn draw_hint_button(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    viewport: &Viewport,
+    layout: &HudLayout,
+    menu_state: &mut MenuState,
+    config: &Config,
+) {
+    let menu_h = viewport.hud_h;
+    let margin = HUD_MARGIN * menu_h / HUD_BASE_HEIGHT;
+    let left = if config.hud.show_difficulty_buttons || layout.narrow {
+        layout.left_edge
+    } else {
+        layout.counter.right() + margin / 2.0
+    };
+    let btn_w = menu_h * 0.9;
+    let btn_h = menu_h * 0.35;
+    let btn_x = layout.face.x - margin / 2.0 - btn_w;
+    if btn_x < left {
+        return;
+    }
+    let rect = Rect::new(btn_x, (menu_h - btn_h) / 2.0, btn_w, btn_h);
+    draw_list_button(
+        ctx,
+        canvas,
+        rect,
+        "HINT",
+        config.theme.panel_text,
+        &config.theme,
+    );
+    menu_state.hint_button_rect = Some(rect);
+}
+
+// the open boards between the face and the timer, three to a column, each with the
+// seconds on its clock; the one being played is lit
+fn draw_tabs(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    viewport: &Viewport,
+    tabs: &[(TabKey, u64, bool)],
+    menu_state: &mut MenuState,
+    theme: &Theme,
+) {
+    menu_state.tab_rects.clear();
+    if tabs.is_empty() {
+        return;
+    }
+    let (screen_dim, menu_h) = (viewport.screen_dim, viewport.hud_h);
+    let padding = 4.0 * menu_h / HUD_BASE_HEIGHT;
+    let (left, right) = HudLayout::new(screen_dim.0, menu_h).gap;
+    let columns = tabs.len().div_ceil(3);
+    let tab_w = (right - left - padding * (columns - 1) as f32) / columns as f32;
+    let tab_h = (menu_h * 0.9 - padding * 2.0) / 3.0;
+    if tab_w <= 0.0 {
+        return;
+    }
+
+    for (i, (key, secs, active)) in tabs.iter().enumerate() {
+        let rect = Rect::new(
+            left + (i / 3) as f32 * (tab_w + padding),
+            menu_h * 0.05 + (i % 3) as f32 * (tab_h + padding),
+            tab_w,
+            tab_h,
+        );
+        let (fill, text_color) = if *active {
+            (theme.accent, theme.panel)
+        } else {
+            (theme.panel, theme.panel_text)
+        };
+        let bg = Mesh::new_rectangle(ctx, DrawMode::fill(), rect, fill).unwrap();
+        canvas.draw(&bg, DrawParam::default());
+
+        let label = format!("{} {}", key.label(), (*secs).min(999));
+        let text = Text::new(
+            TextFragment::new(label.as_str())
+                .color(text_color)
+                .font("pressStart2P")
+                .scale(PxScale {
+                    x: (tab_w / (label.len() + 1) as f32).min(tab_h * 0.7),
+                    y: tab_h * 0.7,
+                }),
+        );
+        let dims = text.dimensions(ctx).unwrap();
+        canvas.draw(
+            &text,
+            DrawParam::new().dest([
+                rect.x + (rect.w - dims.w) / 2.0,
+                rect.y + (rect.h - dims.h) / 2.0,
+            ]),
+        );
+        menu_state.tab_rects.push((*key, rect));
+    }
+}
+
+fn draw_progress_bar(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    viewport: &Viewport,
+    engine: &Engine,
+    theme: &Theme,
+) {
+    let (screen_dim, menu_h) = (viewport.screen_dim, viewport.hud_h);
+    // thin strip along the bottom edge of the HUD
+    let bar_h = menu_h * 0.04;
+    let bar_y = menu_h - bar_h;
+
+    let bar_bg = Mesh::new_rectangle(
+        ctx,
+        DrawMode::fill(),
+        Rect::new(0.0, bar_y, screen_dim.0, bar_h),
+        theme.panel,
+    )
+    .unwrap();
+    canvas.draw(&bar_bg, DrawParam::default());
+
+    let progress_w = screen_dim.0 * engine.progress();
+    if progress_w <= 0.0 {
+        return;
+    }
+
+    let bar_fill = Mesh::new_rectangle(
+        ctx,
+        DrawMode::fill(),
+        Rect::new(0.0, bar_y, progress_w, bar_h),
+        Color::from_rgb(0, 200, 0),
+    )
+    .unwrap();
+    canvas.draw(&bar_fill, DrawParam::default());
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn draw_title(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    sprite_manager: &SpriteManager,
+    screen_dim: (f32, f32),
+    title_state: &mut TitleState,
+    profile_name: &str,
+    hint: Option<&str>,
+    continue_thumbnail: Option<&Image>,
+    theme: &Theme,
+) {
+    let (screen_w, screen_h) = screen_dim;
+
+    let logo_y = screen_h * 0.08;
+    // without the logo sprite the title is plain text of about the same height
+    let logo = sprite_manager
+        .get_uv(Sprite::Logo)
+        .zip(sprite_manager.size(Sprite::Logo));
+    let logo_h = match logo {
+        Some(((atlas, uv), (logo_w, logo_h))) => {
+            let logo_scale = (screen_w * 0.6) / logo_w;
+            let logo_w = logo_w * logo_scale;
+            atlas.draw(
+                canvas,
+                DrawParam::new().src(uv).dest_rect(Rect::new(
+                    (screen_w - logo_w) / 2.0,
+                    logo_y,
+                    logo_scale,
+                    logo_scale,
+                )),
+            );
+            logo_h * logo_scale
+        }
+        None => {
+            let text = Text::new(
+                TextFragment::new("RUST SWEEPER")
+                    .color(theme.text)
+                    .font("pressStart2P")
+                    .scale(PxScale::from(screen_w * 0.05)),
+            );
+            let text_dimensions = text.dimensions(ctx).unwrap();
+            canvas.draw(
+                &text,
+                DrawParam::new().dest([(screen_w - text_dimensions.w) / 2.0, logo_y]),
+            );
+            text_dimensions.h * 2.0
+        }
+    };
+
+    let profile_text = Text::new(
+        TextFragment::new(format!("PLAYER: {}", profile_name.to_uppercase()))
+            .color(theme.text)
+            .font("pressStart2P")
+            .scale(PxScale::from(screen_h * 0.03)),
+    );
+    let profile_dimensions = profile_text.dimensions(ctx).unwrap();
+    canvas.draw(
+        &profile_text,
+        DrawParam::new().dest([
+            (screen_w - profile_dimensions.w) / 2.0,
+            logo_y + logo_h + screen_h * 0.02,
+        ]),
+    );
+
+    let btn_w = screen_w * 0.4;
+    // ten buttons and the hint still fit under the logo on the easy window
+    let btn_h = screen_h * 0.041;
+    let padding = btn_h * 0.25;
+    let btn_x = (screen_w - btn_w) / 2.0;
+    let first_btn_y = logo_y + logo_h + screen_h * 0.08;
+
+    title_state.buttons.clear();
+
+    for (i, option) in TitleOption::ALL.iter().enumerate() {
+        let btn_y = first_btn_y + i as f32 * (btn_h + padding);
+        let btn = Rect::new(btn_x, btn_y, btn_w, btn_h);
+        title_state.buttons.push((*option, btn));
+
+        let btn_bg = Mesh::new_rectangle(ctx, DrawMode::fill(), btn, theme.panel).unwrap();
+        canvas.draw(&btn_bg, DrawParam::default());
+
+        let text_color = if title_state.is_enabled(*option) {
+            theme.panel_text
+        } else {
+            theme.disabled_text
+        };
+
+        let label = option.label();
+        let text_fragment = TextFragment::new(label)
+            .color(text_color)
+            .font("pressStart2P")
+            .scale(PxScale {
+                x: (btn_w * 0.8) / 10.0,
+                y: btn_h * 0.6,
+            });
+        let text = Text::new(text_fragment);
+        let text_dimensions = text.dimensions(ctx).unwrap();
+
+        canvas.draw(
+            &text,
+            DrawParam::new().dest([
+                btn_x + (btn_w - text_dimensions.w) / 2.0,
+                btn_y + (btn_h - text_dimensions.h) / 2.0,
+            ]),
+        );
+    }
+
+    // level with CONTINUE, the first button, in the margin right of them all
+    if let Some(thumbnail) = continue_thumbnail {
+        let box_x = btn_x + btn_w + padding;
+        let box_w = screen_w - box_x - padding;
+        let box_h = screen_h - first_btn_y - padding;
+        let scale = (box_w / thumbnail.width() as f32)
+            .min(box_h / thumbnail.height() as f32)
+            .min(1.0);
+        if scale > 0.0 {
+            let frame = Rect::new(
+                box_x - 2.0,
+                first_btn_y - 2.0,
+                thumbnail.width() as f32 * scale + 4.0,
+                thumbnail.height() as f32 * scale + 4.0,
+            );
+            let border = Mesh::new_rectangle(ctx, DrawMode::fill(), frame, theme.panel).unwrap();
+            canvas.draw(&border, DrawParam::default());
+            canvas.draw(
+                thumbnail,
+                DrawParam::new()
+                    .dest([box_x, first_btn_y])
+                    .scale([scale, scale]),
+            );
+        }
+    }
+
+    draw_focus_ring(ctx, canvas, title_state.focused_rect(), theme);
+
+    if let Some(hint) = hint {
+        let hint_y = first_btn_y + TitleOption::ALL.len() as f32 * (btn_h + padding);
+        draw_centered_label(
+            ctx,
+            canvas,
+            hint,
+            screen_w,
+            hint_y,
+            screen_h * 0.025,
+            theme.text,
+        );
+    }
+}
+
+pub fn draw_pause(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    screen_dim: (f32, f32),
+    hud_h: f32,
+    theme: &Theme,
+) {
+    let (screen_w, screen_h) = screen_dim;
+
+    // hide the board so a pause can't be used to study it
+    let cover = Mesh::new_rectangle(
+        ctx,
+        DrawMode::fill(),
+        Rect::new(0.0, hud_h, screen_w, screen_h - hud_h),
+        theme.panel,
+    )
+    .unwrap();
+    canvas.draw(&cover, DrawParam::default());
+
+    for (label, y, size) in [
+        ("PAUSED", 0.45, 0.06),
+        ("CLICK OR PRESS ANY KEY", 0.55, 0.025),
+    ] {
+        let text = Text::new(
+            TextFragment::new(label)
+                .color(theme.panel_text)
+                .font("pressStart2P")
+                .scale(PxScale::from(screen_h * size)),
+        );
+        let text_dimensions = text.dimensions(ctx).unwrap();
+        canvas.draw(
+            &text,
+            DrawParam::new().dest([(screen_w - text_dimensions.w) / 2.0, screen_h * y]),
+        );
+    }
+}
+
+// the board dimmed nearly to black rather than hidden, under a column of choices
+pub fn draw_pause_menu(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    screen_dim: (f32, f32),
+    pause_state: &mut PauseState,
+    theme: &Theme,
+) {
+    let (screen_w, screen_h) = screen_dim;
+    let dim = Mesh::new_rectangle(
+        ctx,
+        DrawMode::fill(),
+        Rect::new(0.0, 0.0, screen_w, screen_h),
+        Color::from_rgba(0, 0, 0, 220),
+    )
+    .unwrap();
+    canvas.draw(&dim, DrawParam::default());
+
+    let btn_w = screen_w * 0.4;
+    let btn_h = screen_h * 0.07;
+    let padding = btn_h * 0.3;
+    let btn_x = (screen_w - btn_w) / 2.0;
+    let column_h = PauseOption::ALL.len() as f32 * (btn_h + padding) - padding;
+    let first_btn_y = (screen_h - column_h) / 2.0;
+
+    draw_centered_label(
+        ctx,
+        canvas,
+        "PAUSED",
+        screen_w,
+        first_btn_y - btn_h * 2.0,
+        screen_h * 0.06,
+        theme.panel_text,
+    );
+
+    pause_state.buttons.clear();
+    for (i, option) in PauseOption::ALL.iter().enumerate() {
+        let rect = Rect::new(
+            btn_x,
+            first_btn_y + i as f32 * (btn_h + padding),
+            btn_w,
+            btn_h,
+        );
+        pause_state.buttons.push((*option, rect));
+        draw_list_button(ctx, canvas, rect, option.label(), theme.panel_text, theme);
+    }
+
+    draw_focus_ring(ctx, canvas, pause_state.focused_rect(), theme);
+}
+
+pub fn draw_countdown(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    screen_dim: (f32, f32),
+    hud_h: f32,
+    remaining: Duration,
+    theme: &Theme,
+) {
+    let (screen_w, screen_h) = screen_dim;
+
+    let shade = Mesh::new_rectangle(
+        ctx,
+        DrawMode::fill(),
+        Rect::new(0.0, hud_h, screen_w, screen_h - hud_h),
+        Color::new(0.0, 0.0, 0.0, 0.5),
+    )
+    .unwrap();
+    canvas.draw(&shade, DrawParam::default());
+
+    let count = remaining.as_secs_f32().ceil() as u32;
+    let text = Text::new(
+        TextFragment::new(count.to_string())
+            .color(theme.accent)
+            .font("pressStart2P")
+            .scale(PxScale::from(screen_h * 0.2)),
+    );
+    let text_dimensions = text.dimensions(ctx).unwrap();
+    canvas.draw(
+        &text,
+        DrawParam::new().dest([
+            (screen_w - text_dimensions.w) / 2.0,
+            hud_h + (screen_h - hud_h - text_dimensions.h) / 2.0,
+        ]),
+    );
+}
+
+pub fn draw_confirm(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    screen_dim: (f32, f32),
+    message: &str,
+    confirm_state: &mut ConfirmState,
+    theme: &Theme,
+) {
+    let (screen_w, screen_h) = screen_dim;
+
+    let dim = Mesh::new_rectangle(
+        ctx,
+        DrawMode::fill(),
+        Rect::new(0.0, 0.0, screen_w, screen_h),
+        Color::from_rgba(0, 0, 0, 150),
+    )
+    .unwrap();
+    canvas.draw(&dim, DrawParam::default());
+
+    let panel_w = screen_w * 0.5;
+    let panel_h = screen_h * 0.3;
+    let panel_x = (screen_w - panel_w) / 2.0;
+    let panel_y = (screen_h - panel_h) / 2.0;
+
+    let panel = Mesh::new_rectangle(
+        ctx,
+        DrawMode::fill(),
+        Rect::new(panel_x, panel_y, panel_w, panel_h),
+        theme.panel,
+    )
+    .unwrap();
+    canvas.draw(&panel, DrawParam::default());
+
+    let message_text = Text::new(
+        TextFragment::new(message)
+            .color(theme.panel_text)
+            .font("pressStart2P")
+            .scale(PxScale::from(panel_h * 0.15)),
+    );
+    let message_dimensions = message_text.dimensions(ctx).unwrap();
+    canvas.draw(
+        &message_text,
+        DrawParam::new().dest([
+            panel_x + (panel_w - message_dimensions.w) / 2.0,
+            panel_y + panel_h * 0.2,
+        ]),
+    );
+
+    let btn_w = panel_w * 0.3;
+    let btn_h = panel_h * 0.25;
+    let btn_y = panel_y + panel_h * 0.6;
+    let yes_btn = Rect::new(panel_x + panel_w * 0.15, btn_y, btn_w, btn_h);
+    let no_btn = Rect::new(panel_x + panel_w * 0.55, btn_y, btn_w, btn_h);
+
+    confirm_state.yes_rect = Some(yes_btn);
+    confirm_state.no_rect = Some(no_btn);
+
+    for (btn, label) in [(yes_btn, "YES"), (no_btn, "NO")] {
+        let btn_bg = Mesh::new_rectangle(ctx, DrawMode::fill(), btn, theme.background).unwrap();
+        canvas.draw(&btn_bg, DrawParam::default());
+
+        let text = Text::new(
+            TextFragment::new(label)
+                .color(theme.text)
+                .font("pressStart2P")
+                .scale(PxScale::from(btn_h * 0.5)),
+        );
+        let text_dimensions = text.dimensions(ctx).unwrap();
+        canvas.draw(
+            &text,
+            DrawParam::new().dest([
+                btn.x + (btn.w - text_dimensions.w) / 2.0,
+                btn.y + (btn.h - text_dimensions.h) / 2.0,
+            ]),
+        );
+    }
+
+    draw_focus_ring(ctx, canvas, confirm_state.focused_rect(), theme);
+}
+
+// the finished game's numbers, over the board it was played on
+pub fn draw_game_over(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    screen_dim: (f32, f32),
+    game_over: &mut GameOverState,
+    theme: &Theme,
+) {
+    let (screen_w, screen_h) = screen_dim;
+
+    let dim = Mesh::new_rectangle(
+        ctx,
+        DrawMode::fill(),
+        Rect::new(0.0, 0.0, screen_w, screen_h),
+        Color::from_rgba(0, 0, 0, 150),
+    )
+    .unwrap();
+    canvas.draw(&dim, DrawParam::default());
+
+    let panel_w = screen_w * 0.6;
+    let panel_h = screen_h * 0.6;
+    let panel_x = (screen_w - panel_w) / 2.0;
+    let panel_y = (screen_h - panel_h) / 2.0;
+
+    let panel = Mesh::new_rectangle(
+        ctx,
+        DrawMode::fill(),
+        Rect::new(panel_x, panel_y, panel_w, panel_h),
+        theme.panel,
+    )
+    .unwrap();
+    canvas.draw(&panel, DrawParam::default());
+
+    let stats = game_over.stats;
+    let title = if stats.won { "YOU WIN" } else { "GAME OVER" };
+    draw_centered_label(
+        ctx,
+        canvas,
+        title,
+        screen_w,
+        panel_y + panel_h * 0.08,
+        panel_h * 0.08,
+        theme.panel_text,
+    );
+
+    // a lost board's efficiency would only measure how soon the mine was hit
+    let efficiency = stats
+        .efficiency
+        .map_or("-".to_string(), |efficiency| format!("{:.0}%", efficiency));
+    let lines = [
+        ("TIME", format!("{:.2}", stats.time.as_secs_f32())),
+        ("3BV", stats.three_bv.to_string()),
+        ("CLICKS", stats.clicks.to_string()),
+        ("EFFICIENCY", efficiency),
+    ];
+    let line_h = panel_h * 0.09;
+    let scale = PxScale::from(line_h * 0.6);
+    for (i, (label, value)) in lines.iter().enumerate() {
+        let y = panel_y + panel_h * 0.25 + i as f32 * line_h;
+        let label_text = Text::new(
+            TextFragment::new(*label)
+                .color(theme.panel_text)
+                .font("pressStart2P")
+                .scale(scale),
+        );
+        canvas.draw(
+            &label_text,
+            DrawParam::new().dest([panel_x + panel_w * 0.1, y]),
+        );
+        let value_text = Text::new(
+            TextFragment::new(value.as_str())
+                .color(theme.panel_text)
+                .font("pressStart2P")
+                .scale(scale),
+        );
+        let value_w = value_text.dimensions(ctx).unwrap().w;
+        canvas.draw(
+            &value_text,
+            DrawParam::new().dest([panel_x + panel_w * 0.9 - value_w, y]),
+        );
+    }
+
+    let btn_w = panel_w * 0.8;
+    let btn_h = panel_h * 0.1;
+    let padding = btn_h * 0.3;
+    let btn_x = panel_x + (panel_w - btn_w) / 2.0;
+    let first_btn_y = panel_y + panel_h * 0.68;
+
+    game_over.buttons.clear();
+    for (i, option) in GameOverOption::ALL.iter().enumerate() {
+        let rect = Rect::new(
+            btn_x,
+            first_btn_y + i as f32 * (btn_h + padding),
+            btn_w,
+            btn_h,
+        );
+        game_over.buttons.push((*option, rect));
+
+        let btn_bg = Mesh::new_rectangle(ctx, DrawMode::fill(), rect, theme.background).unwrap();
+        canvas.draw(&btn_bg, DrawParam::default());
+        let text = Text::new(
+            TextFragment::new(option.label())
+                .color(theme.text)
+                .font("pressStart2P")
+                .scale(PxScale::from(btn_h * 0.5)),
+        );
+        let text_dimensions = text.dimensions(ctx).unwrap();
+        canvas.draw(
+            &text,
+            DrawParam::new().dest([
+                rect.x + (rect.w - text_dimensions.w) / 2.0,
+                rect.y + (rect.h - text_dimensions.h) / 2.0,
+            ]),
+        );
+    }
+
+    draw_focus_ring(ctx, canvas, game_over.focused_rect(), theme);
+}
+
+// an outline around the button the keyboard or gamepad is on
+fn draw_focus_ring(ctx: &mut Context, canvas: &mut Canvas, rect: Option<Rect>, theme: &Theme) {
+    let Some(rect) = rect else {
+        return;
+    };
+    let width = (rect.h * 0.08).max(2.0);
+    let ring = Mesh::new_rectangle(ctx, DrawMode::stroke(width), rect, theme.accent).unwrap();
+    canvas.draw(&ring, DrawParam::default());
+}
+
+fn draw_list_button(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    rect: Rect,
+    label: &str,
+    text_color: Color,
+    theme: &Theme,
+) {
+    let btn_bg = Mesh::new_rectangle(ctx, DrawMode::fill(), rect, theme.panel).unwrap();
+    canvas.draw(&btn_bg, DrawParam::default());
+
+    let text = Text::new(
+        TextFragment::new(label)
+            .color(text_color)
+            .font("pressStart2P")
+            .scale(PxScale::from(rect.h * 0.5)),
+    );
+    let text_dimensions = text.dimensions(ctx).unwrap();
+    canvas.draw(
+        &text,
+        DrawParam::new().dest([
+            rect.x + (rect.w - text_dimensions.w) / 2.0,
+            rect.y + (rect.h - text_dimensions.h) / 2.0,
+        ]),
+    );
+}
+
+pub fn draw_profiles(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    screen_dim: (f32, f32),
+    profiles: &Profiles,
+    profile_state: &mut ProfileState,
+    theme: &Theme,
+) {
+    let (screen_w, screen_h) = screen_dim;
+
+    let btn_w = screen_w * 0.5;
+    let btn_h = screen_h * 0.07;
+    let padding = btn_h * 0.25;
+    let btn_x = (screen_w - btn_w) / 2.0;
+    let mut btn_y = screen_h * 0.08;
+
+    profile_state.buttons.clear();
+
+    if let Some(name) = &profile_state.new_name {
+        let prompt = format!("NAME: {}_", name.to_uppercase());
+        let rect = Rect::new(btn_x, btn_y, btn_w, btn_h);
+        draw_list_button(ctx, canvas, rect, &prompt, theme.accent, theme);
+        return;
+    }
+
+    for (i, name) in profiles.names.iter().enumerate() {
+        let rect = Rect::new(btn_x, btn_y, btn_w, btn_h);
+        let text_color = if *name == profiles.active {
+            theme.accent
+        } else {
+            theme.panel_text
+        };
+        draw_list_button(ctx, canvas, rect, &name.to_uppercase(), text_color, theme);
+        profile_state.buttons.push((ProfileOption::Select(i), rect));
+        btn_y += btn_h + padding;
+    }
+
+    for (option, label) in [
+        (ProfileOption::New, "NEW PROFILE"),
+        (ProfileOption::Back, "BACK"),
+    ] {
+        let rect = Rect::new(btn_x, btn_y, btn_w, btn_h);
+        draw_list_button(ctx, canvas, rect, label, theme.panel_text, theme);
+        profile_state.buttons.push((option, rect));
+        btn_y += btn_h + padding;
+    }
+
+    draw_focus_ring(ctx, canvas, profile_state.focused_rect(), theme);
+}
+
+pub fn draw_custom(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    screen_dim: (f32, f32),
+    board: &CustomBoard,
+    generation: Generation,
+    custom_state: &mut CustomState,
+    theme: &Theme,
+) {
+    let (screen_w, screen_h) = screen_dim;
+    let row_h = screen_h * 0.07;
+    let padding = row_h * 0.25;
+    let label_size = row_h * 0.4;
+
+    draw_centered_label(
+        ctx,
+        canvas,
+        "CUSTOM BOARD",
+        screen_w,
+        screen_h * 0.06,
+        screen_h * 0.05,
+        theme.text,
+    );
+
+    custom_state.buttons.clear();
+
+    // a label between a minus and a plus button for each side
+    let stepper_w = screen_w * 0.5;
+    let stepper_x = (screen_w - stepper_w) / 2.0;
+    let mut y = screen_h * 0.18;
+    for (label, value, step) in [
+        ("WIDTH", board.width, (1, 0)),
+        ("HEIGHT", board.height, (0, 1)),
+    ] {
+        let minus = Rect::new(stepper_x, y, row_h, row_h);
+        let plus = Rect::new(stepper_x + stepper_w - row_h, y, row_h, row_h);
+        draw_list_button(ctx, canvas, minus, "-", theme.panel_text, theme);
+        draw_list_button(ctx, canvas, plus, "+", theme.panel_text, theme);
+        custom_state
+            .buttons
+            .push((CustomOption::Resize(-step.0, -step.1), minus));
+        custom_state
+            .buttons
+            .push((CustomOption::Resize(step.0, step.1), plus));
+
+        draw_centered_label(
+            ctx,
+            canvas,
+            &format!("{} {}", label, value),
+            screen_w,
+            y + (row_h - label_size) / 2.0,
+            label_size,
+            theme.text,
+        );
+        y += row_h + padding;
+    }
+
+    y += padding;
+    draw_centered_label(
+        ctx,
+        canvas,
+        &format!("MINES {}% ({})", board.density, board.num_bombs()),
+        screen_w,
+        y,
+        label_size,
+        theme.text,
+    );
+    y += label_size + padding;
 
-    let (w, _) = board_settings.dimensions();
-    for (i, tile) in engine.get_tiles().iter().enumerate() {
-        let x = (i % w as usize) as f32 * tile_size.0;
-        let y = ((i / w as usize) as f32 * tile_size.1) + (screen_dim.1 * MENU_HEIGHT_PERCENT);
-
-        let is_current_tile = current_tile_idx == Some(i);
-
-        let img = match tile.state {
-            TileState::Block(false) if tile.is_bomb() && is_current_tile => sprite_manager
-                .get(Sprite::Block(BlockKind::Revealed))
-                .unwrap(),
-
-            TileState::Block(false) if engine.is_lost() && tile.is_bomb() => sprite_manager
-                .get(Sprite::Bomb(BombKind::NotRevealed))
-                .unwrap(),
-
-            TileState::Block(true) if engine.is_lost() && !tile.is_bomb() => sprite_manager
-                .get(Sprite::Bomb(BombKind::FlaggedWrong))
-                .unwrap(),
-
-            TileState::Block(_) if is_current_tile => sprite_manager
-                .get(Sprite::Block(BlockKind::Revealed))
-                .unwrap(),
-
-            // Normal block - either flagged or solid
-            TileState::Block(flag) => {
-                let kind = if flag {
-                    BlockKind::Flagged
-                } else {
-                    BlockKind::Solid
-                };
-                sprite_manager.get(Sprite::Block(kind)).unwrap()
-            }
+    let track = Rect::new(stepper_x, y, stepper_w, row_h * 0.5);
+    custom_state.slider_rect = Some(track);
+    let track_bg = Mesh::new_rectangle(ctx, DrawMode::fill(), track, theme.panel).unwrap();
+    canvas.draw(&track_bg, DrawParam::default());
+    let t = (board.density - MIN_DENSITY) as f32 / (MAX_DENSITY - MIN_DENSITY) as f32;
+    let knob_w = track.h * 0.6;
+    let knob = Rect::new(
+        track.x + (track.w - knob_w) * t,
+        track.y - track.h * 0.2,
+        knob_w,
+        track.h * 1.4,
+    );
+    let knob_mesh = Mesh::new_rectangle(ctx, DrawMode::fill(), knob, theme.accent).unwrap();
+    canvas.draw(&knob_mesh, DrawParam::default());
+    y += row_h + padding;
 
-            TileState::Revealed if tile.num_adjacent_bombs() > 0 && !tile.is_bomb() => {
-                let bomb_count = tile.num_adjacent_bombs() as u8;
-                sprite_manager
-                    .get(Sprite::Digit(Number(bomb_count)))
-                    .unwrap()
-            }
+    draw_centered_label(
+        ctx,
+        canvas,
+        &format!("DIFFICULTY: {}", board.rating().label()),
+        screen_w,
+        y,
+        label_size,
+        theme.accent,
+    );
+    y += label_size + padding * 3.0;
 
-            TileState::Revealed if tile.is_bomb() => {
-                sprite_manager.get(Sprite::Bomb(BombKind::Clicked)).unwrap()
-            }
+    let btn_w = screen_w * 0.5;
+    let btn_x = (screen_w - btn_w) / 2.0;
+    let layout = format!("LAYOUT: {}", layout_label(generation));
+    for (option, label) in [
+        (CustomOption::Layout, layout.as_str()),
+        (CustomOption::Start, "START"),
+        (CustomOption::Back, "BACK"),
+    ] {
+        let rect = Rect::new(btn_x, y, btn_w, row_h);
+        draw_list_button(ctx, canvas, rect, label, theme.panel_text, theme);
+        custom_state.buttons.push((option, rect));
+        y += row_h + padding;
+    }
 
-            TileState::Revealed => sprite_manager
-                .get(Sprite::Block(BlockKind::Revealed))
-                .unwrap(),
-        };
-        img.draw(
-            canvas,
-            DrawParam::new().dest_rect(Rect::new(x, y, img_w, img_h)),
-        );
+    draw_focus_ring(ctx, canvas, custom_state.focused_rect(), theme);
+}
+
+fn layout_label(generation: Generation) -> &'static str {
+    match generation {
+        Generation::Plain => "PLAIN",
+        Generation::AntiFiftyFifty => "NO 50/50",
+        Generation::NoGuess => "NO GUESS",
+        Generation::Unprotected => "RISKY START",
     }
 }
 
-fn draw_face(
+// one question at a time with its answers as buttons
+pub fn draw_onboarding(
     ctx: &mut Context,
     canvas: &mut Canvas,
-    engine: &Engine,
-    sprite_manager: &SpriteManager,
-    tile_size: (f32, f32),
-    board_settings: &BoardSettings,
     screen_dim: (f32, f32),
-    current_tile_idx: Option<usize>,
-    menu_state: &mut MenuState,
+    onboarding_state: &mut OnboardingState,
+    theme: &Theme,
 ) {
-    let menu_h = screen_dim.1 * MENU_HEIGHT_PERCENT;
-    let menu_size = menu_h * 0.9;
-    let menu_img_size = menu_size / TILE_PX;
+    let (screen_w, screen_h) = screen_dim;
+    let step = onboarding_state.step;
+    let number = OnboardingStep::ALL
+        .iter()
+        .position(|s| *s == step)
+        .unwrap_or(0);
 
-    let face = sprite_manager
-        .get(Sprite::Face(menu_state.face_kind))
-        .unwrap();
+    draw_centered_label(
+        ctx,
+        canvas,
+        "WELCOME",
+        screen_w,
+        screen_h * 0.06,
+        screen_h * 0.05,
+        theme.text,
+    );
+    draw_centered_label(
+        ctx,
+        canvas,
+        step.question(),
+        screen_w,
+        screen_h * 0.18,
+        screen_h * 0.035,
+        theme.text,
+    );
 
-    let face_x = screen_dim.0 / 2.0 - (0.5 * menu_size);
-    let face_y = (0.1 * menu_h) / 2.0;
+    let btn_w = screen_w * 0.5;
+    let btn_h = screen_h * 0.07;
+    let padding = btn_h * 0.3;
+    let btn_x = (screen_w - btn_w) / 2.0;
+    let mut y = screen_h * 0.3;
+    onboarding_state.buttons.clear();
+    for (i, label) in step.choices().iter().enumerate() {
+        let rect = Rect::new(btn_x, y, btn_w, btn_h);
+        draw_list_button(ctx, canvas, rect, label, theme.panel_text, theme);
+        onboarding_state.buttons.push((i, rect));
+        y += btn_h + padding;
+    }
+    draw_focus_ring(ctx, canvas, onboarding_state.focused_rect(), theme);
 
-    let face_rect = Rect::new(face_x, face_y, menu_img_size, menu_img_size);
-    menu_state.face_rect = Some(Rect::new(face_x, face_y, menu_size, menu_size));
-    face.draw(canvas, DrawParam::new().dest_rect(face_rect));
+    draw_centered_label(
+        ctx,
+        canvas,
+        &format!(
+            "{} OF {} - ESCAPE TO SKIP",
+            number + 1,
+            OnboardingStep::ALL.len()
+        ),
+        screen_w,
+        screen_h * 0.9,
+        screen_h * 0.025,
+        theme.disabled_text,
+    );
 }
 
-fn draw_timer(
+// each option with its current value; clicking one flips or cycles it
+pub fn draw_settings(
     ctx: &mut Context,
     canvas: &mut Canvas,
-    menu_state: &MenuState,
-    tile_size: (f32, f32),
     screen_dim: (f32, f32),
+    config: &Config,
+    presets: &Presets,
+    sprite_manager: &SpriteManager,
+    settings_state: &mut SettingsState,
 ) {
-    let menu_h = screen_dim.1 * MENU_HEIGHT_PERCENT;
-
-    let timer_w = screen_dim.0 * 0.2;
-    let timer_h = menu_h * 0.9;
-
-    let timer_x = screen_dim.0 - timer_w - 20.0; // 20px from right edge
-    let timer_y = (menu_h - timer_h) / 2.0;
+    let theme = &config.theme;
+    let (screen_w, screen_h) = screen_dim;
+    let btn_w = screen_w * 0.6;
+    let btn_h = screen_h * 0.045;
+    let padding = btn_h * 0.25;
+    let btn_x = (screen_w - btn_w) / 2.0;
+    let top = screen_h * 0.16;
+    // the options run down two columns, the preview spans both underneath
+    let rows = SettingsOption::ALL.len().div_ceil(2);
+    let column_gap = screen_w * 0.02;
+    let column_w = (screen_w * 0.9 - column_gap) / 2.0;
+    let y = top + rows as f32 * (btn_h + padding);
 
-    let timer_bg = Mesh::new_rectangle(
+    draw_centered_label(
         ctx,
-        DrawMode::fill(),
-        Rect::new(timer_x, timer_y, timer_w, timer_h),
-        Color::BLACK,
-    )
-    .unwrap();
+        canvas,
+        "SETTINGS",
+        screen_w,
+        screen_h * 0.06,
+        screen_h * 0.05,
+        theme.text,
+    );
 
-    canvas.draw(&timer_bg, DrawParam::default());
+    settings_state.buttons.clear();
+    let on_off = |on: bool| if on { "ON" } else { "OFF" }.to_string();
+    for (i, option) in SettingsOption::ALL.into_iter().enumerate() {
+        let value = match option {
+            SettingsOption::Sounds => on_off(config.sounds),
+            SettingsOption::Volume(bus) => format!("{}%", config.volumes[bus as usize]),
+            SettingsOption::PaceCue => on_off(config.pace_cue),
+            SettingsOption::Announce => match config.announce_every {
+                Some(every) => format!("EVERY {}S", every.as_secs()),
+                None => on_off(false),
+            },
+            SettingsOption::QuestionMarks => on_off(config.question_marks),
+            SettingsOption::SafeFirstClick => on_off(config.safe_first_click()),
+            SettingsOption::LeftHanded => on_off(config.left_handed),
+            SettingsOption::LossPolicy => config.loss_policy.key().to_string(),
+            SettingsOption::AutoChord => on_off(config.auto_chord),
+            SettingsOption::AutoFlag => on_off(config.auto_flag),
+            SettingsOption::MisclickGuard => config.misclick_guard.key().to_string(),
+            SettingsOption::Theme => config.base_theme.to_string(),
+            SettingsOption::Hud(element) => on_off(config.hud.shows(element)),
+            SettingsOption::Back => String::new(),
+        };
+        let label = if value.is_empty() {
+            option.label().to_string()
+        } else {
+            format!("{}: {}", option.label(), value.to_uppercase())
+        };
+        let rect = Rect::new(
+            screen_w * 0.05 + (i / rows) as f32 * (column_w + column_gap),
+            top + (i % rows) as f32 * (btn_h + padding),
+            column_w,
+            btn_h,
+        );
+        draw_list_button(ctx, canvas, rect, &label, theme.panel_text, theme);
+        settings_state.buttons.push((option, rect));
+    }
 
-    let time = menu_state.get_time_passed();
+    draw_focus_ring(ctx, canvas, settings_state.focused_rect(), theme);
 
-    let time_text = format!("{:03}", time);
+    let preview = Rect::new(btn_x, y + padding, btn_w, screen_h - y - padding * 3.0);
+    draw_settings_preview(
+        ctx,
+        canvas,
+        preview,
+        config,
+        presets,
+        settings_state.previewed(),
+        sprite_manager,
+    );
+}
 
-    let text_scale = PxScale {
-        x: timer_w / time_text.len() as f32,
-        y: timer_h,
+// a miniature game drawn with the option under the pointer or focus already changed,
+// so its effect shows before it is applied
+fn draw_settings_preview(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    rect: Rect,
+    config: &Config,
+    presets: &Presets,
+    previewed: Option<SettingsOption>,
+    sprite_manager: &SpriteManager,
+) {
+    let mut theme = presets.get(config.base_theme);
+    let mut question_marks = config.question_marks;
+    let pending = match previewed {
+        Some(SettingsOption::Theme) => {
+            let next = theme::next_preset(config.base_theme);
+            theme = presets.get(next);
+            true
+        }
+        Some(SettingsOption::QuestionMarks) => {
+            question_marks = !question_marks;
+            true
+        }
+        _ => false,
     };
 
-    let text_fragment = TextFragment::new(time_text)
-        .color(Color::from_rgb(255, 255, 0))
-        .font("pressStart2P")
-        .scale(text_scale);
+    let bg = Mesh::new_rectangle(ctx, DrawMode::fill(), rect, theme.background).unwrap();
+    canvas.draw(&bg, DrawParam::default());
+    let engine = preview::preview_engine(question_marks);
 
-    let text = Text::new(text_fragment);
+    // a strip of HUD with the mine counter over the board
+    let inset = rect.h * 0.06;
+    let strip_h = rect.h * 0.2;
+    let counter = Rect::new(rect.x + inset, rect.y + inset, strip_h * 2.0, strip_h);
+    let counter_bg = Mesh::new_rectangle(ctx, DrawMode::fill(), counter, theme.panel).unwrap();
+    canvas.draw(&counter_bg, DrawParam::default());
+    let count = Text::new(
+        TextFragment::new(format!("{:03}", engine.mines_left()))
+            .color(theme.alert)
+            .font("pressStart2P")
+            .scale(PxScale::from(strip_h * 0.7)),
+    );
+    let count_dimensions = count.dimensions(ctx).unwrap();
+    canvas.draw(
+        &count,
+        DrawParam::new().dest([
+            counter.x + (counter.w - count_dimensions.w) / 2.0,
+            counter.y + (counter.h - count_dimensions.h) / 2.0,
+        ]),
+    );
+    let caption = Text::new(
+        TextFragment::new(if pending { "CLICK TO APPLY" } else { "PREVIEW" })
+            .color(theme.text)
+            .font("pressStart2P")
+            .scale(PxScale::from(strip_h * 0.5)),
+    );
+    let caption_dimensions = caption.dimensions(ctx).unwrap();
+    canvas.draw(
+        &caption,
+        DrawParam::new().dest([
+            rect.right() - inset - caption_dimensions.w,
+            counter.y + (counter.h - caption_dimensions.h) / 2.0,
+        ]),
+    );
 
-    let text_dimensions = text.dimensions(ctx).unwrap();
-    let text_x = ((timer_w - text_dimensions.w) / 2.0) + screen_dim.0 - timer_w - 15.0;
-    let text_y = (timer_h - text_dimensions.h) / 2.0 + timer_y * 2.0;
+    let size = preview::PREVIEW_SIZE;
+    let board_top = counter.bottom() + inset;
+    let tile = ((rect.w - inset * 2.0) / size.width as f32)
+        .min((rect.bottom() - inset - board_top) / size.height as f32)
+        .max(1.0);
+    let Ok(board_settings) = BoardSettings::builder()
+        .size(size.width, size.height)
+        .mines(engine.num_bombs())
+        .build()
+    else {
+        return;
+    };
+    let viewport = Viewport {
+        screen_dim: (rect.w, rect.h),
+        hud_h: 0.0,
+        tile_size: (tile, tile),
+        origin: ScreenPoint::new(
+            rect.x + (rect.w - tile * size.width as f32) / 2.0,
+            board_top,
+        ),
+        board_settings: &board_settings,
+        pressed_tile: None,
+        chord_tiles: &[],
+        cursor: None,
+        cascading: &[],
+    };
+    draw_tiles(
+        ctx,
+        canvas,
+        &viewport,
+        &engine,
+        sprite_manager,
+        config.texture_filter,
+        &mut TileBatch::new(),
+    );
+}
 
-    // Draw text
-    canvas.draw(&text, DrawParam::new().dest([text_x, text_y]));
+fn draw_centered_label(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    label: &str,
+    screen_w: f32,
+    y: f32,
+    size: f32,
+    color: Color,
+) {
+    let text = Text::new(
+        TextFragment::new(label)
+            .color(color)
+            .font("pressStart2P")
+            .scale(PxScale::from(size)),
+    );
+    let text_dimensions = text.dimensions(ctx).unwrap();
+    canvas.draw(
+        &text,
+        DrawParam::new().dest([(screen_w - text_dimensions.w) / 2.0, y]),
+    );
 }
 
-fn draw_bombs_left(
+pub fn draw_calendar(
     ctx: &mut Context,
     canvas: &mut Canvas,
-    engine: &Engine,
-    sprite_manager: &SpriteManager,
-    tile_size: (f32, f32),
-    board_settings: &BoardSettings,
-    screen_dim: (f32, f32),
-    current_tile_idx: Option<usize>,
-    menu_state: &mut MenuState,
+    viewport: &Viewport,
+    daily_log: &DailyLog,
+    weekly_best: Option<Duration>,
+    calendar_state: &mut CalendarState,
+    theme: &Theme,
 ) {
-    let menu_h = screen_dim.1 * MENU_HEIGHT_PERCENT;
+    let (screen_w, screen_h) = viewport.screen_dim;
+    let today = calendar::today();
+    let month = calendar::date(today);
+    let first_day = today - month.day0() as i64;
 
-    let counter_w = screen_dim.0 * 0.2;
-    let counter_h = menu_h * 0.9;
+    let heading = format!(
+        "{} {}",
+        calendar::MONTH_NAMES[month.month0() as usize],
+        month.year()
+    );
+    draw_centered_label(
+        ctx,
+        canvas,
+        &heading,
+        screen_w,
+        screen_h * 0.06,
+        screen_h * 0.05,
+        theme.text,
+    );
 
-    let counter_x = 20.0; // 20px from left edge
-    let counter_y = (menu_h - counter_h) / 2.0;
+    // a weekday header row plus up to six weeks
+    let cell = (screen_w * 0.8 / 7.0).min(screen_h * 0.55 / 7.0);
+    let grid_x = (screen_w - cell * 7.0) / 2.0;
+    let grid_y = screen_h * 0.15;
 
-    let counter_bg = Mesh::new_rectangle(
-        ctx,
-        DrawMode::fill(),
-        Rect::new(counter_x, counter_y, counter_w, counter_h),
-        Color::BLACK,
-    )
-    .unwrap();
+    for (i, label) in ["MO", "TU", "WE", "TH", "FR", "SA", "SU"]
+        .iter()
+        .enumerate()
+    {
+        let text = Text::new(
+            TextFragment::new(*label)
+                .color(theme.text)
+                .font("pressStart2P")
+                .scale(PxScale::from(cell * 0.3)),
+        );
+        let text_dimensions = text.dimensions(ctx).unwrap();
+        canvas.draw(
+            &text,
+            DrawParam::new().dest([
+                grid_x + i as f32 * cell + (cell - text_dimensions.w) / 2.0,
+                grid_y + (cell - text_dimensions.h) / 2.0,
+            ]),
+        );
+    }
 
-    canvas.draw(&counter_bg, DrawParam::default());
+    let offset = calendar::weekday(first_day);
+    for date in 1..=calendar::days_in_month(month) {
+        let day = first_day + date as i64 - 1;
+        let slot = offset + date - 1;
+        let rect = Rect::new(
+            grid_x + (slot % 7) as f32 * cell + cell * 0.05,
+            grid_y + (slot / 7 + 1) as f32 * cell + cell * 0.05,
+            cell * 0.9,
+            cell * 0.9,
+        );
 
-    let bombs_left = engine.bombs_left();
-    let bombs_text = format!("{:03}", bombs_left);
+        let fill = if daily_log.is_completed(day) {
+            Color::from_rgb(0, 140, 0)
+        } else if day > today {
+            Color::from_rgb(110, 110, 110)
+        } else {
+            theme.panel
+        };
+        let cell_bg = Mesh::new_rectangle(ctx, DrawMode::fill(), rect, fill).unwrap();
+        canvas.draw(&cell_bg, DrawParam::default());
 
-    let text_scale = PxScale {
-        x: counter_w / bombs_text.len() as f32,
-        y: counter_h,
-    };
+        if day == today {
+            let outline =
+                Mesh::new_rectangle(ctx, DrawMode::stroke(cell * 0.06), rect, theme.accent)
+                    .unwrap();
+            canvas.draw(&outline, DrawParam::default());
+        }
 
-    let text_fragment = TextFragment::new(bombs_text)
-        .color(Color::from_rgb(255, 0, 0)) // Red text
-        .font("pressStart2P")
-        .scale(text_scale);
+        let text = Text::new(
+            TextFragment::new(date.to_string())
+                .color(theme.panel_text)
+                .font("pressStart2P")
+                .scale(PxScale::from(cell * 0.35)),
+        );
+        let text_dimensions = text.dimensions(ctx).unwrap();
+        canvas.draw(
+            &text,
+            DrawParam::new().dest([
+                rect.x + (rect.w - text_dimensions.w) / 2.0,
+                rect.y + (rect.h - text_dimensions.h) / 2.0,
+            ]),
+        );
+    }
 
-    let text = Text::new(text_fragment);
+    let streaks = format!(
+        "STREAK: {}  BEST: {}",
+        daily_log.current_streak(today),
+        daily_log.longest_streak()
+    );
+    draw_centered_label(
+        ctx,
+        canvas,
+        &streaks,
+        screen_w,
+        grid_y + cell * 7.3,
+        screen_h * 0.035,
+        theme.text,
+    );
 
-    let text_dimensions = text.dimensions(ctx).unwrap();
-    let text_x = ((counter_w - text_dimensions.w) / 2.0) + counter_x;
-    let text_y = (counter_h - text_dimensions.h) / 2.0 + counter_y * 2.0;
+    let weekly = match weekly_best {
+        Some(time) => format!(
+            "WEEKLY MARATHON: {}:{:02}",
+            time.as_secs() / 60,
+            time.as_secs() % 60
+        ),
+        None => "WEEKLY MARATHON: --:--".to_string(),
+    };
+    draw_centered_label(
+        ctx,
+        canvas,
+        &weekly,
+        screen_w,
+        grid_y + cell * 7.3 + screen_h * 0.05,
+        screen_h * 0.035,
+        theme.text,
+    );
 
-    // Draw text
-    canvas.draw(&text, DrawParam::new().dest([text_x, text_y]));
+    let btn_w = screen_w * 0.4;
+    let btn_h = screen_h * 0.07;
+    let btn_y = screen_h - btn_h * 1.5;
+    let padding = screen_w * 0.04;
+    let weekly_rect = Rect::new(screen_w / 2.0 - btn_w - padding / 2.0, btn_y, btn_w, btn_h);
+    let back_rect = Rect::new(screen_w / 2.0 + padding / 2.0, btn_y, btn_w, btn_h);
+    draw_list_button(
+        ctx,
+        canvas,
+        weekly_rect,
+        "PLAY WEEKLY",
+        theme.panel_text,
+        theme,
+    );
+    draw_list_button(ctx, canvas, back_rect, "BACK", theme.panel_text, theme);
+    calendar_state.weekly_rect = Some(weekly_rect);
+    calendar_state.back_rect = Some(back_rect);
+
+    draw_focus_ring(ctx, canvas, calendar_state.focused_rect(), theme);
 }
 
-fn draw_difficulty_buttons(
+// the board split into regions by where games were started; the bigger a region's
+// average opening, the stronger its tint
+#[allow(clippy::too_many_arguments)]
+pub fn draw_stats(
     ctx: &mut Context,
     canvas: &mut Canvas,
-    menu_state: &mut MenuState,
     screen_dim: (f32, f32),
-    _tile_size: (f32, f32),
-    _sprite_manager: &SpriteManager,
-) -> ggez::GameResult {
-    let menu_h = screen_dim.1 * MENU_HEIGHT_PERCENT;
-
-    let buttons_total_space = menu_h * 0.9;
-
-    let initial_y_offset = (0.1 * menu_h) / 2.0;
+    regions: &[[RegionStats; REGIONS]; REGIONS],
+    losses: &[(LossCause, usize)],
+    luck: (u32, u32),
+    stats_state: &mut StatsState,
+    theme: &Theme,
+) {
+    let (screen_w, screen_h) = screen_dim;
+    draw_centered_label(
+        ctx,
+        canvas,
+        "FIRST CLICKS",
+        screen_w,
+        screen_h * 0.06,
+        screen_h * 0.05,
+        theme.text,
+    );
 
-    let padding = 10.0;
+    let cell = (screen_w * 0.8 / REGIONS as f32).min(screen_h * 0.5 / REGIONS as f32);
+    let grid_x = (screen_w - cell * REGIONS as f32) / 2.0;
+    let grid_y = screen_h * 0.15;
+    let best_opening = regions
+        .iter()
+        .flatten()
+        .filter_map(RegionStats::average_opening)
+        .fold(0.0, f32::max);
 
-    let btn_h = (buttons_total_space - (2.0 * padding)) / 3.0;
+    for (row, line) in regions.iter().enumerate() {
+        for (column, region) in line.iter().enumerate() {
+            let rect = Rect::new(
+                grid_x + column as f32 * cell + cell * 0.03,
+                grid_y + row as f32 * cell + cell * 0.03,
+                cell * 0.94,
+                cell * 0.94,
+            );
+            let cell_bg = Mesh::new_rectangle(ctx, DrawMode::fill(), rect, theme.panel).unwrap();
+            canvas.draw(&cell_bg, DrawParam::default());
 
-    let btn_w = screen_dim.0 * 0.15;
-    let btn_x = screen_dim.0 * 0.2 + 30.0;
-    let btn_y = initial_y_offset;
+            let (Some(opening), Some(win_rate)) = (region.average_opening(), region.win_rate())
+            else {
+                continue;
+            };
+            let mut tint = theme.accent;
+            tint.a = 0.8 * opening / best_opening;
+            let tint = Mesh::new_rectangle(ctx, DrawMode::fill(), rect, tint).unwrap();
+            canvas.draw(&tint, DrawParam::default());
 
-    let easy_btn = Rect::new(btn_x, btn_y, btn_w, btn_h);
-    let medium_btn = Rect::new(btn_x, btn_y + btn_h + padding, btn_w, btn_h);
-    let hard_btn = Rect::new(btn_x, btn_y + 2.0 * (btn_h + padding), btn_w, btn_h);
+            let lines = [
+                format!("OPEN {:.0}", opening),
+                format!("WIN {:.0}%", win_rate * 100.0),
+                format!("{} GAMES", region.games),
+            ];
+            for (i, line) in lines.iter().enumerate() {
+                let text = Text::new(
+                    TextFragment::new(line.as_str())
+                        .color(theme.panel_text)
+                        .font("pressStart2P")
+                        .scale(PxScale::from(cell * 0.09)),
+                );
+                let text_dimensions = text.dimensions(ctx).unwrap();
+                canvas.draw(
+                    &text,
+                    DrawParam::new().dest([
+                        rect.x + (rect.w - text_dimensions.w) / 2.0,
+                        rect.y + rect.h * (0.2 + 0.25 * i as f32),
+                    ]),
+                );
+            }
+        }
+    }
 
-    let easy_btn_bg = Mesh::new_rectangle(ctx, DrawMode::fill(), easy_btn, Color::BLACK).unwrap();
-    let medium_btn_bg =
-        Mesh::new_rectangle(ctx, DrawMode::fill(), medium_btn, Color::BLACK).unwrap();
-    let hard_btn_bg = Mesh::new_rectangle(ctx, DrawMode::fill(), hard_btn, Color::BLACK).unwrap();
-    let easy_scale = PxScale {
-        x: btn_w / 4.0,
-        y: btn_h,
-    };
-    let easy_text_fragment = TextFragment::new("EASY")
-        .color(Color::from_rgb(255, 255, 255))
-        .font("pressStart2P")
-        .scale(easy_scale);
-    let easy_text = Text::new(easy_text_fragment);
+    // two causes to a line keeps the breakdown inside the easy window
+    let losses_y = grid_y + cell * REGIONS as f32 + screen_h * 0.03;
+    draw_centered_label(
+        ctx,
+        canvas,
+        "WHY GAMES WERE LOST",
+        screen_w,
+        losses_y,
+        screen_h * 0.03,
+        theme.text,
+    );
+    for (i, pair) in losses.chunks(2).enumerate() {
+        let line = pair
+            .iter()
+            .map(|(cause, count)| format!("{} {}", cause.label(), count))
+            .collect::<Vec<_>>()
+            .join("  ");
+        draw_centered_label(
+            ctx,
+            canvas,
+            &line,
+            screen_w,
+            losses_y + screen_h * (0.045 + 0.035 * i as f32),
+            screen_h * 0.025,
+            theme.text,
+        );
+    }
 
-    let medium_scale = PxScale {
-        x: btn_w / 6.0,
-        y: btn_h,
-    };
-    let medium_text_fragment = TextFragment::new("MEDIUM")
-        .color(Color::from_rgb(255, 255, 255))
-        .font("pressStart2P")
-        .scale(medium_scale);
-    let medium_text = Text::new(medium_text_fragment);
+    // reveals made when nothing could be proven safe, and how many of them hit no mine
+    let (survived, guesses) = luck;
+    draw_centered_label(
+        ctx,
+        canvas,
+        &format!("GUESSES SURVIVED {}/{}", survived, guesses),
+        screen_w,
+        losses_y + screen_h * (0.045 + 0.035 * losses.len().div_ceil(2) as f32),
+        screen_h * 0.025,
+        theme.text,
+    );
 
-    let hard_scale = PxScale {
-        x: btn_w / 4.0,
-        y: btn_h,
-    };
-    let hard_text_fragment = TextFragment::new("HARD")
-        .color(Color::from_rgb(255, 255, 255))
-        .font("pressStart2P")
-        .scale(hard_scale);
-    let hard_text = Text::new(hard_text_fragment);
+    let btn_w = screen_w * 0.4;
+    let btn_h = screen_h * 0.07;
+    let back_rect = Rect::new(
+        (screen_w - btn_w) / 2.0,
+        screen_h - btn_h * 1.5,
+        btn_w,
+        btn_h,
+    );
+    draw_list_button(ctx, canvas, back_rect, "BACK", theme.panel_text, theme);
+    stats_state.back_rect = Some(back_rect);
 
-    menu_state.easy_button_rect = Some(easy_btn);
-    menu_state.medium_button_rect = Some(medium_btn);
-    menu_state.hard_button_rect = Some(hard_btn);
+    draw_focus_ring(ctx, canvas, stats_state.focused_rect(), theme);
+}
 
-    canvas.draw(&easy_btn_bg, DrawParam::default());
-    canvas.draw(
-        &easy_text,
-        DrawParam::new().dest([
-            btn_x + btn_w / 2.0 - easy_text.dimensions(ctx).unwrap().w / 2.0,
-            btn_y + btn_h / 2.0 - easy_text.dimensions(ctx).unwrap().h / 2.0,
-        ]),
+// a column per board, each place a name over its time; unnamed runs show dashes
+pub fn draw_scores(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    screen_dim: (f32, f32),
+    columns: &[Column],
+    scores_state: &mut ScoresState,
+    theme: &Theme,
+) {
+    let (screen_w, screen_h) = screen_dim;
+    draw_centered_label(
+        ctx,
+        canvas,
+        "HIGH SCORES",
+        screen_w,
+        screen_h * 0.06,
+        screen_h * 0.05,
+        theme.text,
     );
 
-    canvas.draw(&medium_btn_bg, DrawParam::default());
-    canvas.draw(
-        &medium_text,
-        DrawParam::new().dest([
-            btn_x + btn_w / 2.0 - medium_text.dimensions(ctx).unwrap().w / 2.0,
-            btn_y + (btn_h + padding) + btn_h / 2.0 - medium_text.dimensions(ctx).unwrap().h / 2.0,
-        ]),
-    );
+    let column_w = screen_w / columns.len().max(1) as f32;
+    let row_h = screen_h * 0.11;
+    let text_size = (column_w / 11.0).min(screen_h * 0.025);
+    for (i, column) in columns.iter().enumerate() {
+        let mut lines = vec![(column.board.to_uppercase(), theme.accent)];
+        for place in 0..PLACES {
+            let (name, time) = match column.places.get(place) {
+                Some((name, time)) if name.is_empty() => ("---", Some(time)),
+                Some((name, time)) => (name.as_str(), Some(time)),
+                None => ("", None),
+            };
+            lines.push((format!("{} {}", place + 1, name), theme.text));
+            lines.push((
+                time.map_or(String::new(), |time| format!("{:.2}", time.as_secs_f32())),
+                theme.text,
+            ));
+        }
+        for (j, (line, color)) in lines.iter().enumerate() {
+            let text = Text::new(
+                TextFragment::new(line.as_str())
+                    .color(*color)
+                    .font("pressStart2P")
+                    .scale(PxScale::from(text_size)),
+            );
+            let text_dimensions = text.dimensions(ctx).unwrap();
+            // the board name sits a full row above the first place
+            let y = match j {
+                0 => screen_h * 0.15,
+                _ => screen_h * 0.15 + row_h * (j + 1) as f32 / 2.0,
+            };
+            canvas.draw(
+                &text,
+                DrawParam::new().dest([
+                    column_w * i as f32 + (column_w - text_dimensions.w) / 2.0,
+                    y,
+                ]),
+            );
+        }
+    }
 
-    canvas.draw(&hard_btn_bg, DrawParam::default());
-    canvas.draw(
-        &hard_text,
-        DrawParam::new().dest([
-            btn_x + btn_w / 2.0 - hard_text.dimensions(ctx).unwrap().w / 2.0,
-            btn_y + 2.0 * (btn_h + padding) + btn_h / 2.0
-                - hard_text.dimensions(ctx).unwrap().h / 2.0,
-        ]),
+    let btn_w = screen_w * 0.4;
+    let btn_h = screen_h * 0.07;
+    let back_rect = Rect::new(
+        (screen_w - btn_w) / 2.0,
+        screen_h - btn_h * 1.5,
+        btn_w,
+        btn_h,
     );
+    draw_list_button(ctx, canvas, back_rect, "BACK", theme.panel_text, theme);
+    scores_state.back_rect = Some(back_rect);
 
-    Ok(())
+    draw_focus_ring(ctx, canvas, scores_state.focused_rect(), theme);
 }
 
-pub fn draw_menu(
+// the finished board stays dimmed underneath while the name is typed
+pub fn draw_name_entry(
     ctx: &mut Context,
     canvas: &mut Canvas,
-    engine: &Engine,
-    sprite_manager: &SpriteManager,
-    tile_size: (f32, f32),
-    board_settings: &BoardSettings,
     screen_dim: (f32, f32),
-    current_tile_idx: Option<usize>,
-    menu_state: &mut MenuState,
+    name_entry: &NameEntry,
+    theme: &Theme,
 ) {
-    draw_face(
+    let (screen_w, screen_h) = screen_dim;
+    let dim = Mesh::new_rectangle(
         ctx,
-        canvas,
-        engine,
-        sprite_manager,
-        tile_size,
-        board_settings,
-        screen_dim,
-        current_tile_idx,
-        menu_state,
-    );
+        DrawMode::fill(),
+        Rect::new(0.0, 0.0, screen_w, screen_h),
+        Color::from_rgba(0, 0, 0, 150),
+    )
+    .unwrap();
+    canvas.draw(&dim, DrawParam::default());
+
+    let panel_h = screen_h * 0.3;
+    let panel_y = (screen_h - panel_h) / 2.0;
+    let panel = Mesh::new_rectangle(
+        ctx,
+        DrawMode::fill(),
+        Rect::new(screen_w * 0.1, panel_y, screen_w * 0.8, panel_h),
+        theme.panel,
+    )
+    .unwrap();
+    canvas.draw(&panel, DrawParam::default());
 
-    draw_timer(ctx, canvas, menu_state, tile_size, screen_dim);
+    let lines = [
+        (
+            format!(
+                "PLACE {} ON {}",
+                name_entry.rank + 1,
+                name_entry.category.board.to_uppercase()
+            ),
+            panel_h * 0.2,
+            theme.panel_text,
+        ),
+        (
+            format!("NAME: {}_", name_entry.name),
+            panel_h * 0.45,
+            theme.accent,
+        ),
+        (
+            "ENTER SAVES  ESC SKIPS".to_string(),
+            panel_h * 0.75,
+            theme.panel_text,
+        ),
+    ];
+    for (line, y, color) in lines {
+        draw_centered_label(
+            ctx,
+            canvas,
+            &line,
+            screen_w,
+            panel_y + y,
+            (screen_w * 0.03).min(panel_h * 0.1),
+            color,
+        );
+    }
+}
 
-    draw_bombs_left(
+// frame time graph plus the latest frame's numbers, in the bottom right corner
+pub fn draw_diagnostics(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    screen_dim: (f32, f32),
+    diagnostics: &Diagnostics,
+) {
+    let (screen_w, screen_h) = screen_dim;
+    let panel = Rect::new(screen_w - 260.0, screen_h - 150.0, 250.0, 140.0);
+    let background = Mesh::new_rectangle(
         ctx,
-        canvas,
-        engine,
-        sprite_manager,
-        tile_size,
-        board_settings,
-        screen_dim,
-        current_tile_idx,
-        menu_state,
-    );
+        DrawMode::fill(),
+        panel,
+        Color::new(0.0, 0.0, 0.0, 0.75),
+    )
+    .unwrap();
+    canvas.draw(&background, DrawParam::default());
 
-    draw_difficulty_buttons(
+    // bars are scaled so a 60 fps frame reaches the marker line
+    let graph_h = 60.0;
+    let graph_bottom = panel.y + panel.h - 8.0;
+    let target = 1.0 / 60.0;
+    let bar_w = (panel.w - 16.0) / crate::diagnostics::HISTORY_LEN as f32;
+    for (i, frame) in diagnostics.frames().enumerate() {
+        let ratio = frame.frame_time.as_secs_f32() / target;
+        let bar_h = (ratio * graph_h * 0.5).min(graph_h);
+        let color = if ratio > 1.1 {
+            Color::from_rgb(255, 0, 0)
+        } else {
+            Color::from_rgb(0, 255, 0)
+        };
+        let bar = Mesh::new_rectangle(
+            ctx,
+            DrawMode::fill(),
+            Rect::new(
+                panel.x + 8.0 + i as f32 * bar_w,
+                graph_bottom - bar_h,
+                bar_w.max(1.0),
+                bar_h,
+            ),
+            color,
+        )
+        .unwrap();
+        canvas.draw(&bar, DrawParam::default());
+    }
+    let marker = Mesh::new_line(
         ctx,
-        canvas,
-        menu_state,
-        screen_dim,
-        tile_size,
-        sprite_manager,
+        &[
+            [panel.x + 8.0, graph_bottom - graph_h * 0.5],
+            [panel.x + panel.w - 8.0, graph_bottom - graph_h * 0.5],
+        ],
+        1.0,
+        Color::from_rgb(255, 255, 0),
+    )
+    .unwrap();
+    canvas.draw(&marker, DrawParam::default());
+
+    let Some(latest) = diagnostics.latest() else {
+        return;
+    };
+    let ms = |d: Duration| d.as_secs_f32() * 1000.0;
+    let average = diagnostics.average_frame_time();
+    let fps = if average.is_zero() {
+        0.0
+    } else {
+        1.0 / average.as_secs_f32()
+    };
+    let lines = [
+        format!("FRAME {:.1}MS {:.0}FPS", ms(latest.frame_time), fps),
+        format!(
+            "UPD {:.2}MS DRAW {:.2}MS",
+            ms(latest.update_time),
+            ms(latest.draw_time)
+        ),
+        format!("TILES {} ALLOCS {}", latest.tiles_drawn, latest.allocations),
+    ];
+    for (i, line) in lines.into_iter().enumerate() {
+        let text = Text::new(
+            TextFragment::new(line)
+                .color(Color::WHITE)
+                .font("pressStart2P")
+                .scale(PxScale::from(10.0)),
+        );
+        canvas.draw(
+            &text,
+            DrawParam::new().dest([panel.x + 8.0, panel.y + 8.0 + i as f32 * 16.0]),
+        );
+    }
+}
+
+// sits just above the frame stats
+#[cfg(feature = "debug")]
+pub fn draw_generation_stats(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    screen_dim: (f32, f32),
+    stats: &GenerationStats,
+) {
+    let (screen_w, screen_h) = screen_dim;
+    let panel = Rect::new(screen_w - 260.0, screen_h - 220.0, 250.0, 64.0);
+    let background = Mesh::new_rectangle(
+        ctx,
+        DrawMode::fill(),
+        panel,
+        Color::new(0.0, 0.0, 0.0, 0.75),
     )
     .unwrap();
+    canvas.draw(&background, DrawParam::default());
+
+    let [[nw, ne], [sw, se]] = stats.density;
+    let lines = [
+        format!("MINES NW {:.2} NE {:.2}", nw, ne),
+        format!("      SW {:.2} SE {:.2}", sw, se),
+        format!("3BV {} 50/50 {}", stats.three_bv, stats.fifty_fifties),
+    ];
+    for (i, line) in lines.into_iter().enumerate() {
+        let text = Text::new(
+            TextFragment::new(line)
+                .color(Color::WHITE)
+                .font("pressStart2P")
+                .scale(PxScale::from(10.0)),
+        );
+        canvas.draw(
+            &text,
+            DrawParam::new().dest([panel.x + 8.0, panel.y + 8.0 + i as f32 * 16.0]),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Position;
+
+    // one character per tile sprite, so a golden board reads like the screen
+    fn sprite_map(engine: &Engine, pressed: Option<usize>) -> String {
+        let width = engine.size().width;
+        let mut map = String::new();
+        for i in 0..engine.get_tiles().len() {
+            map.push(match tile_sprite(engine, i, pressed == Some(i)) {
+                Sprite::Block(BlockKind::Solid) => '#',
+                Sprite::Block(BlockKind::Flagged) => 'F',
+                Sprite::Block(BlockKind::Questioned) => '?',
+                Sprite::Block(BlockKind::Revealed) => '.',
+                Sprite::Digit(n) => char::from(b'0' + n.0),
+                Sprite::Bomb(BombKind::Clicked) => '*',
+                Sprite::Bomb(BombKind::FlaggedWrong) => 'X',
+                Sprite::Bomb(BombKind::NotRevealed) => 'o',
+                _ => '?',
+            });
+            if (i + 1) % width == 0 {
+                map.push('\n');
+            }
+        }
+        map
+    }
+
+    // one right and one wrong flag on an 8x8 board still in play
+    fn flagged_engine() -> (Engine, Vec<usize>) {
+        let mut engine = Engine::with_seed(GridSize::new(8, 8), 10, 2024);
+        engine.reveal(Position(4, 4));
+        let bombs = (0..64)
+            .filter(|i| engine.get_tiles()[*i].is_bomb())
+            .collect::<Vec<_>>();
+        engine.flag(Position::from_index(bombs[0], 8));
+        engine.flag(Position(0, 7));
+        (engine, bombs)
+    }
+
+    #[test]
+    fn test_flags_match_golden() {
+        let (engine, bombs) = flagged_engine();
+        assert_eq!(
+            sprite_map(&engine, Some(bombs[1])),
+            include_str!("../tests/golden/flags.sprites")
+        );
+    }
+
+    #[test]
+    fn test_loss_reveal_matches_golden() {
+        let (mut engine, bombs) = flagged_engine();
+        engine.reveal(Position::from_index(bombs[2], 8));
+        assert_eq!(
+            sprite_map(&engine, None),
+            include_str!("../tests/golden/loss.sprites")
+        );
+    }
 }
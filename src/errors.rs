@@ -0,0 +1,97 @@
+// parts of the game that can fail while it runs without taking the rest down
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Subsystem {
+    Sprites,
+    Audio,
+    // history, leaderboard, ghost and tournament results
+    Saves,
+    Journal,
+    Profiles,
+    Sync,
+    Webhook,
+    Metrics,
+    Ipc,
+}
+
+impl Subsystem {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Subsystem::Sprites => "SPRITES",
+            Subsystem::Audio => "AUDIO",
+            Subsystem::Saves => "SAVES",
+            Subsystem::Journal => "AUTOSAVE",
+            Subsystem::Profiles => "PROFILES",
+            Subsystem::Sync => "SYNC",
+            Subsystem::Webhook => "WEBHOOK",
+            Subsystem::Metrics => "METRICS",
+            Subsystem::Ipc => "IPC",
+        }
+    }
+}
+
+// the banner shows this many of the newest errors
+pub const BANNER_LINES: usize = 3;
+
+// every runtime failure goes through here: it is logged, shown in the banner until
+// dismissed, and the subsystem stays degraded for the rest of the session
+pub struct ErrorReporter {
+    banner: Vec<(Subsystem, String)>,
+    degraded: Vec<Subsystem>,
+}
+
+impl ErrorReporter {
+    pub fn new() -> Self {
+        Self {
+            banner: Vec::new(),
+            degraded: Vec::new(),
+        }
+    }
+
+    pub fn report(&mut self, subsystem: Subsystem, message: impl ToString) {
+        let message = message.to_string();
+        eprintln!("{}: {}", subsystem.label().to_lowercase(), message);
+        if !self.degraded.contains(&subsystem) {
+            self.degraded.push(subsystem);
+        }
+        self.banner.push((subsystem, message));
+        if self.banner.len() > BANNER_LINES {
+            self.banner.remove(0);
+        }
+    }
+
+    pub fn is_degraded(&self, subsystem: Subsystem) -> bool {
+        self.degraded.contains(&subsystem)
+    }
+
+    pub fn banner(&self) -> &[(Subsystem, String)] {
+        &self.banner
+    }
+
+    // hides the banner; degraded subsystems stay off
+    pub fn dismiss(&mut self) {
+        self.banner.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reports_degrade_until_restart() {
+        let mut errors = ErrorReporter::new();
+        assert!(errors.banner().is_empty());
+        for i in 0..5 {
+            errors.report(Subsystem::Saves, format!("write {} failed", i));
+        }
+        errors.report(Subsystem::Sync, "offline");
+        assert_eq!(errors.banner().len(), BANNER_LINES);
+        assert_eq!(errors.banner()[BANNER_LINES - 1].0, Subsystem::Sync);
+        assert!(errors.is_degraded(Subsystem::Saves));
+        assert!(!errors.is_degraded(Subsystem::Audio));
+
+        errors.dismiss();
+        assert!(errors.banner().is_empty());
+        assert!(errors.is_degraded(Subsystem::Sync));
+    }
+}
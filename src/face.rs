@@ -0,0 +1,94 @@
+use super::game::Engine;
+use super::sprites::FaceKind;
+use std::time::Duration;
+
+const WINK_DURATION: Duration = Duration::from_millis(700);
+// the face starts sweating once fewer safe tiles than this are left
+const NERVOUS_SAFE_TILES: usize = 10;
+const SLEEPY_AFTER: Duration = Duration::from_secs(15);
+
+// the face button's expression, worked out fresh every frame
+pub struct Face {
+    wink_left: Duration,
+}
+
+impl Face {
+    pub fn new() -> Self {
+        Self {
+            wink_left: Duration::ZERO,
+        }
+    }
+
+    pub fn wink(&mut self) {
+        self.wink_left = WINK_DURATION;
+    }
+
+    pub fn update(&mut self, delta: Duration) {
+        self.wink_left = self.wink_left.saturating_sub(delta);
+    }
+
+    pub fn expression(&self, engine: &Engine, pressing: bool, idle_time: Duration) -> FaceKind {
+        if engine.is_won() {
+            FaceKind::Cool
+        } else if engine.is_lost() {
+            FaceKind::Dead
+        } else if pressing {
+            FaceKind::Surprised
+        } else if !self.wink_left.is_zero() {
+            FaceKind::Wink
+        } else if !engine.is_in_progress() {
+            FaceKind::Smile
+        } else if idle_time >= SLEEPY_AFTER {
+            FaceKind::Sleepy
+        } else if engine.safe_tiles_left() < NERVOUS_SAFE_TILES {
+            FaceKind::Sweat
+        } else {
+            FaceKind::Smile
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{GridSize, Position};
+
+    #[test]
+    fn test_expressions() {
+        let mut engine = Engine::with_seed(GridSize::new(16, 16), 40, 7);
+        let mut face = Face::new();
+        assert_eq!(
+            face.expression(&engine, false, SLEEPY_AFTER),
+            FaceKind::Smile
+        );
+        assert_eq!(
+            face.expression(&engine, true, Duration::ZERO),
+            FaceKind::Surprised
+        );
+
+        engine.reveal(Position(0, 0));
+        assert_eq!(
+            face.expression(&engine, false, SLEEPY_AFTER),
+            FaceKind::Sleepy
+        );
+
+        face.wink();
+        assert_eq!(
+            face.expression(&engine, false, Duration::ZERO),
+            FaceKind::Wink
+        );
+        face.update(WINK_DURATION);
+        assert_eq!(
+            face.expression(&engine, false, Duration::ZERO),
+            FaceKind::Smile
+        );
+
+        // the first click keeps its 3x3 area clear, which leaves one safe tile on the border
+        let mut small = Engine::with_seed(GridSize::new(5, 5), 15, 7);
+        small.reveal(Position(2, 2));
+        assert_eq!(
+            face.expression(&small, false, Duration::ZERO),
+            FaceKind::Sweat
+        );
+    }
+}
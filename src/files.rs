@@ -0,0 +1,151 @@
+use super::board::BoardSettings;
+use super::game::Engine;
+use super::replay::Replay;
+use super::storage;
+use super::theme::{Theme, NIGHT_THEME_FILE_NAME, THEME_FILE_NAME};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// both hold a replay body; a save is a game still in progress, a replay a finished one
+pub const SAVE_EXTENSION: &str = "rsweep";
+pub const REPLAY_EXTENSION: &str = "rsrep";
+pub const THEME_EXTENSION: &str = "manifest";
+pub const EXPORT_DIR_NAME: &str = "exports";
+
+// games come with the settings of their board, checked before anything is simulated
+pub enum GameFile {
+    Save(BoardSettings, Engine),
+    Replay(BoardSettings, Replay),
+    // installed into the active profile under the file name it replaces
+    Theme {
+        file_name: &'static str,
+        manifest: String,
+    },
+}
+
+pub fn can_open(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| {
+        ext == SAVE_EXTENSION || ext == REPLAY_EXTENSION || ext == THEME_EXTENSION
+    })
+}
+
+// reads a file the game was launched with; everything is checked before anything changes
+pub fn open(path: &Path) -> Result<GameFile, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    if path.extension().is_some_and(|ext| ext == THEME_EXTENSION) {
+        Theme::parse(&text, Theme::classic()).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let is_night = path
+            .file_name()
+            .is_some_and(|name| name == NIGHT_THEME_FILE_NAME);
+        return Ok(GameFile::Theme {
+            file_name: if is_night {
+                NIGHT_THEME_FILE_NAME
+            } else {
+                THEME_FILE_NAME
+            },
+            manifest: text,
+        });
+    }
+    let replay = Replay::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let board_settings = replay
+        .board_settings()
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    if path.extension().is_some_and(|ext| ext == SAVE_EXTENSION) {
+        let engine = replay.simulate()?;
+        if engine.is_won() || engine.is_lost() {
+            return Err(format!(
+                "{}: the saved game is already over",
+                path.display()
+            ));
+        }
+        Ok(GameFile::Save(board_settings, engine))
+    } else {
+        Ok(GameFile::Replay(board_settings, replay))
+    }
+}
+
+// unfinished games are exported as saves, finished ones as replays
+pub fn export(dir: &Path, engine: &Engine) -> io::Result<PathBuf> {
+    let extension = if engine.is_won() || engine.is_lost() {
+        REPLAY_EXTENSION
+    } else {
+        SAVE_EXTENSION
+    };
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let path = dir.join(format!("{:016x}-{}.{}", engine.seed(), now, extension));
+    fs::create_dir_all(dir)?;
+    storage::write_atomic(&path, Replay::from_engine(engine).to_text())?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{GridSize, Position};
+
+    #[test]
+    fn test_exports_open_by_extension() {
+        let dir =
+            std::env::temp_dir().join(format!("rust_sweeper_test_files_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut engine = Engine::with_seed(GridSize::new(8, 8), 10, 5);
+        engine.reveal(Position(4, 4));
+
+        let save = export(&dir, &engine).unwrap();
+        assert!(can_open(&save));
+        match open(&save).unwrap() {
+            GameFile::Save(_, opened) => assert_eq!(opened.moves(), engine.moves()),
+            _ => panic!("an unfinished game opened as something else"),
+        }
+
+        let bomb = engine.get_tiles().iter().position(|t| t.is_bomb()).unwrap();
+        engine.reveal(Position::from_index(bomb, 8));
+        let replay = export(&dir, &engine).unwrap();
+        assert_eq!(replay.extension().unwrap(), REPLAY_EXTENSION);
+        assert!(matches!(open(&replay).unwrap(), GameFile::Replay(..)));
+
+        // a finished game renamed to a save can't be continued
+        let renamed = replay.with_extension(SAVE_EXTENSION);
+        fs::rename(&replay, &renamed).unwrap();
+        assert!(open(&renamed).is_err());
+        assert!(!can_open(Path::new("notes.txt")));
+
+        // a save whose board can't exist is refused before any move is replayed
+        let crafted = dir.join(format!("crafted.{}", SAVE_EXTENSION));
+        let text = Replay::from_engine(&Engine::with_seed(GridSize::new(8, 8), 10, 5)).to_text();
+        fs::write(&crafted, text.replace("board 8 8 10", "board 3 3 1")).unwrap();
+        assert!(open(&crafted).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_themes_are_checked_before_install() {
+        let dir = std::env::temp_dir().join(format!(
+            "rust_sweeper_test_theme_files_{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let night = dir.join(NIGHT_THEME_FILE_NAME);
+        fs::write(&night, "preset dark\naccent #00ff00\n").unwrap();
+        assert!(matches!(
+            open(&night).unwrap(),
+            GameFile::Theme {
+                file_name: NIGHT_THEME_FILE_NAME,
+                ..
+            }
+        ));
+
+        let broken = dir.join("broken.manifest");
+        fs::write(&broken, "accent green\n").unwrap();
+        assert!(open(&broken).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
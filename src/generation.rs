@@ -0,0 +1,108 @@
+use super::game::{Engine, Position};
+
+// what the generator produced for a board, for tuning it; read off the whole solution,
+// so it is only known once the first reveal has placed the mines
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct GenerationStats {
+    // mines per tile, indexed [row][column] from the top left quarter
+    pub density: [[f32; 2]; 2],
+    // the fewest clicks that clear the board
+    pub three_bv: usize,
+    // mine and safe tile pairs no number can ever tell apart
+    pub fifty_fifties: usize,
+}
+
+impl GenerationStats {
+    pub fn of(engine: &Engine) -> Option<Self> {
+        engine.opening()?;
+        Some(Self {
+            density: density(engine),
+            three_bv: engine.board_3bv(),
+            fifty_fifties: fifty_fifties(engine),
+        })
+    }
+}
+
+fn density(engine: &Engine) -> [[f32; 2]; 2] {
+    let size = engine.size();
+    let mut mines = [[0; 2]; 2];
+    let mut tiles = [[0; 2]; 2];
+    for (i, tile) in engine.get_tiles().iter().enumerate() {
+        let pos = Position::from_index(i, size.width);
+        let row = pos.1 as usize * 2 / size.height;
+        let column = pos.0 as usize * 2 / size.width;
+        tiles[row][column] += 1;
+        if tile.is_bomb() {
+            mines[row][column] += 1;
+        }
+    }
+    let mut density = [[0.0; 2]; 2];
+    for row in 0..2 {
+        for column in 0..2 {
+            density[row][column] = mines[row][column] as f32 / tiles[row][column].max(1) as f32;
+        }
+    }
+    density
+}
+
+// a mine and a safe neighbour touching the same safe tiles could swap places without
+// changing a single number, so only a guess separates them
+fn fifty_fifties(engine: &Engine) -> usize {
+    let tiles = engine.get_tiles();
+    let width = engine.size().width;
+    let safe_around = |pos: Position, other: Position| {
+        let mut safe = engine
+            .neighbours(pos)
+            .into_iter()
+            .filter(|adj| *adj != other && !tiles[adj.to_index(width)].is_bomb())
+            .map(|adj| adj.to_index(width))
+            .collect::<Vec<_>>();
+        safe.sort_unstable();
+        safe
+    };
+    let mut pairs = 0;
+    for (i, tile) in tiles.iter().enumerate() {
+        if !tile.is_bomb() {
+            continue;
+        }
+        let mine = Position::from_index(i, width);
+        for safe in engine.neighbours(mine) {
+            if !tiles[safe.to_index(width)].is_bomb()
+                && safe_around(mine, safe) == safe_around(safe, mine)
+            {
+                pairs += 1;
+            }
+        }
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GridSize;
+    use crate::testing::proven_mine;
+
+    #[test]
+    fn test_stats_of_a_known_board() {
+        // "..1*2*": the 2 and the last mine can't be told apart
+        let stats = GenerationStats::of(&proven_mine()).unwrap();
+        assert_eq!(stats.three_bv, 2);
+        assert_eq!(stats.fifty_fifties, 1);
+        assert_eq!(stats.density[0][0], 0.0);
+        assert_eq!(stats.density[0][1], 2.0 / 3.0);
+        assert_eq!(stats.density[1], [0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_nothing_before_the_mines_are_placed() {
+        let engine = Engine::with_seed(GridSize::new(9, 9), 10, 1);
+        assert_eq!(GenerationStats::of(&engine), None);
+
+        let mut empty = Engine::with_seed(GridSize::new(9, 9), 0, 1);
+        empty.reveal(Position(4, 4));
+        let stats = GenerationStats::of(&empty).unwrap();
+        assert_eq!(stats.three_bv, 1);
+        assert_eq!(stats.fifty_fifties, 0);
+    }
+}
@@ -0,0 +1,152 @@
+use super::game::Engine;
+use super::replay::Replay;
+use super::storage;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const GHOST_DIR_NAME: &str = "ghosts";
+// the pace cue beats each time the ghost clears another twentieth of the board
+const PACE_BEATS: usize = 20;
+
+// one personal best replay per board, the seed alone doesn't pin the board size
+fn ghost_path(profile_dir: &Path, engine: &Engine) -> PathBuf {
+    let size = engine.size();
+    profile_dir.join(GHOST_DIR_NAME).join(format!(
+        "{}x{}-{}-{:016x}.replay",
+        size.width,
+        size.height,
+        engine.num_bombs(),
+        engine.seed()
+    ))
+}
+
+// stores the finished game as the board's ghost when it beats the previous one
+pub fn save_if_best(profile_dir: &Path, engine: &Engine) -> io::Result<bool> {
+    if !engine.is_won() {
+        return Ok(false);
+    }
+    let path = ghost_path(profile_dir, engine);
+    let previous = fs::read_to_string(&path)
+        .ok()
+        .and_then(|text| Replay::parse(&text).ok());
+    if previous.is_some_and(|replay| replay.time <= engine.elapsed()) {
+        return Ok(false);
+    }
+
+    fs::create_dir_all(path.parent().unwrap())?;
+    storage::write_atomic(&path, Replay::from_engine(engine).to_text())?;
+    Ok(true)
+}
+
+// the personal best on the current board, played back in step with the player's clock
+pub struct Ghost {
+    replay: Replay,
+    engine: Engine,
+    next_move: usize,
+    // pace beats the ghost has reached so far
+    beats: usize,
+}
+
+impl Ghost {
+    pub fn load(profile_dir: &Path, engine: &Engine) -> Option<Self> {
+        let text = fs::read_to_string(ghost_path(profile_dir, engine)).ok()?;
+        Replay::parse(&text)
+            .ok()
+            .filter(|replay| replay.generation == engine.generation())
+            .map(Ghost::from_replay)
+    }
+
+    pub fn from_replay(replay: Replay) -> Self {
+        let engine = replay.fresh_engine();
+        Self {
+            replay,
+            engine,
+            next_move: 0,
+            beats: 0,
+        }
+    }
+
+    pub fn advance(&mut self, elapsed: Duration) {
+        while let Some(m) = self.replay.moves.get(self.next_move) {
+            if m.time > elapsed {
+                break;
+            }
+            self.engine.replay_move(m);
+            self.next_move += 1;
+        }
+    }
+
+    pub fn engine(&self) -> &Engine {
+        &self.engine
+    }
+
+    // Some once for each beat the ghost reaches, true when the player has cleared at least
+    // as much of the board by then
+    pub fn pace_beat(&mut self, player: &Engine) -> Option<bool> {
+        let ghost = self.engine.progress();
+        let beat = (ghost * PACE_BEATS as f32) as usize;
+        if beat <= self.beats {
+            return None;
+        }
+        self.beats = beat;
+        Some(player.progress() >= ghost)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{GridSize, Position};
+
+    fn won_engine(seed: u64, time: Duration) -> Engine {
+        let mut engine = Engine::with_seed(GridSize::new(5, 5), 1, seed);
+        engine.tick(time);
+        for y in 0..5 {
+            for x in 0..5 {
+                let is_bomb = engine.get_tiles()[y * 5 + x].is_bomb();
+                if !is_bomb && !engine.is_won() {
+                    engine.reveal(Position(x as i32, y as i32));
+                }
+            }
+        }
+        engine
+    }
+
+    #[test]
+    fn test_ghost_keeps_the_best_time() {
+        let dir =
+            std::env::temp_dir().join(format!("rust_sweeper_test_ghost_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let fast = won_engine(7, Duration::from_secs(5));
+        assert!(fast.is_won());
+        assert!(save_if_best(&dir, &fast).unwrap());
+        assert!(!save_if_best(&dir, &won_engine(7, Duration::from_secs(9))).unwrap());
+
+        let mut ghost = Ghost::load(&dir, &Engine::with_seed(GridSize::new(5, 5), 1, 7)).unwrap();
+        assert_eq!(ghost.replay.time, Duration::from_secs(5));
+        assert!(!ghost.engine().is_won());
+        ghost.advance(Duration::from_secs(5));
+        assert!(ghost.engine().is_won());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_pace_beats_once_per_stretch_of_board() {
+        let best = Replay::from_engine(&won_engine(7, Duration::from_secs(5)));
+        let player = Engine::with_seed(GridSize::new(5, 5), 1, 7);
+        let mut ghost = Ghost::from_replay(best.clone());
+        assert_eq!(ghost.pace_beat(&player), None);
+        ghost.advance(Duration::from_secs(5));
+        assert_eq!(ghost.pace_beat(&player), Some(false));
+        assert_eq!(ghost.pace_beat(&player), None);
+
+        let mut ghost = Ghost::from_replay(best);
+        ghost.advance(Duration::from_secs(5));
+        let finished = won_engine(7, Duration::from_secs(4));
+        assert_eq!(ghost.pace_beat(&finished), Some(true));
+    }
+}
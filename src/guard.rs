@@ -0,0 +1,95 @@
+use super::config::MisclickGuard;
+use super::game::Engine;
+use super::solver::{self, Certainty};
+use std::time::Duration;
+
+// the input side of MisclickGuard: only pointer reveals go through it, the rules and
+// the command bar never see it
+pub struct RevealGuard {
+    // the proven mine clicked once, waiting for a second click
+    armed: Option<usize>,
+    hovered: Option<usize>,
+    // how long the pointer has rested on the hovered tile
+    dwell: Duration,
+}
+
+impl RevealGuard {
+    pub fn new() -> Self {
+        Self {
+            armed: None,
+            hovered: None,
+            dwell: Duration::ZERO,
+        }
+    }
+
+    // the pointer moved to another tile, or off the board
+    pub fn hover(&mut self, index: Option<usize>) {
+        if index != self.hovered {
+            self.hovered = index;
+            self.dwell = Duration::ZERO;
+        }
+    }
+
+    pub fn update(&mut self, delta: Duration) {
+        if self.hovered.is_some() {
+            self.dwell += delta;
+        }
+    }
+
+    // whether a click on the tile reveals it now; clicking any other tile disarms
+    pub fn allows(&mut self, mode: MisclickGuard, engine: &Engine, index: usize) -> bool {
+        let proven_mine = !engine.get_tiles()[index].is_revealed()
+            && solver::analyze(engine)[index] == Certainty::Mine;
+        let armed = self.armed.take();
+        if !proven_mine {
+            return true;
+        }
+        match mode {
+            MisclickGuard::Off => true,
+            MisclickGuard::Confirm if armed == Some(index) => true,
+            MisclickGuard::Confirm => {
+                self.armed = Some(index);
+                false
+            }
+            MisclickGuard::Dwell(min) => self.hovered == Some(index) && self.dwell >= min,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::proven_mine;
+
+    #[test]
+    fn test_confirm_needs_a_second_click() {
+        let engine = proven_mine();
+        let mut guard = RevealGuard::new();
+        assert!(guard.allows(MisclickGuard::Off, &engine, 3));
+        // unproven tiles are never held back
+        assert!(guard.allows(MisclickGuard::Confirm, &engine, 4));
+
+        assert!(!guard.allows(MisclickGuard::Confirm, &engine, 3));
+        assert!(guard.allows(MisclickGuard::Confirm, &engine, 3));
+
+        assert!(!guard.allows(MisclickGuard::Confirm, &engine, 3));
+        assert!(guard.allows(MisclickGuard::Confirm, &engine, 4));
+        assert!(!guard.allows(MisclickGuard::Confirm, &engine, 3));
+    }
+
+    #[test]
+    fn test_dwell_needs_the_pointer_to_rest() {
+        let engine = proven_mine();
+        let dwell = MisclickGuard::Dwell(Duration::from_millis(500));
+        let mut guard = RevealGuard::new();
+        guard.hover(Some(3));
+        guard.update(Duration::from_millis(300));
+        assert!(!guard.allows(dwell, &engine, 3));
+        guard.update(Duration::from_millis(300));
+        assert!(guard.allows(dwell, &engine, 3));
+
+        guard.hover(Some(2));
+        guard.hover(Some(3));
+        assert!(!guard.allows(dwell, &engine, 3));
+    }
+}
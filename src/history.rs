@@ -0,0 +1,190 @@
+use super::board::PRESETS;
+use super::storage;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// one "<finished unix ms>\t<updated unix ms>\t<board> won|lost[ assisted]" line per game,
+// merged per record by sync
+const HISTORY_FILE_NAME: &str = "history.records";
+// suggestions look at this many of the latest games on a board
+const RECENT_GAMES: usize = 10;
+const MIN_GAMES: usize = 5;
+const MOVE_UP_WIN_RATE: f32 = 0.7;
+const MOVE_DOWN_WIN_RATE: f32 = 0.3;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Suggestion {
+    Harder(&'static str),
+    Easier(&'static str),
+}
+
+struct GameResult {
+    finished: u64,
+    board: String,
+    won: bool,
+    assisted: bool,
+}
+
+pub struct History {
+    path: PathBuf,
+    // oldest first
+    results: Vec<GameResult>,
+}
+
+impl History {
+    pub fn load(profile_dir: &Path) -> Self {
+        let path = profile_dir.join(HISTORY_FILE_NAME);
+        let mut results = Vec::new();
+        for line in fs::read_to_string(&path).unwrap_or_default().lines() {
+            let fields = line.split('\t').collect::<Vec<_>>();
+            let [finished, _, result] = fields.as_slice() else {
+                continue;
+            };
+            let mut words = result.split(' ');
+            let (Some(board), Some(outcome)) = (words.next(), words.next()) else {
+                continue;
+            };
+            if let Ok(finished) = finished.parse() {
+                results.push(GameResult {
+                    finished,
+                    board: board.to_string(),
+                    won: outcome == "won",
+                    assisted: words.next() == Some("assisted"),
+                });
+            }
+        }
+        results.sort_by_key(|result| result.finished);
+        Self { path, results }
+    }
+
+    pub fn record(&mut self, board: &str, won: bool, assisted: bool) -> io::Result<()> {
+        let finished = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        self.results.push(GameResult {
+            finished,
+            board: board.to_string(),
+            won,
+            assisted,
+        });
+
+        let mut text = String::new();
+        for result in &self.results {
+            text.push_str(&format!(
+                "{}\t{}\t{} {}{}\n",
+                result.finished,
+                result.finished,
+                result.board,
+                if result.won { "won" } else { "lost" },
+                if result.assisted { " assisted" } else { "" }
+            ));
+        }
+        storage::write_atomic(&self.path, text)
+    }
+
+    // share of the latest games on this board that were won, None until enough were played
+    pub fn recent_win_rate(&self, board: &str) -> Option<f32> {
+        let recent = self
+            .results
+            .iter()
+            .rev()
+            .filter(|result| result.board == board)
+            .take(RECENT_GAMES)
+            .collect::<Vec<_>>();
+        if recent.len() < MIN_GAMES {
+            return None;
+        }
+        // an assisted win says little about being ready for a harder board
+        let wins = recent
+            .iter()
+            .filter(|result| result.won && !result.assisted)
+            .count();
+        Some(wins as f32 / recent.len() as f32)
+    }
+
+    pub fn suggestion(&self, preset: &str) -> Option<Suggestion> {
+        let i = PRESETS.iter().position(|name| *name == preset)?;
+        let win_rate = self.recent_win_rate(preset)?;
+        if win_rate >= MOVE_UP_WIN_RATE {
+            PRESETS.get(i + 1).map(|name| Suggestion::Harder(name))
+        } else if win_rate <= MOVE_DOWN_WIN_RATE {
+            i.checked_sub(1).map(|j| Suggestion::Easier(PRESETS[j]))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(results: &[(&str, bool)]) -> History {
+        History {
+            path: PathBuf::new(),
+            results: results
+                .iter()
+                .enumerate()
+                .map(|(i, (board, won))| GameResult {
+                    finished: i as u64,
+                    board: board.to_string(),
+                    won: *won,
+                    assisted: false,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_suggestions() {
+        let winning = history(&[("easy", true); 6]);
+        assert_eq!(
+            winning.suggestion("easy"),
+            Some(Suggestion::Harder("medium"))
+        );
+
+        let losing = history(&[("medium", false); 6]);
+        assert_eq!(
+            losing.suggestion("medium"),
+            Some(Suggestion::Easier("easy"))
+        );
+        // nothing below easy
+        assert_eq!(history(&[("easy", false); 6]).suggestion("easy"), None);
+
+        let too_few = history(&[("easy", true); 3]);
+        assert_eq!(too_few.suggestion("easy"), None);
+    }
+
+    #[test]
+    fn test_only_recent_games_count() {
+        let mut results = vec![("hard", false); 20];
+        results.extend([("hard", true); 4]);
+        results.extend([("medium", false); 3]);
+        let history = history(&results);
+
+        assert_eq!(history.recent_win_rate("hard"), Some(0.4));
+        assert_eq!(history.recent_win_rate("medium"), None);
+    }
+
+    #[test]
+    fn test_history_round_trip() {
+        let dir =
+            std::env::temp_dir().join(format!("rust_sweeper_test_history_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut history = History::load(&dir);
+        for won in [true, true, false, true, true] {
+            history.record("easy", won, false).unwrap();
+        }
+        history.record("easy", true, true).unwrap();
+
+        let loaded = History::load(&dir);
+        assert_eq!(loaded.recent_win_rate("easy"), Some(4.0 / 6.0));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,204 @@
+use super::board::{HUD_BASE_HEIGHT, PRESETS};
+use super::custom::CustomBoard;
+use ggez::graphics::Rect;
+use std::time::Duration;
+
+// the mine counter turns yellow once this few mines are left
+const LOW_MINES: i32 = 5;
+const BLINK_PERIOD_MS: u128 = 400;
+
+// how the mine counter should look, worked out before drawing
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CounterState {
+    Normal,
+    Low,
+    // more flags than mines; blinks on the game clock
+    Over { lit: bool },
+}
+
+impl CounterState {
+    pub fn evaluate(mines_left: i32, elapsed: Duration) -> Self {
+        if mines_left < 0 {
+            CounterState::Over {
+                lit: (elapsed.as_millis() / BLINK_PERIOD_MS) % 2 == 0,
+            }
+        } else if mines_left <= LOW_MINES {
+            CounterState::Low
+        } else {
+            CounterState::Normal
+        }
+    }
+
+    pub fn is_over(&self) -> bool {
+        matches!(self, CounterState::Over { .. })
+    }
+}
+
+// HUD element widths relative to the HUD height so they keep their proportions on any board
+pub const COUNTER_WIDTH_RATIO: f32 = 1.8;
+pub const BUTTON_WIDTH_RATIO: f32 = 1.35;
+pub const HUD_MARGIN: f32 = 20.0;
+
+// the entries of the HUD's mode list, which grows with the presets and recent boards
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ModeEntry {
+    Preset(&'static str),
+    // opens the custom board dialog
+    Custom,
+    Daily,
+    // a custom board started before, played again as it was
+    Recent(CustomBoard),
+}
+
+impl ModeEntry {
+    pub fn label(&self) -> String {
+        match self {
+            ModeEntry::Preset(name) => name.to_uppercase(),
+            ModeEntry::Custom => "CUSTOM...".to_string(),
+            ModeEntry::Daily => "DAILY".to_string(),
+            ModeEntry::Recent(board) => {
+                format!("{}X{} {}%", board.width, board.height, board.density)
+            }
+        }
+    }
+}
+
+pub fn mode_entries(recent: &[CustomBoard]) -> Vec<ModeEntry> {
+    let mut entries = PRESETS.map(ModeEntry::Preset).to_vec();
+    entries.extend([ModeEntry::Custom, ModeEntry::Daily]);
+    entries.extend(recent.iter().copied().map(ModeEntry::Recent));
+    entries
+}
+
+// where each HUD element goes for a window width; worked out in one place so the
+// pieces can't be drawn over each other
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct HudLayout {
+    pub counter: Rect,
+    pub timer: Rect,
+    pub face: Rect,
+    // the button that opens the mode list
+    pub mode: Rect,
+    pub narrow: bool,
+    // the right edge of the left hand group, which the hint button must stay clear of
+    pub left_edge: f32,
+    // the free stretch right of the face, for the tabs and the notes
+    pub gap: (f32, f32),
+}
+
+impl HudLayout {
+    pub fn new(screen_w: f32, menu_h: f32) -> Self {
+        let margin = HUD_MARGIN * menu_h / HUD_BASE_HEIGHT;
+        let padding = 10.0 * menu_h / HUD_BASE_HEIGHT;
+        let top = menu_h * 0.05;
+        let inner_h = menu_h * 0.9;
+        let face = Rect::new(screen_w / 2.0 - inner_h / 2.0, top, inner_h, inner_h);
+        let counter_w = menu_h * COUNTER_WIDTH_RATIO;
+        let btn_w = menu_h * BUTTON_WIDTH_RATIO;
+        let btn_h = (inner_h - 2.0 * padding) / 3.0;
+
+        // the counters side by side with the mode button between counter and face, as
+        // long as that row leaves half a margin either side of the face
+        let column_x = margin + counter_w + margin / 2.0;
+        let wide = column_x + btn_w + margin / 2.0 <= face.x
+            && face.right() + margin / 2.0 <= screen_w - margin - counter_w;
+        if wide {
+            let timer = Rect::new(screen_w - counter_w - margin, top, counter_w, inner_h);
+            return Self {
+                counter: Rect::new(margin, top, counter_w, inner_h),
+                timer,
+                face,
+                mode: Rect::new(column_x, top, btn_w, btn_h),
+                narrow: false,
+                left_edge: column_x + btn_w + margin / 2.0,
+                gap: (face.right() + margin / 2.0, timer.x - margin / 2.0),
+            };
+        }
+
+        // narrow: the counters stacked at half size and the mode button on the other
+        // side of the face, as wide as they are so both sides match
+        let stacked_h = (inner_h - padding) / 2.0;
+        let stacked_w = counter_w / 2.0;
+        let mode = Rect::new(screen_w - stacked_w - margin, top, stacked_w, btn_h);
+        Self {
+            counter: Rect::new(margin, top, stacked_w, stacked_h),
+            timer: Rect::new(margin, top + stacked_h + padding, stacked_w, stacked_h),
+            face,
+            mode,
+            narrow: true,
+            left_edge: margin + stacked_w + margin / 2.0,
+            gap: (
+                face.right() + margin / 2.0,
+                (mode.x - margin / 2.0).max(face.right() + margin / 2.0),
+            ),
+        }
+    }
+}
+
+// the open list hanging below the mode button, kept inside the window
+pub fn mode_list_rects(button: Rect, count: usize, screen_w: f32) -> Vec<Rect> {
+    let item_w = button.w.max(button.h * 6.0).min(screen_w);
+    let x = button.x.min(screen_w - item_w).max(0.0);
+    (0..count)
+        .map(|i| Rect::new(x, button.bottom() + i as f32 * button.h, item_w, button.h))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // no two elements share a pixel at any width down to a third of a full HUD's row
+    #[test]
+    fn test_layout_never_overlaps() {
+        for screen_w in (310..1600).step_by(10) {
+            let layout = HudLayout::new(screen_w as f32, HUD_BASE_HEIGHT);
+            let rects = [layout.counter, layout.timer, layout.mode];
+            for (i, a) in rects.iter().enumerate() {
+                assert!(!a.overlaps(&layout.face), "{} at {}", i, screen_w);
+                for b in &rects[i + 1..] {
+                    assert!(!a.overlaps(b), "{} at {}", i, screen_w);
+                }
+            }
+            assert!(layout.left_edge <= layout.face.x);
+            assert!(layout.gap.0 <= layout.gap.1);
+        }
+        assert!(!HudLayout::new(1200.0, HUD_BASE_HEIGHT).narrow);
+        assert!(HudLayout::new(400.0, HUD_BASE_HEIGHT).narrow);
+    }
+
+    #[test]
+    fn test_mode_list_grows_with_recent_boards() {
+        let recent = CustomBoard {
+            width: 20,
+            height: 12,
+            density: 18,
+        };
+        let entries = mode_entries(&[recent]);
+        assert_eq!(entries.len(), PRESETS.len() + 3);
+        assert_eq!(entries[0].label(), "EASY");
+        assert_eq!(entries.last().unwrap().label(), "20X12 18%");
+
+        // a narrow window's button sits at the right edge, so its list opens leftwards
+        let layout = HudLayout::new(400.0, HUD_BASE_HEIGHT);
+        let rects = mode_list_rects(layout.mode, entries.len(), 400.0);
+        assert!(rects.iter().all(|rect| rect.right() <= 400.0));
+        assert_eq!(rects[1].y, rects[0].bottom());
+    }
+
+    #[test]
+    fn test_counter_state() {
+        let now = Duration::ZERO;
+        assert_eq!(CounterState::evaluate(10, now), CounterState::Normal);
+        assert_eq!(CounterState::evaluate(5, now), CounterState::Low);
+        assert_eq!(CounterState::evaluate(0, now), CounterState::Low);
+        assert_eq!(
+            CounterState::evaluate(-1, now),
+            CounterState::Over { lit: true }
+        );
+        assert_eq!(
+            CounterState::evaluate(-1, Duration::from_millis(500)),
+            CounterState::Over { lit: false }
+        );
+    }
+}
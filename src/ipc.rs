@@ -0,0 +1,261 @@
+use super::diagnostics::Diagnostics;
+use super::game::{Engine, GridSize, Mark, TileState};
+use super::metrics::Metrics;
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+#[cfg(unix)]
+use std::path::PathBuf;
+
+#[cfg(unix)]
+pub const IPC_SOCKET_NAME: &str = "rust_sweeper.sock";
+// named pipes are not in std, so other platforms listen on localhost instead
+#[cfg(not(unix))]
+pub const IPC_PORT: u16 = 47_470;
+
+const WRITE_TIMEOUT: Duration = Duration::from_millis(100);
+// how stale the metrics a scraper receives may be
+const PUBLISH_INTERVAL: Duration = Duration::from_secs(1);
+
+// read-only endpoint: every client that connects receives one JSON snapshot of the game.
+// Clients are served from a thread of their own out of the last published snapshot, as
+// the metrics are, so one that never reads can't hold up a frame
+pub struct IpcServer {
+    snapshot: Arc<Mutex<String>>,
+    #[cfg(unix)]
+    path: PathBuf,
+}
+
+impl IpcServer {
+    // the socket goes in the user's runtime dir, or the user data dir where there is none,
+    // so other users on the machine can neither read it nor take its name
+    #[cfg(unix)]
+    pub fn bind(data_dir: &Path) -> io::Result<Self> {
+        let dir = std::env::var_os("XDG_RUNTIME_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| data_dir.to_path_buf());
+        IpcServer::bind_at(dir.join(IPC_SOCKET_NAME))
+    }
+
+    #[cfg(unix)]
+    fn bind_at(path: PathBuf) -> io::Result<Self> {
+        let listener = match UnixListener::bind(&path) {
+            Ok(listener) => listener,
+            // a socket nobody answers on was left behind by a crashed run; one that answers
+            // belongs to another running game and is left alone
+            Err(e) if e.kind() == io::ErrorKind::AddrInUse => {
+                if UnixStream::connect(&path).is_ok() {
+                    return Err(e);
+                }
+                std::fs::remove_file(&path)?;
+                UnixListener::bind(&path)?
+            }
+            Err(e) => return Err(e),
+        };
+        let snapshot = Arc::new(Mutex::new(String::new()));
+        let served = Arc::clone(&snapshot);
+        // the thread blocks in accept for the rest of the session
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                // a stuck client only holds up the clients queued behind it
+                let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+                send_snapshot(stream, &served);
+            }
+        });
+        Ok(Self { snapshot, path })
+    }
+
+    #[cfg(not(unix))]
+    pub fn bind(_data_dir: &Path) -> io::Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", IPC_PORT))?;
+        let snapshot = Arc::new(Mutex::new(String::new()));
+        let served = Arc::clone(&snapshot);
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+                send_snapshot(stream, &served);
+            }
+        });
+        Ok(Self { snapshot })
+    }
+
+    // the state the next client receives
+    pub fn publish(&self, engine: &Engine) {
+        let json = snapshot_json(engine);
+        if let Ok(mut latest) = self.snapshot.lock() {
+            *latest = json;
+        }
+    }
+}
+
+fn send_snapshot(mut stream: impl Write, snapshot: &Mutex<String>) {
+    let json = snapshot.lock().map(|s| s.clone()).unwrap_or_default();
+    let _ = stream.write_all(json.as_bytes());
+}
+
+#[cfg(unix)]
+impl Drop for IpcServer {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+// plain http on localhost for scrapers such as prometheus; every request gets the
+// metrics, whatever its path. Connections are served from a thread of their own out of
+// the last published snapshot, so a slow or hostile client never touches the game loop
+pub struct MetricsServer {
+    response: Arc<Mutex<String>>,
+    published: Option<Instant>,
+}
+
+impl MetricsServer {
+    pub fn bind(port: u16) -> io::Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", port))?;
+        let response = Arc::new(Mutex::new(http_response("")));
+        let served = Arc::clone(&response);
+        // the thread blocks in accept for the rest of the session
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let response = served.lock().map(|r| r.clone()).unwrap_or_default();
+                serve_metrics(stream, &response);
+            }
+        });
+        Ok(Self {
+            response,
+            published: None,
+        })
+    }
+
+    // renders a new snapshot for the server thread, at most once per PUBLISH_INTERVAL
+    pub fn publish(&mut self, metrics: &Metrics, diagnostics: &Diagnostics) {
+        let now = Instant::now();
+        if self
+            .published
+            .is_some_and(|published| now.duration_since(published) < PUBLISH_INTERVAL)
+        {
+            return;
+        }
+        self.published = Some(now);
+        let response = http_response(&metrics.render(now, diagnostics));
+        if let Ok(mut latest) = self.response.lock() {
+            *latest = response;
+        }
+    }
+}
+
+fn serve_metrics(mut stream: TcpStream, response: &str) {
+    // a stuck scraper only holds up the scrapers queued behind it
+    let _ = stream.set_read_timeout(Some(WRITE_TIMEOUT));
+    let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+    // the request is read so closing the connection doesn't reset it
+    let _ = stream.read(&mut [0; 1024]);
+    let _ = stream.write_all(response.as_bytes());
+}
+
+fn http_response(body: &str) -> String {
+    format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    )
+}
+
+// hidden tiles never leak whether they hold a bomb
+pub fn snapshot_json(engine: &Engine) -> String {
+    let status = if engine.is_won() {
+        "won"
+    } else if engine.is_lost() {
+        "lost"
+    } else if engine.is_in_progress() {
+        "playing"
+    } else {
+        "ready"
+    };
+    let GridSize { width, height } = engine.size();
+
+    let rows = engine
+        .get_tiles()
+        .chunks(width)
+        .map(|row| {
+            let row = row
+                .iter()
+                .map(|tile| match tile.state {
+                    TileState::Block(Mark::Flagged) => 'F',
+                    TileState::Block(Mark::Questioned) => '?',
+                    TileState::Block(Mark::Unmarked) => '#',
+                    TileState::Revealed if tile.is_bomb() => '*',
+                    TileState::Revealed => char::from_digit(tile.num_adjacent_bombs(), 10).unwrap(),
+                })
+                .collect::<String>();
+            format!("\"{}\"", row)
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+
+    format!(
+        "{{\"status\":\"{}\",\"elapsed_ms\":{},\"mines_left\":{},\"width\":{},\"height\":{},\"board\":[{}]}}\n",
+        status,
+        engine.elapsed().as_millis(),
+        engine.bombs_left(),
+        width,
+        height,
+        rows
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Position;
+
+    #[test]
+    fn test_http_response_carries_the_body() {
+        let response = http_response("up 1\n");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("Content-Length: 5\r\n"));
+        assert!(response.ends_with("\r\n\r\nup 1\n"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_bind_replaces_only_a_stale_socket() {
+        let dir = std::env::temp_dir().join(format!("rust_sweeper_ipc_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(IPC_SOCKET_NAME);
+
+        // a listener dropped without cleaning up leaves its file behind
+        drop(UnixListener::bind(&path).unwrap());
+        let server = IpcServer::bind_at(path.clone()).unwrap();
+        // while it runs, a second game can't take the socket over
+        assert!(IpcServer::bind_at(path.clone()).is_err());
+        server.publish(&Engine::new(GridSize::new(5, 5), 1));
+        let mut json = String::new();
+        UnixStream::connect(&path)
+            .unwrap()
+            .read_to_string(&mut json)
+            .unwrap();
+        assert!(json.starts_with("{\"status\":\"ready\""));
+        drop(server);
+        assert!(!path.exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_snapshot_hides_bombs() {
+        let mut engine = Engine::new(GridSize::new(3, 2), 1);
+        engine.flag(Position(0, 0));
+
+        assert_eq!(
+            snapshot_json(&engine),
+            "{\"status\":\"ready\",\"elapsed_ms\":0,\"mines_left\":0,\"width\":3,\"height\":2,\"board\":[\"F##\",\"###\"]}\n"
+        );
+    }
+}
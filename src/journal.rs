@@ -0,0 +1,159 @@
+use super::game::Engine;
+use super::replay::{move_line, Replay};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+pub const JOURNAL_FILE_NAME: &str = "autosave.journal";
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(1);
+
+// append-only log of the current game in replay format, so a crash loses at most
+// the last second of clock time and no moves; closing the window keeps it too
+pub struct Journal {
+    path: PathBuf,
+    file: File,
+    written_moves: usize,
+    since_checkpoint: Duration,
+}
+
+impl Journal {
+    pub fn start(path: PathBuf, engine: &Engine) -> io::Result<Self> {
+        let mut file = File::create(&path)?;
+        // the replay body already holds the board, seed and any moves made so far
+        file.write_all(Replay::from_engine(engine).body().as_bytes())?;
+        file.sync_data()?;
+        Ok(Self {
+            path,
+            file,
+            written_moves: engine.moves().len(),
+            since_checkpoint: Duration::ZERO,
+        })
+    }
+
+    pub fn update(&mut self, delta: Duration, engine: &Engine) -> io::Result<()> {
+        self.since_checkpoint += delta;
+
+        let mut text = String::new();
+        for m in &engine.moves()[self.written_moves..] {
+            text.push_str(&move_line(m));
+        }
+        // a later time line overrides the earlier ones when the journal is read back
+        if !text.is_empty() || self.since_checkpoint >= CHECKPOINT_INTERVAL {
+            text.push_str(&format!("time {}\n", engine.elapsed().as_millis()));
+            self.since_checkpoint = Duration::ZERO;
+        }
+        if text.is_empty() {
+            return Ok(());
+        }
+
+        self.file.write_all(text.as_bytes())?;
+        self.file.sync_data()?;
+        self.written_moves = engine.moves().len();
+        Ok(())
+    }
+
+    // the window is closing mid-game: the clock is written as it stands and the log
+    // is left for the next start to resume
+    pub fn keep(mut self, engine: &Engine) -> io::Result<()> {
+        self.since_checkpoint = CHECKPOINT_INTERVAL;
+        self.update(Duration::ZERO, engine)
+    }
+
+    // the game ended normally, nothing to recover
+    pub fn discard(self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+// rebuilds the game a previous run left behind, if it was still in progress
+pub fn recover(path: &Path) -> Option<Engine> {
+    let text = fs::read_to_string(path).ok()?;
+    // a crash mid-write can leave a partial last line
+    let complete = &text[..text.rfind('\n').map_or(0, |i| i + 1)];
+    let engine = Replay::parse(complete).ok()?.simulate().ok()?;
+    if engine.is_in_progress() {
+        Some(engine)
+    } else {
+        let _ = fs::remove_file(path);
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{GridSize, Position};
+
+    fn started_engine() -> Engine {
+        let mut engine = Engine::with_seed(GridSize::new(9, 9), 10, 42);
+        engine.tick(Duration::from_millis(500));
+        engine.reveal(Position(4, 4));
+        engine
+    }
+
+    #[test]
+    fn test_recover_after_crash() {
+        let path = std::env::temp_dir().join(format!(
+            "rust_sweeper_test_recover_{}.journal",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+
+        let mut engine = Engine::with_seed(GridSize::new(9, 9), 10, 42);
+        let mut journal = Journal::start(path.clone(), &engine).unwrap();
+        engine.tick(Duration::from_millis(500));
+        engine.reveal(Position(4, 4));
+        journal.update(Duration::from_millis(500), &engine).unwrap();
+        engine.tick(Duration::from_millis(1500));
+        journal
+            .update(Duration::from_millis(1500), &engine)
+            .unwrap();
+        // simulate a write cut short by the crash
+        journal.file.write_all(b"move 2100 fl").unwrap();
+        drop(journal);
+
+        let recovered = recover(&path).unwrap();
+        assert_eq!(recovered.moves(), started_engine().moves());
+        assert_eq!(recovered.elapsed(), Duration::from_millis(2000));
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_resume_after_quit() {
+        let path = std::env::temp_dir().join(format!(
+            "rust_sweeper_test_quit_{}.journal",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+
+        let mut engine = Engine::with_seed(GridSize::new(9, 9), 10, 42);
+        let journal = Journal::start(path.clone(), &engine).unwrap();
+        engine.tick(Duration::from_millis(500));
+        engine.reveal(Position(4, 4));
+        engine.tick(Duration::from_millis(250));
+        journal.keep(&engine).unwrap();
+
+        let resumed = recover(&path).unwrap();
+        assert_eq!(resumed.moves(), started_engine().moves());
+        assert_eq!(resumed.elapsed(), Duration::from_millis(750));
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_recover_ignores_unstarted_game() {
+        let path = std::env::temp_dir().join(format!(
+            "rust_sweeper_test_unstarted_{}.journal",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+
+        let engine = Engine::with_seed(GridSize::new(9, 9), 10, 42);
+        Journal::start(path.clone(), &engine).unwrap();
+
+        assert!(recover(&path).is_none());
+        assert!(!path.exists());
+    }
+}
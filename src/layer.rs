@@ -0,0 +1,79 @@
+use ggez::graphics::Canvas;
+use ggez::Context;
+
+// draw order, back to front; a later layer always covers an earlier one
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Layer {
+    // the board, or a full screen menu in its place
+    Board,
+    // highlights drawn over the tiles
+    Overlay,
+    Hud,
+    Dialog,
+    Toast,
+}
+
+type DrawFn<'a> = Box<dyn FnOnce(&mut Context, &mut Canvas) + 'a>;
+
+// collects a frame's drawing so each piece lands in its layer no matter when it was added
+pub struct LayerStack<'a> {
+    entries: Vec<(Layer, DrawFn<'a>)>,
+}
+
+impl<'a> LayerStack<'a> {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, layer: Layer, draw: impl FnOnce(&mut Context, &mut Canvas) + 'a) {
+        self.entries.push((layer, Box::new(draw)));
+    }
+
+    // within a layer, things are drawn in the order they were pushed
+    fn into_ordered(mut self) -> Vec<(Layer, DrawFn<'a>)> {
+        self.entries.sort_by_key(|(layer, _)| *layer);
+        self.entries
+    }
+
+    pub fn draw(self, ctx: &mut Context, canvas: &mut Canvas) {
+        for (_, draw) in self.into_ordered() {
+            draw(ctx, canvas);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layers_draw_back_to_front() {
+        let mut stack = LayerStack::new();
+        for layer in [
+            Layer::Dialog,
+            Layer::Board,
+            Layer::Hud,
+            Layer::Board,
+            Layer::Toast,
+        ] {
+            stack.push(layer, |_, _| {});
+        }
+        let layers: Vec<Layer> = stack
+            .into_ordered()
+            .into_iter()
+            .map(|(layer, _)| layer)
+            .collect();
+        assert_eq!(
+            layers,
+            [
+                Layer::Board,
+                Layer::Board,
+                Layer::Hud,
+                Layer::Dialog,
+                Layer::Toast
+            ]
+        );
+    }
+}
@@ -0,0 +1,282 @@
+use super::storage;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// one "<category>/<finished unix ms>\t<updated unix ms>\t<time ms>" line per entry,
+// followed by "\t<name>" once the run is named, merged per record by sync
+const LEADERBOARD_FILE_NAME: &str = "leaderboard.records";
+
+// the rules a time was set under; runs are only ranked against the same rules
+#[derive(Clone, PartialEq, Debug)]
+pub struct Category {
+    // a preset name or a weekly marathon such as "weekly-2026-10-12"
+    pub board: String,
+    pub auto_chord: bool,
+    pub auto_flag: bool,
+    // hints or undos were used, their penalties are already in the time
+    pub assisted: bool,
+}
+
+impl Category {
+    // classic runs keep the bare board name, so their records predate the rule suffixes
+    pub fn key(&self) -> String {
+        let mut key = self.board.clone();
+        for (enabled, suffix) in [
+            (self.auto_chord, "+auto-chord"),
+            (self.auto_flag, "+auto-flag"),
+            (self.assisted, "+assisted"),
+        ] {
+            if enabled {
+                key.push_str(suffix);
+            }
+        }
+        key
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Entry {
+    // unix milliseconds when the run was finished
+    pub finished: u64,
+    // unix milliseconds of the last change, so sync keeps the named copy
+    updated: u64,
+    pub time: Duration,
+    // typed in after the win, empty until then
+    pub name: String,
+}
+
+// the best times of one profile, grouped by category key such as "easy" or
+// "weekly-2026-10-12+auto-flag"
+pub struct Leaderboard {
+    path: PathBuf,
+    categories: BTreeMap<String, Vec<Entry>>,
+}
+
+impl Leaderboard {
+    pub fn load(profile_dir: &Path) -> Self {
+        let mut leaderboard = Self {
+            path: profile_dir.join(LEADERBOARD_FILE_NAME),
+            categories: BTreeMap::new(),
+        };
+        let text = fs::read_to_string(&leaderboard.path).unwrap_or_default();
+        for line in text.lines() {
+            let fields = line.split('\t').collect::<Vec<_>>();
+            // older files marked assisted runs with a fourth field instead of their category;
+            // names are upper case, so one can't be mistaken for the marker
+            let (id, updated, time, suffix, name) = match fields.as_slice() {
+                [id, updated, time] => (id, updated, time, "", ""),
+                [id, updated, time, "assisted"] => (id, updated, time, "+assisted", ""),
+                [id, updated, time, name] => (id, updated, time, "", *name),
+                _ => continue,
+            };
+            let Some((category, finished)) = id.rsplit_once('/') else {
+                continue;
+            };
+            if let (Ok(finished), Ok(updated), Ok(ms)) =
+                (finished.parse(), updated.parse(), time.parse())
+            {
+                leaderboard.insert(
+                    &format!("{}{}", category, suffix),
+                    Entry {
+                        finished,
+                        updated,
+                        time: Duration::from_millis(ms),
+                        name: name.to_string(),
+                    },
+                );
+            }
+        }
+        leaderboard
+    }
+
+    fn insert(&mut self, category: &str, entry: Entry) -> usize {
+        let entries = self.categories.entry(category.to_string()).or_default();
+        let rank = entries.partition_point(|e| e.time <= entry.time);
+        entries.insert(rank, entry);
+        rank
+    }
+
+    // returns the zero-based rank of the new time within its category
+    pub fn submit(&mut self, category: &Category, time: Duration) -> io::Result<usize> {
+        let finished = now_ms();
+        let rank = self.insert(
+            &category.key(),
+            Entry {
+                finished,
+                updated: finished,
+                time,
+                name: String::new(),
+            },
+        );
+        self.save()?;
+        Ok(rank)
+    }
+
+    // names the entry at a rank submit just returned
+    pub fn set_name(&mut self, category: &Category, rank: usize, name: &str) -> io::Result<()> {
+        let Some(entry) = self
+            .categories
+            .get_mut(&category.key())
+            .and_then(|entries| entries.get_mut(rank))
+        else {
+            return Ok(());
+        };
+        entry.name = name.to_string();
+        entry.updated = now_ms().max(entry.updated + 1);
+        self.save()
+    }
+
+    // fastest first
+    fn entries(&self, key: &str) -> &[Entry] {
+        self.categories.get(key).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn top(&self, category: &Category, count: usize) -> &[Entry] {
+        let entries = self.entries(&category.key());
+        &entries[..entries.len().min(count)]
+    }
+
+    pub fn best(&self, category: &Category) -> Option<Duration> {
+        self.entries(&category.key())
+            .first()
+            .map(|entry| entry.time)
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let mut text = String::new();
+        for (category, entries) in &self.categories {
+            for entry in entries {
+                text.push_str(&format!(
+                    "{}/{}\t{}\t{}",
+                    category,
+                    entry.finished,
+                    entry.updated,
+                    entry.time.as_millis()
+                ));
+                if !entry.name.is_empty() {
+                    text.push_str(&format!("\t{}", entry.name));
+                }
+                text.push('\n');
+            }
+        }
+        storage::write_atomic(&self.path, text)
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classic(board: &str) -> Category {
+        Category {
+            board: board.to_string(),
+            auto_chord: false,
+            auto_flag: false,
+            assisted: false,
+        }
+    }
+
+    #[test]
+    fn test_entries_are_ranked() {
+        let mut leaderboard = Leaderboard {
+            path: PathBuf::new(),
+            categories: BTreeMap::new(),
+        };
+        let entry = |ms| Entry {
+            finished: ms,
+            updated: ms,
+            time: Duration::from_millis(ms),
+            name: String::new(),
+        };
+
+        assert_eq!(leaderboard.insert("easy", entry(300)), 0);
+        assert_eq!(leaderboard.insert("easy", entry(100)), 0);
+        assert_eq!(leaderboard.insert("easy", entry(200)), 1);
+        assert_eq!(leaderboard.insert("hard", entry(900)), 0);
+
+        assert_eq!(
+            leaderboard.best(&classic("easy")),
+            Some(Duration::from_millis(100))
+        );
+        assert_eq!(leaderboard.entries("easy").len(), 3);
+        assert_eq!(leaderboard.best(&classic("medium")), None);
+    }
+
+    #[test]
+    fn test_rules_keep_separate_records() {
+        let mut leaderboard = Leaderboard {
+            path: PathBuf::new(),
+            categories: BTreeMap::new(),
+        };
+        let chorded = Category {
+            auto_chord: true,
+            ..classic("easy")
+        };
+        let assisted = Category {
+            assisted: true,
+            ..chorded.clone()
+        };
+        assert_eq!(classic("easy").key(), "easy");
+        assert_eq!(assisted.key(), "easy+auto-chord+assisted");
+
+        let entry = |ms| Entry {
+            finished: ms,
+            updated: ms,
+            time: Duration::from_millis(ms),
+            name: String::new(),
+        };
+        leaderboard.insert(&classic("easy").key(), entry(300));
+        leaderboard.insert(&assisted.key(), entry(100));
+        assert_eq!(
+            leaderboard.best(&classic("easy")),
+            Some(Duration::from_millis(300))
+        );
+        assert_eq!(leaderboard.best(&chorded), None);
+    }
+
+    #[test]
+    fn test_leaderboard_round_trip() {
+        let dir = std::env::temp_dir().join(format!(
+            "rust_sweeper_test_leaderboard_{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        // an assisted run from before categories carried the rules
+        fs::write(
+            dir.join(LEADERBOARD_FILE_NAME),
+            "weekly-2026-10-12/1\t1\t60000\tassisted\n",
+        )
+        .unwrap();
+
+        let weekly = classic("weekly-2026-10-12");
+        let mut leaderboard = Leaderboard::load(&dir);
+        let rank = leaderboard
+            .submit(&weekly, Duration::from_millis(95_000))
+            .unwrap();
+        leaderboard.set_name(&weekly, rank, "ANNA").unwrap();
+
+        let loaded = Leaderboard::load(&dir);
+        assert_eq!(loaded.best(&weekly), Some(Duration::from_millis(95_000)));
+        let named = &loaded.top(&weekly, 10)[0];
+        assert_eq!(named.name, "ANNA");
+        assert!(named.updated > named.finished);
+        let assisted = Category {
+            assisted: true,
+            ..weekly
+        };
+        assert_eq!(loaded.best(&assisted), Some(Duration::from_millis(60_000)));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,234 @@
+use super::analysis::replayed;
+use super::game::{Engine, MoveKind};
+use super::replay::Replay;
+use super::solver::{self, Certainty};
+use super::storage;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// one "<finished unix ms>\t<updated unix ms>\t<cause>" line per lost game, merged per
+// record by sync
+const LOSSES_FILE_NAME: &str = "losses.records";
+// a click this soon after the previous move, next to a tile that was proven safe,
+// most likely landed one tile off
+const MISCLICK_TIME: Duration = Duration::from_millis(400);
+// guesses with less than this share of the board open count as opening guesses
+const EARLY_PROGRESS: f32 = 0.2;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LossCause {
+    // nothing on the board could be proven safe any more
+    FiftyFifty,
+    // the numbers proved the tile a mine, or proved another tile safe
+    Misread,
+    Misclick,
+    // a blind guess before the numbers gave anything to work with
+    EarlyGuess,
+}
+
+impl LossCause {
+    pub const ALL: [LossCause; 4] = [
+        LossCause::FiftyFifty,
+        LossCause::Misread,
+        LossCause::Misclick,
+        LossCause::EarlyGuess,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            LossCause::FiftyFifty => "50/50",
+            LossCause::Misread => "MISREAD",
+            LossCause::Misclick => "MISCLICK",
+            LossCause::EarlyGuess => "EARLY GUESS",
+        }
+    }
+
+    fn key(&self) -> &'static str {
+        match self {
+            LossCause::FiftyFifty => "fifty-fifty",
+            LossCause::Misread => "misread",
+            LossCause::Misclick => "misclick",
+            LossCause::EarlyGuess => "early-guess",
+        }
+    }
+}
+
+// the board as the player saw it when the losing reveal was made
+struct FatalClick {
+    certainty: Certainty,
+    safe_nearby: bool,
+    safe_anywhere: bool,
+    progress: f32,
+    // time since the move before it
+    hesitation: Duration,
+}
+
+impl FatalClick {
+    fn cause(&self) -> LossCause {
+        match self.certainty {
+            Certainty::Mine if self.safe_nearby => LossCause::Misclick,
+            Certainty::Mine => LossCause::Misread,
+            _ if self.safe_nearby && self.hesitation < MISCLICK_TIME => LossCause::Misclick,
+            _ if self.safe_anywhere => LossCause::Misread,
+            _ if self.progress < EARLY_PROGRESS => LossCause::EarlyGuess,
+            _ => LossCause::FiftyFifty,
+        }
+    }
+}
+
+// plays the lost game up to its last move and asks the solver what was known then
+pub fn diagnose(engine: &Engine) -> Option<LossCause> {
+    let replay = Replay::from_engine(engine);
+    let (fatal, earlier) = replay.moves.split_last()?;
+    if !engine.is_lost() || fatal.kind != MoveKind::Reveal {
+        return None;
+    }
+    let before = replayed(&replay, earlier.len());
+    let width = before.size().width;
+    let known = solver::analyze(&before);
+    let covered_safe =
+        |index: usize| known[index] == Certainty::Safe && !before.get_tiles()[index].is_revealed();
+    let click = FatalClick {
+        certainty: known[fatal.pos.to_index(width)],
+        safe_nearby: before
+            .neighbours(fatal.pos)
+            .iter()
+            .any(|pos| covered_safe(pos.to_index(width))),
+        safe_anywhere: (0..known.len()).any(covered_safe),
+        progress: before.progress(),
+        hesitation: fatal
+            .time
+            .saturating_sub(earlier.last().map_or(Duration::ZERO, |m| m.time)),
+    };
+    Some(click.cause())
+}
+
+// why games were lost, for the stats screen
+pub struct Losses {
+    path: PathBuf,
+    // finished time and cause, oldest first
+    results: Vec<(u64, LossCause)>,
+}
+
+impl Losses {
+    pub fn load(profile_dir: &Path) -> Self {
+        let path = profile_dir.join(LOSSES_FILE_NAME);
+        let mut results = Vec::new();
+        for line in fs::read_to_string(&path).unwrap_or_default().lines() {
+            let fields = line.split('\t').collect::<Vec<_>>();
+            let [finished, _, key] = fields.as_slice() else {
+                continue;
+            };
+            let cause = LossCause::ALL.into_iter().find(|cause| cause.key() == *key);
+            if let (Ok(finished), Some(cause)) = (finished.parse(), cause) {
+                results.push((finished, cause));
+            }
+        }
+        results.sort_by_key(|(finished, _)| *finished);
+        Self { path, results }
+    }
+
+    pub fn record(&mut self, cause: LossCause) -> io::Result<()> {
+        let finished = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        self.results.push((finished, cause));
+
+        let mut text = String::new();
+        for (finished, cause) in &self.results {
+            text.push_str(&format!("{}\t{}\t{}\n", finished, finished, cause.key()));
+        }
+        storage::write_atomic(&self.path, text)
+    }
+
+    pub fn counts(&self) -> [(LossCause, usize); 4] {
+        LossCause::ALL.map(|cause| {
+            let count = self.results.iter().filter(|(_, c)| *c == cause).count();
+            (cause, count)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Position;
+    use crate::testing::proven_mine;
+
+    #[test]
+    fn test_causes() {
+        let click = |certainty, safe_nearby, safe_anywhere, progress, hesitation_ms| {
+            FatalClick {
+                certainty,
+                safe_nearby,
+                safe_anywhere,
+                progress,
+                hesitation: Duration::from_millis(hesitation_ms),
+            }
+            .cause()
+        };
+        assert_eq!(
+            click(Certainty::Mine, true, true, 0.5, 2000),
+            LossCause::Misclick
+        );
+        assert_eq!(
+            click(Certainty::Mine, false, true, 0.5, 2000),
+            LossCause::Misread
+        );
+        assert_eq!(
+            click(Certainty::Unknown, true, true, 0.5, 150),
+            LossCause::Misclick
+        );
+        assert_eq!(
+            click(Certainty::Unknown, true, true, 0.5, 2000),
+            LossCause::Misread
+        );
+        assert_eq!(
+            click(Certainty::Unknown, false, false, 0.1, 2000),
+            LossCause::EarlyGuess
+        );
+        assert_eq!(
+            click(Certainty::Unknown, false, false, 0.8, 2000),
+            LossCause::FiftyFifty
+        );
+    }
+
+    #[test]
+    fn test_diagnose_replays_the_loss() {
+        let mut engine = proven_mine();
+        assert_eq!(diagnose(&engine), None);
+        engine.reveal(Position(3, 0));
+        assert_eq!(diagnose(&engine), Some(LossCause::Misread));
+    }
+
+    #[test]
+    fn test_losses_round_trip() {
+        let dir =
+            std::env::temp_dir().join(format!("rust_sweeper_test_losses_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut losses = Losses::load(&dir);
+        for cause in [
+            LossCause::Misread,
+            LossCause::EarlyGuess,
+            LossCause::Misread,
+        ] {
+            losses.record(cause).unwrap();
+        }
+        assert_eq!(
+            Losses::load(&dir).counts(),
+            [
+                (LossCause::FiftyFifty, 0),
+                (LossCause::Misread, 2),
+                (LossCause::Misclick, 0),
+                (LossCause::EarlyGuess, 1)
+            ]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,169 @@
+use super::game::{Engine, MoveKind};
+use super::solver;
+use super::storage;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// one "<finished unix ms>\t<updated unix ms>\t<forced guesses> <survived>" line per
+// finished game, merged per record by sync
+const LUCK_FILE_NAME: &str = "luck.records";
+
+// follows a game move by move, noting when nothing was left to deduce and how the
+// reveal that came next turned out
+pub struct GuessWatch {
+    moves: usize,
+    required: bool,
+    pub guesses: u32,
+    pub survived: u32,
+}
+
+impl GuessWatch {
+    pub fn new() -> Self {
+        Self {
+            moves: 0,
+            required: false,
+            guesses: 0,
+            survived: 0,
+        }
+    }
+
+    // cheap until a move is made; moves taken back are not counted twice
+    pub fn update(&mut self, engine: &Engine) {
+        let moves = engine.moves();
+        if moves.len() == self.moves {
+            return;
+        }
+        let forced = self.required && moves.len() > self.moves;
+        if forced && moves.last().is_some_and(|m| m.kind == MoveKind::Reveal) {
+            self.guesses += 1;
+            if !engine.is_lost() {
+                self.survived += 1;
+            }
+        }
+        self.moves = moves.len();
+        self.required = solver::guess_required(engine);
+    }
+
+    // whether the board as it stands needs a guess
+    pub fn is_required(&self) -> bool {
+        self.required
+    }
+}
+
+struct GameLuck {
+    finished: u64,
+    guesses: u32,
+    survived: u32,
+}
+
+// how often games came down to a guess and how those guesses went, for the stats screen
+pub struct Luck {
+    path: PathBuf,
+    // oldest first
+    results: Vec<GameLuck>,
+}
+
+impl Luck {
+    pub fn load(profile_dir: &Path) -> Self {
+        let path = profile_dir.join(LUCK_FILE_NAME);
+        let mut results = Vec::new();
+        for line in fs::read_to_string(&path).unwrap_or_default().lines() {
+            let fields = line.split('\t').collect::<Vec<_>>();
+            let [finished, _, counts] = fields.as_slice() else {
+                continue;
+            };
+            let Some((guesses, survived)) = counts.split_once(' ') else {
+                continue;
+            };
+            if let (Ok(finished), Ok(guesses), Ok(survived)) =
+                (finished.parse(), guesses.parse(), survived.parse())
+            {
+                results.push(GameLuck {
+                    finished,
+                    guesses,
+                    survived,
+                });
+            }
+        }
+        results.sort_by_key(|result| result.finished);
+        Self { path, results }
+    }
+
+    pub fn record(&mut self, watch: &GuessWatch) -> io::Result<()> {
+        let finished = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        self.results.push(GameLuck {
+            finished,
+            guesses: watch.guesses,
+            survived: watch.survived,
+        });
+
+        let mut text = String::new();
+        for result in &self.results {
+            text.push_str(&format!(
+                "{}\t{}\t{} {}\n",
+                result.finished, result.finished, result.guesses, result.survived
+            ));
+        }
+        storage::write_atomic(&self.path, text)
+    }
+
+    // forced guesses survived out of those made
+    pub fn totals(&self) -> (u32, u32) {
+        let survived = self.results.iter().map(|r| r.survived).sum();
+        let guesses = self.results.iter().map(|r| r.guesses).sum();
+        (survived, guesses)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Position;
+    use crate::testing::proven_mine;
+
+    #[test]
+    fn test_watch_counts_forced_guesses() {
+        // "..1*2*": the 2 and the last mine can't be told apart
+        let mut engine = proven_mine();
+        let mut watch = GuessWatch::new();
+        watch.update(&engine);
+        assert!(watch.is_required());
+
+        // flagging the proven mine is no guess
+        engine.flag(Position(3, 0));
+        watch.update(&engine);
+        assert_eq!(watch.guesses, 0);
+        assert!(watch.is_required());
+
+        engine.reveal(Position(4, 0));
+        watch.update(&engine);
+        assert_eq!((watch.guesses, watch.survived), (1, 1));
+        assert!(!watch.is_required());
+    }
+
+    #[test]
+    fn test_luck_round_trip() {
+        let dir =
+            std::env::temp_dir().join(format!("rust_sweeper_test_luck_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut luck = Luck::load(&dir);
+        for (guesses, survived) in [(0, 0), (2, 1), (1, 1)] {
+            let watch = GuessWatch {
+                guesses,
+                survived,
+                ..GuessWatch::new()
+            };
+            luck.record(&watch).unwrap();
+        }
+        assert_eq!(Luck::load(&dir).totals(), (2, 3));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
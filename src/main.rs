@@ -1,17 +1,265 @@
-use ggez::event::{self, EventHandler, MouseButton};
-use ggez::graphics::{self, Color, FontData, Rect};
+use ggez::event::{self, Button, EventHandler, GamepadId, MouseButton};
+use ggez::graphics::{self, FontData, Rect};
+use ggez::input::keyboard::{KeyCode, KeyInput, KeyMods};
 use ggez::{Context, ContextBuilder, GameError, GameResult};
+use rust_sweeper_core::game;
 
+mod accessibility;
+mod action;
+mod analysis;
+mod batch;
 mod board;
+mod calendar;
+mod cascade;
+mod cli;
+mod command;
+mod config;
+mod crypto;
+mod custom;
+mod diagnostics;
 mod draw;
-mod game;
+mod errors;
+mod face;
+mod files;
+#[cfg(feature = "debug")]
+mod generation;
+mod ghost;
+mod guard;
+mod history;
+mod hud;
+mod ipc;
+mod journal;
+mod layer;
+mod leaderboard;
+mod losses;
+mod luck;
+mod marathon;
+mod metrics;
+mod mixer;
+mod openings;
+mod opponent;
+mod overlay;
+mod preview;
+mod profile;
+mod render;
+mod replay;
+mod scene;
+mod scores;
+mod sketch;
+mod solver;
 mod sprites;
+mod storage;
+mod sync;
+mod tabs;
+#[cfg(test)]
+mod testing;
+mod theme;
+mod thumbnail;
+mod toast;
+mod tooltip;
+mod tournament;
+mod trainer;
+mod verify;
+mod webhook;
 
-use board::{calculate_tile_size, get_tile_index, BoardSettings, EASY_BOARD};
-use game::{Engine, Position};
+use accessibility::{Announcer, SystemSpeech};
+use action::Action;
+use analysis::Analysis;
+use batch::TileBatch;
+use board::{
+    board_overflow, calculate_tile_size, daily_seed, error_banner_rect, get_tile_index,
+    get_tile_rect, hud_height, opponent_panel_rect, pixel_perfect_tile_size, screen_to_cell,
+    scrubber_rect, BoardCode, BoardSettings, CellCoord, Lens, ScreenPoint, COORDINATE_GUTTER,
+    EASY_BOARD, PRESETS,
+};
+use calendar::DailyLog;
+use cascade::Cascade;
+use cli::Command;
+use command::{CommandBar, Flash};
+use config::{Config, LossPolicy, MisclickGuard, NightMode, RevealInput};
+use custom::{CustomBoard, RecentBoards};
+use diagnostics::{CountingAllocator, Diagnostics};
+use draw::Viewport;
+use errors::{ErrorReporter, Subsystem};
+use face::Face;
+use files::GameFile;
+use game::{Engine, GridSize, MoveKind, Position};
+use ghost::Ghost;
+use guard::RevealGuard;
+use history::{History, Suggestion};
+use hud::{CounterState, ModeEntry};
+use ipc::{IpcServer, MetricsServer};
+use journal::Journal;
+use layer::{Layer, LayerStack};
+use leaderboard::{Category, Leaderboard};
+use losses::Losses;
+use luck::{GuessWatch, Luck};
+use marathon::Marathon;
+use metrics::Metrics;
+use mixer::{Cue, Mixer};
+use openings::Openings;
+use opponent::{OpponentEvent, OpponentPanel};
+use overlay::OverlayWriter;
+use profile::{Profiles, MAX_NAME_LEN};
+use replay::Replay;
+use scene::{
+    CalendarOption, CalendarState, ConfirmState, CustomOption, CustomState, GameOverOption,
+    GameOverState, OnboardingState, OnboardingStep, PauseOption, PauseState, ProfileOption,
+    ProfileState, Scene, SceneStack, ScoresState, SettingsOption, SettingsState, StatsState,
+    TitleOption, TitleState,
+};
+use scores::NameEntry;
+use sketch::Sketch;
+use solver::{Certainty, HeatHint, TileHint};
 use sprites::{FaceKind, SpriteManager};
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tabs::{OpenBoard, TabKey, Tabs};
+use theme::{Presets, Theme, NIGHT_THEME_FILE_NAME, THEME_FILE_NAME};
+use thumbnail::Thumbnails;
+use toast::Toast;
+use tooltip::{HudElement, Tooltips};
+use tournament::{Tournament, TournamentSession};
+use trainer::{ClickKind, GameStats, Trainer};
+use webhook::Webhook;
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const COUNTDOWN: Duration = Duration::from_secs(3);
+// the simulation advances in steps of exactly 1/TICKS_PER_SECOND, however fast frames are drawn
+const TICKS_PER_SECOND: u32 = 60;
+// how many tiles one notch of the mouse wheel scrolls a board too big for the window
+const SCROLL_TILES: f32 = 2.0;
+
+fn gutter_width(config: &Config) -> f32 {
+    if config.show_coordinates {
+        COORDINATE_GUTTER
+    } else {
+        0.0
+    }
+}
+
+fn board_tile_size(
+    config: &Config,
+    w: f32,
+    h: f32,
+    hud_h: f32,
+    board_size: GridSize,
+) -> (f32, f32) {
+    let tile_size = calculate_tile_size(w, h, hud_h, gutter_width(config), board_size);
+    if config.pixel_perfect {
+        pixel_perfect_tile_size(tile_size, draw::TILE_PX)
+    } else {
+        tile_size
+    }
+}
+
+fn tick_duration() -> Duration {
+    Duration::from_secs_f64(1.0 / TICKS_PER_SECOND as f64)
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn load_tournament(bundle: PathBuf, key: &str) -> Result<TournamentSession, String> {
+    let text = std::fs::read_to_string(&bundle).map_err(|e| e.to_string())?;
+    let tournament = Tournament::parse(&text)?;
+
+    let now = unix_now();
+    if now < tournament.start {
+        return Err(format!(
+            "tournament '{}' starts in {} seconds",
+            tournament.name,
+            tournament.start - now
+        ));
+    }
+
+    TournamentSession::new(tournament, key, bundle.with_extension("results"))
+}
+
+fn run_verify(file: PathBuf, bundle: Option<(PathBuf, String)>) -> Result<bool, String> {
+    let text = std::fs::read_to_string(&file).map_err(|e| e.to_string())?;
+    match bundle {
+        Some((bundle, key)) => {
+            let bundle_text = std::fs::read_to_string(&bundle).map_err(|e| e.to_string())?;
+            verify::run(&text, Some((&bundle_text, &key)))
+        }
+        None => verify::run(&text, None),
+    }
+}
+
+// renders without ever showing the window
+fn run_render(replay: PathBuf, out: PathBuf) -> Result<(), String> {
+    let text = std::fs::read_to_string(&replay).map_err(|e| e.to_string())?;
+    let engine = Replay::parse(&text)?.simulate()?;
+
+    let (mut ctx, _event_loop) = ContextBuilder::new("rust_sweeper", "Jan Rudowski")
+        .window_mode(ggez::conf::WindowMode::default().visible(false))
+        .build()
+        .map_err(|e| e.to_string())?;
+    ctx.fs
+        .add_zip_file(std::io::Cursor::new(include_bytes!("../resources.zip")))
+        .map_err(|e| e.to_string())?;
+    let sprite_manager = SpriteManager::new(&ctx, "classic");
+
+    let (width, height, pixels) = render::render_board(&mut ctx, &engine, &sprite_manager)?;
+    std::fs::write(&out, render::to_ppm(width, height, &pixels)).map_err(|e| e.to_string())
+}
+
+fn exit_with_error(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1)
+}
 
 fn main() -> GameResult {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let command = cli::parse_args(&args).unwrap_or_else(|usage| exit_with_error(&usage));
+
+    let (tournament, file, code) = match command {
+        Command::Play => (None, None, None),
+        Command::Open(path) => (
+            None,
+            Some(files::open(&path).unwrap_or_else(|e| exit_with_error(&e))),
+            None,
+        ),
+        Command::PlayCode(code) => (None, None, Some(code)),
+        Command::Tournament { bundle, key } => (
+            Some(load_tournament(bundle, &key).unwrap_or_else(|e| exit_with_error(&e))),
+            None,
+            None,
+        ),
+        Command::Verify { file, bundle } => {
+            let all_valid = run_verify(file, bundle).unwrap_or_else(|e| exit_with_error(&e));
+            std::process::exit(if all_valid { 0 } else { 1 });
+        }
+        Command::Render { replay, out } => {
+            run_render(replay, out).unwrap_or_else(|e| exit_with_error(&e));
+            return Ok(());
+        }
+        Command::MakeTournament {
+            name,
+            start,
+            difficulty,
+            rounds,
+            key,
+        } => {
+            let board_settings = BoardSettings::by_name(&difficulty).unwrap_or_else(|| {
+                exit_with_error(&format!("unknown difficulty '{}'", difficulty))
+            });
+            let seeds = (0..rounds).map(|_| rand::random()).collect::<Vec<u64>>();
+            print!(
+                "{}",
+                Tournament::create(&name, start, &board_settings, &seeds, key.as_bytes())
+            );
+            return Ok(());
+        }
+    };
+
     let builder = ContextBuilder::new("rust_sweeper", "Jan Rudowski")
         .window_setup(ggez::conf::WindowSetup::default().title("Rust Sweeper"))
         .window_mode(
@@ -26,34 +274,53 @@ fn main() -> GameResult {
     ctx.fs
         .add_zip_file(std::io::Cursor::new(include_bytes!("../resources.zip")))?;
 
-    let game: MainState = MainState::new(&mut ctx);
+    let mut game: MainState = MainState::new(&mut ctx, tournament);
+    if let Some(file) = file {
+        game.open_file(file, &mut ctx);
+    }
+    if let Some(code) = code {
+        game.play_code(code, &mut ctx);
+    }
 
     event::run(ctx, event_loop, game)
 }
 
 pub struct MenuState {
-    pub time_passed: u32,
     pub face_kind: FaceKind,
+    pub counter_state: CounterState,
     pub face_rect: Option<Rect>,
-    pub easy_button_rect: Option<Rect>,
-    pub medium_button_rect: Option<Rect>,
-    pub hard_button_rect: Option<Rect>,
+    pub hint_button_rect: Option<Rect>,
+    // the button that opens the list of boards to play, and the list while it is open
+    pub mode_button_rect: Option<Rect>,
+    pub mode_list_open: bool,
+    pub mode_items: Vec<(ModeEntry, Rect)>,
+    pub timer_rect: Option<Rect>,
+    pub counter_rect: Option<Rect>,
+    // where the board tabs were last drawn
+    pub tab_rects: Vec<(TabKey, Rect)>,
 }
 
 impl MenuState {
     pub fn new() -> Self {
         Self {
-            time_passed: 0,
             face_kind: FaceKind::Smile,
+            counter_state: CounterState::Normal,
             face_rect: None,
-            easy_button_rect: None,
-            medium_button_rect: None,
-            hard_button_rect: None,
+            hint_button_rect: None,
+            mode_button_rect: None,
+            mode_list_open: false,
+            mode_items: Vec::new(),
+            timer_rect: None,
+            counter_rect: None,
+            tab_rects: Vec::new(),
         }
     }
 
-    pub fn get_time_passed(&self) -> u32 {
-        self.time_passed
+    pub fn get_tab_clicked(&self, x: f32, y: f32) -> Option<TabKey> {
+        self.tab_rects
+            .iter()
+            .find(|(_, rect)| rect.contains([x, y]))
+            .map(|(key, _)| *key)
     }
 
     pub fn is_face_clicked(&self, x: f32, y: f32) -> bool {
@@ -64,42 +331,175 @@ impl MenuState {
         }
     }
 
-    pub fn get_difficulty_button_clicked(&self, x: f32, y: f32) -> Option<&str> {
-        if let Some(rect) = self.easy_button_rect {
-            if rect.contains([x, y]) {
-                return Some("easy");
-            }
-        }
+    pub fn is_hint_clicked(&self, x: f32, y: f32) -> bool {
+        self.hint_button_rect
+            .is_some_and(|rect| rect.contains([x, y]))
+    }
 
-        if let Some(rect) = self.medium_button_rect {
-            if rect.contains([x, y]) {
-                return Some("medium");
-            }
-        }
+    pub fn is_mode_button_clicked(&self, x: f32, y: f32) -> bool {
+        self.mode_button_rect
+            .is_some_and(|rect| rect.contains([x, y]))
+    }
 
-        if let Some(rect) = self.hard_button_rect {
-            if rect.contains([x, y]) {
-                return Some("hard");
-            }
-        }
+    // the open list hangs over the rest of the HUD, so it is checked first
+    pub fn get_mode_entry_clicked(&self, x: f32, y: f32) -> Option<ModeEntry> {
+        self.mode_items
+            .iter()
+            .find(|(_, rect)| rect.contains([x, y]))
+            .map(|(entry, _)| *entry)
+    }
 
-        None
+    // the element a tooltip would describe
+    pub fn element_at(&self, x: f32, y: f32) -> Option<HudElement> {
+        let over = |rect: Option<Rect>| rect.is_some_and(|rect| rect.contains([x, y]));
+        if let Some(entry) = self.get_mode_entry_clicked(x, y) {
+            Some(HudElement::ModeEntry(entry))
+        } else if over(self.timer_rect) {
+            Some(HudElement::Timer)
+        } else if over(self.counter_rect) {
+            Some(HudElement::Counter)
+        } else if over(self.face_rect) {
+            Some(HudElement::Face)
+        } else if over(self.hint_button_rect) {
+            Some(HudElement::Hint)
+        } else if over(self.mode_button_rect) {
+            Some(HudElement::Mode)
+        } else {
+            None
+        }
     }
 }
 
 struct MainState {
     engine: Engine,
+    // what of the last reveal is still to be uncovered on screen
+    cascade: Cascade,
     tile_size: (f32, f32),
     board_settings: BoardSettings,
     screen_dim: (f32, f32),
     sprite_manager: SpriteManager,
+    // the board's sprites, kept between frames until a tile changes
+    tile_batch: TileBatch,
     current_tile_idx: Option<usize>,
+    // how long the left button has been held on the board
+    pressed_for: Duration,
+    // the number held with the middle button, or both buttons, to chord on release
+    chord_tile: Option<usize>,
     menu_state: MenuState,
-    accumulated_time: f32,
+    config: Config,
+    scenes: SceneStack,
+    title_state: TitleState,
+    confirm_state: ConfirmState,
+    pause_state: PauseState,
+    settings_state: SettingsState,
+    onboarding_state: OnboardingState,
+    quit_confirmed: bool,
+    overlay: Option<OverlayWriter>,
+    ipc: Option<IpcServer>,
+    metrics: Metrics,
+    metrics_server: Option<MetricsServer>,
+    webhook: Webhook,
+    tournament: Option<TournamentSession>,
+    profiles: Profiles,
+    profile_state: ProfileState,
+    journal: Option<Journal>,
+    daily_log: DailyLog,
+    // the local day whose daily challenge is being played
+    daily_day: Option<i64>,
+    calendar_state: CalendarState,
+    stats_state: StatsState,
+    scores_state: ScoresState,
+    leaderboard: Leaderboard,
+    // Some while a win that made the high score table is being named
+    name_entry: Option<NameEntry>,
+    // Some while the panel over a finished game is up
+    game_over: Option<GameOverState>,
+    // the assist was on at some point of the current game, so its time is ranked with it
+    auto_chord_used: bool,
+    auto_flag_used: bool,
+    marathon: Option<Marathon>,
+    // set once the finished game has been counted in the daily log or leaderboard
+    result_recorded: bool,
+    ghost: Option<Ghost>,
+    history: History,
+    openings: Openings,
+    losses: Losses,
+    luck: Luck,
+    // whether the game in play has come down to a guess, for the notice and the luck stats
+    guess_watch: GuessWatch,
+    // time since the last input while a game is running
+    idle_time: Duration,
+    countdown_left: Duration,
+    sketch: Sketch,
+    // the tile under the mouse, shown in the HUD when coordinates are on
+    hovered_tile_idx: Option<usize>,
+    // how far the board is scrolled when it doesn't fit the window at the smallest tiles
+    scroll: (f32, f32),
+    command_bar: CommandBar,
+    flash: Option<Flash>,
+    hint: Option<HeatHint>,
+    tile_hint: Option<TileHint>,
+    // set while the efficiency trainer is on, with a fresh tally for every board
+    trainer: Option<Trainer>,
+    thumbnails: Thumbnails,
+    // a preview of the game the title screen offers to continue
+    continue_thumbnail: Option<graphics::Image>,
+    diagnostics: Diagnostics,
+    // config.theme is set to one of these by night mode
+    day_theme: Theme,
+    night_theme: Theme,
+    // the theme presets as their resource folders color them
+    presets: Presets,
+    face: Face,
+    mixer: Mixer,
+    announcer: Announcer,
+    // the ghost stands in as a bot opponent until there are real ones
+    opponent_panel: OpponentPanel,
+    custom_board: CustomBoard,
+    // the last few custom boards started, offered again in the mode list
+    recent_boards: RecentBoards,
+    custom_state: CustomState,
+    errors: ErrorReporter,
+    toast: Option<Toast>,
+    tooltips: Tooltips,
+    reveal_guard: RevealGuard,
+    // the tile picked by the first tap when reveals take two
+    selected_tile: Option<usize>,
+    // the tile keyboard play acts on; hidden until an arrow key is pressed
+    cursor: Option<usize>,
+    // boards from a shared code or replay are known in advance, so they aren't ranked
+    shared_board: bool,
+    // only while the analysis scene is open
+    analysis: Option<Analysis>,
+    // unfinished boards put aside for another one, switched between with the HUD tabs
+    tabs: Tabs,
 }
 
 impl MainState {
+    // a fresh free play board, laid out the way the config asks
     pub fn reset_game(&mut self, board_settings: BoardSettings, ctx: &mut Context) {
+        let engine = Engine::with_generation(
+            board_settings.size(),
+            board_settings.num_bombs(),
+            rand::random(),
+            self.config.generation,
+        );
+        self.load_game(board_settings, engine, ctx);
+    }
+
+    // seeded boards are shared through codes, dailies and tournaments, so they are
+    // always generated plain
+    pub fn reset_game_with_seed(
+        &mut self,
+        board_settings: BoardSettings,
+        seed: u64,
+        ctx: &mut Context,
+    ) {
+        let engine = Engine::with_seed(board_settings.size(), board_settings.num_bombs(), seed);
+        self.load_game(board_settings, engine, ctx);
+    }
+
+    fn load_game(&mut self, board_settings: BoardSettings, engine: Engine, ctx: &mut Context) {
         let (screen_width, screen_height) = board_settings.screen_dimensions();
 
         let (board_w, board_h) = board_settings.screen_dimensions();
@@ -115,23 +515,146 @@ impl MainState {
 
         self.screen_dim = (screen_width, screen_height);
 
-        let (tile_size, engine) = MainState::new_game(&board_settings, screen_width, screen_height);
+        let hud_h = self.hud_height();
+        let tile_size = board_tile_size(
+            &self.config,
+            screen_width,
+            screen_height,
+            hud_h,
+            board_settings.size(),
+        );
 
         self.engine = engine;
+        self.engine.set_question_marks(self.config.question_marks);
+        self.cascade = Cascade::new(&self.engine);
         self.tile_size = tile_size;
         self.board_settings = board_settings;
-        self.menu_state.face_kind = FaceKind::Smile;
-        self.menu_state.time_passed = 0;
-        self.accumulated_time = 0.0;
+        self.scroll = (0.0, 0.0);
         self.current_tile_idx = None;
+        self.chord_tile = None;
+        self.daily_day = None;
+        self.marathon = None;
+        self.shared_board = false;
+        self.reveal_guard = RevealGuard::new();
+        self.selected_tile = None;
+        self.cursor = None;
+        self.result_recorded = false;
+        self.game_over = None;
+        self.auto_chord_used = self.config.auto_chord;
+        self.auto_flag_used = self.config.auto_flag;
+        self.sketch.clear();
+        self.flash = None;
+        self.hint = None;
+        self.tile_hint = None;
+        self.guess_watch = GuessWatch::new();
+        if self.trainer.is_some() {
+            self.trainer = Some(Trainer::new(&self.engine));
+        }
+        self.ghost = if self.config.show_ghost && self.tournament.is_none() {
+            Ghost::load(&self.profiles.active_dir(), &self.engine)
+        } else {
+            None
+        };
+        self.opponent_panel.handle(if self.ghost.is_some() {
+            OpponentEvent::Joined("BEST".to_string())
+        } else {
+            OpponentEvent::Left
+        });
+        self.start_journal();
+
+        if let Some(session) = &self.tournament {
+            ctx.gfx.set_window_title(&format!(
+                "Rust Sweeper - {} - round {}/{}",
+                session.tournament.name,
+                session.round + 1,
+                session.num_rounds()
+            ));
+        } else {
+            ctx.gfx.set_window_title("Rust Sweeper");
+        }
+    }
+
+    // picking the difficulty already in play still starts over
+    fn switch_difficulty(
+        &mut self,
+        name: &'static str,
+        board_settings: BoardSettings,
+        ctx: &mut Context,
+    ) {
+        self.put_aside(Some(TabKey::Preset(name)));
+        match self.tabs.take(TabKey::Preset(name)) {
+            Some(board) => self.resume_tab(board, ctx),
+            None => self.reset_game(board_settings, ctx),
+        }
+    }
+
+    // the board being played as a tab; marathon stages, tournament rounds and shared
+    // boards can't be put aside
+    fn tab_key(&self) -> Option<TabKey> {
+        if self.marathon.is_some() || self.tournament.is_some() || self.shared_board {
+            return None;
+        }
+        Some(match (self.daily_day, self.board_settings.preset_name()) {
+            (Some(day), _) => TabKey::Daily(day),
+            (None, Some(name)) => TabKey::Preset(name),
+            (None, None) => TabKey::Custom,
+        })
+    }
+
+    // keeps an unfinished game open in a tab before another board replaces it, unless
+    // the next board is the same one started over
+    fn put_aside(&mut self, next: Option<TabKey>) {
+        let Some(key) = self.tab_key() else {
+            return;
+        };
+        if Some(key) == next || !self.engine.is_in_progress() {
+            return;
+        }
+        let placeholder = Engine::new(self.board_settings.size(), 0);
+        self.tabs.put(OpenBoard {
+            key,
+            settings: self.board_settings.clone(),
+            engine: std::mem::replace(&mut self.engine, placeholder),
+        });
+    }
+
+    fn resume_tab(&mut self, board: OpenBoard, ctx: &mut Context) {
+        self.load_game(board.settings, board.engine, ctx);
+        if let TabKey::Daily(day) = board.key {
+            self.daily_day = Some(day);
+        }
     }
 
-    pub fn new(ctx: &mut Context) -> MainState {
+    pub fn new(ctx: &mut Context, tournament: Option<TournamentSession>) -> MainState {
         let board_settings = EASY_BOARD;
         let screen_dim = (board_settings.screen_width, board_settings.screen_height);
+        let profiles = Profiles::load(ctx.fs.user_data_dir());
+        // settings.toml used to sit next to the profiles, shared by all of them; a profile
+        // without its own copy starts from that one and keeps it on the next save
+        let first_run = Config::is_first_run(&profiles.active_dir())
+            && Config::is_first_run(ctx.fs.user_data_dir());
+        let config = if Config::is_first_run(&profiles.active_dir()) {
+            Config::load(ctx.fs.user_data_dir(), profiles.data_dir())
+        } else {
+            Config::load(&profiles.active_dir(), profiles.data_dir())
+        };
 
-        let (tile_size, engine) = MainState::new_game(&board_settings, screen_dim.0, screen_dim.1);
-        let sprite_manager = SpriteManager::new(ctx, "/sprites");
+        let hud_h = hud_height(screen_dim.1, &config.hud_scale);
+        let (tile_size, engine) = MainState::new_game(
+            &board_settings,
+            screen_dim.0,
+            screen_dim.1,
+            hud_h,
+            &config,
+            rand::random(),
+        );
+        let mut errors = ErrorReporter::new();
+        let sprite_manager = SpriteManager::new(ctx, config.base_theme);
+        let missing = sprite_manager.missing();
+        if !missing.is_empty() {
+            let files: Vec<&str> = missing.iter().map(|key| key.file()).collect();
+            errors.report(Subsystem::Sprites, format!("missing {}", files.join(", ")));
+        }
 
         let font_dir = ctx.fs.read_dir("/resources/assets").unwrap();
 
@@ -144,172 +667,2375 @@ impl MainState {
 
         ctx.gfx.add_font("pressStart2P", font_data);
 
-        MainState {
+        let auto_chord_used = config.auto_chord;
+        let auto_flag_used = config.auto_flag;
+        let overlay = config
+            .overlay_path
+            .clone()
+            .map(|path| OverlayWriter::new(path, config.overlay_format));
+
+        // the game runs fine without the endpoint if the socket can't be bound
+        let ipc = if config.ipc_enabled {
+            IpcServer::bind(ctx.fs.user_data_dir())
+                .map_err(|e| errors.report(Subsystem::Ipc, e))
+                .ok()
+        } else {
+            None
+        };
+        let metrics_server = config.metrics_port.and_then(|port| {
+            MetricsServer::bind(port)
+                .map_err(|e| errors.report(Subsystem::Metrics, format!("port {}: {}", port, e)))
+                .ok()
+        });
+
+        let daily_log = DailyLog::load(&profiles.active_dir());
+        let leaderboard = Leaderboard::load(&profiles.active_dir());
+        let history = History::load(&profiles.active_dir());
+        let openings = Openings::load(&profiles.active_dir());
+        let losses = Losses::load(&profiles.active_dir());
+        let luck = Luck::load(&profiles.active_dir());
+        let recent_boards = RecentBoards::load(&profiles.active_dir());
+        let presets = Presets::load(ctx);
+        let day_theme = Theme::load(
+            &profiles.active_dir(),
+            THEME_FILE_NAME,
+            presets.get(config.base_theme),
+        );
+        let night_theme = Theme::load(
+            &profiles.active_dir(),
+            NIGHT_THEME_FILE_NAME,
+            presets.get("dark"),
+        );
+
+        let mixer = Mixer::new(ctx, &mut errors);
+
+        let mut state = MainState {
+            cascade: Cascade::new(&engine),
             engine,
             tile_size,
             board_settings,
             screen_dim,
             sprite_manager,
+            tile_batch: TileBatch::new(),
             current_tile_idx: None,
+            pressed_for: Duration::ZERO,
+            chord_tile: None,
             menu_state: MenuState::new(),
-            accumulated_time: 0.0,
+            config,
+            scenes: SceneStack::new(Scene::Title),
+            title_state: TitleState::new(),
+            confirm_state: ConfirmState::new(),
+            pause_state: PauseState::new(),
+            settings_state: SettingsState::new(),
+            onboarding_state: OnboardingState::new(),
+            quit_confirmed: false,
+            overlay,
+            ipc,
+            metrics: Metrics::new(),
+            metrics_server,
+            webhook: Webhook::new(),
+            tournament: None,
+            profiles,
+            profile_state: ProfileState::new(),
+            journal: None,
+            daily_log,
+            daily_day: None,
+            calendar_state: CalendarState::new(),
+            stats_state: StatsState::new(),
+            scores_state: ScoresState::new(),
+            leaderboard,
+            name_entry: None,
+            game_over: None,
+            auto_chord_used,
+            auto_flag_used,
+            marathon: None,
+            result_recorded: false,
+            ghost: None,
+            history,
+            openings,
+            losses,
+            luck,
+            guess_watch: GuessWatch::new(),
+            idle_time: Duration::ZERO,
+            countdown_left: Duration::ZERO,
+            sketch: Sketch::new(),
+            hovered_tile_idx: None,
+            scroll: (0.0, 0.0),
+            command_bar: CommandBar::new(),
+            flash: None,
+            hint: None,
+            tile_hint: None,
+            trainer: None,
+            thumbnails: Thumbnails::new(ctx.fs.user_data_dir()),
+            continue_thumbnail: None,
+            diagnostics: Diagnostics::new(),
+            day_theme,
+            night_theme,
+            presets,
+            face: Face::new(),
+            mixer,
+            announcer: Announcer::new(Box::new(SystemSpeech::new())),
+            opponent_panel: OpponentPanel::new(),
+            custom_board: CustomBoard::new(),
+            recent_boards,
+            custom_state: CustomState::new(),
+            errors,
+            toast: None,
+            tooltips: Tooltips::new(),
+            reveal_guard: RevealGuard::new(),
+            selected_tile: None,
+            cursor: None,
+            shared_board: false,
+            analysis: None,
+            tabs: Tabs::new(),
+        };
+        // settings and records pulled from the remote copy replace the ones just loaded
+        if state.sync_profile() {
+            state.load_profile_data(ctx);
+        }
+
+        // tournaments skip the title screen and start on the first round
+        if let Some(session) = tournament {
+            let board_settings = session.tournament.board_settings();
+            let seed = session.current_seed();
+            state.tournament = Some(session);
+            state.reset_game_with_seed(board_settings, seed, ctx);
+            state.scenes.push(Scene::Game);
+            state.start_countdown();
+        } else if let Some(engine) = journal::recover(&state.journal_path()) {
+            // left by a quit or a crash, offered through the Continue button on the title screen
+            let size = engine.size();
+            match BoardSettings::builder()
+                .size(size.width, size.height)
+                .mines(engine.num_bombs())
+                .build()
+            {
+                Ok(board_settings) => state.load_game(board_settings, engine, ctx),
+                Err(e) => state.errors.report(Subsystem::Journal, e),
+            }
+        } else if let Some(board_settings) = BoardSettings::by_name(state.config.difficulty)
+            .filter(|_| state.board_settings.preset_name() != Some(state.config.difficulty))
+        {
+            state.reset_game(board_settings, ctx);
         }
+        // asked once, before the first game; opening a file or a code still goes straight
+        // to its board, with the questions waiting underneath
+        if state.tournament.is_none() && first_run {
+            state.scenes.push(Scene::Onboarding);
+        }
+
+        state
     }
 
-    fn new_game(board_settings: &BoardSettings, w: f32, h: f32) -> ((f32, f32), Engine) {
-        let board_size = board_settings.dimensions();
-        let num_bombs = board_settings.num_bombs();
-        let engine = Engine::new(board_size, num_bombs);
-        (calculate_tile_size(w, h, board_size), engine)
+    // a save picks up where it left off, a replay races the player as the ghost on its
+    // board and a theme is installed into the active profile
+    fn open_file(&mut self, file: GameFile, ctx: &mut Context) {
+        match file {
+            GameFile::Save(board_settings, engine) => {
+                self.load_game(board_settings, engine, ctx);
+                self.toast = Some(Toast::new("SAVED GAME OPENED"));
+            }
+            GameFile::Replay(board_settings, replay) => {
+                self.load_game(board_settings, replay.fresh_engine(), ctx);
+                self.shared_board = true;
+                self.ghost = Some(Ghost::from_replay(replay));
+                self.opponent_panel
+                    .handle(OpponentEvent::Joined("REPLAY".to_string()));
+                self.toast = Some(Toast::new("RACING THE REPLAY"));
+            }
+            GameFile::Theme {
+                file_name,
+                manifest,
+            } => {
+                let path = self.profiles.active_dir().join(file_name);
+                match storage::write_atomic(&path, manifest) {
+                    Ok(()) => {
+                        self.load_profile_data(ctx);
+                        self.toast = Some(Toast::new("THEME INSTALLED"));
+                    }
+                    Err(e) => self
+                        .errors
+                        .report(Subsystem::Saves, format!("theme: {}", e)),
+                }
+                return;
+            }
+        }
+        self.scenes.push(Scene::Game);
     }
-}
 
-impl EventHandler for MainState {
-    fn update(&mut self, ctx: &mut Context) -> GameResult {
-        if self.engine.is_won() {
-            self.menu_state.face_kind = FaceKind::Cool;
-        } else if self.engine.is_lost() {
-            self.menu_state.face_kind = FaceKind::Dead;
+    // starts the board a code describes from whichever menu is open
+    fn play_code(&mut self, code: BoardCode, ctx: &mut Context) {
+        if self.tournament.is_some() {
+            return;
+        }
+        while self.scenes.top() != Scene::Title {
+            self.scenes.pop();
+        }
+        self.reset_game_with_seed(code.settings, code.seed, ctx);
+        self.shared_board = true;
+        self.scenes.push(Scene::Game);
+        self.toast = Some(Toast::new("SHARED BOARD"));
+    }
+
+    fn copy_code(&mut self) {
+        let code = BoardCode {
+            settings: self.board_settings.clone(),
+            seed: self.engine.seed(),
+        };
+        let copied =
+            arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_text(code.encode()));
+        self.toast = Some(Toast::new(match copied {
+            Ok(()) => "BOARD CODE COPIED",
+            Err(_) => "CLIPBOARD UNAVAILABLE",
+        }));
+    }
+
+    fn paste_code(&mut self, ctx: &mut Context) {
+        let code = arboard::Clipboard::new()
+            .and_then(|mut clipboard| clipboard.get_text())
+            .map_err(|e| e.to_string())
+            .and_then(|text| BoardCode::parse(&text));
+        match code {
+            Ok(code) => self.play_code(code, ctx),
+            Err(_) => self.toast = Some(Toast::new("NO BOARD CODE TO PASTE")),
+        }
+    }
+
+    fn journal_path(&self) -> PathBuf {
+        self.profiles.active_dir().join(journal::JOURNAL_FILE_NAME)
+    }
+
+    fn start_journal(&mut self) {
+        // tournament rounds are already recorded in their own results file
+        if self.tournament.is_some() || self.errors.is_degraded(Subsystem::Journal) {
+            return;
+        }
+        if let Some(journal) = self.journal.take() {
+            journal.discard();
         }
-        if !self.engine.is_lost() && !self.engine.is_won() {
-            let delta = ctx.time.delta().as_secs_f32();
-            self.accumulated_time += delta;
+        match Journal::start(self.journal_path(), &self.engine) {
+            Ok(journal) => self.journal = Some(journal),
+            Err(e) => self.errors.report(Subsystem::Journal, e),
+        }
+    }
 
-            if self.accumulated_time >= 1.0 {
-                self.menu_state.time_passed += 1;
-                self.accumulated_time -= 1.0;
+    fn update_journal(&mut self, delta: Duration) {
+        let Some(journal) = &mut self.journal else {
+            return;
+        };
+        if self.engine.is_won() || self.engine.is_lost() {
+            if let Some(journal) = self.journal.take() {
+                journal.discard();
             }
+            return;
         }
+        if let Err(e) = journal.update(delta, &self.engine) {
+            self.errors.report(Subsystem::Journal, e);
+            self.journal = None;
+        }
+    }
 
-        Ok(())
+    fn select_profile_option(&mut self, option: ProfileOption, ctx: &Context) {
+        match option {
+            ProfileOption::Select(i) => {
+                let name = self.profiles.names[i].clone();
+                if let Err(e) = self.profiles.select(&name) {
+                    self.errors.report(Subsystem::Profiles, e);
+                }
+                self.load_profile_data(ctx);
+                self.scenes.pop();
+            }
+            ProfileOption::New => self.profile_state.new_name = Some(String::new()),
+            ProfileOption::Back => {
+                self.scenes.pop();
+            }
+        }
     }
 
-    fn draw(&mut self, ctx: &mut Context) -> GameResult {
-        let mut canvas = graphics::Canvas::from_frame(ctx, Color::from_rgb(150, 150, 150));
+    fn finish_profile_name(&mut self, ctx: &Context) {
+        let Some(name) = self.profile_state.new_name.take() else {
+            return;
+        };
+        if name.is_empty() {
+            return;
+        }
+        if let Err(e) = self.profiles.select(&name) {
+            self.errors.report(Subsystem::Profiles, e);
+        }
+        self.load_profile_data(ctx);
+        self.scenes.pop();
+    }
 
-        draw::draw_menu(
-            ctx,
-            &mut canvas,
-            &self.engine,
-            &self.sprite_manager,
-            self.tile_size,
-            &self.board_settings,
-            self.screen_dim,
-            self.current_tile_idx,
-            &mut self.menu_state,
-        );
+    fn finish_name_entry(&mut self) {
+        let Some(entry) = self.name_entry.take() else {
+            return;
+        };
+        self.scenes.pop();
+        if let Err(e) = self
+            .leaderboard
+            .set_name(&entry.category, entry.rank, &entry.name)
+        {
+            self.errors
+                .report(Subsystem::Saves, format!("high score: {}", e));
+        }
+    }
 
-        draw::draw_tiles(
-            ctx,
-            &mut canvas,
-            &self.engine,
-            &self.sprite_manager,
-            self.tile_size,
-            &self.board_settings,
-            self.screen_dim,
-            self.current_tile_idx,
+    fn load_profile_data(&mut self, ctx: &Context) {
+        let dir = self.profiles.active_dir();
+        // a new profile starts from the settings in use
+        if Config::is_first_run(&dir) {
+            self.save_config();
+        } else {
+            self.config = Config::load(&dir, self.profiles.data_dir());
+            self.note_assists();
+        }
+        self.daily_log = DailyLog::load(&dir);
+        self.leaderboard = Leaderboard::load(&dir);
+        self.history = History::load(&dir);
+        self.openings = Openings::load(&dir);
+        self.losses = Losses::load(&dir);
+        self.luck = Luck::load(&dir);
+        self.recent_boards = RecentBoards::load(&dir);
+        self.night_theme = Theme::load(&dir, NIGHT_THEME_FILE_NAME, self.presets.get("dark"));
+        self.set_base_theme(self.config.base_theme, ctx);
+        self.engine.set_question_marks(self.config.question_marks);
+        // the HUD scale and the board gutter are settings too
+        let (width, height) = self.screen_dim;
+        self.tile_size = board_tile_size(
+            &self.config,
+            width,
+            height,
+            self.hud_height(),
+            self.board_settings.size(),
         );
+        self.scroll_by(0.0, 0.0);
+        // a put aside game belongs to the profile that started it
+        self.tabs.clear();
+    }
 
-        canvas.finish(ctx)?;
-        Ok(())
+    fn start_marathon_stage(&mut self, marathon: Marathon, ctx: &mut Context) {
+        self.put_aside(None);
+        self.reset_game_with_seed(marathon.board_settings(), marathon.seed(), ctx);
+        ctx.gfx.set_window_title(&format!(
+            "Rust Sweeper - weekly marathon - stage {}/{}",
+            marathon.stage + 1,
+            marathon::STAGES.len()
+        ));
+        self.marathon = Some(marathon);
+        self.start_countdown();
     }
 
-    fn mouse_button_down_event(
-        &mut self,
-        ctx: &mut Context,
-        button: MouseButton,
-        x: f32,
-        y: f32,
-    ) -> GameResult {
-        let tile_idx = get_tile_index(x, y, self.screen_dim, self.tile_size, &self.board_settings);
-        match button {
-            MouseButton::Left => {
-                if let Some(face_rect) = self.menu_state.face_rect {
-                    if face_rect.contains([x, y]) {
-                        self.reset_game(self.board_settings.clone(), ctx);
-                        return Ok(());
-                    }
-                }
+    // moves on after a won stage, anything else restarts the gauntlet
+    fn advance_marathon(&mut self, ctx: &mut Context) {
+        let Some(mut marathon) = self.marathon.take() else {
+            return;
+        };
+        if !marathon.next_stage() {
+            marathon = Marathon::new(calendar::today());
+        }
+        self.start_marathon_stage(marathon, ctx);
+    }
 
-                if let Some(difficulty) = self.menu_state.get_difficulty_button_clicked(x, y) {
-                    match difficulty {
-                        "easy" => self.reset_game(BoardSettings::easy(), ctx),
-                        "medium" => self.reset_game(BoardSettings::medium(), ctx),
-                        "hard" => self.reset_game(BoardSettings::hard(), ctx),
-                        _ => {}
-                    }
-                    return Ok(());
-                }
+    fn record_result(&mut self, ctx: &mut Context) {
+        let won = self.engine.is_won();
+        if !(won || self.engine.is_lost()) || self.result_recorded || self.tournament.is_some() {
+            return;
+        }
+        // the loss may still be taken back
+        if self.scenes.contains(Scene::UndoPrompt) {
+            return;
+        }
+        self.result_recorded = true;
+        self.metrics.record_game(won);
+        let summary = self.engine.summary(self.config.penalties);
+        let stats = GameStats::new(&self.engine, self.config.penalties);
+        // after one failure games stop being posted until the next start
+        if let Some(url) = &self.config.webhook_url {
+            if !self.errors.is_degraded(Subsystem::Webhook) {
+                let difficulty = self.board_settings.preset_name();
+                self.webhook
+                    .post(url, webhook::payload(&self.engine, difficulty, &stats));
+            }
+        }
+        // pushed first, so a name entry for the high score table goes on top of it
+        self.game_over = Some(GameOverState::new(stats));
+        self.scenes.push(Scene::GameOver);
 
-                if !self.engine.is_lost() && !self.engine.is_won() {
-                    self.current_tile_idx = tile_idx;
-                    if tile_idx.is_some() {
-                        self.menu_state.face_kind = FaceKind::Surprised;
-                    }
+        // daily and marathon boards aren't picked by the player, so they don't feed suggestions
+        if self.daily_day.is_none() && self.marathon.is_none() {
+            if let Some(preset) = self.board_settings.preset_name() {
+                if let Err(e) = self.history.record(preset, won, summary.assisted) {
+                    self.errors
+                        .report(Subsystem::Saves, format!("game history: {}", e));
                 }
             }
-            MouseButton::Right if !self.engine.is_lost() && !self.engine.is_won() => {
-                if let Some(tile_idx) = tile_idx {
-                    let pos = Position::from_index(tile_idx, self.board_settings.w as usize);
-                    self.engine.flag(pos);
+        }
+        if let Err(e) = self.luck.record(&self.guess_watch) {
+            self.errors
+                .report(Subsystem::Saves, format!("luck stats: {}", e));
+        }
+        if let Err(e) = self.openings.record(&self.engine) {
+            self.errors
+                .report(Subsystem::Saves, format!("opening stats: {}", e));
+        }
+        if !won {
+            if let Some(cause) = losses::diagnose(&self.engine) {
+                if let Err(e) = self.losses.record(cause) {
+                    self.errors
+                        .report(Subsystem::Saves, format!("loss stats: {}", e));
                 }
             }
-            _ => {}
+            return;
         }
-        Ok(())
-    }
+        let time = summary.final_time();
+        let category = self.leaderboard_category();
 
-    fn mouse_button_up_event(
-        &mut self,
-        _ctx: &mut Context,
-        button: MouseButton,
-        _x: f32,
-        _y: f32,
-    ) -> Result<(), GameError> {
-        match button {
-            MouseButton::Left => {
-                if let Some(tile_idx) = self.current_tile_idx {
-                    let pos = Position::from_index(tile_idx, self.board_settings.w as usize);
-                    self.engine.reveal(pos);
-                    self.menu_state.face_kind = FaceKind::Smile;
-                    self.current_tile_idx = None;
+        // the ghost replays clock time, so only clean runs race against it
+        if !summary.assisted {
+            if let Err(e) = ghost::save_if_best(&self.profiles.active_dir(), &self.engine) {
+                self.errors
+                    .report(Subsystem::Saves, format!("ghost replay: {}", e));
+            }
+        }
+
+        let submitted = if let Some(day) = self.daily_day {
+            self.daily_log.record(day, time)
+        } else if let Some(marathon) = &mut self.marathon {
+            marathon.finish_stage(time, summary.assisted);
+            if !marathon.is_finished() {
+                return;
+            }
+            let total = marathon.total_time();
+            ctx.gfx.set_window_title(&format!(
+                "Rust Sweeper - weekly marathon - finished in {}:{:02}",
+                total.as_secs() / 60,
+                total.as_secs() % 60
+            ));
+            match &category {
+                Some(category) => self.leaderboard.submit(category, total).map(|_| ()),
+                None => Ok(()),
+            }
+        } else if let Some(category) = &category {
+            self.leaderboard.submit(category, time).map(|rank| {
+                self.name_entry =
+                    NameEntry::for_rank(category.clone(), rank, &self.profiles.active);
+                if self.name_entry.is_some() {
+                    self.scenes.push(Scene::NameEntry);
                 }
+            })
+        } else {
+            Ok(())
+        };
+
+        if let Err(e) = submitted {
+            self.errors
+                .report(Subsystem::Saves, format!("result: {}", e));
+        }
+    }
+
+    // the best time on the current game's leaderboard; a marathon ranks the whole
+    // gauntlet, so a single stage has nothing to compare against
+    fn personal_best(&self) -> Option<Duration> {
+        if self.marathon.is_some() {
+            return None;
+        }
+        self.leaderboard.best(&self.leaderboard_category()?)
+    }
+
+    // the leaderboard the current game counts towards, None when it isn't ranked
+    fn leaderboard_category(&self) -> Option<Category> {
+        if self.tournament.is_some() || self.daily_day.is_some() || self.shared_board {
+            return None;
+        }
+        let assisted = self.engine.summary(self.config.penalties).assisted;
+        match &self.marathon {
+            Some(marathon) => Some(self.category(
+                marathon.leaderboard_category(),
+                marathon.assisted || assisted,
+            )),
+            None => Some(self.category(self.board_settings.preset_name()?.to_string(), assisted)),
+        }
+    }
+
+    // an assist turned on mid game ranks that game with it, and turning it off doesn't
+    // take back the moves it already made
+    fn note_assists(&mut self) {
+        let in_progress = self.engine.is_in_progress();
+        self.auto_chord_used = self.config.auto_chord || (self.auto_chord_used && in_progress);
+        self.auto_flag_used = self.config.auto_flag || (self.auto_flag_used && in_progress);
+    }
+
+    fn category(&self, board: String, assisted: bool) -> Category {
+        Category {
+            board,
+            auto_chord: self.auto_chord_used,
+            auto_flag: self.auto_flag_used,
+            assisted,
+        }
+    }
+
+    fn advance_tournament(&mut self, ctx: &mut Context) {
+        let Some(session) = &mut self.tournament else {
+            return;
+        };
+        if let Some(seed) = session.next_round() {
+            let board_settings = session.tournament.board_settings();
+            self.reset_game_with_seed(board_settings, seed, ctx);
+            self.start_countdown();
+        }
+    }
+
+    fn update_tournament(&mut self, ctx: &mut Context) {
+        let Some(session) = &mut self.tournament else {
+            return;
+        };
+        if !(self.engine.is_won() || self.engine.is_lost()) || session.is_round_recorded() {
+            return;
+        }
+
+        if let Err(e) = session.finish_round(&self.engine) {
+            self.errors
+                .report(Subsystem::Saves, format!("tournament results: {}", e));
+        }
+        ctx.gfx.set_window_title(&format!(
+            "Rust Sweeper - {} - round {}/{} finished",
+            session.tournament.name,
+            session.round + 1,
+            session.num_rounds()
+        ));
+    }
+
+    // after one failure the profile stays local until the next start; true once synced
+    fn sync_profile(&mut self) -> bool {
+        let Some(backend) = &self.config.sync else {
+            return false;
+        };
+        if self.errors.is_degraded(Subsystem::Sync) {
+            return false;
+        }
+        match sync::sync_profile(backend, &self.profiles.active_dir(), &self.profiles.active) {
+            Ok(()) => true,
+            Err(e) => {
+                self.errors.report(Subsystem::Sync, e);
+                false
             }
-            _ => {}
         }
-        Ok(())
     }
 
-    fn mouse_motion_event(
-        &mut self,
-        _ctx: &mut Context,
-        x: f32,
-        y: f32,
-        _dx: f32,
-        _dy: f32,
-    ) -> Result<(), GameError> {
-        if let Some(_) = self.current_tile_idx {
-            if let Some(new_idx) =
-                get_tile_index(x, y, self.screen_dim, self.tile_size, &self.board_settings)
-            {
-                self.current_tile_idx = Some(new_idx);
+    // called once right before the window closes
+    fn shutdown(&mut self, _ctx: &mut Context) {
+        // an unfinished game is left in the journal for the Continue button next time
+        if let Some(journal) = self.journal.take() {
+            if self.engine.is_in_progress() {
+                if let Err(e) = journal.keep(&self.engine) {
+                    self.errors.report(Subsystem::Journal, e);
+                }
             } else {
-                self.current_tile_idx = None;
-                self.menu_state.face_kind = FaceKind::Smile;
+                journal.discard();
             }
         }
-        Ok(())
+        self.sync_profile();
+        // dropping the server removes its socket file
+        self.ipc = None;
     }
 
-    fn resize_event(
-        &mut self,
-        _ctx: &mut Context,
-        width: f32,
-        height: f32,
-    ) -> Result<(), GameError> {
-        self.screen_dim = (width, height);
-        let board_size = self.board_settings.dimensions();
-        self.tile_size = calculate_tile_size(width, height, board_size);
+    fn answer_quit_confirm(&mut self, quit: bool, ctx: &mut Context) {
+        self.scenes.pop();
+        if quit {
+            self.quit_confirmed = true;
+            ctx.request_quit();
+        }
+    }
+
+    // assists run after the player's own reveal or flag
+    fn after_move(&mut self) {
+        if self.config.auto_flag && self.engine.is_in_progress() {
+            let width = self.engine.size().width;
+            let mines: Vec<Position> = solver::analyze(&self.engine)
+                .iter()
+                .enumerate()
+                .filter(|(_, certainty)| **certainty == Certainty::Mine)
+                .map(|(i, _)| Position::from_index(i, width))
+                .collect();
+            for pos in mines {
+                self.engine.auto_flag(pos);
+                if self.config.auto_chord {
+                    self.engine.auto_chord(pos);
+                }
+            }
+        }
+        if self.engine.is_lost() && self.offers_undo() {
+            self.scenes.push(Scene::UndoPrompt);
+        }
+    }
+
+    fn train(&mut self, kind: ClickKind, pos: Position, opened: usize) {
+        if let Some(trainer) = &mut self.trainer {
+            trainer.click(kind, pos, opened);
+        }
+    }
+
+    // only free play; dailies, marathons and tournaments are always played straight
+    fn is_free_play(&self) -> bool {
+        self.tournament.is_none() && self.daily_day.is_none() && self.marathon.is_none()
+    }
+
+    fn offers_undo(&self) -> bool {
+        self.config.loss_policy == LossPolicy::Casual && self.is_free_play()
+    }
+
+    fn answer_undo_prompt(&mut self, undo: bool) {
+        self.scenes.pop();
+        if undo && self.engine.undo_loss() {
+            // the loss discarded the journal
+            self.start_journal();
+        }
+    }
+
+    // a finished game has already been counted, so only a running one steps back; a
+    // loss is taken back through the casual prompt
+    fn undo_move(&mut self) {
+        if !self.config.allow_undo || !self.is_free_play() || !self.engine.is_in_progress() {
+            return;
+        }
+        if self.engine.undo() {
+            // the journal still holds the moves that were taken back
+            self.start_journal();
+            self.hint = None;
+        }
+    }
+
+    fn select_pause_option(&mut self, option: PauseOption, ctx: &mut Context) {
+        // settings open over the menu, which is still there on the way back
+        if option == PauseOption::Settings {
+            self.settings_state = SettingsState::new();
+            self.scenes.push(Scene::Settings);
+            return;
+        }
+        self.scenes.pop();
+        match option {
+            PauseOption::Resume | PauseOption::Settings => {}
+            PauseOption::Restart => self.dispatch(Action::Restart, ctx),
+            PauseOption::Quit => self.back(ctx),
+        }
+    }
+
+    fn select_game_over_option(&mut self, option: GameOverOption, ctx: &mut Context) {
+        self.scenes.pop();
+        self.game_over = None;
+        match option {
+            GameOverOption::PlayAgain => self.dispatch(Action::Restart, ctx),
+            GameOverOption::ChangeDifficulty => self.menu_state.mode_list_open = true,
+        }
+    }
+
+    // today's board, picked up where it was left if it is open in a tab
+    fn play_daily(&mut self, ctx: &mut Context) {
+        let today = calendar::today();
+        self.put_aside(Some(TabKey::Daily(today)));
+        match self.tabs.take(TabKey::Daily(today)) {
+            Some(board) => self.resume_tab(board, ctx),
+            None => {
+                self.reset_game_with_seed(BoardSettings::medium(), daily_seed(today), ctx);
+                self.daily_day = Some(today);
+            }
+        }
+    }
+
+    fn select_mode(&mut self, entry: ModeEntry, ctx: &mut Context) {
+        match entry {
+            ModeEntry::Preset(name) => self.dispatch(Action::NewGame(name), ctx),
+            ModeEntry::Custom => self.scenes.push(Scene::Custom),
+            ModeEntry::Daily => {
+                self.play_daily(ctx);
+                self.start_countdown();
+            }
+            ModeEntry::Recent(board) => {
+                self.custom_board = board;
+                self.select_custom_option(CustomOption::Start, ctx);
+            }
+        }
+    }
+
+    fn select_title_option(&mut self, option: TitleOption, ctx: &mut Context) {
+        match option {
+            TitleOption::Continue => {}
+            TitleOption::NewGame => self.reset_game(self.board_settings.clone(), ctx),
+            TitleOption::Daily => self.play_daily(ctx),
+            TitleOption::Custom => {
+                self.scenes.push(Scene::Custom);
+                return;
+            }
+            TitleOption::Calendar => {
+                self.scenes.push(Scene::Calendar);
+                return;
+            }
+            TitleOption::Stats => {
+                self.scenes.push(Scene::Stats);
+                return;
+            }
+            TitleOption::Scores => {
+                self.scenes.push(Scene::Scores);
+                return;
+            }
+            TitleOption::Settings => {
+                self.settings_state = SettingsState::new();
+                self.scenes.push(Scene::Settings);
+                return;
+            }
+            TitleOption::Profile => {
+                self.scenes.push(Scene::Profiles);
+                return;
+            }
+            TitleOption::Quit => {
+                ctx.request_quit();
+                return;
+            }
+        }
+        self.scenes.push(Scene::Game);
+        if option == TitleOption::Daily {
+            self.start_countdown();
+        }
+    }
+
+    // every change is written straight away, so nothing is lost if the game is killed
+    fn select_settings_option(&mut self, option: SettingsOption, ctx: &mut Context) {
+        match option {
+            SettingsOption::Sounds => self.config.sounds = !self.config.sounds,
+            SettingsOption::PaceCue => self.config.pace_cue = !self.config.pace_cue,
+            SettingsOption::Announce => {
+                self.config.announce_every =
+                    accessibility::next_interval(self.config.announce_every)
+            }
+            // the music follows on the next update
+            SettingsOption::Volume(bus) => {
+                let volume = &mut self.config.volumes[bus as usize];
+                *volume = mixer::next_volume(*volume);
+            }
+            SettingsOption::QuestionMarks => {
+                self.config.question_marks = !self.config.question_marks;
+                self.engine.set_question_marks(self.config.question_marks);
+            }
+            // applies from the next free play board on, like the layout
+            SettingsOption::SafeFirstClick => {
+                let safe = self.config.safe_first_click();
+                self.config.set_safe_first_click(!safe);
+            }
+            SettingsOption::LeftHanded => self.config.left_handed = !self.config.left_handed,
+            // casual only offers the undo in free play
+            SettingsOption::LossPolicy => self.config.loss_policy = self.config.loss_policy.next(),
+            SettingsOption::AutoChord => {
+                self.config.auto_chord = !self.config.auto_chord;
+                self.note_assists();
+            }
+            SettingsOption::MisclickGuard => {
+                self.config.misclick_guard = self.config.misclick_guard.next()
+            }
+            // flags the proven mines from the next move on
+            SettingsOption::AutoFlag => {
+                self.config.auto_flag = !self.config.auto_flag;
+                self.note_assists();
+            }
+            SettingsOption::Theme => {
+                self.set_base_theme(theme::next_preset(self.config.base_theme), ctx)
+            }
+            SettingsOption::Hud(element) => {
+                let shown = self.config.hud.shows(element);
+                self.config.hud.set(element, !shown);
+            }
+            SettingsOption::Back => {
+                self.scenes.pop();
+                return;
+            }
+        }
+        self.save_config();
+    }
+
+    fn save_config(&mut self) {
+        if let Err(e) = self
+            .config
+            .save(&self.profiles.active_dir(), self.profiles.data_dir())
+        {
+            self.errors
+                .report(Subsystem::Saves, format!("settings: {}", e));
+        }
+    }
+
+    // a profile's own theme manifest is applied over the new preset
+    fn set_base_theme(&mut self, name: &'static str, ctx: &Context) {
+        self.config.base_theme = name;
+        let base_theme = self.presets.get(name);
+        self.day_theme = Theme::load(&self.profiles.active_dir(), THEME_FILE_NAME, base_theme);
+        self.update_night_mode(ctx);
+        self.sprite_manager.load_theme(ctx, name);
+        // the batch holds on to the old atlas
+        self.tile_batch = TileBatch::new();
+    }
+
+    fn select_onboarding_choice(&mut self, choice: usize, ctx: &mut Context) {
+        match self.onboarding_state.step {
+            OnboardingStep::Handedness => self.config.left_handed = choice == 1,
+            OnboardingStep::Input => {
+                self.config.reveal_input = RevealInput::ALL[choice.min(RevealInput::ALL.len() - 1)]
+            }
+            OnboardingStep::Theme => {
+                self.set_base_theme(theme::PRESETS[choice.min(theme::PRESETS.len() - 1)], ctx)
+            }
+            // the first three presets are the ones offered
+            OnboardingStep::Difficulty => self.config.difficulty = PRESETS[choice.min(2)],
+        }
+        if !self.onboarding_state.advance() {
+            self.finish_onboarding(ctx);
+        }
+    }
+
+    // skipping keeps the answers given so far and the defaults for the rest; either way
+    // the settings file is written, so the questions aren't asked again
+    fn finish_onboarding(&mut self, ctx: &mut Context) {
+        self.save_config();
+        self.scenes.pop();
+        if self.board_settings.preset_name() != Some(self.config.difficulty) {
+            if let Some(board_settings) = BoardSettings::by_name(self.config.difficulty) {
+                self.reset_game(board_settings, ctx);
+            }
+        }
+    }
+
+    fn select_custom_option(&mut self, option: CustomOption, ctx: &mut Context) {
+        match option {
+            CustomOption::Resize(dw, dh) => self.custom_board.resize(dw, dh),
+            // applies to every free play board from the next one on
+            CustomOption::Layout => {
+                self.config.generation = self.config.generation.next();
+                self.save_config();
+            }
+            // tournament settings are locked
+            CustomOption::Start if self.tournament.is_none() => {
+                match self.custom_board.settings() {
+                    Ok(board_settings) => {
+                        // recent boards start straight from the game's HUD
+                        if self.scenes.top() == Scene::Custom {
+                            self.scenes.pop();
+                        }
+                        if let Err(e) = self.recent_boards.record(self.custom_board) {
+                            self.errors
+                                .report(Subsystem::Saves, format!("recent boards: {}", e));
+                        }
+                        // a new custom board replaces the one kept open
+                        self.put_aside(Some(TabKey::Custom));
+                        self.tabs.take(TabKey::Custom);
+                        self.reset_game(board_settings, ctx);
+                        if self.scenes.top() != Scene::Game {
+                            self.scenes.push(Scene::Game);
+                        }
+                    }
+                    Err(e) => eprintln!("invalid custom board: {}", e),
+                }
+            }
+            CustomOption::Start => {}
+            CustomOption::Back => {
+                self.scenes.pop();
+            }
+        }
+    }
+
+    // gives everyone the same start on boards that are raced
+    fn start_countdown(&mut self) {
+        if !self.config.countdown || self.scenes.top() != Scene::Game {
+            return;
+        }
+        self.countdown_left = COUNTDOWN;
+        self.current_tile_idx = None;
+        self.scenes.push(Scene::Countdown);
+    }
+
+    fn update_countdown(&mut self, delta: Duration) {
+        self.countdown_left = self.countdown_left.saturating_sub(delta);
+        if self.countdown_left.is_zero() {
+            self.scenes.pop();
+        }
+    }
+
+    fn difficulty_hint(&self) -> Option<String> {
+        let preset = self.board_settings.preset_name()?;
+        let hint = match self.history.suggestion(preset)? {
+            Suggestion::Harder(name) => format!("ON A ROLL? TRY {}", name.to_uppercase()),
+            Suggestion::Easier(name) => format!("TOUGH GOING? TRY {}", name.to_uppercase()),
+        };
+        Some(hint)
+    }
+
+    fn pause(&mut self) {
+        self.current_tile_idx = None;
+        self.scenes.push(Scene::Paused);
+    }
+
+    fn update_idle(&mut self, delta: Duration) {
+        if !self.engine.is_in_progress() {
+            self.idle_time = Duration::ZERO;
+            return;
+        }
+        self.idle_time += delta;
+        if let Some(limit) = self.config.idle_pause_after {
+            if self.idle_time >= limit {
+                self.idle_time = Duration::ZERO;
+                self.pause();
+            }
+        }
+    }
+
+    // the game goes on silently when a sound fails
+    fn play(&mut self, ctx: &Context, cue: Cue) {
+        if let Err(e) = self.mixer.play(ctx, cue, &self.config) {
+            self.errors.report(Subsystem::Audio, e);
+        }
+    }
+
+    // the single place player input is acted on, whether it came from the
+    // mouse, the keyboard or the command bar
+    fn dispatch(&mut self, action: Action, ctx: &mut Context) {
+        let game_over = self.engine.is_lost() || self.engine.is_won();
+        if action.is_selection() {
+            self.play(ctx, Cue::Click);
+        }
+        match action {
+            Action::Reveal(pos) => {
+                let safe_tiles_left = self.engine.safe_tiles_left();
+                self.engine.reveal(pos);
+                let opened = safe_tiles_left - self.engine.safe_tiles_left();
+                // more than one tile means the reveal opened up an area
+                if opened > 1 {
+                    self.face.wink();
+                }
+                if let Some(cue) = mixer::reveal_cue(opened) {
+                    self.play(ctx, cue);
+                }
+                if !game_over {
+                    self.train(ClickKind::Reveal, pos, opened);
+                }
+                self.current_tile_idx = None;
+                self.after_move();
+            }
+            Action::Flag(pos) if !game_over => {
+                self.engine.flag(pos);
+                self.play(ctx, Cue::Flag);
+                self.train(ClickKind::Flag, pos, 0);
+                if self.config.auto_chord {
+                    self.engine.auto_chord(pos);
+                }
+                self.after_move();
+            }
+            Action::Chord(pos) if !game_over => {
+                let safe_tiles_left = self.engine.safe_tiles_left();
+                self.engine.chord(pos);
+                let opened = safe_tiles_left - self.engine.safe_tiles_left();
+                if let Some(cue) = mixer::reveal_cue(opened) {
+                    self.play(ctx, cue);
+                }
+                self.train(ClickKind::Chord, pos, opened);
+                self.after_move();
+            }
+            Action::Annotate(pos) if !game_over => self.engine.annotate(pos),
+            Action::MoveCursor(dx, dy) => self.move_cursor(dx, dy),
+            // a revealed number chords, as a click on it would
+            Action::RevealAtCursor => {
+                if let Some(index) = self.cursor_tile() {
+                    let pos = Position::from_index(index, self.board_settings.size().width);
+                    if self.engine.get_tiles()[index].is_revealed() {
+                        self.dispatch(Action::Chord(pos), ctx);
+                    } else {
+                        self.dispatch(Action::Reveal(pos), ctx);
+                    }
+                }
+            }
+            Action::FlagAtCursor => {
+                if let Some(index) = self.cursor_tile() {
+                    let pos = Position::from_index(index, self.board_settings.size().width);
+                    self.dispatch(Action::Flag(pos), ctx);
+                }
+            }
+            Action::Restart => {
+                if self.tournament.is_some() {
+                    self.advance_tournament(ctx);
+                } else if self.marathon.is_some() {
+                    self.advance_marathon(ctx);
+                } else {
+                    self.reset_game(self.board_settings.clone(), ctx);
+                }
+            }
+            // tournament settings are locked
+            Action::NewGame(name) if self.tournament.is_none() => {
+                if let Some(board_settings) = BoardSettings::by_name(name) {
+                    self.switch_difficulty(name, board_settings, ctx);
+                }
+            }
+            Action::SelectMode(entry) if self.tournament.is_none() => self.select_mode(entry, ctx),
+            Action::SwitchTab(key) if self.tournament.is_none() => {
+                self.put_aside(Some(key));
+                if let Some(board) = self.tabs.take(key) {
+                    self.resume_tab(board, ctx);
+                }
+            }
+            Action::CopyCode => self.copy_code(),
+            Action::PasteCode => self.paste_code(ctx),
+            Action::ToggleTrainer => {
+                self.trainer = match self.trainer {
+                    Some(_) => None,
+                    None => Some(Trainer::new(&self.engine)),
+                };
+                self.toast = Some(Toast::new(match &self.trainer {
+                    Some(trainer) if trainer.is_counting() => "TRAINER ON",
+                    Some(_) => "TRAINER ON FROM THE NEXT BOARD",
+                    None => "TRAINER OFF",
+                }));
+            }
+            Action::Export => {
+                let dir = self.profiles.active_dir().join(files::EXPORT_DIR_NAME);
+                match files::export(&dir, &self.engine) {
+                    Ok(path) => ctx
+                        .gfx
+                        .set_window_title(&format!("Rust Sweeper - exported {}", path.display())),
+                    Err(e) => self
+                        .errors
+                        .report(Subsystem::Saves, format!("export: {}", e)),
+                }
+            }
+            Action::GoTo(pos) => {
+                let index = pos.to_index(self.board_settings.size().width);
+                self.flash = Some(Flash::new(index));
+            }
+            Action::Pause if self.engine.is_in_progress() => self.pause(),
+            Action::Resume => {
+                self.scenes.pop();
+            }
+            // tournament rounds can't be left or restarted, so escape does nothing there
+            Action::OpenPauseMenu if self.tournament.is_none() => {
+                self.current_tile_idx = None;
+                self.chord_tile = None;
+                self.pause_state = PauseState::new();
+                self.scenes.push(Scene::PauseMenu);
+            }
+            Action::SelectPause(option) => self.select_pause_option(option, ctx),
+            Action::SelectGameOver(option) => self.select_game_over_option(option, ctx),
+            Action::OpenCommandBar => {
+                self.command_bar = CommandBar::new();
+                self.current_tile_idx = None;
+                self.scenes.push(Scene::Command);
+            }
+            Action::Type(character) => self.type_character(character),
+            Action::Erase => {
+                if self.scenes.top() == Scene::Command {
+                    self.command_bar.pop();
+                } else if let Some(entry) = &mut self.name_entry {
+                    entry.pop();
+                } else if let Some(name) = &mut self.profile_state.new_name {
+                    name.pop();
+                }
+            }
+            Action::Submit => {
+                if self.scenes.top() == Scene::Command {
+                    self.run_command(ctx);
+                } else if self.scenes.top() == Scene::NameEntry {
+                    self.finish_name_entry();
+                } else if self.profile_state.new_name.is_some() {
+                    self.finish_profile_name(ctx);
+                } else {
+                    self.activate_focus(ctx);
+                }
+            }
+            Action::Back => self.back(ctx),
+            Action::ConfirmQuit(quit) => self.answer_quit_confirm(quit, ctx),
+            Action::AnswerUndo(undo) => self.answer_undo_prompt(undo),
+            Action::Undo => self.undo_move(),
+            Action::SelectTitle(option) => self.select_title_option(option, ctx),
+            Action::SelectProfile(option) => self.select_profile_option(option, ctx),
+            Action::SelectCustom(option) => self.select_custom_option(option, ctx),
+            Action::SelectSettings(option) => self.select_settings_option(option, ctx),
+            Action::SelectOnboarding(choice) => self.select_onboarding_choice(choice, ctx),
+            Action::SetDensity(density) => self.custom_board.set_density(density),
+            Action::AdjustDensity(step) => {
+                let density = self.custom_board.density as i32 + step;
+                self.custom_board.set_density(density.max(0) as u32);
+            }
+            Action::ToggleDiagnostics => self.diagnostics.visible = !self.diagnostics.visible,
+            Action::ToggleOpponentPanel => self.opponent_panel.toggle(),
+            // tournament rounds are checked against their replays, which can't carry penalties
+            Action::Analyze if self.engine.is_won() || self.engine.is_lost() => {
+                self.analysis = Some(Analysis::new(&self.engine));
+                self.scenes.push(Scene::Analysis);
+            }
+            Action::Scrub(step) => {
+                if let Some(analysis) = &mut self.analysis {
+                    analysis.step(step);
+                }
+            }
+            Action::Branch(kind, pos) => {
+                if let Some(analysis) = &mut self.analysis {
+                    analysis.play(kind, pos);
+                }
+            }
+            Action::ScrubTo(fraction) => {
+                if let Some(analysis) = &mut self.analysis {
+                    analysis.seek_time(analysis.time_at(fraction));
+                }
+            }
+            Action::Announce => {
+                if let Err(e) = self.announcer.announce(&self.engine) {
+                    self.errors
+                        .report(Subsystem::Audio, format!("speech: {}", e));
+                }
+            }
+            Action::ShowHint if self.tournament.is_none() && self.engine.record_hint() => {
+                self.hint = Some(HeatHint::new(&self.engine));
+            }
+            Action::HintTile if self.tournament.is_none() && self.engine.record_hint() => {
+                self.tile_hint = TileHint::new(&self.engine);
+                if self.tile_hint.is_none() {
+                    self.toast = Some(Toast::new("NOTHING LEFT TO PROVE"));
+                }
+            }
+            Action::DismissErrors => self.errors.dismiss(),
+            Action::MoveFocus(step) => match self.scenes.top() {
+                Scene::Title => self.title_state.move_focus(step),
+                Scene::Profiles => self.profile_state.move_focus(step),
+                Scene::Calendar => self.calendar_state.move_focus(step),
+                Scene::Custom => self.custom_state.move_focus(step),
+                Scene::Settings => self.settings_state.move_focus(step),
+                Scene::Onboarding => self.onboarding_state.move_focus(step),
+                Scene::Stats => self.stats_state.move_focus(step),
+                Scene::Scores => self.scores_state.move_focus(step),
+                Scene::QuitConfirm | Scene::UndoPrompt => self.confirm_state.move_focus(step),
+                Scene::PauseMenu => self.pause_state.move_focus(step),
+                Scene::GameOver => {
+                    if let Some(game_over) = &mut self.game_over {
+                        game_over.move_focus(step);
+                    }
+                }
+                _ => {}
+            },
+            Action::Activate => self.activate_focus(ctx),
+            Action::PlayWeekly => {
+                self.scenes.push(Scene::Game);
+                self.start_marathon_stage(Marathon::new(calendar::today()), ctx);
+            }
+            _ => {}
+        }
+    }
+
+    fn back(&mut self, ctx: &mut Context) {
+        match self.scenes.top() {
+            Scene::Game if self.tournament.is_none() => {
+                self.scenes.pop();
+                self.current_tile_idx = None;
+            }
+            Scene::Title => ctx.request_quit(),
+            Scene::Calendar
+            | Scene::Command
+            | Scene::Custom
+            | Scene::Settings
+            | Scene::Stats
+            | Scene::Scores
+            | Scene::PauseMenu => {
+                self.scenes.pop();
+            }
+            // the finished board stays up to be looked over
+            Scene::GameOver => {
+                self.scenes.pop();
+                self.game_over = None;
+            }
+            // the run stays on the table without a name
+            Scene::NameEntry => {
+                self.scenes.pop();
+                self.name_entry = None;
+            }
+            // backing out of a name being typed only cancels the name
+            Scene::Profiles if self.profile_state.new_name.take().is_none() => {
+                self.scenes.pop();
+            }
+            Scene::Analysis => {
+                self.scenes.pop();
+                self.analysis = None;
+            }
+            Scene::Onboarding => self.finish_onboarding(ctx),
+            Scene::QuitConfirm => self.answer_quit_confirm(false, ctx),
+            Scene::UndoPrompt => self.answer_undo_prompt(false),
+            _ => {}
+        }
+    }
+
+    fn type_character(&mut self, character: char) {
+        if self.scenes.top() == Scene::Command {
+            self.command_bar.push(character);
+        } else if let Some(entry) = &mut self.name_entry {
+            entry.push(character);
+        } else if let Some(name) = &mut self.profile_state.new_name {
+            let allowed = character.is_ascii_alphanumeric() || character == '_' || character == '-';
+            if allowed && name.len() < MAX_NAME_LEN {
+                name.push(character.to_ascii_lowercase());
+            }
+        }
+    }
+
+    fn run_command(&mut self, ctx: &mut Context) {
+        match command::parse(&self.command_bar.input, self.board_settings.size()) {
+            Ok(action) => {
+                self.scenes.pop();
+                self.dispatch(action, ctx);
+            }
+            Err(e) => self.command_bar.error = Some(e),
+        }
+    }
+
+    // presses tiles and starts sketches itself, since those follow the mouse
+    // until it is released
+    fn game_click_action(
+        &mut self,
+        ctx: &Context,
+        button: MouseButton,
+        x: f32,
+        y: f32,
+    ) -> Option<Action> {
+        let point = ScreenPoint::new(x, y);
+        let size = self.board_settings.size();
+        let cell = screen_to_cell(point, self.board_origin(), self.tile_size, size);
+        let tile_idx = cell.map(|cell| cell.to_index(size.width));
+        let tile_pos = cell.map(|cell| cell.to_position());
+        // the HUD keeps the usual buttons for left handed players
+        let button = if tile_idx.is_some() {
+            self.config.board_button(button)
+        } else {
+            button
+        };
+        // holding ctrl over the board sketches instead of pressing tiles
+        if button == MouseButton::Left
+            && tile_idx.is_some()
+            && ctx.keyboard.is_mod_active(KeyMods::CTRL)
+        {
+            self.sketch.begin(self.board_point(point));
+            return None;
+        }
+        let both_buttons = match button {
+            MouseButton::Left => ctx.mouse.button_pressed(MouseButton::Right),
+            MouseButton::Right => ctx.mouse.button_pressed(MouseButton::Left),
+            _ => false,
+        };
+        if (button == MouseButton::Middle || both_buttons) && tile_idx.is_some() {
+            self.chord_tile = tile_idx;
+            self.current_tile_idx = None;
+            return None;
+        }
+        match button {
+            MouseButton::Left => {
+                if self.opponent_panel.opponent().is_some()
+                    && self.opponent_panel_rect().contains([x, y])
+                {
+                    return Some(Action::ToggleOpponentPanel);
+                }
+
+                if let Some(face_rect) = self.menu_state.face_rect {
+                    if face_rect.contains([x, y]) {
+                        return Some(Action::Restart);
+                    }
+                }
+
+                if self.menu_state.is_mode_button_clicked(x, y) && self.tournament.is_none() {
+                    self.menu_state.mode_list_open = !self.menu_state.mode_list_open;
+                    return None;
+                }
+
+                let entry_clicked = self
+                    .menu_state
+                    .get_mode_entry_clicked(x, y)
+                    .filter(|_| self.tournament.is_none());
+                // any other click closes the list, whether or not it picked an entry
+                self.menu_state.mode_list_open = false;
+                if let Some(entry) = entry_clicked {
+                    return Some(Action::SelectMode(entry));
+                }
+
+                if self.menu_state.is_hint_clicked(x, y) {
+                    return Some(Action::HintTile);
+                }
+
+                if let Some(key) = self.menu_state.get_tab_clicked(x, y) {
+                    return Some(Action::SwitchTab(key));
+                }
+
+                if !self.engine.is_lost() && !self.engine.is_won() {
+                    self.current_tile_idx = tile_idx;
+                    self.pressed_for = Duration::ZERO;
+                }
+                None
+            }
+            MouseButton::Right if ctx.keyboard.is_mod_active(KeyMods::SHIFT) => {
+                tile_pos.map(Action::Annotate)
+            }
+            MouseButton::Right => tile_pos.map(Action::Flag),
+            _ => None,
+        }
+    }
+
+    // beeps once each time the flags outnumber the mines
+    fn update_counter_state(&mut self, ctx: &Context) {
+        let state = CounterState::evaluate(self.engine.mines_left(), self.engine.elapsed());
+        if state.is_over() && !self.menu_state.counter_state.is_over() {
+            // without the sound the counter still changes color
+            if self.config.counter_warning_sound {
+                self.play(ctx, Cue::Warning);
+            }
+        }
+        self.menu_state.counter_state = state;
+    }
+
+    // speedrunners hear whether they're ahead of their best without looking at the ghost
+    fn update_pace(&mut self, ctx: &Context) {
+        let beat = self
+            .ghost
+            .as_mut()
+            .filter(|_| self.config.pace_cue)
+            .and_then(|ghost| ghost.pace_beat(&self.engine));
+        if let Some(ahead) = beat {
+            self.play(
+                ctx,
+                if ahead {
+                    Cue::PaceAhead
+                } else {
+                    Cue::PaceBehind
+                },
+            );
+        }
+    }
+
+    // only the title screen shows it, and the same board is only rendered once
+    fn update_continue_thumbnail(&mut self, ctx: &mut Context) {
+        if self.scenes.base() != Scene::Title || !self.engine.is_in_progress() {
+            self.continue_thumbnail = None;
+            return;
+        }
+        self.continue_thumbnail = self
+            .thumbnails
+            .get(ctx, &self.engine, &self.sprite_manager)
+            .unwrap_or_else(|e| {
+                self.errors
+                    .report(Subsystem::Sprites, format!("thumbnail: {}", e));
+                None
+            });
+    }
+
+    // picks the day or night theme; cheap enough to run every frame
+    fn update_night_mode(&mut self, ctx: &Context) {
+        let system_dark = match self.config.night_mode {
+            NightMode::System => ctx
+                .gfx
+                .window()
+                .theme()
+                .map(|theme| theme == ggez::winit::window::Theme::Dark),
+            _ => None,
+        };
+        let night = self
+            .config
+            .night_mode
+            .is_night(calendar::current_hour(), system_dark);
+        self.config.theme = if night {
+            self.night_theme
+        } else {
+            self.day_theme
+        };
+    }
+
+    // one fixed simulation tick; anything timed belongs here rather than in update
+    fn step(&mut self, dt: Duration) {
+        // the clock keeps running while a command is typed
+        if matches!(self.scenes.top(), Scene::Game | Scene::Command) {
+            self.engine.tick(dt);
+            self.reveal_guard.update(dt);
+            if self.current_tile_idx.is_some() {
+                self.pressed_for += dt;
+            }
+            self.update_journal(dt);
+            self.update_idle(dt);
+            let every = self.config.announce_every;
+            if let Err(e) = self.announcer.update(dt, every, &self.engine) {
+                self.errors
+                    .report(Subsystem::Audio, format!("speech: {}", e));
+            }
+            if let Some(ghost) = &mut self.ghost {
+                ghost.advance(self.engine.elapsed());
+                self.opponent_panel
+                    .handle(OpponentEvent::from_engine(ghost.engine()));
+            }
+        } else if self.scenes.top() == Scene::Countdown {
+            self.update_countdown(dt);
+        }
+
+        self.face.update(dt);
+        self.cascade.step();
+
+        if let Some(flash) = &mut self.flash {
+            if !flash.update(dt) {
+                self.flash = None;
+            }
+        }
+
+        if let Some(hint) = &mut self.hint {
+            if !hint.update(dt) {
+                self.hint = None;
+            }
+        }
+        if self
+            .tile_hint
+            .as_ref()
+            .is_some_and(|hint| !hint.is_current(&self.engine))
+        {
+            self.tile_hint = None;
+        }
+        self.tooltips.update(dt);
+        if let Some(toast) = &mut self.toast {
+            if !toast.update(dt) {
+                self.toast = None;
+            }
+        }
+    }
+
+    // screen position of the board's top left corner, past the HUD and label gutter
+    fn board_origin(&self) -> ScreenPoint {
+        let gutter = gutter_width(&self.config);
+        ScreenPoint::new(
+            gutter - self.scroll.0,
+            self.hud_height() + gutter - self.scroll.1,
+        )
+    }
+
+    // the part of the window the board is drawn in, past the HUD and label gutter
+    fn board_area(&self) -> Rect {
+        let gutter = gutter_width(&self.config);
+        let top = self.hud_height() + gutter;
+        Rect::new(
+            gutter,
+            top,
+            (self.screen_dim.0 - gutter).max(1.0),
+            (self.screen_dim.1 - top).max(1.0),
+        )
+    }
+
+    // the first press shows the cursor in the middle of the board; after that it moves
+    // and the board scrolls to keep it in view
+    fn move_cursor(&mut self, dx: i32, dy: i32) {
+        let size = self.board_settings.size();
+        let cell = match self.cursor_tile() {
+            Some(index) => CellCoord::from_index(index, size.width).step(dx, dy, size),
+            None => CellCoord {
+                col: size.width / 2,
+                row: size.height / 2,
+            },
+        };
+        self.cursor = Some(cell.to_index(size.width));
+
+        let rect = get_tile_rect(cell, self.tile_size, self.board_origin());
+        let area = self.board_area();
+        let scroll_x = (rect.x - area.x).min(0.0) + (rect.right() - area.right()).max(0.0);
+        let scroll_y = (rect.y - area.y).min(0.0) + (rect.bottom() - area.bottom()).max(0.0);
+        self.scroll_by(scroll_x, scroll_y);
+    }
+
+    // a board of another size swapped in from a tab or a save leaves no cursor behind
+    fn cursor_tile(&self) -> Option<usize> {
+        self.cursor.filter(|i| *i < self.engine.get_tiles().len())
+    }
+
+    // keeps the board from scrolling past its edges; a board that fits doesn't move
+    fn scroll_by(&mut self, dx: f32, dy: f32) {
+        let (max_x, max_y) = board_overflow(
+            self.screen_dim.0,
+            self.screen_dim.1,
+            self.hud_height(),
+            gutter_width(&self.config),
+            self.tile_size,
+            self.board_settings.size(),
+        );
+        self.scroll = (
+            (self.scroll.0 + dx).clamp(0.0, max_x),
+            (self.scroll.1 + dy).clamp(0.0, max_y),
+        );
+    }
+
+    // screen position in tile units, the space sketch strokes are stored in
+    fn board_point(&self, point: ScreenPoint) -> [f32; 2] {
+        let origin = self.board_origin();
+        [
+            (point.x - origin.x) / self.tile_size.0,
+            (point.y - origin.y) / self.tile_size.1,
+        ]
+    }
+
+    // with nothing focused, Return keeps its old meaning on the custom and quit screens
+    fn activate_focus(&mut self, ctx: &mut Context) {
+        let action = match self.scenes.top() {
+            Scene::Title => self.title_state.focused().map(Action::SelectTitle),
+            Scene::Profiles => self.profile_state.focused().map(Action::SelectProfile),
+            Scene::Calendar => self.calendar_state.focused().map(|option| match option {
+                CalendarOption::Weekly => Action::PlayWeekly,
+                CalendarOption::Back => Action::Back,
+            }),
+            Scene::Custom => Some(Action::SelectCustom(
+                self.custom_state.focused().unwrap_or(CustomOption::Start),
+            )),
+            Scene::Settings => self.settings_state.focused().map(Action::SelectSettings),
+            Scene::Onboarding => self
+                .onboarding_state
+                .focused()
+                .map(Action::SelectOnboarding),
+            Scene::Stats => self.stats_state.is_back_focused().then_some(Action::Back),
+            Scene::Scores => self.scores_state.is_back_focused().then_some(Action::Back),
+            Scene::QuitConfirm => Some(Action::ConfirmQuit(
+                self.confirm_state.focused().unwrap_or(true),
+            )),
+            Scene::UndoPrompt => Some(Action::AnswerUndo(
+                self.confirm_state.focused().unwrap_or(true),
+            )),
+            Scene::PauseMenu => Some(Action::SelectPause(
+                self.pause_state.focused().unwrap_or(PauseOption::Resume),
+            )),
+            Scene::GameOver => Some(Action::SelectGameOver(
+                self.game_over
+                    .as_ref()
+                    .and_then(|game_over| game_over.focused())
+                    .unwrap_or(GameOverOption::PlayAgain),
+            )),
+            _ => None,
+        };
+        if let Some(action) = action {
+            self.dispatch(action, ctx);
+        }
+    }
+
+    fn error_banner_rect(&self) -> Option<Rect> {
+        let lines = self.errors.banner().len();
+        (lines > 0).then(|| error_banner_rect(self.screen_dim, self.hud_height(), lines))
+    }
+
+    // the scrubber, or a move on the board that takes over the game from the moment shown
+    fn analysis_click_action(&self, button: MouseButton, x: f32, y: f32) -> Option<Action> {
+        let fraction = self.scrubber_fraction(x, y);
+        if button == MouseButton::Left && fraction.is_some() {
+            return fraction.map(Action::ScrubTo);
+        }
+        let button = self.config.board_button(button);
+        let size = self.board_settings.size();
+        let pos = screen_to_cell(
+            ScreenPoint::new(x, y),
+            self.board_origin(),
+            self.tile_size,
+            size,
+        )?
+        .to_position();
+        match button {
+            MouseButton::Left => Some(Action::Branch(MoveKind::Reveal, pos)),
+            MouseButton::Right => Some(Action::Branch(MoveKind::Flag, pos)),
+            _ => None,
+        }
+    }
+
+    // how far along the timeline the cursor is, when it is on the scrubber
+    fn scrubber_fraction(&self, x: f32, y: f32) -> Option<f32> {
+        let rect = scrubber_rect(self.screen_dim, self.hud_height());
+        rect.contains([x, y]).then(|| (x - rect.x) / rect.w)
+    }
+
+    fn opponent_panel_rect(&self) -> Rect {
+        opponent_panel_rect(
+            self.screen_dim,
+            self.hud_height(),
+            self.opponent_panel.collapsed,
+        )
+    }
+
+    fn hud_height(&self) -> f32 {
+        hud_height(self.screen_dim.1, &self.config.hud_scale)
+    }
+
+    fn new_game(
+        board_settings: &BoardSettings,
+        w: f32,
+        h: f32,
+        hud_h: f32,
+        config: &Config,
+        seed: u64,
+    ) -> ((f32, f32), Engine) {
+        let board_size = board_settings.size();
+        let num_bombs = board_settings.num_bombs();
+        let engine = Engine::with_generation(board_size, num_bombs, seed, config.generation);
+        (board_tile_size(config, w, h, hud_h, board_size), engine)
+    }
+}
+
+impl EventHandler for MainState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        let started = Instant::now();
+        self.menu_state.face_kind = self.face.expression(
+            &self.engine,
+            self.current_tile_idx.is_some(),
+            self.idle_time,
+        );
+        self.update_counter_state(ctx);
+        self.update_pace(ctx);
+        for e in self.webhook.failures() {
+            self.errors.report(Subsystem::Webhook, e);
+        }
+        if let Err(e) = self.mixer.update(ctx, ctx.time.delta(), &self.config) {
+            self.errors.report(Subsystem::Audio, e);
+        }
+        self.title_state.can_continue = self.engine.is_in_progress();
+        self.update_continue_thumbnail(ctx);
+        self.update_night_mode(ctx);
+
+        // moves are made between frames, so their tiles are held back before any is drawn
+        self.cascade.follow(&self.engine);
+        // slow frames run several ticks, so the clock never falls behind
+        while ctx.time.check_update_time(TICKS_PER_SECOND) {
+            self.step(tick_duration());
+        }
+
+        self.update_tournament(ctx);
+        self.guess_watch.update(&self.engine);
+        self.record_result(ctx);
+
+        if self
+            .overlay
+            .as_mut()
+            .is_some_and(|overlay| overlay.is_due(ctx.time.delta()))
+        {
+            let best = self.personal_best();
+            if let Some(overlay) = &self.overlay {
+                // a failed write only costs one overlay refresh
+                let _ = overlay.write(&self.engine, self.config.penalties, best);
+            }
+        }
+
+        if let Some(ipc) = &self.ipc {
+            ipc.publish(&self.engine);
+        }
+        if let Some(server) = &mut self.metrics_server {
+            server.publish(&self.metrics, &self.diagnostics);
+        }
+
+        self.diagnostics.current.update_time = started.elapsed();
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let started = Instant::now();
+        let mut canvas = graphics::Canvas::from_frame(ctx, self.config.theme.background);
+        let hud_h = self.hud_height();
+        let opponent_rect = self.opponent_panel_rect();
+        let banner_rect = self.error_banner_rect();
+        let hint = self.difficulty_hint();
+        let today = calendar::today();
+        let category = self.leaderboard_category();
+        let weekly_best = self
+            .leaderboard
+            .best(&self.category(Marathon::new(today).leaderboard_category(), false));
+        // a held chord presses down the neighbours it would open
+        let width = self.board_settings.size().width;
+        let chord_tiles = self.chord_tile.map_or(Vec::new(), |index| {
+            self.engine
+                .chord_targets(Position::from_index(index, width))
+                .iter()
+                .map(|pos| pos.to_index(width))
+                .collect()
+        });
+        let viewport = Viewport {
+            screen_dim: self.screen_dim,
+            hud_h,
+            tile_size: self.tile_size,
+            origin: self.board_origin(),
+            board_settings: &self.board_settings,
+            pressed_tile: self.current_tile_idx,
+            chord_tiles: &chord_tiles,
+            cursor: self.cursor,
+            cascading: if self.analysis.is_some() {
+                &[]
+            } else {
+                self.cascade.covered()
+            },
+        };
+        // the analysis shows its own rebuilt board in place of the finished one
+        let board_engine = self
+            .analysis
+            .as_ref()
+            .map_or(&self.engine, Analysis::engine);
+        let scrubber_rect = scrubber_rect(self.screen_dim, hud_h);
+        let selected_tile = self
+            .selected_tile
+            .filter(|_| !self.engine.is_lost() && !self.engine.is_won());
+        // holding M magnifies the tiles around the pointer
+        let lens = (matches!(self.scenes.top(), Scene::Game | Scene::Analysis)
+            && ctx.keyboard.is_key_pressed(KeyCode::M)
+            && self.hovered_tile_idx.is_some())
+        .then(|| {
+            let pointer = ctx.mouse.position();
+            Lens::new(ScreenPoint::new(pointer.x, pointer.y), self.screen_dim)
+        });
+        let board_area = self.board_area();
+        #[cfg(feature = "debug")]
+        let generation_stats = self
+            .diagnostics
+            .visible
+            .then(|| generation::GenerationStats::of(&self.engine))
+            .flatten();
+        // the analysis shows a finished game, there is nothing to switch to from it
+        let modes = hud::mode_entries(self.recent_boards.boards());
+        let tab_strip = if self.analysis.is_some() {
+            Vec::new()
+        } else {
+            self.tabs
+                .strip(self.tab_key().map(|key| (key, &self.engine)))
+        };
+        // the table under the rules currently set, read off the leaderboard each frame
+        let score_columns = (self.scenes.base() == Scene::Scores)
+            .then(|| scores::columns(&self.leaderboard, |board| self.category(board, false)));
+        let mut tiles_drawn = 0;
+        let mut lens_drawn = 0;
+        // the trainer's verdict on a won board, left out of the analysis
+        let trainer_report = self
+            .trainer
+            .as_ref()
+            .filter(|_| self.analysis.is_none())
+            .and_then(|trainer| trainer.report(&self.engine));
+        let clock_running = matches!(self.scenes.top(), Scene::Game | Scene::Command);
+        let tooltip = self.tooltips.shown().map(|(element, anchor)| {
+            let time = self.engine.summary(self.config.penalties).final_time();
+            (
+                anchor,
+                tooltip::lines(element, &self.engine, time, clock_running),
+            )
+        });
+        let mut layers = LayerStack::new();
+
+        if self.scenes.base() == Scene::Title {
+            layers.push(Layer::Board, |ctx, canvas| {
+                draw::draw_title(
+                    ctx,
+                    canvas,
+                    &self.sprite_manager,
+                    self.screen_dim,
+                    &mut self.title_state,
+                    &self.profiles.active,
+                    hint.as_deref(),
+                    self.continue_thumbnail.as_ref(),
+                    &self.config.theme,
+                )
+            });
+        } else if self.scenes.base() == Scene::Calendar {
+            layers.push(Layer::Board, |ctx, canvas| {
+                draw::draw_calendar(
+                    ctx,
+                    canvas,
+                    &viewport,
+                    &self.daily_log,
+                    weekly_best,
+                    &mut self.calendar_state,
+                    &self.config.theme,
+                )
+            });
+        } else if self.scenes.base() == Scene::Stats {
+            layers.push(Layer::Board, |ctx, canvas| {
+                draw::draw_stats(
+                    ctx,
+                    canvas,
+                    self.screen_dim,
+                    &self.openings.by_region(),
+                    &self.losses.counts(),
+                    self.luck.totals(),
+                    &mut self.stats_state,
+                    &self.config.theme,
+                )
+            });
+        } else if let Some(columns) = &score_columns {
+            layers.push(Layer::Board, |ctx, canvas| {
+                draw::draw_scores(
+                    ctx,
+                    canvas,
+                    self.screen_dim,
+                    columns,
+                    &mut self.scores_state,
+                    &self.config.theme,
+                )
+            });
+        } else if self.scenes.base() == Scene::Custom {
+            layers.push(Layer::Board, |ctx, canvas| {
+                draw::draw_custom(
+                    ctx,
+                    canvas,
+                    self.screen_dim,
+                    &self.custom_board,
+                    self.config.generation,
+                    &mut self.custom_state,
+                    &self.config.theme,
+                )
+            });
+        } else if self.scenes.base() == Scene::Onboarding {
+            layers.push(Layer::Board, |ctx, canvas| {
+                draw::draw_onboarding(
+                    ctx,
+                    canvas,
+                    self.screen_dim,
+                    &mut self.onboarding_state,
+                    &self.config.theme,
+                )
+            });
+        } else if self.scenes.base() == Scene::Settings {
+            layers.push(Layer::Board, |ctx, canvas| {
+                draw::draw_settings(
+                    ctx,
+                    canvas,
+                    self.screen_dim,
+                    &self.config,
+                    &self.presets,
+                    &self.sprite_manager,
+                    &mut self.settings_state,
+                )
+            });
+        } else if self.scenes.base() == Scene::Profiles {
+            layers.push(Layer::Board, |ctx, canvas| {
+                draw::draw_profiles(
+                    ctx,
+                    canvas,
+                    self.screen_dim,
+                    &self.profiles,
+                    &mut self.profile_state,
+                    &self.config.theme,
+                )
+            });
+        } else {
+            layers.push(Layer::Board, |ctx, canvas| {
+                // a scrolled board would otherwise spill over the HUD
+                let clipped = canvas.set_scissor_rect(board_area).is_ok();
+                tiles_drawn = draw::draw_tiles(
+                    ctx,
+                    canvas,
+                    &viewport,
+                    board_engine,
+                    &self.sprite_manager,
+                    self.config.texture_filter,
+                    &mut self.tile_batch,
+                );
+                if clipped {
+                    canvas.set_default_scissor_rect();
+                }
+            });
+
+            layers.push(Layer::Hud, |ctx, canvas| {
+                draw::draw_menu(
+                    ctx,
+                    canvas,
+                    &viewport,
+                    board_engine,
+                    &self.sprite_manager,
+                    &mut self.menu_state,
+                    &tab_strip,
+                    &modes,
+                    &self.config,
+                )
+            });
+
+            if let Some(category) = &category {
+                layers.push(Layer::Hud, |ctx, canvas| {
+                    draw::draw_category(ctx, canvas, &viewport, category, &self.config.theme)
+                });
+            }
+
+            if self.config.hud.show_guess_notice
+                && self.guess_watch.is_required()
+                && self.analysis.is_none()
+                && self.tournament.is_none()
+            {
+                layers.push(Layer::Hud, |ctx, canvas| {
+                    draw::draw_guess_notice(ctx, canvas, &viewport, &self.config.theme)
+                });
+            }
+
+            if self.config.show_coordinates {
+                layers.push(Layer::Overlay, |ctx, canvas| {
+                    draw::draw_coordinates(
+                        ctx,
+                        canvas,
+                        &viewport,
+                        self.hovered_tile_idx,
+                        &self.config.theme,
+                    )
+                });
+            }
+
+            layers.push(Layer::Overlay, |ctx, canvas| {
+                draw::draw_sketch(ctx, canvas, &viewport, &self.sketch)
+            });
+
+            if let Some(hint) = &self.hint {
+                layers.push(Layer::Overlay, |ctx, canvas| {
+                    draw::draw_heat_hint(ctx, canvas, &viewport, &self.engine, hint)
+                });
+            }
+
+            if let Some(hint) = &self.tile_hint {
+                layers.push(Layer::Overlay, |ctx, canvas| {
+                    draw::draw_tile_hint(ctx, canvas, &viewport, hint)
+                });
+            }
+
+            if let Some(report) = &trainer_report {
+                layers.push(Layer::Overlay, |ctx, canvas| {
+                    draw::draw_trainer_report(ctx, canvas, &viewport, report, &self.config.theme)
+                });
+            }
+
+            if let Some(index) = &selected_tile {
+                layers.push(Layer::Overlay, |ctx, canvas| {
+                    draw::draw_selection(ctx, canvas, &viewport, *index, &self.config.theme)
+                });
+            }
+
+            if let Some(flash) = self.flash.as_ref().filter(|flash| flash.is_lit()) {
+                layers.push(Layer::Overlay, |ctx, canvas| {
+                    draw::draw_flash(ctx, canvas, &viewport, flash.index)
+                });
+            }
+
+            if let Some(ghost) = self.ghost.as_ref().filter(|_| self.analysis.is_none()) {
+                layers.push(Layer::Overlay, |ctx, canvas| {
+                    draw::draw_ghost(ctx, canvas, &viewport, &self.engine, ghost.engine())
+                });
+            }
+
+            if let Some(lens) = &lens {
+                layers.push(Layer::Overlay, |ctx, canvas| {
+                    lens_drawn = draw::draw_lens(
+                        ctx,
+                        canvas,
+                        &viewport,
+                        *lens,
+                        board_engine,
+                        &self.sprite_manager,
+                        self.config.texture_filter,
+                        &self.config.theme,
+                    );
+                });
+            }
+
+            if let Some(analysis) = &self.analysis {
+                layers.push(Layer::Hud, |ctx, canvas| {
+                    draw::draw_scrubber(ctx, canvas, scrubber_rect, analysis, &self.config.theme)
+                });
+            }
+
+            if let Some(opponent) = self.opponent_panel.opponent() {
+                layers.push(Layer::Hud, |ctx, canvas| {
+                    draw::draw_opponent_panel(
+                        ctx,
+                        canvas,
+                        &self.sprite_manager,
+                        opponent_rect,
+                        self.opponent_panel.collapsed,
+                        opponent,
+                        &self.config.theme,
+                    )
+                });
+            }
+        }
+
+        if self.scenes.contains(Scene::Paused) {
+            layers.push(Layer::Dialog, |ctx, canvas| {
+                draw::draw_pause(ctx, canvas, self.screen_dim, hud_h, &self.config.theme)
+            });
+        }
+
+        if self.scenes.top() == Scene::PauseMenu {
+            layers.push(Layer::Dialog, |ctx, canvas| {
+                draw::draw_pause_menu(
+                    ctx,
+                    canvas,
+                    self.screen_dim,
+                    &mut self.pause_state,
+                    &self.config.theme,
+                )
+            });
+        }
+
+        if self.scenes.contains(Scene::Countdown) {
+            layers.push(Layer::Dialog, |ctx, canvas| {
+                draw::draw_countdown(
+                    ctx,
+                    canvas,
+                    self.screen_dim,
+                    hud_h,
+                    self.countdown_left,
+                    &self.config.theme,
+                )
+            });
+        }
+
+        if self.scenes.top() == Scene::Command {
+            layers.push(Layer::Dialog, |ctx, canvas| {
+                draw::draw_command_bar(ctx, canvas, self.screen_dim, &self.command_bar)
+            });
+        }
+
+        if let Some(game_over) = &mut self.game_over {
+            layers.push(Layer::Dialog, |ctx, canvas| {
+                draw::draw_game_over(ctx, canvas, self.screen_dim, game_over, &self.config.theme)
+            });
+        }
+
+        if let Some(entry) = &self.name_entry {
+            layers.push(Layer::Dialog, |ctx, canvas| {
+                draw::draw_name_entry(ctx, canvas, self.screen_dim, entry, &self.config.theme)
+            });
+        }
+
+        let confirm_message = match self.scenes.top() {
+            Scene::QuitConfirm => Some("QUIT GAME?"),
+            Scene::UndoPrompt => Some("UNDO THE MINE?"),
+            _ => None,
+        };
+        if let Some(message) = confirm_message {
+            layers.push(Layer::Dialog, |ctx, canvas| {
+                draw::draw_confirm(
+                    ctx,
+                    canvas,
+                    self.screen_dim,
+                    message,
+                    &mut self.confirm_state,
+                    &self.config.theme,
+                )
+            });
+        }
+
+        if let Some((anchor, lines)) = &tooltip {
+            layers.push(Layer::Toast, |ctx, canvas| {
+                draw::draw_tooltip(
+                    ctx,
+                    canvas,
+                    self.screen_dim,
+                    *anchor,
+                    lines,
+                    &self.config.theme,
+                )
+            });
+        }
+
+        if let Some(toast) = &self.toast {
+            layers.push(Layer::Toast, |ctx, canvas| {
+                draw::draw_toast(ctx, canvas, self.screen_dim, toast, &self.config.theme)
+            });
+        }
+
+        if let Some(rect) = &banner_rect {
+            layers.push(Layer::Toast, |ctx, canvas| {
+                draw::draw_error_banner(
+                    ctx,
+                    canvas,
+                    *rect,
+                    self.errors.banner(),
+                    &self.config.theme,
+                )
+            });
+        }
+
+        if self.diagnostics.visible {
+            layers.push(Layer::Toast, |ctx, canvas| {
+                draw::draw_diagnostics(ctx, canvas, self.screen_dim, &self.diagnostics)
+            });
+        }
+
+        #[cfg(feature = "debug")]
+        if let Some(stats) = &generation_stats {
+            layers.push(Layer::Toast, |ctx, canvas| {
+                draw::draw_generation_stats(ctx, canvas, self.screen_dim, stats)
+            });
+        }
+
+        layers.draw(ctx, &mut canvas);
+        self.diagnostics.current.tiles_drawn = tiles_drawn + lens_drawn;
+
+        canvas.finish(ctx)?;
+        self.diagnostics
+            .finish_frame(ctx.time.delta(), started.elapsed());
+        Ok(())
+    }
+
+    fn mouse_button_down_event(
+        &mut self,
+        ctx: &mut Context,
+        button: MouseButton,
+        x: f32,
+        y: f32,
+    ) -> GameResult {
+        self.idle_time = Duration::ZERO;
+        let left = button == MouseButton::Left;
+        let action = match self.scenes.top() {
+            // the banner sits over whatever scene is showing
+            _ if left
+                && self
+                    .error_banner_rect()
+                    .is_some_and(|rect| rect.contains([x, y])) =>
+            {
+                Some(Action::DismissErrors)
+            }
+            Scene::Title if left => self
+                .title_state
+                .get_option_clicked(x, y)
+                .map(Action::SelectTitle),
+            Scene::QuitConfirm if left => self
+                .confirm_state
+                .get_answer_clicked(x, y)
+                .map(Action::ConfirmQuit),
+            Scene::UndoPrompt if left => self
+                .confirm_state
+                .get_answer_clicked(x, y)
+                .map(Action::AnswerUndo),
+            Scene::Profiles if left && self.profile_state.new_name.is_none() => self
+                .profile_state
+                .get_option_clicked(x, y)
+                .map(Action::SelectProfile),
+            Scene::Calendar if left && self.calendar_state.is_back_clicked(x, y) => {
+                Some(Action::Back)
+            }
+            Scene::Calendar if left && self.calendar_state.is_weekly_clicked(x, y) => {
+                Some(Action::PlayWeekly)
+            }
+            Scene::Stats if left && self.stats_state.is_back_clicked(x, y) => Some(Action::Back),
+            Scene::Scores if left && self.scores_state.is_back_clicked(x, y) => Some(Action::Back),
+            Scene::Custom if left => self
+                .custom_state
+                .get_option_clicked(x, y)
+                .map(Action::SelectCustom)
+                .or_else(|| {
+                    self.custom_state
+                        .get_density_clicked(x, y)
+                        .map(Action::SetDensity)
+                }),
+            Scene::Settings if left => self
+                .settings_state
+                .get_option_clicked(x, y)
+                .map(Action::SelectSettings),
+            Scene::Onboarding if left => self
+                .onboarding_state
+                .get_choice_clicked(x, y)
+                .map(Action::SelectOnboarding),
+            Scene::Analysis => self.analysis_click_action(button, x, y),
+            Scene::Paused => Some(Action::Resume),
+            Scene::PauseMenu if left => self
+                .pause_state
+                .get_option_clicked(x, y)
+                .map(Action::SelectPause),
+            Scene::GameOver if left => self
+                .game_over
+                .as_ref()
+                .and_then(|game_over| game_over.get_option_clicked(x, y))
+                .map(Action::SelectGameOver),
+            // clicking anywhere closes the command bar
+            Scene::Command => Some(Action::Back),
+            Scene::Game => self.game_click_action(ctx, button, x, y),
+            _ => None,
+        };
+        if let Some(action) = action {
+            self.dispatch(action, ctx);
+        }
+        Ok(())
+    }
+
+    fn mouse_button_up_event(
+        &mut self,
+        ctx: &mut Context,
+        button: MouseButton,
+        _x: f32,
+        _y: f32,
+    ) -> Result<(), GameError> {
+        // the release matches the swapped press that started it
+        let button = self.config.board_button(button);
+        // whichever button is let go first finishes the chord
+        if let Some(tile_idx) = self.chord_tile.take() {
+            let pos = Position::from_index(tile_idx, self.board_settings.size().width);
+            self.dispatch(Action::Chord(pos), ctx);
+            return Ok(());
+        }
+        match button {
+            MouseButton::Left if self.sketch.is_drawing() => self.sketch.end(),
+            MouseButton::Left => {
+                if let Some(tile_idx) = self.current_tile_idx {
+                    let guard = self.config.misclick_guard;
+                    // a tap on any other tile only moves the selection
+                    let confirmed = self.config.reveal_input == RevealInput::Direct
+                        || self.selected_tile == Some(tile_idx);
+                    let too_short = self
+                        .config
+                        .min_press
+                        .is_some_and(|min| self.pressed_for < min);
+                    if too_short {
+                        self.current_tile_idx = None;
+                    } else if !confirmed {
+                        self.selected_tile = Some(tile_idx);
+                        self.current_tile_idx = None;
+                    } else if self.reveal_guard.allows(guard, &self.engine, tile_idx) {
+                        self.selected_tile = None;
+                        let pos = Position::from_index(tile_idx, self.board_settings.size().width);
+                        self.dispatch(Action::Reveal(pos), ctx);
+                    } else {
+                        self.current_tile_idx = None;
+                        self.toast = Some(Toast::new(match guard {
+                            MisclickGuard::Confirm => "PROVEN MINE - CLICK AGAIN",
+                            _ => "PROVEN MINE - HOVER TO REVEAL",
+                        }));
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn mouse_motion_event(
+        &mut self,
+        ctx: &mut Context,
+        x: f32,
+        y: f32,
+        _dx: f32,
+        _dy: f32,
+    ) -> Result<(), GameError> {
+        self.idle_time = Duration::ZERO;
+        // dragging along the density slider
+        if self.scenes.top() == Scene::Custom && ctx.mouse.button_pressed(MouseButton::Left) {
+            if let Some(density) = self.custom_state.get_density_clicked(x, y) {
+                self.dispatch(Action::SetDensity(density), ctx);
+            }
+            return Ok(());
+        }
+        if self.scenes.top() == Scene::Settings {
+            self.settings_state.hovered = self.settings_state.get_option_clicked(x, y);
+            return Ok(());
+        }
+        // dragging along the analysis timeline
+        if self.scenes.top() == Scene::Analysis {
+            if ctx.mouse.button_pressed(MouseButton::Left) {
+                if let Some(fraction) = self.scrubber_fraction(x, y) {
+                    self.dispatch(Action::ScrubTo(fraction), ctx);
+                }
+            }
+            return Ok(());
+        }
+        let point = ScreenPoint::new(x, y);
+        if self.sketch.is_drawing() {
+            self.sketch.extend(self.board_point(point));
+            return Ok(());
+        }
+        let hovered_idx = get_tile_index(
+            point,
+            self.board_origin(),
+            self.tile_size,
+            &self.board_settings,
+        );
+        self.hovered_tile_idx = hovered_idx;
+        self.reveal_guard.hover(hovered_idx);
+        let element = match self.scenes.top() {
+            Scene::Game => self.menu_state.element_at(x, y),
+            _ => None,
+        };
+        self.tooltips.hover(element, [x, y]);
+        if self.chord_tile.is_some() {
+            self.chord_tile = hovered_idx;
+        }
+        if let Some(_) = self.current_tile_idx {
+            if let Some(new_idx) = hovered_idx {
+                self.current_tile_idx = Some(new_idx);
+            } else {
+                self.current_tile_idx = None;
+            }
+        }
+        Ok(())
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        input: KeyInput,
+        _repeated: bool,
+    ) -> Result<(), GameError> {
+        self.idle_time = Duration::ZERO;
+        let action = if ctx.keyboard.is_mod_active(KeyMods::CTRL) {
+            action::ctrl_key_action(self.scenes.top(), input.keycode)
+        } else {
+            action::key_action(self.scenes.top(), input.keycode)
+        };
+        if let Some(action) = action {
+            self.dispatch(action, ctx);
+        }
+        Ok(())
+    }
+
+    fn gamepad_button_down_event(
+        &mut self,
+        ctx: &mut Context,
+        button: Button,
+        _id: GamepadId,
+    ) -> GameResult {
+        self.idle_time = Duration::ZERO;
+        if let Some(action) = action::button_action(self.scenes.top(), button) {
+            self.dispatch(action, ctx);
+        }
+        Ok(())
+    }
+
+    fn text_input_event(&mut self, ctx: &mut Context, character: char) -> GameResult {
+        if let Some(action) = action::text_action(self.scenes.top(), character) {
+            self.dispatch(action, ctx);
+        }
+        Ok(())
+    }
+
+    fn quit_event(&mut self, ctx: &mut Context) -> Result<bool, GameError> {
+        let needs_confirm = self.config.confirm_quit && self.engine.is_in_progress();
+
+        if needs_confirm && !self.quit_confirmed {
+            if self.scenes.top() != Scene::QuitConfirm {
+                self.current_tile_idx = None;
+                self.scenes.push(Scene::QuitConfirm);
+            }
+            // cancel the quit until the player answers
+            return Ok(true);
+        }
+
+        self.shutdown(ctx);
+        Ok(false)
+    }
+
+    fn resize_event(
+        &mut self,
+        _ctx: &mut Context,
+        width: f32,
+        height: f32,
+    ) -> Result<(), GameError> {
+        self.screen_dim = (width, height);
+        let board_size = self.board_settings.size();
+        self.tile_size =
+            board_tile_size(&self.config, width, height, self.hud_height(), board_size);
+        self.scroll_by(0.0, 0.0);
+        Ok(())
+    }
+
+    // the wheel moves a board too big for the window; shift scrolls sideways
+    fn mouse_wheel_event(&mut self, ctx: &mut Context, x: f32, y: f32) -> GameResult {
+        if !matches!(self.scenes.top(), Scene::Game | Scene::Analysis) {
+            return Ok(());
+        }
+        let (x, y) = if ctx.keyboard.is_mod_active(KeyMods::SHIFT) {
+            (y, x)
+        } else {
+            (x, y)
+        };
+        self.scroll_by(
+            -x * self.tile_size.0 * SCROLL_TILES,
+            -y * self.tile_size.1 * SCROLL_TILES,
+        );
         Ok(())
     }
 }
@@ -0,0 +1,110 @@
+use super::board::{daily_seed, BoardSettings};
+use super::calendar::{format_date, weekday};
+use std::time::Duration;
+
+// the weekly gauntlet, played in this order on boards seeded from the week
+pub const STAGES: [&str; 3] = ["easy", "medium", "hard"];
+
+// weeks start on monday in local time
+pub fn week_start(day: i64) -> i64 {
+    day - weekday(day) as i64
+}
+
+pub struct Marathon {
+    // local day number of the monday that seeds this week's boards
+    pub week: i64,
+    pub stage: usize,
+    // winning time of every finished stage
+    pub stage_times: Vec<Duration>,
+    // one assisted stage marks the whole run
+    pub assisted: bool,
+}
+
+impl Marathon {
+    pub fn new(today: i64) -> Self {
+        Self {
+            week: week_start(today),
+            stage: 0,
+            stage_times: Vec::new(),
+            assisted: false,
+        }
+    }
+
+    pub fn board_settings(&self) -> BoardSettings {
+        BoardSettings::by_name(STAGES[self.stage]).unwrap()
+    }
+
+    pub fn seed(&self) -> u64 {
+        // offset from the week's daily seed so no stage repeats a daily board
+        daily_seed(self.week) ^ (self.stage as u64 + 1).wrapping_mul(0xBF58_476D_1CE4_E5B9)
+    }
+
+    pub fn leaderboard_category(&self) -> String {
+        format!("weekly-{}", format_date(self.week))
+    }
+
+    pub fn is_stage_recorded(&self) -> bool {
+        self.stage_times.len() > self.stage
+    }
+
+    pub fn finish_stage(&mut self, time: Duration, assisted: bool) {
+        if !self.is_stage_recorded() {
+            self.stage_times.push(time);
+            self.assisted |= assisted;
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.stage_times.len() == STAGES.len()
+    }
+
+    pub fn total_time(&self) -> Duration {
+        self.stage_times.iter().sum()
+    }
+
+    pub fn next_stage(&mut self) -> bool {
+        if !self.is_stage_recorded() || self.stage + 1 >= STAGES.len() {
+            return false;
+        }
+        self.stage += 1;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calendar::day_number;
+    use chrono::NaiveDate;
+
+    fn days_from_civil(year: i32, month: u32, day: u32) -> i64 {
+        day_number(NaiveDate::from_ymd_opt(year, month, day).unwrap())
+    }
+
+    #[test]
+    fn test_week_start_is_monday() {
+        let monday = days_from_civil(2026, 10, 12);
+        assert_eq!(week_start(monday), monday);
+        assert_eq!(week_start(days_from_civil(2026, 10, 18)), monday);
+        assert_eq!(week_start(days_from_civil(2026, 10, 19)), monday + 7);
+    }
+
+    #[test]
+    fn test_stages_advance_only_after_a_win() {
+        let mut marathon = Marathon::new(days_from_civil(2026, 10, 16));
+        let first_seed = marathon.seed();
+        assert!(!marathon.next_stage());
+
+        for stage in 0..STAGES.len() {
+            assert_eq!(marathon.stage, stage);
+            marathon.finish_stage(Duration::from_secs(10), stage == 1);
+            marathon.next_stage();
+        }
+
+        assert!(marathon.is_finished());
+        assert_eq!(marathon.total_time(), Duration::from_secs(30));
+        assert!(marathon.assisted);
+        assert_ne!(marathon.seed(), first_seed);
+        assert_eq!(marathon.leaderboard_category(), "weekly-2026-10-12");
+    }
+}
@@ -0,0 +1,84 @@
+use super::diagnostics::{Diagnostics, HISTORY_LEN};
+use std::time::Instant;
+
+// the frame time quantiles reported, over the diagnostics history
+const QUANTILES: [f32; 3] = [0.5, 0.9, 0.99];
+
+// counters for operators watching a long running install, such as an arcade kiosk;
+// served in the prometheus text format by MetricsServer
+pub struct Metrics {
+    started: Instant,
+    won: u64,
+    lost: u64,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            won: 0,
+            lost: 0,
+        }
+    }
+
+    pub fn record_game(&mut self, won: bool) {
+        if won {
+            self.won += 1;
+        } else {
+            self.lost += 1;
+        }
+    }
+
+    pub fn render(&self, now: Instant, diagnostics: &Diagnostics) -> String {
+        let mut text = format!(
+            "# HELP rust_sweeper_games_total Games finished since startup.\n\
+             # TYPE rust_sweeper_games_total counter\n\
+             rust_sweeper_games_total{{result=\"won\"}} {}\n\
+             rust_sweeper_games_total{{result=\"lost\"}} {}\n\
+             # HELP rust_sweeper_uptime_seconds Time since startup.\n\
+             # TYPE rust_sweeper_uptime_seconds gauge\n\
+             rust_sweeper_uptime_seconds {}\n\
+             # HELP rust_sweeper_frame_time_seconds Frame time quantiles over the last {} frames.\n\
+             # TYPE rust_sweeper_frame_time_seconds gauge\n",
+            self.won,
+            self.lost,
+            now.saturating_duration_since(self.started).as_secs(),
+            HISTORY_LEN
+        );
+        for quantile in QUANTILES {
+            text += &format!(
+                "rust_sweeper_frame_time_seconds{{quantile=\"{}\"}} {:.6}\n",
+                quantile,
+                diagnostics.frame_time_percentile(quantile).as_secs_f64()
+            );
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_render_prometheus_text() {
+        let mut metrics = Metrics::new();
+        metrics.record_game(true);
+        metrics.record_game(false);
+        metrics.record_game(false);
+        let mut diagnostics = Diagnostics::new();
+        diagnostics.finish_frame(Duration::from_millis(16), Duration::ZERO);
+
+        let text = metrics.render(metrics.started + Duration::from_secs(90), &diagnostics);
+        assert!(text.contains("\nrust_sweeper_games_total{result=\"won\"} 1\n"));
+        assert!(text.contains("\nrust_sweeper_games_total{result=\"lost\"} 2\n"));
+        assert!(text.contains("\nrust_sweeper_uptime_seconds 90\n"));
+        assert!(text.ends_with("rust_sweeper_frame_time_seconds{quantile=\"0.99\"} 0.016000\n"));
+        // every sample has its family declared before it
+        for line in text.lines().filter(|line| !line.starts_with('#')) {
+            let family = line.split(['{', ' ']).next().unwrap();
+            assert!(text.contains(&format!("# TYPE {} ", family)));
+        }
+    }
+}
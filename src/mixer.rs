@@ -0,0 +1,239 @@
+use super::config::Config;
+use super::errors::{ErrorReporter, Subsystem};
+use ggez::audio::{self, SoundSource};
+use ggez::{Context, GameResult};
+use std::time::Duration;
+
+// a reveal opening more tiles than this plays one cascade instead of a pop
+const CASCADE_TILES: usize = 8;
+// at most this many of one cue start within the window, the rest are dropped
+const MAX_VOICES: usize = 3;
+const VOICE_WINDOW: Duration = Duration::from_millis(150);
+// looped on the music bus when it ships
+const MUSIC_FILE: &str = "/resources/sounds/music.ogg";
+// the settings screen steps a bus's volume by this many percent, wrapping to silence
+const VOLUME_STEP: u8 = 25;
+
+// each bus has its own volume in the settings
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Bus {
+    Ui,
+    Game,
+    Music,
+}
+
+impl Bus {
+    pub const ALL: [Bus; 3] = [Bus::Ui, Bus::Game, Bus::Music];
+
+    // settings.toml spells the volumes ui_volume, game_volume and music_volume
+    pub fn key(&self) -> &'static str {
+        match self {
+            Bus::Ui => "ui",
+            Bus::Game => "game",
+            Bus::Music => "music",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Bus> {
+        Bus::ALL.into_iter().find(|bus| bus.key() == key)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Cue {
+    // a menu button pressed
+    Click,
+    Reveal,
+    // a reveal that opened up a whole area
+    Cascade,
+    Flag,
+    // more flags than mines
+    Warning,
+    // the ghost reached another pace beat with the player ahead of it, or behind
+    PaceAhead,
+    PaceBehind,
+}
+
+impl Cue {
+    const ALL: [Cue; 7] = [
+        Cue::Click,
+        Cue::Reveal,
+        Cue::Cascade,
+        Cue::Flag,
+        Cue::Warning,
+        Cue::PaceAhead,
+        Cue::PaceBehind,
+    ];
+
+    fn file(&self) -> &'static str {
+        match self {
+            Cue::Click => "/resources/sounds/click.wav",
+            Cue::Reveal => "/resources/sounds/reveal.wav",
+            Cue::Cascade => "/resources/sounds/cascade.wav",
+            Cue::Flag => "/resources/sounds/flag.wav",
+            Cue::Warning => "/resources/sounds/warning.wav",
+            Cue::PaceAhead => "/resources/sounds/pace_ahead.wav",
+            Cue::PaceBehind => "/resources/sounds/pace_behind.wav",
+        }
+    }
+
+    fn bus(&self) -> Bus {
+        match self {
+            Cue::Click => Bus::Ui,
+            _ => Bus::Game,
+        }
+    }
+}
+
+// the sound for a reveal of this many tiles: a flood fill is one whoosh, not a pop per tile
+pub fn reveal_cue(opened: usize) -> Option<Cue> {
+    match opened {
+        0 => None,
+        1..=CASCADE_TILES => Some(Cue::Reveal),
+        _ => Some(Cue::Cascade),
+    }
+}
+
+pub fn next_volume(volume: u8) -> u8 {
+    if volume >= 100 {
+        0
+    } else {
+        (volume + VOLUME_STEP).min(100)
+    }
+}
+
+// keeps fast repeats of a cue, from chording across a board or a replay, from piling up
+struct Throttle {
+    // cues started within the window and when
+    started: Vec<(Cue, Duration)>,
+}
+
+impl Throttle {
+    fn admit(&mut self, cue: Cue, now: Duration) -> bool {
+        self.started
+            .retain(|(_, at)| now.saturating_sub(*at) < VOICE_WINDOW);
+        if self.started.iter().filter(|(c, _)| *c == cue).count() >= MAX_VOICES {
+            return false;
+        }
+        self.started.push((cue, now));
+        true
+    }
+}
+
+// every sound the game makes goes through here, at its bus's volume; cues whose file
+// doesn't ship are silent
+pub struct Mixer {
+    sources: Vec<(Cue, audio::Source)>,
+    music: Option<audio::Source>,
+    // time since start, advanced by update, that the throttle counts in
+    clock: Duration,
+    throttle: Throttle,
+}
+
+impl Mixer {
+    pub fn new(ctx: &Context, errors: &mut ErrorReporter) -> Self {
+        let mut load = |file: &str| {
+            if !ctx.fs.exists(file) {
+                return None;
+            }
+            audio::Source::new(ctx, file)
+                .map_err(|e| errors.report(Subsystem::Audio, e))
+                .ok()
+        };
+        let sources = Cue::ALL
+            .into_iter()
+            .filter_map(|cue| load(cue.file()).map(|source| (cue, source)))
+            .collect();
+        let music = load(MUSIC_FILE).map(|mut music| {
+            music.set_repeat(true);
+            music
+        });
+        Self {
+            sources,
+            music,
+            clock: Duration::ZERO,
+            throttle: Throttle {
+                started: Vec::new(),
+            },
+        }
+    }
+
+    // a source that fails is dropped, so each failure is only reported once
+    pub fn play(&mut self, ctx: &Context, cue: Cue, config: &Config) -> GameResult {
+        let volume = config.volume(cue.bus());
+        if !config.sounds || volume == 0.0 || !self.throttle.admit(cue, self.clock) {
+            return Ok(());
+        }
+        let Some(i) = self.sources.iter().position(|(c, _)| *c == cue) else {
+            return Ok(());
+        };
+        let source = &mut self.sources[i].1;
+        source.set_volume(volume);
+        let played = source.play_detached(ctx);
+        if played.is_err() {
+            self.sources.remove(i);
+        }
+        played
+    }
+
+    // starts, stops and turns the music to follow the settings
+    pub fn update(&mut self, ctx: &Context, dt: Duration, config: &Config) -> GameResult {
+        self.clock += dt;
+        let Some(music) = self.music.as_mut() else {
+            return Ok(());
+        };
+        let volume = config.volume(Bus::Music);
+        let result = if !config.sounds || volume == 0.0 {
+            if music.playing() {
+                music.stop(ctx)
+            } else {
+                Ok(())
+            }
+        } else {
+            music.set_volume(volume);
+            if music.playing() {
+                Ok(())
+            } else {
+                music.play(ctx)
+            }
+        };
+        if result.is_err() {
+            self.music = None;
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flood_fill_is_one_cascade() {
+        assert_eq!(reveal_cue(0), None);
+        assert_eq!(reveal_cue(1), Some(Cue::Reveal));
+        assert_eq!(reveal_cue(200), Some(Cue::Cascade));
+    }
+
+    #[test]
+    fn test_throttle_caps_voices_per_cue() {
+        let mut throttle = Throttle {
+            started: Vec::new(),
+        };
+        let now = Duration::from_secs(1);
+        for _ in 0..MAX_VOICES {
+            assert!(throttle.admit(Cue::Reveal, now));
+        }
+        assert!(!throttle.admit(Cue::Reveal, now));
+        // other cues have voices of their own
+        assert!(throttle.admit(Cue::Flag, now));
+        assert!(throttle.admit(Cue::Reveal, now + VOICE_WINDOW));
+    }
+
+    #[test]
+    fn test_volume_steps_wrap_to_silence() {
+        assert_eq!(next_volume(0), 25);
+        assert_eq!(next_volume(90), 100);
+        assert_eq!(next_volume(100), 0);
+    }
+}
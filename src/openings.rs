@@ -0,0 +1,166 @@
+use super::game::Engine;
+use super::storage;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// one "<finished unix ms>\t<updated unix ms>\t<width>x<height> <x>,<y> <tiles opened> won|lost"
+// line per game, merged per record by sync
+const OPENINGS_FILE_NAME: &str = "openings.records";
+// the chart splits every board into this many regions a side, so any board size adds up
+pub const REGIONS: usize = 3;
+
+struct Opening {
+    finished: u64,
+    width: usize,
+    height: usize,
+    x: usize,
+    y: usize,
+    opened: usize,
+    won: bool,
+}
+
+impl Opening {
+    fn parse(line: &str) -> Option<Self> {
+        let fields = line.split('\t').collect::<Vec<_>>();
+        let [finished, _, result] = fields.as_slice() else {
+            return None;
+        };
+        let [size, click, opened, outcome] = result.split(' ').collect::<Vec<_>>()[..] else {
+            return None;
+        };
+        let (width, height) = size.split_once('x')?;
+        let (x, y) = click.split_once(',')?;
+        Some(Self {
+            finished: finished.parse().ok()?,
+            width: width.parse().ok()?,
+            height: height.parse().ok()?,
+            x: x.parse().ok()?,
+            y: y.parse().ok()?,
+            opened: opened.parse().ok()?,
+            won: outcome == "won",
+        })
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct RegionStats {
+    pub games: usize,
+    pub wins: usize,
+    opened: usize,
+}
+
+impl RegionStats {
+    pub fn average_opening(&self) -> Option<f32> {
+        (self.games > 0).then(|| self.opened as f32 / self.games as f32)
+    }
+
+    pub fn win_rate(&self) -> Option<f32> {
+        (self.games > 0).then(|| self.wins as f32 / self.games as f32)
+    }
+}
+
+// where games were started and how much the first click opened
+pub struct Openings {
+    path: PathBuf,
+    results: Vec<Opening>,
+}
+
+impl Openings {
+    pub fn load(profile_dir: &Path) -> Self {
+        let path = profile_dir.join(OPENINGS_FILE_NAME);
+        let results = fs::read_to_string(&path)
+            .unwrap_or_default()
+            .lines()
+            .filter_map(Opening::parse)
+            .collect();
+        Self { path, results }
+    }
+
+    // games that ended before a tile was opened have nothing to record
+    pub fn record(&mut self, engine: &Engine) -> io::Result<()> {
+        let Some((pos, opened)) = engine.opening() else {
+            return Ok(());
+        };
+        let finished = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        self.results.push(Opening {
+            finished,
+            width: engine.size().width,
+            height: engine.size().height,
+            x: pos.0 as usize,
+            y: pos.1 as usize,
+            opened,
+            won: engine.is_won(),
+        });
+
+        let mut text = String::new();
+        for result in &self.results {
+            text.push_str(&format!(
+                "{}\t{}\t{}x{} {},{} {} {}\n",
+                result.finished,
+                result.finished,
+                result.width,
+                result.height,
+                result.x,
+                result.y,
+                result.opened,
+                if result.won { "won" } else { "lost" }
+            ));
+        }
+        storage::write_atomic(&self.path, text)
+    }
+
+    // indexed [row][column], top left first
+    pub fn by_region(&self) -> [[RegionStats; REGIONS]; REGIONS] {
+        let mut regions = [[RegionStats::default(); REGIONS]; REGIONS];
+        for result in &self.results {
+            let row = (result.y * REGIONS / result.height).min(REGIONS - 1);
+            let column = (result.x * REGIONS / result.width).min(REGIONS - 1);
+            let region = &mut regions[row][column];
+            region.games += 1;
+            region.opened += result.opened;
+            if result.won {
+                region.wins += 1;
+            }
+        }
+        regions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{GridSize, Position};
+
+    #[test]
+    fn test_openings_group_by_region() {
+        let dir =
+            std::env::temp_dir().join(format!("rust_sweeper_test_openings_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let mut openings = Openings::load(&dir);
+
+        // an empty board opens everything from any click and wins straight away
+        for (x, y) in [(0, 0), (1, 1), (8, 4)] {
+            let mut engine = Engine::with_seed(GridSize::new(9, 9), 0, 1);
+            engine.reveal(Position(x, y));
+            openings.record(&engine).unwrap();
+        }
+        openings
+            .record(&Engine::with_seed(GridSize::new(9, 9), 10, 1))
+            .unwrap();
+
+        let regions = Openings::load(&dir).by_region();
+        assert_eq!(regions[0][0].games, 2);
+        assert_eq!(regions[0][0].average_opening(), Some(81.0));
+        assert_eq!(regions[0][0].win_rate(), Some(1.0));
+        assert_eq!(regions[1][2].games, 1);
+        assert_eq!(regions[2][2].average_opening(), None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,123 @@
+use super::game::Engine;
+use super::sprites::FaceKind;
+
+// what the other side reports as it plays; a bot or a network peer sends the same events
+#[derive(Clone, PartialEq, Debug)]
+pub enum OpponentEvent {
+    Joined(String),
+    Progress { progress: f32, mines_left: i32 },
+    Finished { won: bool },
+    Left,
+}
+
+impl OpponentEvent {
+    // the event a locally simulated opponent sends after each of its moves
+    pub fn from_engine(engine: &Engine) -> Self {
+        if engine.is_won() || engine.is_lost() {
+            OpponentEvent::Finished {
+                won: engine.is_won(),
+            }
+        } else {
+            OpponentEvent::Progress {
+                progress: engine.progress(),
+                mines_left: engine.mines_left(),
+            }
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Opponent {
+    pub name: String,
+    pub progress: f32,
+    pub mines_left: i32,
+    pub face: FaceKind,
+}
+
+// the right-hand panel; it stays hidden until someone joins
+pub struct OpponentPanel {
+    pub collapsed: bool,
+    opponent: Option<Opponent>,
+}
+
+impl OpponentPanel {
+    pub fn new() -> Self {
+        Self {
+            collapsed: false,
+            opponent: None,
+        }
+    }
+
+    pub fn handle(&mut self, event: OpponentEvent) {
+        match event {
+            OpponentEvent::Joined(name) => {
+                self.opponent = Some(Opponent {
+                    name,
+                    progress: 0.0,
+                    mines_left: 0,
+                    face: FaceKind::Smile,
+                });
+            }
+            OpponentEvent::Left => self.opponent = None,
+            OpponentEvent::Progress {
+                progress,
+                mines_left,
+            } => {
+                if let Some(opponent) = &mut self.opponent {
+                    opponent.progress = progress;
+                    opponent.mines_left = mines_left;
+                }
+            }
+            OpponentEvent::Finished { won } => {
+                if let Some(opponent) = &mut self.opponent {
+                    if won {
+                        opponent.progress = 1.0;
+                        opponent.face = FaceKind::Cool;
+                    } else {
+                        opponent.face = FaceKind::Dead;
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.collapsed = !self.collapsed;
+    }
+
+    pub fn opponent(&self) -> Option<&Opponent> {
+        self.opponent.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_update_the_opponent() {
+        let mut panel = OpponentPanel::new();
+        panel.handle(OpponentEvent::Progress {
+            progress: 0.5,
+            mines_left: 3,
+        });
+        assert!(panel.opponent().is_none());
+
+        panel.handle(OpponentEvent::Joined("bot".to_string()));
+        panel.handle(OpponentEvent::Progress {
+            progress: 0.5,
+            mines_left: 3,
+        });
+        let opponent = panel.opponent().unwrap();
+        assert_eq!(opponent.progress, 0.5);
+        assert_eq!(opponent.mines_left, 3);
+        assert_eq!(opponent.face, FaceKind::Smile);
+
+        panel.handle(OpponentEvent::Finished { won: true });
+        assert_eq!(panel.opponent().unwrap().progress, 1.0);
+        assert_eq!(panel.opponent().unwrap().face, FaceKind::Cool);
+
+        panel.handle(OpponentEvent::Left);
+        assert!(panel.opponent().is_none());
+    }
+}
@@ -0,0 +1,145 @@
+use super::game::{Engine, Penalties};
+use super::storage;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+
+const WRITE_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum OverlayFormat {
+    Text,
+    Json,
+}
+
+impl OverlayFormat {
+    pub const ALL: [OverlayFormat; 2] = [OverlayFormat::Text, OverlayFormat::Json];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            OverlayFormat::Text => "text",
+            OverlayFormat::Json => "json",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|format| format.key() == key)
+    }
+}
+
+// periodically dumps the live game stats to a file that streaming software can read
+pub struct OverlayWriter {
+    path: PathBuf,
+    format: OverlayFormat,
+    since_last_write: Duration,
+}
+
+impl OverlayWriter {
+    pub fn new(path: PathBuf, format: OverlayFormat) -> Self {
+        Self {
+            path,
+            format,
+            // write on the first update
+            since_last_write: WRITE_INTERVAL,
+        }
+    }
+
+    // true once a second, when the caller should write
+    pub fn is_due(&mut self, delta: Duration) -> bool {
+        self.since_last_write += delta;
+        if self.since_last_write < WRITE_INTERVAL {
+            return false;
+        }
+        self.since_last_write = Duration::ZERO;
+        true
+    }
+
+    // best is the personal best the game is ranked against, None when there is none
+    pub fn write(
+        &self,
+        engine: &Engine,
+        penalties: Penalties,
+        best: Option<Duration>,
+    ) -> io::Result<()> {
+        // readers never see a half written file
+        storage::write_atomic(&self.path, self.render(engine, penalties, best))
+    }
+
+    fn render(&self, engine: &Engine, penalties: Penalties, best: Option<Duration>) -> String {
+        let status = if engine.is_won() {
+            "won"
+        } else if engine.is_lost() {
+            "lost"
+        } else {
+            "playing"
+        };
+        let seconds = engine.elapsed().as_secs();
+        let mines_left = engine.bombs_left();
+        let progress = (engine.progress() * 100.0).round() as u32;
+        // whole seconds behind the personal best, negative while ahead of it; penalties
+        // count, as they do in the recorded time
+        let pb_delta = best.map(|best| {
+            engine.summary(penalties).final_time().as_secs() as i64 - best.as_secs() as i64
+        });
+
+        match self.format {
+            OverlayFormat::Text => format!(
+                "Time: {}\nMines: {}\nProgress: {}%\nPB: {}\nStatus: {}\n",
+                seconds,
+                mines_left,
+                progress,
+                pb_delta.map_or("--".to_string(), |delta| format!("{:+}", delta)),
+                status
+            ),
+            OverlayFormat::Json => format!(
+                "{{\"time\":{},\"mines_left\":{},\"progress\":{},\"pb_delta\":{},\"status\":\"{}\"}}\n",
+                seconds,
+                mines_left,
+                progress,
+                pb_delta.map_or("null".to_string(), |delta| delta.to_string()),
+                status
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GridSize;
+
+    const NO_PENALTIES: Penalties = Penalties {
+        per_hint: Duration::ZERO,
+        per_undo: Duration::ZERO,
+    };
+
+    #[test]
+    fn test_render_json() {
+        let engine = Engine::new(GridSize::new(8, 8), 10);
+        let writer = OverlayWriter::new(PathBuf::from("overlay.json"), OverlayFormat::Json);
+
+        assert_eq!(
+            writer.render(&engine, NO_PENALTIES, None),
+            "{\"time\":0,\"mines_left\":10,\"progress\":0,\"pb_delta\":null,\"status\":\"playing\"}\n"
+        );
+    }
+
+    #[test]
+    fn test_pb_delta() {
+        let mut engine = Engine::new(GridSize::new(8, 8), 10);
+        engine.tick(Duration::from_secs(30));
+        let json = OverlayWriter::new(PathBuf::from("overlay.json"), OverlayFormat::Json);
+        let text = OverlayWriter::new(PathBuf::from("overlay.txt"), OverlayFormat::Text);
+
+        let best = Some(Duration::from_secs(42));
+        assert!(json
+            .render(&engine, NO_PENALTIES, best)
+            .contains("\"pb_delta\":-12,"));
+        assert!(text
+            .render(&engine, NO_PENALTIES, Some(Duration::from_secs(25)))
+            .contains("\nPB: +5\n"));
+        assert!(text
+            .render(&engine, NO_PENALTIES, None)
+            .contains("\nPB: --\n"));
+    }
+}
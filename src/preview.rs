@@ -0,0 +1,55 @@
+use super::game::{Engine, GridSize, Mark, Position, TileState};
+
+pub const PREVIEW_SIZE: GridSize = GridSize::new(8, 4);
+const PREVIEW_MINES: usize = 6;
+const PREVIEW_SEED: u64 = 7;
+
+// the miniature board the settings screen draws its preview with; always the same few
+// moves, so only the settings change between frames: an opening from the corner, a flag
+// on a mine and, when they are on, a question mark on a covered tile
+pub fn preview_engine(question_marks: bool) -> Engine {
+    let mut engine = Engine::with_seed(PREVIEW_SIZE, PREVIEW_MINES, PREVIEW_SEED);
+    engine.set_question_marks(question_marks);
+    engine.reveal(Position(0, 0));
+
+    let covered = |engine: &Engine, mine: bool| {
+        engine
+            .get_tiles()
+            .iter()
+            .position(|t| t.state == TileState::Block(Mark::Unmarked) && t.is_bomb() == mine)
+            .map(|i| Position::from_index(i, PREVIEW_SIZE.width))
+    };
+    if let Some(mine) = covered(&engine, true) {
+        engine.flag(mine);
+    }
+    if let Some(tile) = covered(&engine, false) {
+        engine.flag(tile);
+        engine.flag(tile);
+    }
+    engine
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preview_shows_every_kind_of_tile() {
+        let questioned = |engine: &Engine| {
+            engine
+                .get_tiles()
+                .iter()
+                .filter(|t| t.state == TileState::Block(Mark::Questioned))
+                .count()
+        };
+        let engine = preview_engine(true);
+        assert!(engine.is_in_progress());
+        assert!(engine.get_tiles().iter().any(|t| t.is_revealed()));
+        assert_eq!(engine.mines_left(), PREVIEW_MINES as i32 - 1);
+        assert_eq!(questioned(&engine), 1);
+
+        let engine = preview_engine(false);
+        assert_eq!(engine.mines_left(), PREVIEW_MINES as i32 - 1);
+        assert_eq!(questioned(&engine), 0);
+    }
+}
@@ -0,0 +1,143 @@
+use super::storage;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_PROFILE: &str = "player";
+pub const MAX_NAME_LEN: usize = 12;
+const ACTIVE_FILE: &str = "active";
+// the marker file lives next to the profile directories, and windows won't create a
+// directory named after a device
+const RESERVED_NAMES: [&str; 5] = [ACTIVE_FILE, "con", "prn", "aux", "nul"];
+const RESERVED_NUMBERED: [&str; 2] = ["com", "lpt"];
+
+// each profile is a directory, everything a player saves lives inside it
+pub struct Profiles {
+    data_dir: PathBuf,
+    root: PathBuf,
+    pub names: Vec<String>,
+    pub active: String,
+}
+
+impl Profiles {
+    pub fn load(data_dir: &Path) -> Self {
+        let root = data_dir.join("profiles");
+
+        let mut names = fs::read_dir(&root)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .filter(|entry| entry.path().is_dir())
+                    .filter_map(|entry| entry.file_name().into_string().ok())
+                    .filter(|name| is_valid_name(name))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        names.sort();
+
+        let active = fs::read_to_string(root.join(ACTIVE_FILE))
+            .map(|name| name.trim().to_string())
+            .ok()
+            .filter(|name| names.contains(name))
+            .unwrap_or_else(|| DEFAULT_PROFILE.to_string());
+
+        let mut profiles = Self {
+            data_dir: data_dir.to_path_buf(),
+            root,
+            names,
+            active,
+        };
+        if !profiles.names.contains(&profiles.active) {
+            // failing to create the directory only matters once something is saved
+            let _ = profiles.create(&profiles.active.clone());
+        }
+        profiles
+    }
+
+    // the folder the profiles sit in, for what belongs to the machine rather than a player
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+
+    pub fn active_dir(&self) -> PathBuf {
+        self.root.join(&self.active)
+    }
+
+    pub fn create(&mut self, name: &str) -> io::Result<()> {
+        if !is_valid_name(name) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid profile name '{}'", name),
+            ));
+        }
+        fs::create_dir_all(self.root.join(name))?;
+        if !self.names.iter().any(|n| n == name) {
+            self.names.push(name.to_string());
+            self.names.sort();
+        }
+        Ok(())
+    }
+
+    pub fn select(&mut self, name: &str) -> io::Result<()> {
+        if !self.names.iter().any(|n| n == name) {
+            self.create(name)?;
+        }
+        self.active = name.to_string();
+        storage::write_atomic(&self.root.join(ACTIVE_FILE), name)
+    }
+}
+
+// names double as directory names, so only allow characters and names that are safe
+// everywhere; case is ignored since some file systems do too
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        && !is_reserved(&name.to_ascii_lowercase())
+}
+
+fn is_reserved(name: &str) -> bool {
+    RESERVED_NAMES.contains(&name)
+        || RESERVED_NUMBERED.iter().any(|prefix| {
+            name.strip_prefix(prefix)
+                .is_some_and(|n| n.len() == 1 && n.as_bytes()[0].is_ascii_digit())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_names() {
+        assert!(is_valid_name("anna_2"));
+        assert!(!is_valid_name(""));
+        assert!(!is_valid_name("../escape"));
+        assert!(!is_valid_name("a_very_long_name"));
+        assert!(!is_valid_name(ACTIVE_FILE));
+        assert!(!is_valid_name("Active"));
+        assert!(!is_valid_name("CON"));
+        assert!(!is_valid_name("com1"));
+        assert!(is_valid_name("com"));
+        assert!(is_valid_name("console"));
+    }
+
+    #[test]
+    fn test_create_and_select() {
+        let data_dir =
+            std::env::temp_dir().join(format!("rust_sweeper_profiles_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&data_dir);
+        let mut profiles = Profiles::load(&data_dir);
+        assert_eq!(profiles.active, DEFAULT_PROFILE);
+
+        profiles.select("bob").unwrap();
+        let reloaded = Profiles::load(&data_dir);
+        assert_eq!(reloaded.active, "bob");
+        assert_eq!(reloaded.names, vec!["bob", DEFAULT_PROFILE]);
+        assert!(reloaded.active_dir().ends_with("profiles/bob"));
+
+        fs::remove_dir_all(&data_dir).unwrap();
+    }
+}
@@ -0,0 +1,178 @@
+use super::batch::TileBatch;
+use super::board::{BoardSettings, ScreenPoint};
+use super::config::TextureFilter;
+use super::draw::{self, Viewport, TILE_PX};
+use super::game::{Engine, GridSize};
+use super::sprites::SpriteManager;
+use ggez::graphics::{Canvas, Color, Image, ImageFormat};
+use ggez::Context;
+
+// draws only the board, one sprite pixel per screen pixel, into an offscreen image and
+// reads it back as RGBA rows; nothing has to be shown on screen
+pub fn render_board(
+    ctx: &mut Context,
+    engine: &Engine,
+    sprite_manager: &SpriteManager,
+) -> Result<(u32, u32, Vec<u8>), String> {
+    render_board_at(ctx, engine, sprite_manager, TILE_PX)
+}
+
+// the same at any tile size; sprites are smoothed once they are scaled
+pub fn render_board_at(
+    ctx: &mut Context,
+    engine: &Engine,
+    sprite_manager: &SpriteManager,
+    tile_px: f32,
+) -> Result<(u32, u32, Vec<u8>), String> {
+    let GridSize { width, height } = engine.size();
+    let board_settings = BoardSettings::builder()
+        .size(width, height)
+        .mines(engine.num_bombs())
+        .build()?;
+    let (image_w, image_h) = (
+        width as u32 * tile_px as u32,
+        height as u32 * tile_px as u32,
+    );
+    let viewport = Viewport {
+        screen_dim: (image_w as f32, image_h as f32),
+        hud_h: 0.0,
+        tile_size: (tile_px, tile_px),
+        origin: ScreenPoint::new(0.0, 0.0),
+        board_settings: &board_settings,
+        pressed_tile: None,
+        chord_tiles: &[],
+        cursor: None,
+        cascading: &[],
+    };
+
+    let image = Image::new_canvas_image(ctx, ImageFormat::Rgba8UnormSrgb, image_w, image_h, 1);
+    let mut canvas = Canvas::from_image(ctx, image.clone(), Color::BLACK);
+    draw::draw_tiles(
+        ctx,
+        &mut canvas,
+        &viewport,
+        engine,
+        sprite_manager,
+        if tile_px == TILE_PX {
+            TextureFilter::Nearest
+        } else {
+            TextureFilter::Linear
+        },
+        &mut TileBatch::new(),
+    );
+    canvas.finish(ctx).map_err(|e| e.to_string())?;
+    let pixels = image.to_pixels(ctx).map_err(|e| e.to_string())?;
+    Ok((image_w, image_h, pixels))
+}
+
+// binary PPM: no image crate needed, and most viewers and diff tools open it
+pub fn to_ppm(width: u32, height: u32, rgba: &[u8]) -> Vec<u8> {
+    let mut ppm = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    for pixel in rgba.chunks_exact(4) {
+        ppm.extend_from_slice(&pixel[..3]);
+    }
+    ppm
+}
+
+// PNG with stored deflate blocks: larger than a compressed one, but any viewer reads it
+// and no encoder is needed
+pub fn to_png(width: u32, height: u32, rgba: &[u8]) -> Vec<u8> {
+    // every row starts with filter type 0, none
+    let mut raw = Vec::with_capacity((width as usize * 4 + 1) * height as usize);
+    for row in rgba.chunks_exact((width as usize * 4).max(1)) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+
+    let mut zlib = vec![0x78, 0x01];
+    let blocks = raw.chunks(u16::MAX as usize).collect::<Vec<_>>();
+    if blocks.is_empty() {
+        zlib.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    for (i, block) in blocks.iter().enumerate() {
+        zlib.push((i + 1 == blocks.len()) as u8);
+        let len = block.len() as u16;
+        zlib.extend_from_slice(&len.to_le_bytes());
+        zlib.extend_from_slice(&(!len).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+
+    let mut header = Vec::new();
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // 8 bits per channel, RGBA, no interlacing
+    header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    push_chunk(&mut png, b"IHDR", &header);
+    push_chunk(&mut png, b"IDAT", &zlib);
+    push_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn push_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in bytes {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ppm_drops_alpha() {
+        let rgba = [255, 0, 0, 255, 0, 0, 255, 128];
+        assert_eq!(
+            to_ppm(2, 1, &rgba),
+            b"P6\n2 1\n255\n\xff\x00\x00\x00\x00\xff".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_png_chunks_and_checksums() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+
+        let rgba = [255, 0, 0, 255, 0, 0, 255, 128];
+        let png = to_png(2, 1, &rgba);
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(&png[16..24], &[0, 0, 0, 2, 0, 0, 0, 1]);
+        // the end chunk never changes
+        assert!(png.ends_with(&[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]));
+        // one stored block holding the filter byte and both pixels
+        let idat = &png[33 + 8..];
+        assert_eq!(&idat[..2], &[0x78, 0x01]);
+        assert_eq!(&idat[2..7], &[1, 9, 0, 0xf6, 0xff]);
+        assert_eq!(&idat[7..16], &[0, 255, 0, 0, 255, 0, 0, 255, 128]);
+    }
+}
@@ -0,0 +1,318 @@
+use super::board::BoardSettings;
+use super::crypto::{hmac_sha256, to_hex};
+use super::game::{Engine, Generation, GridSize, Move, MoveKind, Position};
+use super::storage::Format;
+use std::time::Duration;
+
+pub const REPLAY_FORMAT: Format = Format::new("replay", 2, &[without_draws]);
+
+// version 1 files predate the draws line and read as they are
+fn without_draws(body: &str) -> Result<String, String> {
+    Ok(body.to_string())
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Outcome {
+    Won,
+    Lost,
+    Unfinished,
+}
+
+impl Outcome {
+    pub fn of(engine: &Engine) -> Self {
+        if engine.is_won() {
+            Outcome::Won
+        } else if engine.is_lost() {
+            Outcome::Lost
+        } else {
+            Outcome::Unfinished
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Outcome::Won => "won",
+            Outcome::Lost => "lost",
+            Outcome::Unfinished => "unfinished",
+        }
+    }
+}
+
+// a seed plus the timestamped move list reproduces a game exactly
+#[derive(Clone, PartialEq, Debug)]
+pub struct Replay {
+    pub width: usize,
+    pub height: usize,
+    pub num_bombs: usize,
+    pub seed: u64,
+    pub generation: Generation,
+    // the tile indices the seed produced while placing mines, so a disputed board can
+    // be checked draw by draw; empty when no mines were placed or the file predates it
+    pub draws: Vec<usize>,
+    pub time: Duration,
+    pub outcome: Outcome,
+    pub moves: Vec<Move>,
+    // a keyed checksum of the rest of the file, see add_checksum
+    pub checksum: Option<String>,
+}
+
+impl Replay {
+    pub fn from_engine(engine: &Engine) -> Self {
+        let GridSize { width, height } = engine.size();
+        Self {
+            width,
+            height,
+            num_bombs: engine.num_bombs(),
+            seed: engine.seed(),
+            generation: engine.generation(),
+            draws: engine.generation_draws().to_vec(),
+            time: engine.elapsed(),
+            outcome: Outcome::of(engine),
+            moves: engine.moves().to_vec(),
+            checksum: None,
+        }
+    }
+
+    // the checksummed part of the file: everything but the checksum line
+    pub fn body(&self) -> String {
+        let mut text = format!(
+            "{}\nboard {} {} {}\nseed {}\ntime {}\nresult {}\n",
+            REPLAY_FORMAT.header(),
+            self.width,
+            self.height,
+            self.num_bombs,
+            self.seed,
+            self.time.as_millis(),
+            self.outcome.as_str()
+        );
+        // plain boards leave the line out, as every file before it did
+        if self.generation != Generation::Plain {
+            text.push_str(&format!("generation {}\n", self.generation.key()));
+        }
+        if !self.draws.is_empty() {
+            let draws = self.draws.iter().map(|d| d.to_string()).collect::<Vec<_>>();
+            text.push_str(&format!("draws {}\n", draws.join(" ")));
+        }
+        for m in &self.moves {
+            text.push_str(&move_line(m));
+        }
+        text
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = self.body();
+        if let Some(checksum) = &self.checksum {
+            text.push_str(&format!("checksum {}\n", checksum));
+        }
+        text
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let body = REPLAY_FORMAT.read(text)?;
+        let lines = body.lines().filter(|line| !line.trim().is_empty());
+
+        let mut board = None;
+        let mut seed = None;
+        let mut generation = Generation::Plain;
+        let mut draws = Vec::new();
+        let mut time = None;
+        let mut outcome = None;
+        let mut moves = Vec::new();
+        let mut checksum = None;
+
+        for line in lines {
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            match fields.as_slice() {
+                ["board", w, h, bombs] => {
+                    board = Some((parse_num(w)?, parse_num(h)?, parse_num(bombs)?));
+                }
+                ["seed", s] => seed = Some(parse_num(s)?),
+                ["generation", key] => {
+                    generation = Generation::from_key(key)
+                        .ok_or_else(|| format!("unknown generation '{}'", key))?;
+                }
+                ["draws", indices @ ..] => {
+                    draws = indices
+                        .iter()
+                        .map(|d| parse_num(d))
+                        .collect::<Result<_, _>>()?;
+                }
+                ["time", ms] => time = Some(Duration::from_millis(parse_num(ms)?)),
+                ["result", result] => {
+                    outcome = Some(match *result {
+                        "won" => Outcome::Won,
+                        "lost" => Outcome::Lost,
+                        "unfinished" => Outcome::Unfinished,
+                        _ => return Err(format!("unknown result '{}'", result)),
+                    });
+                }
+                ["move", ms, kind, x, y] => {
+                    let kind = match *kind {
+                        "reveal" => MoveKind::Reveal,
+                        "flag" => MoveKind::Flag,
+                        _ => return Err(format!("unknown move '{}'", kind)),
+                    };
+                    moves.push(Move {
+                        kind,
+                        pos: Position(parse_num(x)?, parse_num(y)?),
+                        time: Duration::from_millis(parse_num(ms)?),
+                    });
+                }
+                // files written before the rename call it a signature
+                ["checksum" | "signature", sum] => checksum = Some(sum.to_string()),
+                _ => return Err(format!("unexpected line '{}'", line)),
+            }
+        }
+
+        let (width, height, num_bombs) = board.ok_or("missing board line")?;
+        let replay = Self {
+            width,
+            height,
+            num_bombs,
+            seed: seed.ok_or("missing seed line")?,
+            generation,
+            draws,
+            time: time.ok_or("missing time line")?,
+            outcome: outcome.ok_or("missing result line")?,
+            moves,
+            checksum,
+        };
+        // the board is rebuilt from these on load, so they get the same checks as a custom board
+        replay.board_settings()?;
+        Ok(replay)
+    }
+
+    pub fn board_settings(&self) -> Result<BoardSettings, String> {
+        BoardSettings::builder()
+            .size(self.width, self.height)
+            .mines(self.num_bombs)
+            .build()
+    }
+
+    // an HMAC keyed with a key the player holds too, so it catches a damaged or hand edited
+    // file but anyone with the key can forge one
+    pub fn add_checksum(&mut self, key: &[u8]) {
+        self.checksum = Some(to_hex(&hmac_sha256(key, self.body().as_bytes())));
+    }
+
+    pub fn has_valid_checksum(&self, key: &[u8]) -> bool {
+        self.checksum.as_deref() == Some(&to_hex(&hmac_sha256(key, self.body().as_bytes())))
+    }
+
+    // the board the replay was played on, before any move
+    pub fn fresh_engine(&self) -> Engine {
+        Engine::with_generation(
+            GridSize::new(self.width, self.height),
+            self.num_bombs,
+            self.seed,
+            self.generation,
+        )
+    }
+
+    // replays every move against a fresh engine built from the same seed
+    pub fn simulate(&self) -> Result<Engine, String> {
+        // the fields are public, so a replay built in code is checked here too
+        self.board_settings()?;
+        let mut engine = self.fresh_engine();
+
+        for m in &self.moves {
+            let in_bounds = m.pos.0 >= 0
+                && m.pos.1 >= 0
+                && (m.pos.0 as usize) < self.width
+                && (m.pos.1 as usize) < self.height;
+            if !in_bounds {
+                return Err(format!("move outside the board at {:?}", m.pos));
+            }
+            if m.time < engine.elapsed() {
+                return Err("moves are not in chronological order".to_string());
+            }
+
+            engine.tick(m.time - engine.elapsed());
+            engine.replay_move(m);
+        }
+
+        engine.tick(self.time.saturating_sub(engine.elapsed()));
+        Ok(engine)
+    }
+}
+
+pub fn move_line(m: &Move) -> String {
+    let kind = match m.kind {
+        MoveKind::Reveal => "reveal",
+        MoveKind::Flag => "flag",
+    };
+    format!(
+        "move {} {} {} {}\n",
+        m.time.as_millis(),
+        kind,
+        m.pos.0,
+        m.pos.1
+    )
+}
+
+fn parse_num<T: std::str::FromStr>(field: &str) -> Result<T, String> {
+    field
+        .parse()
+        .map_err(|_| format!("invalid number '{}'", field))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn played_engine() -> Engine {
+        let mut engine =
+            Engine::with_generation(GridSize::new(8, 8), 10, 42, Generation::AntiFiftyFifty);
+        engine.tick(Duration::from_millis(800));
+        engine.reveal(Position(3, 3));
+        engine.tick(Duration::from_millis(1200));
+        engine.flag(Position(0, 0));
+        engine.tick(Duration::from_millis(500));
+        engine
+    }
+
+    #[test]
+    fn test_text_round_trip() {
+        let mut replay = Replay::from_engine(&played_engine());
+        replay.add_checksum(b"key");
+
+        let parsed = Replay::parse(&replay.to_text()).unwrap();
+        assert_eq!(parsed, replay);
+        assert!(parsed.has_valid_checksum(b"key"));
+        assert!(!parsed.has_valid_checksum(b"other key"));
+    }
+
+    #[test]
+    fn test_parse_rejects_impossible_boards() {
+        let text = Replay::from_engine(&played_engine()).to_text();
+        for board in [
+            "board 3 3 1",
+            "board 0 8 10",
+            "board 8 8 60",
+            "board 100000 100000 10",
+        ] {
+            let crafted = text.replace("board 8 8 10", board);
+            assert!(Replay::parse(&crafted).is_err(), "{}", board);
+        }
+
+        let mut replay = Replay::from_engine(&played_engine());
+        replay.width = 0;
+        assert!(replay.simulate().is_err());
+    }
+
+    #[test]
+    fn test_simulate_reproduces_game() {
+        let engine = played_engine();
+        let replayed = Replay::from_engine(&engine).simulate().unwrap();
+
+        assert_eq!(replayed.elapsed(), engine.elapsed());
+        assert_eq!(replayed.moves(), engine.moves());
+        let states = |e: &Engine| {
+            e.get_tiles()
+                .iter()
+                .map(|t| t.state.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(states(&replayed), states(&engine));
+    }
+}
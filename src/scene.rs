@@ -0,0 +1,904 @@
+use super::config::HudElement;
+use super::custom::{MAX_DENSITY, MIN_DENSITY};
+use super::mixer::Bus;
+use super::trainer::GameStats;
+use ggez::graphics::Rect;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Scene {
+    Title,
+    Game,
+    Profiles,
+    Calendar,
+    // picks the size and mine density of a custom board
+    Custom,
+    // the options kept in the config file, from the title or the pause menu
+    Settings,
+    // a few questions over the title screen the first time the game is started
+    Onboarding,
+    // overlays keep drawing the scene underneath them
+    QuitConfirm,
+    // casual rules offer to take back the reveal that hit a mine
+    UndoPrompt,
+    // the clock is stopped and the board hidden until the next input
+    Paused,
+    // escape during a game: the clock stopped under a dimmed board and a few choices
+    PauseMenu,
+    // the board is shown but ignores input until the count reaches zero
+    Countdown,
+    // the `:` command bar, typed into while the board stays live
+    Command,
+    // a finished game's board at any point of its timeline
+    Analysis,
+    Stats,
+    // the best times on the standard boards
+    Scores,
+    // a win that made the high score table, waiting for a name
+    NameEntry,
+    // the finished game's numbers over its board, with the way on to the next one
+    GameOver,
+}
+
+impl Scene {
+    pub fn is_overlay(&self) -> bool {
+        matches!(
+            self,
+            Scene::QuitConfirm
+                | Scene::UndoPrompt
+                | Scene::Paused
+                | Scene::PauseMenu
+                | Scene::Countdown
+                | Scene::Command
+                | Scene::NameEntry
+                | Scene::GameOver
+        )
+    }
+
+    // scenes made of buttons, which the keyboard and gamepad move a focus over
+    pub fn is_menu(&self) -> bool {
+        matches!(
+            self,
+            Scene::Title
+                | Scene::Profiles
+                | Scene::Calendar
+                | Scene::Custom
+                | Scene::Settings
+                | Scene::Onboarding
+                | Scene::Stats
+                | Scene::Scores
+                | Scene::QuitConfirm
+                | Scene::UndoPrompt
+                | Scene::PauseMenu
+                | Scene::GameOver
+        )
+    }
+}
+
+// which of a menu's buttons the keyboard or gamepad is on; nothing is focused until
+// the first step, so mouse players never see it
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct Focus(Option<usize>);
+
+impl Focus {
+    // wraps around at both ends
+    pub fn step(&mut self, step: i32, len: usize) {
+        if len == 0 {
+            self.0 = None;
+            return;
+        }
+        let index = match self.0 {
+            Some(index) => (index as i32 + step).rem_euclid(len as i32) as usize,
+            None if step < 0 => len - 1,
+            None => 0,
+        };
+        self.0 = Some(index);
+    }
+
+    pub fn get<T: Copy>(&self, items: &[T]) -> Option<T> {
+        items.get(self.0?).copied()
+    }
+}
+
+fn focused_rect<T: PartialEq>(buttons: &[(T, Rect)], focused: Option<T>) -> Option<Rect> {
+    let focused = focused?;
+    buttons
+        .iter()
+        .find(|(option, _)| *option == focused)
+        .map(|(_, rect)| *rect)
+}
+
+// the top scene receives input and is drawn; the bottom one is never popped
+pub struct SceneStack {
+    scenes: Vec<Scene>,
+}
+
+impl SceneStack {
+    pub fn new(initial: Scene) -> Self {
+        Self {
+            scenes: vec![initial],
+        }
+    }
+
+    pub fn top(&self) -> Scene {
+        *self.scenes.last().unwrap()
+    }
+
+    // the first non-overlay scene from the top, which overlays draw over
+    pub fn base(&self) -> Scene {
+        *self
+            .scenes
+            .iter()
+            .rev()
+            .find(|scene| !scene.is_overlay())
+            .unwrap()
+    }
+
+    pub fn contains(&self, scene: Scene) -> bool {
+        self.scenes.contains(&scene)
+    }
+
+    pub fn push(&mut self, scene: Scene) {
+        self.scenes.push(scene);
+    }
+
+    pub fn pop(&mut self) -> Option<Scene> {
+        if self.scenes.len() > 1 {
+            self.scenes.pop()
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TitleOption {
+    Continue,
+    NewGame,
+    Custom,
+    Daily,
+    Calendar,
+    Stats,
+    Scores,
+    Settings,
+    Profile,
+    Quit,
+}
+
+impl TitleOption {
+    pub const ALL: [TitleOption; 10] = [
+        TitleOption::Continue,
+        TitleOption::NewGame,
+        TitleOption::Custom,
+        TitleOption::Daily,
+        TitleOption::Calendar,
+        TitleOption::Stats,
+        TitleOption::Scores,
+        TitleOption::Settings,
+        TitleOption::Profile,
+        TitleOption::Quit,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TitleOption::Continue => "CONTINUE",
+            TitleOption::NewGame => "NEW GAME",
+            TitleOption::Custom => "CUSTOM",
+            TitleOption::Daily => "DAILY",
+            TitleOption::Calendar => "CALENDAR",
+            TitleOption::Stats => "STATS",
+            TitleOption::Scores => "SCORES",
+            TitleOption::Settings => "SETTINGS",
+            TitleOption::Profile => "PROFILE",
+            TitleOption::Quit => "QUIT",
+        }
+    }
+}
+
+pub struct TitleState {
+    pub buttons: Vec<(TitleOption, Rect)>,
+    pub can_continue: bool,
+    focus: Focus,
+}
+
+impl TitleState {
+    pub fn new() -> Self {
+        Self {
+            buttons: Vec::new(),
+            can_continue: false,
+            focus: Focus::default(),
+        }
+    }
+
+    // disabled buttons are skipped
+    fn focusable(&self) -> Vec<TitleOption> {
+        TitleOption::ALL
+            .into_iter()
+            .filter(|option| self.is_enabled(*option))
+            .collect()
+    }
+
+    pub fn move_focus(&mut self, step: i32) {
+        self.focus.step(step, self.focusable().len());
+    }
+
+    pub fn focused(&self) -> Option<TitleOption> {
+        self.focus.get(&self.focusable())
+    }
+
+    pub fn focused_rect(&self) -> Option<Rect> {
+        focused_rect(&self.buttons, self.focused())
+    }
+
+    pub fn is_enabled(&self, option: TitleOption) -> bool {
+        match option {
+            TitleOption::Continue => self.can_continue,
+            _ => true,
+        }
+    }
+
+    pub fn get_option_clicked(&self, x: f32, y: f32) -> Option<TitleOption> {
+        self.buttons
+            .iter()
+            .find(|(option, rect)| rect.contains([x, y]) && self.is_enabled(*option))
+            .map(|(option, _)| *option)
+    }
+}
+
+pub struct ConfirmState {
+    pub yes_rect: Option<Rect>,
+    pub no_rect: Option<Rect>,
+    focus: Focus,
+}
+
+impl ConfirmState {
+    const ANSWERS: [bool; 2] = [true, false];
+
+    pub fn new() -> Self {
+        Self {
+            yes_rect: None,
+            no_rect: None,
+            focus: Focus::default(),
+        }
+    }
+
+    pub fn move_focus(&mut self, step: i32) {
+        self.focus.step(step, Self::ANSWERS.len());
+    }
+
+    pub fn focused(&self) -> Option<bool> {
+        self.focus.get(&Self::ANSWERS)
+    }
+
+    pub fn focused_rect(&self) -> Option<Rect> {
+        if self.focused()? {
+            self.yes_rect
+        } else {
+            self.no_rect
+        }
+    }
+
+    // Some(true) for yes, Some(false) for no, None when neither was clicked
+    pub fn get_answer_clicked(&self, x: f32, y: f32) -> Option<bool> {
+        if let Some(rect) = self.yes_rect {
+            if rect.contains([x, y]) {
+                return Some(true);
+            }
+        }
+
+        if let Some(rect) = self.no_rect {
+            if rect.contains([x, y]) {
+                return Some(false);
+            }
+        }
+
+        None
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PauseOption {
+    Resume,
+    Restart,
+    Settings,
+    // back to the title screen, where the game can be continued
+    Quit,
+}
+
+impl PauseOption {
+    pub const ALL: [PauseOption; 4] = [
+        PauseOption::Resume,
+        PauseOption::Restart,
+        PauseOption::Settings,
+        PauseOption::Quit,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PauseOption::Resume => "RESUME",
+            PauseOption::Restart => "RESTART",
+            PauseOption::Settings => "SETTINGS",
+            PauseOption::Quit => "QUIT TO TITLE",
+        }
+    }
+}
+
+pub struct PauseState {
+    pub buttons: Vec<(PauseOption, Rect)>,
+    focus: Focus,
+}
+
+impl PauseState {
+    pub fn new() -> Self {
+        Self {
+            buttons: Vec::new(),
+            focus: Focus::default(),
+        }
+    }
+
+    pub fn move_focus(&mut self, step: i32) {
+        self.focus.step(step, PauseOption::ALL.len());
+    }
+
+    pub fn focused(&self) -> Option<PauseOption> {
+        self.focus.get(&PauseOption::ALL)
+    }
+
+    pub fn focused_rect(&self) -> Option<Rect> {
+        focused_rect(&self.buttons, self.focused())
+    }
+
+    pub fn get_option_clicked(&self, x: f32, y: f32) -> Option<PauseOption> {
+        self.buttons
+            .iter()
+            .find(|(_, rect)| rect.contains([x, y]))
+            .map(|(option, _)| *option)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GameOverOption {
+    PlayAgain,
+    // opens the mode list on the game screen
+    ChangeDifficulty,
+}
+
+impl GameOverOption {
+    pub const ALL: [GameOverOption; 2] =
+        [GameOverOption::PlayAgain, GameOverOption::ChangeDifficulty];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            GameOverOption::PlayAgain => "PLAY AGAIN",
+            GameOverOption::ChangeDifficulty => "CHANGE DIFFICULTY",
+        }
+    }
+}
+
+pub struct GameOverState {
+    pub stats: GameStats,
+    pub buttons: Vec<(GameOverOption, Rect)>,
+    focus: Focus,
+}
+
+impl GameOverState {
+    pub fn new(stats: GameStats) -> Self {
+        Self {
+            stats,
+            buttons: Vec::new(),
+            focus: Focus::default(),
+        }
+    }
+
+    pub fn move_focus(&mut self, step: i32) {
+        self.focus.step(step, GameOverOption::ALL.len());
+    }
+
+    pub fn focused(&self) -> Option<GameOverOption> {
+        self.focus.get(&GameOverOption::ALL)
+    }
+
+    pub fn focused_rect(&self) -> Option<Rect> {
+        focused_rect(&self.buttons, self.focused())
+    }
+
+    pub fn get_option_clicked(&self, x: f32, y: f32) -> Option<GameOverOption> {
+        self.buttons
+            .iter()
+            .find(|(_, rect)| rect.contains([x, y]))
+            .map(|(option, _)| *option)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SettingsOption {
+    Sounds,
+    // steps the bus's volume
+    Volume(Bus),
+    PaceCue,
+    // how often the counters are read aloud
+    Announce,
+    QuestionMarks,
+    SafeFirstClick,
+    LeftHanded,
+    // standard or casual, which offers to undo the move that lost
+    LossPolicy,
+    AutoChord,
+    AutoFlag,
+    // off, a second click or a dwell before a proven mine can be revealed
+    MisclickGuard,
+    // cycles the theme presets
+    Theme,
+    // shows or hides a part of the HUD
+    Hud(HudElement),
+    Back,
+}
+
+impl SettingsOption {
+    pub const ALL: [SettingsOption; 21] = [
+        SettingsOption::Sounds,
+        SettingsOption::Volume(Bus::Ui),
+        SettingsOption::Volume(Bus::Game),
+        SettingsOption::Volume(Bus::Music),
+        SettingsOption::PaceCue,
+        SettingsOption::Announce,
+        SettingsOption::QuestionMarks,
+        SettingsOption::SafeFirstClick,
+        SettingsOption::LeftHanded,
+        SettingsOption::LossPolicy,
+        SettingsOption::AutoChord,
+        SettingsOption::AutoFlag,
+        SettingsOption::MisclickGuard,
+        SettingsOption::Theme,
+        SettingsOption::Hud(HudElement::Timer),
+        SettingsOption::Hud(HudElement::BombCounter),
+        SettingsOption::Hud(HudElement::DifficultyButtons),
+        SettingsOption::Hud(HudElement::ProgressBar),
+        SettingsOption::Hud(HudElement::HintButton),
+        SettingsOption::Hud(HudElement::GuessNotice),
+        SettingsOption::Back,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SettingsOption::Sounds => "SOUNDS",
+            SettingsOption::Volume(Bus::Ui) => "UI VOLUME",
+            SettingsOption::Volume(Bus::Game) => "GAME VOLUME",
+            SettingsOption::Volume(Bus::Music) => "MUSIC VOLUME",
+            SettingsOption::PaceCue => "PACE CUE",
+            SettingsOption::Announce => "READ COUNTERS",
+            SettingsOption::QuestionMarks => "QUESTION MARKS",
+            SettingsOption::SafeFirstClick => "SAFE FIRST CLICK",
+            SettingsOption::LeftHanded => "LEFT HANDED",
+            SettingsOption::LossPolicy => "ON LOSS",
+            SettingsOption::AutoChord => "AUTO CHORD",
+            SettingsOption::AutoFlag => "AUTO FLAG",
+            SettingsOption::MisclickGuard => "MINE GUARD",
+            SettingsOption::Theme => "THEME",
+            SettingsOption::Hud(HudElement::Timer) => "TIMER",
+            SettingsOption::Hud(HudElement::BombCounter) => "MINE COUNTER",
+            SettingsOption::Hud(HudElement::DifficultyButtons) => "MODE BUTTON",
+            SettingsOption::Hud(HudElement::ProgressBar) => "PROGRESS BAR",
+            SettingsOption::Hud(HudElement::HintButton) => "HINT BUTTON",
+            SettingsOption::Hud(HudElement::GuessNotice) => "GUESS NOTICE",
+            SettingsOption::Back => "BACK",
+        }
+    }
+}
+
+pub struct SettingsState {
+    pub buttons: Vec<(SettingsOption, Rect)>,
+    // the option under the pointer, whose next value the preview shows
+    pub hovered: Option<SettingsOption>,
+    focus: Focus,
+}
+
+impl SettingsState {
+    pub fn new() -> Self {
+        Self {
+            buttons: Vec::new(),
+            hovered: None,
+            focus: Focus::default(),
+        }
+    }
+
+    // the pointer wins over the keyboard focus
+    pub fn previewed(&self) -> Option<SettingsOption> {
+        self.hovered.or(self.focused())
+    }
+
+    pub fn move_focus(&mut self, step: i32) {
+        self.focus.step(step, SettingsOption::ALL.len());
+    }
+
+    pub fn focused(&self) -> Option<SettingsOption> {
+        self.focus.get(&SettingsOption::ALL)
+    }
+
+    pub fn focused_rect(&self) -> Option<Rect> {
+        focused_rect(&self.buttons, self.focused())
+    }
+
+    pub fn get_option_clicked(&self, x: f32, y: f32) -> Option<SettingsOption> {
+        self.buttons
+            .iter()
+            .find(|(_, rect)| rect.contains([x, y]))
+            .map(|(option, _)| *option)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ProfileOption {
+    // index into the profile list
+    Select(usize),
+    New,
+    Back,
+}
+
+pub struct ProfileState {
+    pub buttons: Vec<(ProfileOption, Rect)>,
+    // Some while the player is typing a new profile name
+    pub new_name: Option<String>,
+    focus: Focus,
+}
+
+impl ProfileState {
+    pub fn new() -> Self {
+        Self {
+            buttons: Vec::new(),
+            new_name: None,
+            focus: Focus::default(),
+        }
+    }
+
+    pub fn move_focus(&mut self, step: i32) {
+        self.focus.step(step, self.buttons.len());
+    }
+
+    pub fn focused(&self) -> Option<ProfileOption> {
+        let options: Vec<ProfileOption> = self.buttons.iter().map(|(option, _)| *option).collect();
+        self.focus.get(&options)
+    }
+
+    pub fn focused_rect(&self) -> Option<Rect> {
+        focused_rect(&self.buttons, self.focused())
+    }
+
+    pub fn get_option_clicked(&self, x: f32, y: f32) -> Option<ProfileOption> {
+        self.buttons
+            .iter()
+            .find(|(_, rect)| rect.contains([x, y]))
+            .map(|(option, _)| *option)
+    }
+}
+
+// the stats screen's only button is the way back
+pub struct StatsState {
+    pub back_rect: Option<Rect>,
+    focus: Focus,
+}
+
+impl StatsState {
+    pub fn new() -> Self {
+        Self {
+            back_rect: None,
+            focus: Focus::default(),
+        }
+    }
+
+    pub fn move_focus(&mut self, step: i32) {
+        self.focus.step(step, 1);
+    }
+
+    pub fn is_back_focused(&self) -> bool {
+        self.focus.get(&[()]).is_some()
+    }
+
+    pub fn focused_rect(&self) -> Option<Rect> {
+        self.back_rect.filter(|_| self.is_back_focused())
+    }
+
+    pub fn is_back_clicked(&self, x: f32, y: f32) -> bool {
+        self.back_rect.is_some_and(|rect| rect.contains([x, y]))
+    }
+}
+
+// the high score screen has nothing to press but the way back either
+pub struct ScoresState {
+    pub back_rect: Option<Rect>,
+    focus: Focus,
+}
+
+impl ScoresState {
+    pub fn new() -> Self {
+        Self {
+            back_rect: None,
+            focus: Focus::default(),
+        }
+    }
+
+    pub fn move_focus(&mut self, step: i32) {
+        self.focus.step(step, 1);
+    }
+
+    pub fn is_back_focused(&self) -> bool {
+        self.focus.get(&[()]).is_some()
+    }
+
+    pub fn focused_rect(&self) -> Option<Rect> {
+        self.back_rect.filter(|_| self.is_back_focused())
+    }
+
+    pub fn is_back_clicked(&self, x: f32, y: f32) -> bool {
+        self.back_rect.is_some_and(|rect| rect.contains([x, y]))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CalendarOption {
+    Weekly,
+    Back,
+}
+
+pub struct CalendarState {
+    pub weekly_rect: Option<Rect>,
+    pub back_rect: Option<Rect>,
+    focus: Focus,
+}
+
+impl CalendarState {
+    const OPTIONS: [CalendarOption; 2] = [CalendarOption::Weekly, CalendarOption::Back];
+
+    pub fn new() -> Self {
+        Self {
+            weekly_rect: None,
+            back_rect: None,
+            focus: Focus::default(),
+        }
+    }
+
+    pub fn move_focus(&mut self, step: i32) {
+        self.focus.step(step, Self::OPTIONS.len());
+    }
+
+    pub fn focused(&self) -> Option<CalendarOption> {
+        self.focus.get(&Self::OPTIONS)
+    }
+
+    pub fn focused_rect(&self) -> Option<Rect> {
+        match self.focused()? {
+            CalendarOption::Weekly => self.weekly_rect,
+            CalendarOption::Back => self.back_rect,
+        }
+    }
+
+    pub fn is_weekly_clicked(&self, x: f32, y: f32) -> bool {
+        self.weekly_rect.is_some_and(|rect| rect.contains([x, y]))
+    }
+
+    pub fn is_back_clicked(&self, x: f32, y: f32) -> bool {
+        self.back_rect.is_some_and(|rect| rect.contains([x, y]))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CustomOption {
+    // width and height steps
+    Resize(i32, i32),
+    // cycles how free play boards lay out their mines
+    Layout,
+    Start,
+    Back,
+}
+
+pub struct CustomState {
+    pub buttons: Vec<(CustomOption, Rect)>,
+    pub slider_rect: Option<Rect>,
+    focus: Focus,
+}
+
+impl CustomState {
+    pub fn new() -> Self {
+        Self {
+            buttons: Vec::new(),
+            slider_rect: None,
+            focus: Focus::default(),
+        }
+    }
+
+    pub fn move_focus(&mut self, step: i32) {
+        self.focus.step(step, self.buttons.len());
+    }
+
+    pub fn focused(&self) -> Option<CustomOption> {
+        let options: Vec<CustomOption> = self.buttons.iter().map(|(option, _)| *option).collect();
+        self.focus.get(&options)
+    }
+
+    pub fn focused_rect(&self) -> Option<Rect> {
+        focused_rect(&self.buttons, self.focused())
+    }
+
+    pub fn get_option_clicked(&self, x: f32, y: f32) -> Option<CustomOption> {
+        self.buttons
+            .iter()
+            .find(|(_, rect)| rect.contains([x, y]))
+            .map(|(option, _)| *option)
+    }
+
+    // the density under the cursor when it is on the slider track
+    pub fn get_density_clicked(&self, x: f32, y: f32) -> Option<u32> {
+        let rect = self.slider_rect.filter(|rect| rect.contains([x, y]))?;
+        let t = (x - rect.x) / rect.w;
+        let span = (MAX_DENSITY - MIN_DENSITY) as f32;
+        Some(MIN_DENSITY + (t * span).round() as u32)
+    }
+}
+
+// the questions asked on the first run, in order
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum OnboardingStep {
+    Handedness,
+    Input,
+    Theme,
+    Difficulty,
+}
+
+impl OnboardingStep {
+    pub const ALL: [OnboardingStep; 4] = [
+        OnboardingStep::Handedness,
+        OnboardingStep::Input,
+        OnboardingStep::Theme,
+        OnboardingStep::Difficulty,
+    ];
+
+    pub fn question(&self) -> &'static str {
+        match self {
+            OnboardingStep::Handedness => "WHICH HAND IS ON THE MOUSE?",
+            OnboardingStep::Input => "HOW DO YOU PLAY?",
+            OnboardingStep::Theme => "PICK A THEME",
+            OnboardingStep::Difficulty => "WHERE DO YOU START?",
+        }
+    }
+
+    // the answers, in the order the config values they stand for are listed
+    pub fn choices(&self) -> &'static [&'static str] {
+        match self {
+            OnboardingStep::Handedness => &["RIGHT", "LEFT"],
+            OnboardingStep::Input => &["MOUSE", "TOUCHSCREEN"],
+            OnboardingStep::Theme => &["CLASSIC", "DARK", "FLAT"],
+            OnboardingStep::Difficulty => &["EASY", "MEDIUM", "HARD"],
+        }
+    }
+}
+
+pub struct OnboardingState {
+    pub step: OnboardingStep,
+    // index into the step's choices
+    pub buttons: Vec<(usize, Rect)>,
+    focus: Focus,
+}
+
+impl OnboardingState {
+    pub fn new() -> Self {
+        Self {
+            step: OnboardingStep::Handedness,
+            buttons: Vec::new(),
+            focus: Focus::default(),
+        }
+    }
+
+    // moves on to the next question; false once the last one is answered
+    pub fn advance(&mut self) -> bool {
+        let i = OnboardingStep::ALL
+            .iter()
+            .position(|step| *step == self.step)
+            .unwrap_or(0);
+        self.focus = Focus::default();
+        match OnboardingStep::ALL.get(i + 1) {
+            Some(step) => {
+                self.step = *step;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn move_focus(&mut self, step: i32) {
+        self.focus.step(step, self.step.choices().len());
+    }
+
+    pub fn focused(&self) -> Option<usize> {
+        let choices: Vec<usize> = (0..self.step.choices().len()).collect();
+        self.focus.get(&choices)
+    }
+
+    pub fn focused_rect(&self) -> Option<Rect> {
+        focused_rect(&self.buttons, self.focused())
+    }
+
+    pub fn get_choice_clicked(&self, x: f32, y: f32) -> Option<usize> {
+        self.buttons
+            .iter()
+            .find(|(_, rect)| rect.contains([x, y]))
+            .map(|(choice, _)| *choice)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_focus_wraps() {
+        let items = ['a', 'b', 'c'];
+        let mut focus = Focus::default();
+        assert_eq!(focus.get(&items), None);
+        focus.step(-1, items.len());
+        assert_eq!(focus.get(&items), Some('c'));
+        focus.step(1, items.len());
+        assert_eq!(focus.get(&items), Some('a'));
+        focus.step(0, 0);
+        assert_eq!(focus.get(&items), None);
+    }
+
+    #[test]
+    fn test_title_focus_skips_disabled() {
+        let mut title = TitleState::new();
+        title.move_focus(1);
+        assert_eq!(title.focused(), Some(TitleOption::NewGame));
+        title.move_focus(-1);
+        assert_eq!(title.focused(), Some(TitleOption::Quit));
+    }
+
+    #[test]
+    fn test_onboarding_walks_every_step_once() {
+        let mut onboarding = OnboardingState::new();
+        let mut asked = vec![onboarding.step];
+        onboarding.move_focus(1);
+        while onboarding.advance() {
+            assert_eq!(onboarding.focused(), None);
+            asked.push(onboarding.step);
+        }
+        assert_eq!(asked, OnboardingStep::ALL);
+        assert!(!onboarding.advance());
+    }
+
+    #[test]
+    fn test_pause_menu_sits_over_the_game() {
+        let mut scenes = SceneStack::new(Scene::Title);
+        scenes.push(Scene::Game);
+        scenes.push(Scene::PauseMenu);
+        assert_eq!(scenes.base(), Scene::Game);
+        assert!(scenes.top().is_menu());
+
+        let mut pause = PauseState::new();
+        pause.move_focus(1);
+        pause.move_focus(1);
+        assert_eq!(pause.focused(), Some(PauseOption::Restart));
+        pause.move_focus(1);
+        assert_eq!(pause.focused(), Some(PauseOption::Settings));
+        pause.move_focus(1);
+        assert_eq!(pause.focused(), Some(PauseOption::Quit));
+    }
+
+    #[test]
+    fn test_game_over_panel_sits_over_the_board() {
+        let mut scenes = SceneStack::new(Scene::Title);
+        scenes.push(Scene::Game);
+        scenes.push(Scene::GameOver);
+        assert_eq!(scenes.base(), Scene::Game);
+        assert!(scenes.top().is_menu());
+
+        let mut game_over = GameOverState::new(GameStats {
+            won: false,
+            time: std::time::Duration::from_secs(12),
+            three_bv: 30,
+            clicks: 8,
+            efficiency: None,
+        });
+        game_over.move_focus(-1);
+        assert_eq!(game_over.focused(), Some(GameOverOption::ChangeDifficulty));
+        game_over.move_focus(1);
+        assert_eq!(game_over.focused(), Some(GameOverOption::PlayAgain));
+    }
+}
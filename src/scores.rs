@@ -0,0 +1,124 @@
+use super::leaderboard::{Category, Leaderboard};
+use std::time::Duration;
+
+// the boards the high score table covers, one column each from the left
+pub const BOARDS: [&str; 3] = ["easy", "medium", "hard"];
+// places shown per board; a run that lands in one is asked for a name
+pub const PLACES: usize = 5;
+const MAX_NAME_LEN: usize = 8;
+
+// one board's places, fastest first; an unnamed run has an empty name
+pub struct Column {
+    pub board: &'static str,
+    pub places: Vec<(String, Duration)>,
+}
+
+// the table under the player's current rules, as category builds it for a board
+pub fn columns(leaderboard: &Leaderboard, category: impl Fn(String) -> Category) -> Vec<Column> {
+    BOARDS
+        .iter()
+        .map(|board| Column {
+            board,
+            places: leaderboard
+                .top(&category(board.to_string()), PLACES)
+                .iter()
+                .map(|entry| (entry.name.clone(), entry.time))
+                .collect(),
+        })
+        .collect()
+}
+
+// the name being typed for a run that made the table
+pub struct NameEntry {
+    pub category: Category,
+    pub rank: usize,
+    pub name: String,
+}
+
+impl NameEntry {
+    // None when the run isn't on the table; the profile name is offered to start with
+    pub fn for_rank(category: Category, rank: usize, profile: &str) -> Option<Self> {
+        if rank >= PLACES || category.assisted || !BOARDS.contains(&category.board.as_str()) {
+            return None;
+        }
+        let mut entry = Self {
+            category,
+            rank,
+            name: String::new(),
+        };
+        profile.chars().for_each(|character| entry.push(character));
+        Some(entry)
+    }
+
+    pub fn push(&mut self, character: char) {
+        if character.is_ascii_alphanumeric() && self.name.len() < MAX_NAME_LEN {
+            self.name.push(character.to_ascii_uppercase());
+        }
+    }
+
+    pub fn pop(&mut self) {
+        self.name.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classic(board: &str) -> Category {
+        Category {
+            board: board.to_string(),
+            auto_chord: false,
+            auto_flag: false,
+            assisted: false,
+        }
+    }
+
+    #[test]
+    fn test_only_table_runs_are_named() {
+        let entry = NameEntry::for_rank(classic("medium"), 2, "anna_2000_x").unwrap();
+        assert_eq!(entry.name, "ANNA2000");
+        assert_eq!(entry.rank, 2);
+
+        assert!(NameEntry::for_rank(classic("medium"), PLACES, "anna").is_none());
+        assert!(NameEntry::for_rank(classic("expert+"), 0, "anna").is_none());
+        let assisted = Category {
+            assisted: true,
+            ..classic("easy")
+        };
+        assert!(NameEntry::for_rank(assisted, 0, "anna").is_none());
+    }
+
+    #[test]
+    fn test_columns_follow_the_rules() {
+        let dir =
+            std::env::temp_dir().join(format!("rust_sweeper_test_scores_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut leaderboard = Leaderboard::load(&dir);
+        for ms in [9000, 7000, 8000, 6000, 5000, 4000] {
+            leaderboard
+                .submit(&classic("easy"), Duration::from_millis(ms))
+                .unwrap();
+        }
+        leaderboard.set_name(&classic("easy"), 0, "BOB").unwrap();
+
+        let table = columns(&leaderboard, |board| classic(&board));
+        assert_eq!(table.len(), BOARDS.len());
+        assert_eq!(table[0].places.len(), PLACES);
+        assert_eq!(
+            table[0].places[0],
+            ("BOB".to_string(), Duration::from_millis(4000))
+        );
+        assert_eq!(table[0].places[4].1, Duration::from_millis(8000));
+        assert!(table[1].places.is_empty());
+
+        let chorded = columns(&leaderboard, |board| Category {
+            auto_chord: true,
+            ..classic(&board)
+        });
+        assert!(chorded[0].places.is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,74 @@
+// points closer than this to the previous one, in tiles, are dropped to keep strokes small
+const MIN_POINT_DISTANCE: f32 = 0.05;
+
+// freehand strokes drawn over the board, stored in tile units so they follow resizes
+pub struct Sketch {
+    strokes: Vec<Vec<[f32; 2]>>,
+    drawing: bool,
+}
+
+impl Sketch {
+    pub fn new() -> Self {
+        Self {
+            strokes: Vec::new(),
+            drawing: false,
+        }
+    }
+
+    pub fn is_drawing(&self) -> bool {
+        self.drawing
+    }
+
+    pub fn begin(&mut self, point: [f32; 2]) {
+        self.strokes.push(vec![point]);
+        self.drawing = true;
+    }
+
+    pub fn extend(&mut self, point: [f32; 2]) {
+        if !self.drawing {
+            return;
+        }
+        let Some(stroke) = self.strokes.last_mut() else {
+            return;
+        };
+        let last = stroke[stroke.len() - 1];
+        if (point[0] - last[0]).hypot(point[1] - last[1]) >= MIN_POINT_DISTANCE {
+            stroke.push(point);
+        }
+    }
+
+    pub fn end(&mut self) {
+        self.drawing = false;
+    }
+
+    pub fn clear(&mut self) {
+        self.strokes.clear();
+        self.drawing = false;
+    }
+
+    pub fn strokes(&self) -> &[Vec<[f32; 2]>] {
+        &self.strokes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strokes() {
+        let mut sketch = Sketch::new();
+        sketch.extend([1.0, 1.0]);
+        assert!(sketch.strokes().is_empty());
+
+        sketch.begin([0.0, 0.0]);
+        sketch.extend([0.01, 0.0]);
+        sketch.extend([1.0, 0.0]);
+        sketch.end();
+        sketch.extend([2.0, 0.0]);
+
+        assert_eq!(sketch.strokes(), &[vec![[0.0, 0.0], [1.0, 0.0]]]);
+        sketch.clear();
+        assert!(sketch.strokes().is_empty());
+    }
+}
@@ -0,0 +1,90 @@
+use super::game::Engine;
+use std::time::Duration;
+
+// the deductions live with the rules, where no-guess generation needs them too
+pub use rust_sweeper_core::solver::{analyze, guess_required, next_step, Certainty};
+
+const HEAT_DURATION: Duration = Duration::from_secs(2);
+
+// the soft hint: the board tinted by certainty for a moment, without naming a tile
+pub struct HeatHint {
+    certainty: Vec<Certainty>,
+    left: Duration,
+}
+
+impl HeatHint {
+    pub fn new(engine: &Engine) -> Self {
+        Self {
+            certainty: analyze(engine),
+            left: HEAT_DURATION,
+        }
+    }
+
+    // returns false once the tint should disappear
+    pub fn update(&mut self, delta: Duration) -> bool {
+        self.left = self.left.saturating_sub(delta);
+        !self.left.is_zero()
+    }
+
+    pub fn certainty(&self, index: usize) -> Certainty {
+        self.certainty[index]
+    }
+}
+
+// the hint button's answer: one tile the numbers prove, marked until the next move
+pub struct TileHint {
+    pub index: usize,
+    pub certainty: Certainty,
+    moves: usize,
+}
+
+impl TileHint {
+    // None when the numbers prove nothing left to play
+    pub fn new(engine: &Engine) -> Option<Self> {
+        let (index, certainty) = next_step(engine)?;
+        Some(Self {
+            index,
+            certainty,
+            moves: engine.moves().len(),
+        })
+    }
+
+    // any move, undone ones too, may have settled the tile
+    pub fn is_current(&self, engine: &Engine) -> bool {
+        engine.moves().len() == self.moves
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{GridSize, Position};
+
+    #[test]
+    fn test_heat_hint_fades() {
+        let mut engine = Engine::with_seed(GridSize::new(16, 16), 40, 11);
+        engine.reveal(Position(8, 8));
+
+        let mut hint = HeatHint::new(&engine);
+        assert_eq!(hint.certainty(Position(8, 8).to_index(16)), Certainty::Safe);
+        assert!(hint.update(Duration::from_secs(1)));
+        assert!(!hint.update(HEAT_DURATION));
+    }
+
+    #[test]
+    fn test_tile_hint_lasts_until_the_next_move() {
+        let mut engine = Engine::with_seed(GridSize::new(16, 16), 40, 11);
+        assert!(TileHint::new(&engine).is_none());
+        engine.reveal(Position(8, 8));
+
+        let hint = TileHint::new(&engine).unwrap();
+        assert!(hint.is_current(&engine));
+        let pos = Position::from_index(hint.index, 16);
+        match hint.certainty {
+            Certainty::Safe => engine.reveal(pos),
+            _ => engine.flag(pos),
+        }
+        assert!(!engine.is_lost());
+        assert!(!hint.is_current(&engine));
+    }
+}
@@ -1,6 +1,8 @@
-use ggez::graphics::{Image, Rect};
-use std::{collections::HashMap, fs, path::Path};
+use super::theme::THEMES_DIR;
+use ggez::graphics::{Image, ImageFormat, Rect};
+use std::io::Read;
 
+#[derive(Clone, Copy)]
 pub struct Number(pub u8);
 
 impl From<u32> for Number {
@@ -18,90 +20,386 @@ impl Number {
     }
 }
 
+#[derive(Clone, Copy)]
 pub enum GameMode {
     Easy,
     Medium,
     Hard,
 }
 
+#[derive(Clone, Copy)]
 pub enum BombKind {
     Clicked,
     FlaggedWrong,
     NotRevealed,
 }
 
+#[derive(Clone, Copy)]
 pub enum BlockKind {
     Revealed,
     Solid,
     Flagged,
+    Questioned,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum FaceKind {
     Smile,
     Dead,
     Surprised,
     Cool,
+    // a reveal that opened up a whole area
+    Wink,
+    // only a few safe tiles are left
+    Sweat,
+    // nothing has happened for a while
+    Sleepy,
 }
+
+#[derive(Clone, Copy)]
 pub enum Sprite {
     Digit(Number),
     Bomb(BombKind),
     Block(BlockKind),
     Face(FaceKind),
     GameMode(GameMode),
+    Logo,
 }
 
-pub struct SpriteManager {
-    sprites: HashMap<String, Image>,
+// one per sprite file, so a sprite that can't be drawn is found at startup rather than
+// when it's first asked for
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SpriteKey {
+    One,
+    Two,
+    Three,
+    Four,
+    Five,
+    Six,
+    Seven,
+    Eight,
+    BombClicked,
+    BombFlaggedWrong,
+    Bomb,
+    Revealed,
+    Block,
+    BlockFlagged,
+    Question,
+    Smile,
+    Dead,
+    Surprised,
+    Cool,
+    Wink,
+    Sweat,
+    Sleepy,
+    Easy,
+    Medium,
+    Hard,
+    Logo,
 }
 
-impl SpriteManager {
-    pub fn new(ctx: &ggez::Context, _path: &str) -> Self {
-        let images = ctx
-            .fs
-            .read_dir("/resources/sprites")
-            .unwrap()
-            .map(|path| {
-                let file_name: String = path.file_name().unwrap().to_string_lossy().into();
-                let image = Image::from_path(ctx, &path);
-                (file_name, image)
-            })
-            .filter(|item| item.1.is_ok())
-            .map(|item| (item.0, item.1.unwrap()))
-            .collect::<Vec<_>>();
-        let mut sprites = HashMap::new();
-
-        for (file, image) in images {
-            sprites.insert(file, image);
+impl SpriteKey {
+    pub const ALL: [SpriteKey; 26] = [
+        SpriteKey::One,
+        SpriteKey::Two,
+        SpriteKey::Three,
+        SpriteKey::Four,
+        SpriteKey::Five,
+        SpriteKey::Six,
+        SpriteKey::Seven,
+        SpriteKey::Eight,
+        SpriteKey::BombClicked,
+        SpriteKey::BombFlaggedWrong,
+        SpriteKey::Bomb,
+        SpriteKey::Revealed,
+        SpriteKey::Block,
+        SpriteKey::BlockFlagged,
+        SpriteKey::Question,
+        SpriteKey::Smile,
+        SpriteKey::Dead,
+        SpriteKey::Surprised,
+        SpriteKey::Cool,
+        SpriteKey::Wink,
+        SpriteKey::Sweat,
+        SpriteKey::Sleepy,
+        SpriteKey::Easy,
+        SpriteKey::Medium,
+        SpriteKey::Hard,
+        SpriteKey::Logo,
+    ];
+
+    pub fn file(&self) -> &'static str {
+        match self {
+            SpriteKey::One => "1.png",
+            SpriteKey::Two => "2.png",
+            SpriteKey::Three => "3.png",
+            SpriteKey::Four => "4.png",
+            SpriteKey::Five => "5.png",
+            SpriteKey::Six => "6.png",
+            SpriteKey::Seven => "7.png",
+            SpriteKey::Eight => "8.png",
+            SpriteKey::BombClicked => "bomb_clicked.png",
+            SpriteKey::BombFlaggedWrong => "bomb_flagged_wrong.png",
+            SpriteKey::Bomb => "bomb.png",
+            SpriteKey::Revealed => "revealed.png",
+            SpriteKey::Block => "block.png",
+            SpriteKey::BlockFlagged => "block_flagged.png",
+            SpriteKey::Question => "question.png",
+            SpriteKey::Smile => "smile.png",
+            SpriteKey::Dead => "dead.png",
+            SpriteKey::Surprised => "surprised.png",
+            SpriteKey::Cool => "cool.png",
+            SpriteKey::Wink => "wink.png",
+            SpriteKey::Sweat => "sweat.png",
+            SpriteKey::Sleepy => "sleepy.png",
+            SpriteKey::Easy => "easy.png",
+            SpriteKey::Medium => "mid.png",
+            SpriteKey::Hard => "hard.png",
+            SpriteKey::Logo => "logo.png",
         }
+    }
 
-        Self { sprites }
+    fn from_file(file: &str) -> Option<SpriteKey> {
+        SpriteKey::ALL.into_iter().find(|key| key.file() == file)
     }
+}
 
-    pub fn get(&self, sprite: Sprite) -> Option<&Image> {
+impl From<Sprite> for SpriteKey {
+    fn from(sprite: Sprite) -> Self {
         match sprite {
-            Sprite::Digit(n) => self.sprites.get(&format!("{}.png", n.0)),
+            Sprite::Digit(n) => SpriteKey::ALL[(n.0 as usize).clamp(1, 8) - 1],
             Sprite::Bomb(b) => match b {
-                BombKind::Clicked => self.sprites.get("bomb_clicked.png"),
-                BombKind::FlaggedWrong => self.sprites.get("bomb_flagged_wrong.png"),
-                BombKind::NotRevealed => self.sprites.get("bomb.png"),
+                BombKind::Clicked => SpriteKey::BombClicked,
+                BombKind::FlaggedWrong => SpriteKey::BombFlaggedWrong,
+                BombKind::NotRevealed => SpriteKey::Bomb,
             },
             Sprite::Block(b) => match b {
-                BlockKind::Revealed => self.sprites.get("revealed.png"),
-                BlockKind::Solid => self.sprites.get("block.png"),
-                BlockKind::Flagged => self.sprites.get("block_flagged.png"),
+                BlockKind::Revealed => SpriteKey::Revealed,
+                BlockKind::Solid => SpriteKey::Block,
+                BlockKind::Flagged => SpriteKey::BlockFlagged,
+                BlockKind::Questioned => SpriteKey::Question,
             },
             Sprite::Face(f) => match f {
-                FaceKind::Smile => self.sprites.get("smile.png"),
-                FaceKind::Dead => self.sprites.get("dead.png"),
-                FaceKind::Surprised => self.sprites.get("surprised.png"),
-                FaceKind::Cool => self.sprites.get("cool.png"),
+                FaceKind::Smile => SpriteKey::Smile,
+                FaceKind::Dead => SpriteKey::Dead,
+                FaceKind::Surprised => SpriteKey::Surprised,
+                FaceKind::Cool => SpriteKey::Cool,
+                FaceKind::Wink => SpriteKey::Wink,
+                FaceKind::Sweat => SpriteKey::Sweat,
+                FaceKind::Sleepy => SpriteKey::Sleepy,
             },
             Sprite::GameMode(m) => match m {
-                GameMode::Easy => self.sprites.get("easy.png"),
-                GameMode::Medium => self.sprites.get("mid.png"),
-                GameMode::Hard => self.sprites.get("hard.png"),
+                GameMode::Easy => SpriteKey::Easy,
+                GameMode::Medium => SpriteKey::Medium,
+                GameMode::Hard => SpriteKey::Hard,
             },
+            Sprite::Logo => SpriteKey::Logo,
+        }
+    }
+}
+
+// where each key's sprite sits in the atlas, in pixels, indexed by the key
+type Regions = [Option<Rect>; SpriteKey::ALL.len()];
+
+// the classic sprites, and the fallback for any a theme folder doesn't have
+const SPRITE_DIR: &str = "/resources/sprites";
+// a prebuilt atlas and the description of where each sprite sits in it, one
+// "file x y w h" line per sprite; without them the sprites are packed at load time
+const ATLAS_IMAGE: &str = "atlas.png";
+const ATLAS_DESCRIPTION: &str = "atlas.txt";
+// a packed atlas grows downwards in shelves no wider than this
+const ATLAS_MAX_W: u32 = 1024;
+// a transparent gap around every packed sprite so filtering doesn't bleed its neighbours in
+const ATLAS_PADDING: u32 = 1;
+
+// every sprite lives in one atlas image, so anything drawn from it can share a draw call
+pub struct SpriteManager {
+    atlas: Option<Image>,
+    regions: Regions,
+}
+
+impl SpriteManager {
+    pub fn new(ctx: &ggez::Context, theme: &str) -> Self {
+        let mut sprite_manager = Self {
+            atlas: None,
+            regions: [None; SpriteKey::ALL.len()],
+        };
+        sprite_manager.load_theme(ctx, theme);
+        sprite_manager
+    }
+
+    // swaps in the sprites of a theme folder; a missing directory leaves every sprite
+    // missing, which the draw code copes with
+    pub fn load_theme(&mut self, ctx: &ggez::Context, name: &str) {
+        let theme_dir = format!("{}/{}/sprites", THEMES_DIR, name);
+        // the classic atlas only stands in for a theme without sprites of its own
+        let prebuilt = load_atlas(ctx, &theme_dir).or_else(|| {
+            (!ctx.fs.is_dir(&theme_dir))
+                .then(|| load_atlas(ctx, SPRITE_DIR))
+                .flatten()
+        });
+        (self.atlas, self.regions) = match prebuilt {
+            Some((atlas, regions)) => (Some(atlas), regions),
+            None => pack_atlas(ctx, &[&theme_dir, SPRITE_DIR]),
+        };
+    }
+
+    // checked once at startup so every missing sprite is reported together; at draw time
+    // they are plain shapes instead
+    pub fn missing(&self) -> Vec<SpriteKey> {
+        SpriteKey::ALL
+            .into_iter()
+            .filter(|key| self.regions[*key as usize].is_none())
+            .collect()
+    }
+
+    // the atlas and the sprite's place in it as a fraction of the atlas, ready for
+    // DrawParam::src; scaling by the sprite's own size in pixels is left to the caller
+    pub fn get_uv(&self, sprite: impl Into<SpriteKey>) -> Option<(Image, Rect)> {
+        let atlas = self.atlas.as_ref()?;
+        let region = self.regions[sprite.into() as usize]?;
+        let (w, h) = (atlas.width() as f32, atlas.height() as f32);
+        let uv = Rect::new(region.x / w, region.y / h, region.w / w, region.h / h);
+        Some((atlas.clone(), uv))
+    }
+
+    // the sprite's size in pixels
+    pub fn size(&self, sprite: impl Into<SpriteKey>) -> Option<(f32, f32)> {
+        let region = self.regions[sprite.into() as usize]?;
+        Some((region.w, region.h))
+    }
+}
+
+fn load_atlas(ctx: &ggez::Context, dir: &str) -> Option<(Image, Regions)> {
+    let mut description = String::new();
+    ctx.fs
+        .open(format!("{}/{}", dir, ATLAS_DESCRIPTION))
+        .ok()?
+        .read_to_string(&mut description)
+        .ok()?;
+    let regions = parse_atlas(&description).ok()?;
+    let atlas = Image::from_path(ctx, format!("{}/{}", dir, ATLAS_IMAGE)).ok()?;
+    Some((atlas, regions))
+}
+
+// stitches every sprite's own file into one image, each from the first directory that
+// has it
+fn pack_atlas(ctx: &ggez::Context, dirs: &[&str]) -> (Option<Image>, Regions) {
+    let sprites = SpriteKey::ALL
+        .into_iter()
+        .filter_map(|key| {
+            let image = dirs
+                .iter()
+                .find_map(|dir| Image::from_path(ctx, format!("{}/{}", dir, key.file())).ok())?;
+            let pixels = image.to_pixels(ctx).ok()?;
+            let (w, h) = (image.width(), image.height());
+            (w > 0 && pixels.len() == (w * h * 4) as usize).then_some((key, (w, h), pixels))
+        })
+        .collect::<Vec<_>>();
+    let mut regions = [None; SpriteKey::ALL.len()];
+    if sprites.is_empty() {
+        return (None, regions);
+    }
+
+    let sizes: Vec<(u32, u32)> = sprites.iter().map(|sprite| sprite.1).collect();
+    let (places, atlas_w, atlas_h) = pack(&sizes, ATLAS_MAX_W);
+    let mut atlas = vec![0u8; (atlas_w * atlas_h * 4) as usize];
+    for ((key, (w, h), pixels), (x, y)) in sprites.into_iter().zip(places) {
+        let row = w as usize * 4;
+        for (i, line) in pixels.chunks_exact(row).enumerate() {
+            let start = ((y as usize + i) * atlas_w as usize + x as usize) * 4;
+            atlas[start..start + row].copy_from_slice(line);
+        }
+        regions[key as usize] = Some(Rect::new(x as f32, y as f32, w as f32, h as f32));
+    }
+    let atlas = Image::from_pixels(ctx, &atlas, ImageFormat::Rgba8UnormSrgb, atlas_w, atlas_h);
+    (Some(atlas), regions)
+}
+
+// places sprites left to right in shelves, returning where each one goes and the atlas size
+fn pack(sizes: &[(u32, u32)], max_w: u32) -> (Vec<(u32, u32)>, u32, u32) {
+    let mut places = Vec::new();
+    let (mut x, mut y, mut shelf_h, mut atlas_w) = (0, 0, 0, 0);
+    for &(w, h) in sizes {
+        if x > 0 && x + w > max_w {
+            x = 0;
+            y += shelf_h + ATLAS_PADDING;
+            shelf_h = 0;
+        }
+        places.push((x, y));
+        x += w + ATLAS_PADDING;
+        atlas_w = atlas_w.max(x);
+        shelf_h = shelf_h.max(h);
+    }
+    (places, atlas_w, y + shelf_h)
+}
+
+fn parse_atlas(description: &str) -> Result<Regions, String> {
+    let mut regions = [None; SpriteKey::ALL.len()];
+    for (i, line) in description.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut fields = line.split_whitespace();
+        let file = fields.next().unwrap_or_default();
+        let key = SpriteKey::from_file(file)
+            .ok_or_else(|| format!("line {}: unknown sprite '{}'", i + 1, file))?;
+        let numbers = fields
+            .map(str::parse::<f32>)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("line {}: {}", i + 1, e))?;
+        let [x, y, w, h] = numbers[..] else {
+            return Err(format!("line {}: expected a file name and x y w h", i + 1));
+        };
+        regions[key as usize] = Some(Rect::new(x, y, w, h));
+    }
+    Ok(regions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_atlas_description_parses() {
+        let regions = parse_atlas("# tiles\nblock.png 0 0 32 32\n\n1.png 33 0 32 32\n").unwrap();
+        assert_eq!(regions.iter().flatten().count(), 2);
+        assert_eq!(
+            regions[SpriteKey::One as usize],
+            Some(Rect::new(33.0, 0.0, 32.0, 32.0))
+        );
+        assert!(parse_atlas("tile.png 0 0 32 32").is_err());
+        assert!(parse_atlas("block.png 0 0 32").is_err());
+        assert!(parse_atlas("block.png 0 0 32 wide").is_err());
+    }
+
+    #[test]
+    fn test_every_key_has_its_own_file() {
+        for key in SpriteKey::ALL {
+            // the keys index the atlas regions
+            assert_eq!(SpriteKey::ALL[key as usize], key);
+            assert_eq!(SpriteKey::from_file(key.file()), Some(key));
+        }
+        assert_eq!(SpriteKey::from(Sprite::Digit(Number(3))), SpriteKey::Three);
+    }
+
+    #[test]
+    fn test_packed_sprites_fit_without_overlapping() {
+        let sizes = [(32, 32), (32, 32), (48, 48), (200, 40), (32, 32)];
+        let (places, w, h) = pack(&sizes, 128);
+        let rects: Vec<Rect> = places
+            .iter()
+            .zip(sizes)
+            .map(|(&(x, y), (sw, sh))| Rect::new(x as f32, y as f32, sw as f32, sh as f32))
+            .collect();
+        for (i, a) in rects.iter().enumerate() {
+            assert!(a.right() <= w as f32 && a.bottom() <= h as f32);
+            for b in &rects[i + 1..] {
+                assert!(!a.overlaps(b));
+            }
         }
+        // a sprite wider than the atlas limit still gets a shelf of its own
+        assert_eq!(places[3], (0, 49));
     }
 }
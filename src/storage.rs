@@ -0,0 +1,146 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::SystemTime;
+
+// turns the body of one format version into the body of the next
+pub type Migration = fn(&str) -> Result<String, String>;
+
+// every saved text file starts with "rust_sweeper_<kind> <version>"
+pub struct Format {
+    pub kind: &'static str,
+    pub version: u32,
+    // migrations[i] upgrades a version i + 1 body to version i + 2
+    pub migrations: &'static [Migration],
+}
+
+impl Format {
+    pub const fn new(kind: &'static str, version: u32, migrations: &'static [Migration]) -> Self {
+        Self {
+            kind,
+            version,
+            migrations,
+        }
+    }
+
+    pub fn header(&self) -> String {
+        format!("rust_sweeper_{} {}", self.kind, self.version)
+    }
+
+    pub fn parse_version(&self, header: &str) -> Result<u32, String> {
+        let prefix = format!("rust_sweeper_{} ", self.kind);
+        let version = header
+            .trim()
+            .strip_prefix(&prefix)
+            .and_then(|v| v.parse::<u32>().ok())
+            .filter(|v| *v > 0)
+            .ok_or_else(|| format!("not a rust_sweeper {} file", self.kind))?;
+        if version > self.version {
+            return Err(format!(
+                "{} file version {} is newer than this build supports ({})",
+                self.kind, version, self.version
+            ));
+        }
+        Ok(version)
+    }
+
+    // checks the header and returns the rest of the file migrated to the current version
+    pub fn read(&self, text: &str) -> Result<String, String> {
+        let text = text.trim_start();
+        let (header, body) = text.split_once('\n').unwrap_or((text, ""));
+        let version = self.parse_version(header)?;
+
+        let mut body = body.to_string();
+        for migrate in &self.migrations[version as usize - 1..self.version as usize - 1] {
+            body = migrate(&body)?;
+        }
+        Ok(body)
+    }
+}
+
+// writes next to the target and renames, so a crash never leaves a half written file
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    write_atomic_at(path, contents.as_ref(), None)
+}
+
+// for a copy of a file from elsewhere, such as a synced one, that keeps its own time
+pub fn write_atomic_modified(
+    path: &Path,
+    contents: impl AsRef<[u8]>,
+    modified: SystemTime,
+) -> io::Result<()> {
+    write_atomic_at(path, contents.as_ref(), Some(modified))
+}
+
+fn write_atomic_at(path: &Path, contents: &[u8], modified: Option<SystemTime>) -> io::Result<()> {
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let mut file = fs::File::create(&tmp_path)?;
+    file.write_all(contents)?;
+    if let Some(modified) = modified {
+        file.set_modified(modified)?;
+    }
+    file.sync_all()?;
+    fs::rename(&tmp_path, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rename_foo(body: &str) -> Result<String, String> {
+        Ok(body.replace("foo ", "bar "))
+    }
+
+    fn double_bar(body: &str) -> Result<String, String> {
+        Ok(body.replace("bar ", "bar bar "))
+    }
+
+    const TEST_FORMAT: Format = Format::new("test", 3, &[rename_foo, double_bar]);
+
+    #[test]
+    fn test_read_current_version() {
+        let text = format!("{}\nbar 1\n", TEST_FORMAT.header());
+        assert_eq!(TEST_FORMAT.read(&text), Ok("bar 1\n".to_string()));
+    }
+
+    #[test]
+    fn test_read_migrates_old_versions() {
+        assert_eq!(
+            TEST_FORMAT.read("rust_sweeper_test 1\nfoo 1\n"),
+            Ok("bar bar 1\n".to_string())
+        );
+        assert_eq!(
+            TEST_FORMAT.read("rust_sweeper_test 2\nbar 1\n"),
+            Ok("bar bar 1\n".to_string())
+        );
+    }
+
+    #[test]
+    fn test_read_rejects_unknown_files() {
+        assert!(TEST_FORMAT.read("rust_sweeper_test 4\n").is_err());
+        assert!(TEST_FORMAT.read("rust_sweeper_test 0\n").is_err());
+        assert!(TEST_FORMAT.read("rust_sweeper_other 1\n").is_err());
+        assert!(TEST_FORMAT.read("").is_err());
+    }
+
+    #[test]
+    fn test_write_atomic() {
+        let path = std::env::temp_dir().join(format!(
+            "rust_sweeper_test_atomic_{}.txt",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        write_atomic(&path, "first").unwrap();
+        write_atomic(&path, "second").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        assert!(!path
+            .with_file_name("rust_sweeper_test_atomic.txt.tmp")
+            .exists());
+
+        let _ = fs::remove_file(&path);
+    }
+}
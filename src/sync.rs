@@ -0,0 +1,533 @@
+use super::crypto::{hmac_sha256, sha256, to_base64, to_hex};
+use super::storage::{self, Format};
+use chrono::DateTime;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Component, Path};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const BLOB_FORMAT: Format = Format::new("sync", 1, &[]);
+// files with this extension hold one record per line and are merged record by record
+pub const RECORDS_EXTENSION: &str = "records";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum SyncBackend {
+    WebDav {
+        // collection url, e.g. https://dav.example.com/rust_sweeper
+        url: String,
+        username: String,
+        password: String,
+    },
+    // any S3-compatible store, addressed path-style as endpoint/bucket/key
+    S3 {
+        endpoint: String,
+        bucket: String,
+        region: String,
+        access_key: String,
+        secret_key: String,
+    },
+}
+
+impl SyncBackend {
+    // settings.toml spells the backend sync = "webdav" or "s3", followed by its fields as
+    // sync_<field> lines
+    pub fn from_key(key: &str) -> Option<Self> {
+        let empty = String::new;
+        match key {
+            "webdav" => Some(SyncBackend::WebDav {
+                url: empty(),
+                username: empty(),
+                password: empty(),
+            }),
+            "s3" => Some(SyncBackend::S3 {
+                endpoint: empty(),
+                bucket: empty(),
+                region: empty(),
+                access_key: empty(),
+                secret_key: empty(),
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            SyncBackend::WebDav { .. } => "webdav",
+            SyncBackend::S3 { .. } => "s3",
+        }
+    }
+
+    pub fn fields(&self) -> Vec<(&'static str, &str)> {
+        match self {
+            SyncBackend::WebDav {
+                url,
+                username,
+                password,
+            } => vec![("url", url), ("username", username), ("password", password)],
+            SyncBackend::S3 {
+                endpoint,
+                bucket,
+                region,
+                access_key,
+                secret_key,
+            } => vec![
+                ("endpoint", endpoint),
+                ("bucket", bucket),
+                ("region", region),
+                ("access_key", access_key),
+                ("secret_key", secret_key),
+            ],
+        }
+    }
+
+    pub fn field_mut(&mut self, field: &str) -> Option<&mut String> {
+        match (self, field) {
+            (SyncBackend::WebDav { url, .. }, "url") => Some(url),
+            (SyncBackend::WebDav { username, .. }, "username") => Some(username),
+            (SyncBackend::WebDav { password, .. }, "password") => Some(password),
+            (SyncBackend::S3 { endpoint, .. }, "endpoint") => Some(endpoint),
+            (SyncBackend::S3 { bucket, .. }, "bucket") => Some(bucket),
+            (SyncBackend::S3 { region, .. }, "region") => Some(region),
+            (SyncBackend::S3 { access_key, .. }, "access_key") => Some(access_key),
+            (SyncBackend::S3 { secret_key, .. }, "secret_key") => Some(secret_key),
+            _ => None,
+        }
+    }
+
+    // the first field the backend can't work without that is still empty
+    pub fn missing_field(&self) -> Option<&'static str> {
+        let required: &[&str] = match self {
+            SyncBackend::WebDav { .. } => &["url"],
+            SyncBackend::S3 { .. } => &["endpoint", "bucket", "region"],
+        };
+        self.fields()
+            .into_iter()
+            .find(|(name, value)| required.contains(name) && value.is_empty())
+            .map(|(name, _)| name)
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct SyncedFile {
+    pub name: String,
+    // unix milliseconds
+    pub modified: u64,
+    pub content: Vec<u8>,
+}
+
+pub fn read_dir_files(dir: &Path) -> io::Result<Vec<SyncedFile>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        let Ok(name) = entry.file_name().into_string() else {
+            continue;
+        };
+        if !metadata.is_file() || !is_file_name(&name) {
+            continue;
+        }
+        let modified = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        files.push(SyncedFile {
+            name,
+            modified,
+            content: fs::read(entry.path())?,
+        });
+    }
+    Ok(files)
+}
+
+// a name the blob can carry and that stays inside the profile folder when joined onto it
+fn is_file_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    matches!(components.next(), Some(Component::Normal(_)))
+        && components.next().is_none()
+        && !name.chars().any(char::is_whitespace)
+}
+
+pub fn pack(files: &[SyncedFile]) -> Vec<u8> {
+    let mut blob = format!("{}\n", BLOB_FORMAT.header()).into_bytes();
+    for file in files {
+        blob.extend_from_slice(
+            format!(
+                "file {} {} {}\n",
+                file.name,
+                file.modified,
+                file.content.len()
+            )
+            .as_bytes(),
+        );
+        blob.extend_from_slice(&file.content);
+        blob.push(b'\n');
+    }
+    blob
+}
+
+pub fn unpack(blob: &[u8]) -> Result<Vec<SyncedFile>, String> {
+    fn next_line<'a>(blob: &'a [u8], pos: &mut usize) -> Option<&'a str> {
+        let end = blob[*pos..].iter().position(|b| *b == b'\n')? + *pos;
+        let line = std::str::from_utf8(&blob[*pos..end]).ok()?;
+        *pos = end + 1;
+        Some(line)
+    }
+
+    let mut pos = 0;
+    BLOB_FORMAT.parse_version(next_line(blob, &mut pos).unwrap_or(""))?;
+
+    let mut files = Vec::new();
+    while pos < blob.len() {
+        let line = next_line(blob, &mut pos).ok_or("truncated sync blob")?;
+        let fields = line.split(' ').collect::<Vec<_>>();
+        let ["file", name, modified, len] = fields.as_slice() else {
+            return Err(format!("unexpected line '{}'", line));
+        };
+        if !is_file_name(name) {
+            return Err(format!("invalid file name '{}'", name));
+        }
+        let modified = modified.parse().map_err(|_| "invalid modified time")?;
+        let len = len.parse::<usize>().map_err(|_| "invalid file length")?;
+        let end = pos.checked_add(len).ok_or("invalid file length")?;
+        let content = blob.get(pos..end).ok_or("truncated sync blob")?;
+        files.push(SyncedFile {
+            name: name.to_string(),
+            modified,
+            content: content.to_vec(),
+        });
+        // skip the content and its trailing newline
+        pos = end + 1;
+    }
+    Ok(files)
+}
+
+// record lines are "<id>\t<updated unix ms>\t<payload>", the newer copy of each id wins
+fn merge_records(local: &[u8], remote: &[u8]) -> Vec<u8> {
+    let mut records: BTreeMap<String, (u64, String)> = BTreeMap::new();
+    for content in [local, remote] {
+        for line in String::from_utf8_lossy(content).lines() {
+            let mut fields = line.splitn(3, '\t');
+            let (Some(id), Some(updated)) = (fields.next(), fields.next()) else {
+                continue;
+            };
+            let Ok(updated) = updated.parse::<u64>() else {
+                continue;
+            };
+            let newer = records
+                .get(id)
+                .is_none_or(|(existing, _)| updated > *existing);
+            if newer {
+                records.insert(id.to_string(), (updated, line.to_string()));
+            }
+        }
+    }
+
+    let mut merged = String::new();
+    for (_, line) in records.values() {
+        merged.push_str(line);
+        merged.push('\n');
+    }
+    merged.into_bytes()
+}
+
+pub fn merge(local: Vec<SyncedFile>, remote: Vec<SyncedFile>) -> Vec<SyncedFile> {
+    let mut merged: BTreeMap<String, SyncedFile> = BTreeMap::new();
+    for file in local.into_iter().chain(remote) {
+        let Some(existing) = merged.get(&file.name) else {
+            merged.insert(file.name.clone(), file);
+            continue;
+        };
+
+        let is_records = Path::new(&file.name)
+            .extension()
+            .is_some_and(|ext| ext == RECORDS_EXTENSION);
+        let combined = if is_records {
+            SyncedFile {
+                name: file.name.clone(),
+                modified: existing.modified.max(file.modified),
+                content: merge_records(&existing.content, &file.content),
+            }
+        } else if file.modified > existing.modified {
+            file
+        } else {
+            continue;
+        };
+        merged.insert(combined.name.clone(), combined);
+    }
+    merged.into_values().collect()
+}
+
+// the merged files that differ from the local copies
+fn changed_files<'a>(local: &[SyncedFile], merged: &'a [SyncedFile]) -> Vec<&'a SyncedFile> {
+    merged.iter().filter(|file| !local.contains(file)).collect()
+}
+
+// "20130524T000000Z" as used by AWS signature version 4
+fn amz_date(unix_secs: u64) -> String {
+    i64::try_from(unix_secs)
+        .ok()
+        .and_then(|secs| DateTime::from_timestamp(secs, 0))
+        .map(|time| time.format("%Y%m%dT%H%M%SZ").to_string())
+        .unwrap_or_default()
+}
+
+struct S3Request {
+    url: String,
+    headers: Vec<(String, String)>,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn sign_s3(
+    method: &str,
+    endpoint: &str,
+    bucket: &str,
+    region: &str,
+    access_key: &str,
+    secret_key: &str,
+    object: &str,
+    payload: &[u8],
+    unix_secs: u64,
+) -> S3Request {
+    let endpoint = endpoint.trim_end_matches('/');
+    let host = endpoint
+        .split("://")
+        .last()
+        .unwrap_or(endpoint)
+        .split('/')
+        .next()
+        .unwrap_or("");
+    let path = format!("/{}/{}", bucket, object);
+    let date_time = amz_date(unix_secs);
+    let date = &date_time[..8];
+    let payload_hash = to_hex(&sha256(&[payload]));
+
+    let canonical_request = format!(
+        "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\nhost;x-amz-content-sha256;x-amz-date\n{}",
+        method, path, host, payload_hash, date_time, payload_hash
+    );
+    let scope = format!("{}/{}/s3/aws4_request", date, region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        date_time,
+        scope,
+        to_hex(&sha256(&[canonical_request.as_bytes()]))
+    );
+
+    let k_date = hmac_sha256(format!("AWS4{}", secret_key).as_bytes(), date.as_bytes());
+    let k_region = hmac_sha256(&k_date, region.as_bytes());
+    let k_service = hmac_sha256(&k_region, b"s3");
+    let k_signing = hmac_sha256(&k_service, b"aws4_request");
+    let signature = to_hex(&hmac_sha256(&k_signing, string_to_sign.as_bytes()));
+
+    S3Request {
+        url: format!("{}{}", endpoint, path),
+        headers: vec![
+            ("x-amz-date".to_string(), date_time),
+            ("x-amz-content-sha256".to_string(), payload_hash),
+            (
+                "Authorization".to_string(),
+                format!(
+                    "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={}",
+                    access_key, scope, signature
+                ),
+            ),
+        ],
+    }
+}
+
+fn build_request(
+    agent: &ureq::Agent,
+    backend: &SyncBackend,
+    method: &str,
+    object: &str,
+    payload: &[u8],
+) -> ureq::Request {
+    match backend {
+        SyncBackend::WebDav {
+            url,
+            username,
+            password,
+        } => {
+            let credentials = to_base64(format!("{}:{}", username, password).as_bytes());
+            agent
+                .request(method, &format!("{}/{}", url.trim_end_matches('/'), object))
+                .set("Authorization", &format!("Basic {}", credentials))
+        }
+        SyncBackend::S3 {
+            endpoint,
+            bucket,
+            region,
+            access_key,
+            secret_key,
+        } => {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            let signed = sign_s3(
+                method, endpoint, bucket, region, access_key, secret_key, object, payload, now,
+            );
+            signed
+                .headers
+                .iter()
+                .fold(agent.request(method, &signed.url), |request, (k, v)| {
+                    request.set(k, v)
+                })
+        }
+    }
+}
+
+// pulls the remote copy, merges it into the profile directory and pushes the result back
+pub fn sync_profile(
+    backend: &SyncBackend,
+    profile_dir: &Path,
+    profile: &str,
+) -> Result<(), String> {
+    let agent = ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build();
+    let object = format!("{}.sync", profile);
+
+    let remote = match build_request(&agent, backend, "GET", &object, b"").call() {
+        Ok(response) => {
+            let mut blob = Vec::new();
+            response
+                .into_reader()
+                .read_to_end(&mut blob)
+                .map_err(|e| e.to_string())?;
+            unpack(&blob)?
+        }
+        // nothing uploaded yet
+        Err(ureq::Error::Status(404, _)) => Vec::new(),
+        Err(e) => return Err(e.to_string()),
+    };
+
+    let local = read_dir_files(profile_dir).map_err(|e| e.to_string())?;
+    let merged = merge(local.clone(), remote);
+
+    // a file written back keeps the time the merge gave it; stamping it with now would
+    // make a stale copy look newer than the remote one on the next sync
+    for file in changed_files(&local, &merged) {
+        let modified = UNIX_EPOCH + Duration::from_millis(file.modified);
+        storage::write_atomic_modified(&profile_dir.join(&file.name), &file.content, modified)
+            .map_err(|e| e.to_string())?;
+    }
+
+    let blob = pack(&merged);
+    build_request(&agent, backend, "PUT", &object, &blob)
+        .send_bytes(&blob)
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(name: &str, modified: u64, content: &str) -> SyncedFile {
+        SyncedFile {
+            name: name.to_string(),
+            modified,
+            content: content.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_pack_round_trip() {
+        let files = vec![file("a.txt", 5, "hello\nworld"), file("b", 7, "")];
+        assert_eq!(unpack(&pack(&files)), Ok(files));
+    }
+
+    #[test]
+    fn test_unpack_rejects_hostile_blobs() {
+        let header = format!("{}\n", BLOB_FORMAT.header());
+        for name in ["../../.bashrc", "/etc/passwd", "..", ".", "a/b", ""] {
+            let blob = format!("{}file {} 1 2\nhi\n", header, name);
+            assert!(unpack(blob.as_bytes()).is_err(), "{:?}", name);
+        }
+        let blob = format!("{}file a.txt 1 {}\nhi\n", header, usize::MAX);
+        assert!(unpack(blob.as_bytes()).is_err());
+        let blob = format!("{}file a.txt 1 99\nhi\n", header);
+        assert!(unpack(blob.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_merge_latest_wins() {
+        let local = vec![
+            file("config.toml", 10, "local"),
+            file("games.records", 10, "g1\t100\twon\ng2\t300\tlost\n"),
+        ];
+        let remote = vec![
+            file("config.toml", 20, "remote"),
+            file("games.records", 5, "g1\t200\tlost\ng3\t50\twon\n"),
+        ];
+
+        let merged = merge(local, remote);
+        assert_eq!(
+            merged,
+            vec![
+                file("config.toml", 20, "remote"),
+                file(
+                    "games.records",
+                    10,
+                    "g1\t200\tlost\ng2\t300\tlost\ng3\t50\twon\n"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merged_files_keep_their_time() {
+        let dir = std::env::temp_dir().join(format!("rust_sweeper_sync_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("settings.toml"), "local").unwrap();
+        fs::write(dir.join("same.txt"), "same").unwrap();
+        let local = read_dir_files(&dir).unwrap();
+        let same = local.iter().find(|f| f.name == "same.txt").unwrap().clone();
+
+        let merged = merge(
+            local.clone(),
+            vec![
+                file("settings.toml", 4_000_000_000_000, "remote"),
+                same.clone(),
+            ],
+        );
+        let changed = changed_files(&local, &merged);
+        assert_eq!(changed.len(), 1);
+        for file in changed {
+            let modified = UNIX_EPOCH + Duration::from_millis(file.modified);
+            storage::write_atomic_modified(&dir.join(&file.name), &file.content, modified).unwrap();
+        }
+
+        // a second sync sees the remote's time, not the time it was written back
+        let reread = read_dir_files(&dir).unwrap();
+        let settings = reread.iter().find(|f| f.name == "settings.toml").unwrap();
+        assert_eq!(settings.modified, 4_000_000_000_000);
+        assert_eq!(settings.content, b"remote");
+        assert!(reread.contains(&same));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_backend_fields() {
+        let mut backend = SyncBackend::from_key("s3").unwrap();
+        assert_eq!(backend.missing_field(), Some("endpoint"));
+        *backend.field_mut("endpoint").unwrap() = "https://s3.example.com".to_string();
+        *backend.field_mut("bucket").unwrap() = "saves".to_string();
+        *backend.field_mut("region").unwrap() = "auto".to_string();
+        assert_eq!(backend.missing_field(), None);
+        assert!(backend.field_mut("url").is_none());
+        assert_eq!(backend.fields()[1], ("bucket", "saves"));
+        assert_eq!(SyncBackend::from_key("webdav").unwrap().key(), "webdav");
+    }
+
+    #[test]
+    fn test_amz_date() {
+        assert_eq!(amz_date(0), "19700101T000000Z");
+        assert_eq!(amz_date(1_369_353_600), "20130524T000000Z");
+        assert_eq!(amz_date(951_782_400 + 3661), "20000229T010101Z");
+    }
+}
@@ -0,0 +1,127 @@
+use super::board::{BoardSettings, PRESETS};
+use super::game::Engine;
+
+// which board a tab holds; there is never more than one open board under a key
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TabKey {
+    Preset(&'static str),
+    Custom,
+    // the day the daily board is for
+    Daily(i64),
+}
+
+impl TabKey {
+    pub fn label(&self) -> &'static str {
+        match self {
+            TabKey::Preset("easy") => "EASY",
+            TabKey::Preset("medium") => "MED",
+            TabKey::Preset("hard") => "HARD",
+            TabKey::Preset("expert+") => "EXP+",
+            TabKey::Preset(_) => "INS",
+            TabKey::Custom => "CUST",
+            TabKey::Daily(_) => "DAILY",
+        }
+    }
+
+    // presets from easiest up, then the custom board, then the daily
+    fn rank(&self) -> usize {
+        match self {
+            TabKey::Preset(name) => PRESETS.iter().position(|p| p == name).unwrap_or(0),
+            TabKey::Custom => PRESETS.len(),
+            TabKey::Daily(_) => PRESETS.len() + 1,
+        }
+    }
+}
+
+// a board put aside for another one; its clock stands still until it is picked up
+pub struct OpenBoard {
+    pub key: TabKey,
+    pub settings: BoardSettings,
+    pub engine: Engine,
+}
+
+// the unfinished boards kept open next to the one being played
+pub struct Tabs {
+    open: Vec<OpenBoard>,
+}
+
+impl Tabs {
+    pub fn new() -> Self {
+        Self { open: Vec::new() }
+    }
+
+    // replaces a board already kept under the same key
+    pub fn put(&mut self, board: OpenBoard) {
+        self.open.retain(|open| open.key != board.key);
+        self.open.push(board);
+    }
+
+    pub fn take(&mut self, key: TabKey) -> Option<OpenBoard> {
+        let index = self.open.iter().position(|open| open.key == key)?;
+        Some(self.open.remove(index))
+    }
+
+    pub fn clear(&mut self) {
+        self.open.clear();
+    }
+
+    // the tabs to show with the seconds on each clock, the board being played marked
+    // active; nothing to switch to means no tabs at all
+    pub fn strip(&self, active: Option<(TabKey, &Engine)>) -> Vec<(TabKey, u64, bool)> {
+        if self.open.is_empty() {
+            return Vec::new();
+        }
+        let mut strip = self
+            .open
+            .iter()
+            .map(|open| (open.key, open.engine.elapsed().as_secs(), false))
+            .chain(active.map(|(key, engine)| (key, engine.elapsed().as_secs(), true)))
+            .collect::<Vec<_>>();
+        strip.sort_by_key(|(key, _, _)| key.rank());
+        strip
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Position;
+    use std::time::Duration;
+
+    fn open(key: TabKey, settings: BoardSettings, secs: u64) -> OpenBoard {
+        let mut engine = Engine::with_seed(settings.size(), settings.num_bombs(), 7);
+        engine.reveal(Position(0, 0));
+        engine.tick(Duration::from_secs(secs));
+        OpenBoard {
+            key,
+            settings,
+            engine,
+        }
+    }
+
+    #[test]
+    fn test_tabs_keep_one_board_per_key() {
+        let mut tabs = Tabs::new();
+        let playing = Engine::new(BoardSettings::medium().size(), 40);
+        assert!(tabs
+            .strip(Some((TabKey::Preset("medium"), &playing)))
+            .is_empty());
+
+        tabs.put(open(TabKey::Daily(20000), BoardSettings::medium(), 30));
+        tabs.put(open(TabKey::Preset("hard"), BoardSettings::hard(), 5));
+        tabs.put(open(TabKey::Preset("hard"), BoardSettings::hard(), 12));
+        assert_eq!(
+            tabs.strip(Some((TabKey::Preset("medium"), &playing))),
+            [
+                (TabKey::Preset("medium"), 0, true),
+                (TabKey::Preset("hard"), 12, false),
+                (TabKey::Daily(20000), 30, false)
+            ]
+        );
+
+        let hard = tabs.take(TabKey::Preset("hard")).unwrap();
+        assert_eq!(hard.engine.elapsed(), Duration::from_secs(12));
+        assert!(tabs.take(TabKey::Preset("hard")).is_none());
+        assert!(tabs.take(TabKey::Custom).is_none());
+    }
+}
@@ -0,0 +1,109 @@
+use super::game::{Engine, GridSize, Mark, Position, TileState};
+use super::replay::Replay;
+use super::verify::verify_replay;
+
+// recorded games that pin down how the engine plays them out; a refactor that changes
+// any of these boards changed the rules too
+const REGRESSION_REPLAYS: [(&str, &str, &str); 3] = [
+    (
+        "easy won",
+        include_str!("../tests/replays/easy-won.replay"),
+        include_str!("../tests/replays/easy-won.board"),
+    ),
+    (
+        "easy lost",
+        include_str!("../tests/replays/easy-lost.replay"),
+        include_str!("../tests/replays/easy-lost.board"),
+    ),
+    (
+        "medium unfinished",
+        include_str!("../tests/replays/medium-unfinished.replay"),
+        include_str!("../tests/replays/medium-unfinished.board"),
+    ),
+];
+
+// one row per line: '#' covered, 'F' flagged, '?' question marked, '*' a revealed mine,
+// '.' or a digit for revealed tiles
+pub fn render_board(engine: &Engine) -> String {
+    let width = engine.size().width;
+    let mut text = String::new();
+    for (i, tile) in engine.get_tiles().iter().enumerate() {
+        text.push(match tile.state {
+            TileState::Block(Mark::Unmarked) => '#',
+            TileState::Block(Mark::Flagged) => 'F',
+            TileState::Block(Mark::Questioned) => '?',
+            TileState::Revealed if tile.is_bomb() => '*',
+            TileState::Revealed => match tile.num_adjacent_bombs() {
+                0 => '.',
+                n => char::from_digit(n, 10).unwrap(),
+            },
+        });
+        if (i + 1) % width == 0 {
+            text.push('\n');
+        }
+    }
+    text
+}
+
+// a single row "...*.*" opened from the left, so the 1 proves the tile after it a mine
+pub fn proven_mine() -> Engine {
+    let board = |seed| {
+        let mut engine = Engine::with_seed(GridSize::new(6, 1), 2, seed);
+        engine.reveal(Position(0, 0));
+        engine
+    };
+    let seed = (0..)
+        .find(|&seed| {
+            let engine = board(seed);
+            engine.get_tiles()[3].is_bomb() && engine.get_tiles()[5].is_bomb()
+        })
+        .unwrap();
+    board(seed)
+}
+
+// plays the replay back, checks its claimed result and time, then compares the final board
+pub fn check_replay(replay_text: &str, expected_board: &str) -> Result<Engine, String> {
+    let replay = Replay::parse(replay_text)?;
+    verify_replay(&replay)?;
+    let engine = replay.simulate()?;
+    let board = render_board(&engine);
+    if board.trim_end() != expected_board.trim_end() {
+        return Err(format!("final board differs, got:\n{}", board));
+    }
+    Ok(engine)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::MoveKind;
+    use crate::replay::Outcome;
+
+    #[test]
+    fn test_regression_replays() {
+        for (name, replay, board) in REGRESSION_REPLAYS {
+            if let Err(e) = check_replay(replay, board) {
+                panic!("{}: {}", name, e);
+            }
+        }
+    }
+
+    #[test]
+    fn test_regression_outcomes() {
+        let outcomes = REGRESSION_REPLAYS
+            .iter()
+            .map(|(_, replay, board)| Outcome::of(&check_replay(replay, board).unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(outcomes, [Outcome::Won, Outcome::Lost, Outcome::Unfinished]);
+    }
+
+    #[test]
+    fn test_changed_board_is_reported() {
+        let (_, replay, board) = REGRESSION_REPLAYS[1];
+        let mut parsed = Replay::parse(replay).unwrap();
+        // without the flag the loss reveals the same tiles but one flag fewer
+        parsed.moves.retain(|m| m.kind != MoveKind::Flag);
+        assert!(check_replay(&parsed.to_text(), board).is_err());
+        assert!(check_replay(replay, &board.replace('F', "#")).is_err());
+    }
+}
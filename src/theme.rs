@@ -0,0 +1,207 @@
+use ggez::graphics::Color;
+use ggez::Context;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+pub const THEME_FILE_NAME: &str = "theme.manifest";
+// colors used by night mode, starting from the dark preset
+pub const NIGHT_THEME_FILE_NAME: &str = "night.manifest";
+// the names Theme::by_name knows, in the order the settings screen cycles them
+pub const PRESETS: [&str; 3] = ["classic", "dark", "flat"];
+// a folder per preset, with a theme.manifest of its colors and a sprites folder; what a
+// folder leaves out comes from the built-in colors and the classic sprites
+pub const THEMES_DIR: &str = "/resources/themes";
+
+pub fn next_preset(name: &str) -> &'static str {
+    let i = PRESETS
+        .iter()
+        .position(|preset| *preset == name)
+        .unwrap_or(0);
+    PRESETS[(i + 1) % PRESETS.len()]
+}
+
+// colors for everything that isn't a sprite
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Theme {
+    pub background: Color,
+    // counters, buttons and other filled panels
+    pub panel: Color,
+    pub panel_text: Color,
+    pub disabled_text: Color,
+    // labels drawn straight onto the background
+    pub text: Color,
+    // the timer, highlights and the selected entry
+    pub accent: Color,
+    // the mine counter and error messages
+    pub alert: Color,
+}
+
+impl Theme {
+    pub const fn classic() -> Self {
+        Self {
+            background: Color::from_rgb(150, 150, 150),
+            panel: Color::BLACK,
+            panel_text: Color::WHITE,
+            disabled_text: Color::from_rgb(100, 100, 100),
+            text: Color::BLACK,
+            accent: Color::YELLOW,
+            alert: Color::RED,
+        }
+    }
+
+    pub const fn dark() -> Self {
+        Self {
+            background: Color::from_rgb(30, 30, 36),
+            panel: Color::from_rgb(10, 10, 12),
+            panel_text: Color::from_rgb(230, 230, 230),
+            disabled_text: Color::from_rgb(90, 90, 90),
+            text: Color::from_rgb(215, 215, 215),
+            accent: Color::from_rgb(255, 210, 0),
+            alert: Color::from_rgb(255, 80, 80),
+        }
+    }
+
+    pub const fn flat() -> Self {
+        Self {
+            background: Color::from_rgb(236, 240, 241),
+            panel: Color::from_rgb(52, 73, 94),
+            panel_text: Color::from_rgb(236, 240, 241),
+            disabled_text: Color::from_rgb(149, 165, 166),
+            text: Color::from_rgb(44, 62, 80),
+            accent: Color::from_rgb(241, 196, 15),
+            alert: Color::from_rgb(231, 76, 60),
+        }
+    }
+
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name {
+            "classic" => Some(Theme::classic()),
+            "dark" => Some(Theme::dark()),
+            "flat" => Some(Theme::flat()),
+            _ => None,
+        }
+    }
+
+    // one setting per line: `preset <name>` replaces the base colors, then
+    // `<key> #rrggbb` overrides single colors; `#` starts a comment line
+    pub fn parse(manifest: &str, base: Theme) -> Result<Self, String> {
+        let mut theme = base;
+        for (i, line) in manifest.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once(char::is_whitespace)
+                .map(|(key, value)| (key, value.trim()))
+                .ok_or_else(|| format!("line {}: missing value", i + 1))?;
+            if key == "preset" {
+                theme = Theme::by_name(value)
+                    .ok_or_else(|| format!("line {}: unknown preset '{}'", i + 1, value))?;
+                continue;
+            }
+            let color = parse_color(value)
+                .ok_or_else(|| format!("line {}: '{}' is not a #rrggbb color", i + 1, value))?;
+            let slot = match key {
+                "background" => &mut theme.background,
+                "panel" => &mut theme.panel,
+                "panel_text" => &mut theme.panel_text,
+                "disabled_text" => &mut theme.disabled_text,
+                "text" => &mut theme.text,
+                "accent" => &mut theme.accent,
+                "alert" => &mut theme.alert,
+                _ => return Err(format!("line {}: unknown key '{}'", i + 1, key)),
+            };
+            *slot = color;
+        }
+        Ok(theme)
+    }
+
+    // a missing manifest is the base theme; a broken one is reported and ignored
+    pub fn load(dir: &Path, file_name: &str, base: Theme) -> Self {
+        let Ok(manifest) = fs::read_to_string(dir.join(file_name)) else {
+            return base;
+        };
+        Theme::parse(&manifest, base).unwrap_or_else(|e| {
+            eprintln!("ignoring {}: {}", file_name, e);
+            base
+        })
+    }
+}
+
+fn parse_color(value: &str) -> Option<Color> {
+    let hex = value.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let rgb = u32::from_str_radix(hex, 16).ok()?;
+    Some(Color::from_rgb(
+        (rgb >> 16) as u8,
+        (rgb >> 8) as u8,
+        rgb as u8,
+    ))
+}
+
+// the presets as their resource folders color them, read once at startup
+pub struct Presets([Theme; PRESETS.len()]);
+
+impl Presets {
+    pub fn load(ctx: &Context) -> Self {
+        Self(PRESETS.map(|name| {
+            let base = Theme::by_name(name).unwrap_or(Theme::classic());
+            let path = format!("{}/{}/{}", THEMES_DIR, name, THEME_FILE_NAME);
+            let mut manifest = String::new();
+            let read = ctx
+                .fs
+                .open(&path)
+                .map(|mut file| file.read_to_string(&mut manifest));
+            if !matches!(read, Ok(Ok(_))) {
+                return base;
+            }
+            Theme::parse(&manifest, base).unwrap_or_else(|e| {
+                eprintln!("ignoring {}: {}", path, e);
+                base
+            })
+        }))
+    }
+
+    // an unknown name is classic, as a broken settings file would be
+    pub fn get(&self, name: &str) -> Theme {
+        PRESETS
+            .iter()
+            .position(|preset| *preset == name)
+            .map_or(Theme::classic(), |i| self.0[i])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest = "# night\npreset dark\nbackground #102030\n";
+        let theme = Theme::parse(manifest, Theme::classic()).unwrap();
+        assert_eq!(theme.background, Color::from_rgb(0x10, 0x20, 0x30));
+        assert_eq!(theme.panel, Theme::dark().panel);
+
+        let base = Theme::dark();
+        assert_eq!(Theme::parse("", base).unwrap(), base);
+        assert!(Theme::parse("preset neon", base).is_err());
+        assert!(Theme::parse("text #12345", base).is_err());
+        assert!(Theme::parse("border #123456", base).is_err());
+        assert!(Theme::parse("text", base).is_err());
+    }
+
+    #[test]
+    fn test_presets_cycle_back_to_classic() {
+        let mut name = "classic";
+        for preset in PRESETS.iter().skip(1) {
+            name = next_preset(name);
+            assert_eq!(name, *preset);
+            assert!(Theme::by_name(name).is_some());
+        }
+        assert_eq!(next_preset(name), "classic");
+    }
+}
@@ -0,0 +1,123 @@
+use super::game::{Engine, Mark, TileState};
+use super::render;
+use super::sprites::SpriteManager;
+use ggez::graphics::{Image, ImageFormat};
+use ggez::Context;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const THUMBNAIL_DIR: &str = "thumbnails";
+// an expert board comes out 240 pixels wide
+const THUMBNAIL_TILE_PX: f32 = 8.0;
+
+// small previews of boards for the menus, rendered offscreen once per board state and
+// kept as PNGs under the user data folder, so a resumed game doesn't render it again
+pub struct Thumbnails {
+    dir: PathBuf,
+    // the last board asked for, which is asked for again every frame it is shown; a
+    // render that failed is remembered so it is reported once
+    current: Option<(String, Option<Image>)>,
+}
+
+impl Thumbnails {
+    pub fn new(data_dir: &Path) -> Self {
+        Self {
+            dir: data_dir.join(THUMBNAIL_DIR),
+            current: None,
+        }
+    }
+
+    pub fn get(
+        &mut self,
+        ctx: &mut Context,
+        engine: &Engine,
+        sprite_manager: &SpriteManager,
+    ) -> Result<Option<Image>, String> {
+        let name = file_name(engine);
+        if let Some((current, image)) = &self.current {
+            if *current == name {
+                return Ok(image.clone());
+            }
+        }
+        self.current = Some((name.clone(), None));
+
+        let path = self.dir.join(&name);
+        let image = match fs::read(&path) {
+            Ok(png) => Image::from_bytes(ctx, &png).map_err(|e| e.to_string())?,
+            Err(_) => {
+                let (width, height, pixels) =
+                    render::render_board_at(ctx, engine, sprite_manager, THUMBNAIL_TILE_PX)?;
+                // an uncached thumbnail is only rendered again next time
+                let _ = self.store(engine, &path, render::to_png(width, height, &pixels));
+                Image::from_pixels(ctx, &pixels, ImageFormat::Rgba8UnormSrgb, width, height)
+            }
+        };
+        self.current = Some((name, Some(image.clone())));
+        Ok(Some(image))
+    }
+
+    // the same game further on replaces its older thumbnails
+    fn store(&self, engine: &Engine, path: &Path, png: Vec<u8>) -> std::io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let prefix = format!("{:016x}-", engine.seed());
+        for entry in fs::read_dir(&self.dir)?.filter_map(|entry| entry.ok()) {
+            if entry.file_name().to_string_lossy().starts_with(&prefix) {
+                let _ = fs::remove_file(entry.path());
+            }
+        }
+        fs::write(path, png)
+    }
+}
+
+// the seed, then a hash of everything drawn, so any move or undo names a new file
+pub fn file_name(engine: &Engine) -> String {
+    // FNV-1a, stable across builds unlike the standard hasher
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let size = engine.size();
+    let header = [size.width, size.height, engine.num_bombs()];
+    let states = engine.get_tiles().iter().map(|tile| match tile.state {
+        TileState::Block(Mark::Unmarked) => 0,
+        TileState::Block(Mark::Flagged) => 1,
+        TileState::Block(Mark::Questioned) => 2,
+        TileState::Revealed if tile.is_bomb() => 3,
+        TileState::Revealed => 4 + tile.num_adjacent_bombs() as usize,
+    });
+    for value in header.into_iter().chain(states) {
+        for byte in (value as u64).to_le_bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    format!("{:016x}-{:016x}.png", engine.seed(), hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{GridSize, Position};
+
+    #[test]
+    fn test_file_name_follows_the_board() {
+        let board = || Engine::with_seed(GridSize::new(9, 9), 10, 0xbeef);
+        let mut engine = board();
+        let fresh = file_name(&engine);
+        assert_eq!(fresh, file_name(&board()));
+        assert!(fresh.starts_with("000000000000beef-"));
+        assert!(fresh.ends_with(".png"));
+
+        engine.reveal(Position(4, 4));
+        let opened = file_name(&engine);
+        assert_ne!(opened, fresh);
+
+        engine.tick(std::time::Duration::from_secs(1));
+        let covered = engine
+            .get_tiles()
+            .iter()
+            .position(|t| !t.is_revealed())
+            .unwrap();
+        engine.flag(Position::from_index(covered, 9));
+        assert_ne!(file_name(&engine), opened);
+        assert!(engine.undo());
+        assert_eq!(file_name(&engine), opened);
+    }
+}
@@ -0,0 +1,36 @@
+use std::time::Duration;
+
+const TOAST_DURATION: Duration = Duration::from_secs(3);
+
+// a short confirmation drawn over everything, then gone on its own
+pub struct Toast {
+    pub message: String,
+    left: Duration,
+}
+
+impl Toast {
+    pub fn new(message: impl ToString) -> Self {
+        Self {
+            message: message.to_string(),
+            left: TOAST_DURATION,
+        }
+    }
+
+    // returns false once the toast should disappear
+    pub fn update(&mut self, delta: Duration) -> bool {
+        self.left = self.left.saturating_sub(delta);
+        !self.left.is_zero()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toast_runs_out() {
+        let mut toast = Toast::new("THEME INSTALLED");
+        assert!(toast.update(TOAST_DURATION / 2));
+        assert!(!toast.update(TOAST_DURATION / 2));
+    }
+}
@@ -0,0 +1,193 @@
+use super::game::Engine;
+use super::hud::ModeEntry;
+use std::time::Duration;
+
+// how long the pointer rests on a HUD element before its tooltip shows
+const SHOW_DELAY: Duration = Duration::from_millis(500);
+// a tooltip lingers after the pointer leaves, so crossing a gap doesn't flicker it
+const HIDE_DELAY: Duration = Duration::from_millis(200);
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum HudElement {
+    Timer,
+    Counter,
+    Face,
+    // the button that opens the list of boards
+    Mode,
+    // an entry in that list while it is open
+    ModeEntry(ModeEntry),
+    Hint,
+}
+
+// which HUD element the pointer rests on, and whether it has rested long enough
+pub struct Tooltips {
+    hovered: Option<HudElement>,
+    dwell: Duration,
+    // time since the pointer left the element shown
+    leaving: Duration,
+    pointer: [f32; 2],
+    shown: Option<(HudElement, [f32; 2])>,
+}
+
+impl Tooltips {
+    pub fn new() -> Self {
+        Self {
+            hovered: None,
+            dwell: Duration::ZERO,
+            leaving: Duration::ZERO,
+            pointer: [0.0, 0.0],
+            shown: None,
+        }
+    }
+
+    pub fn hover(&mut self, element: Option<HudElement>, pointer: [f32; 2]) {
+        self.pointer = pointer;
+        if element != self.hovered {
+            self.hovered = element;
+            self.dwell = Duration::ZERO;
+        }
+    }
+
+    pub fn update(&mut self, delta: Duration) {
+        match self.hovered {
+            Some(element) => {
+                self.dwell += delta;
+                self.leaving = Duration::ZERO;
+                // once one tooltip is up, the next element's shows without waiting
+                let waited = self.dwell >= SHOW_DELAY || self.shown.is_some();
+                let changed = self.shown.map(|(shown, _)| shown) != Some(element);
+                if waited && changed {
+                    self.shown = Some((element, self.pointer));
+                }
+            }
+            None => {
+                self.leaving += delta;
+                if self.leaving >= HIDE_DELAY {
+                    self.shown = None;
+                }
+            }
+        }
+    }
+
+    // the element to describe and where the pointer was when its tooltip came up
+    pub fn shown(&self) -> Option<(HudElement, [f32; 2])> {
+        self.shown
+    }
+}
+
+// what a tooltip says; clock_running is false while the game is paused or a menu is up
+pub fn lines(
+    element: HudElement,
+    engine: &Engine,
+    time: Duration,
+    clock_running: bool,
+) -> Vec<String> {
+    let finished = engine.is_won() || engine.is_lost();
+    match element {
+        HudElement::Timer => {
+            let state = if finished {
+                "STOPPED"
+            } else if engine.moves().is_empty() {
+                "STARTS ON THE FIRST CLICK"
+            } else if clock_running {
+                "RUNNING"
+            } else {
+                "PAUSED"
+            };
+            vec![
+                format!("{}.{:03} S", time.as_secs(), time.subsec_millis()),
+                state.to_string(),
+            ]
+        }
+        HudElement::Counter => {
+            let flags = engine.num_bombs() as i32 - engine.mines_left();
+            vec![
+                format!("FLAGS PLACED {}", flags),
+                format!("MINES {}", engine.num_bombs()),
+            ]
+        }
+        HudElement::Face => {
+            let state = if engine.is_won() {
+                "WON"
+            } else if engine.is_lost() {
+                "LOST"
+            } else if engine.moves().is_empty() {
+                "READY"
+            } else {
+                "IN PROGRESS"
+            };
+            vec![state.to_string(), "RESTART: R".to_string()]
+        }
+        HudElement::Mode => vec![
+            "PICK A BOARD TO PLAY".to_string(),
+            "PRESETS, CUSTOM, DAILY OR A RECENT BOARD".to_string(),
+        ],
+        HudElement::ModeEntry(ModeEntry::Preset(name)) => vec![
+            format!("NEW {} BOARD", name.to_uppercase()),
+            format!("SHORTCUT: :n {}", name),
+        ],
+        HudElement::ModeEntry(ModeEntry::Custom) => vec!["SET UP A CUSTOM BOARD".to_string()],
+        HudElement::ModeEntry(ModeEntry::Daily) => vec!["TODAY'S SHARED BOARD".to_string()],
+        HudElement::ModeEntry(ModeEntry::Recent(board)) => vec![
+            format!("{} X {} BOARD", board.width, board.height),
+            format!("{}% MINES", board.density),
+        ],
+        HudElement::Hint => vec![
+            "MARKS ONE TILE THE SOLVER CAN PROVE".to_string(),
+            "SHADE THE BOARD: H".to_string(),
+        ],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{GridSize, Position};
+
+    #[test]
+    fn test_tooltip_waits_then_lingers() {
+        let mut tooltips = Tooltips::new();
+        tooltips.hover(Some(HudElement::Face), [10.0, 5.0]);
+        tooltips.update(SHOW_DELAY / 2);
+        assert_eq!(tooltips.shown(), None);
+        tooltips.update(SHOW_DELAY / 2);
+        assert_eq!(tooltips.shown(), Some((HudElement::Face, [10.0, 5.0])));
+
+        // straight on to the next element, then off the HUD for a moment
+        tooltips.hover(Some(HudElement::Timer), [90.0, 5.0]);
+        tooltips.update(Duration::from_millis(16));
+        assert_eq!(tooltips.shown(), Some((HudElement::Timer, [90.0, 5.0])));
+        tooltips.hover(None, [90.0, 50.0]);
+        tooltips.update(HIDE_DELAY / 2);
+        assert!(tooltips.shown().is_some());
+        tooltips.update(HIDE_DELAY / 2);
+        assert_eq!(tooltips.shown(), None);
+    }
+
+    #[test]
+    fn test_lines_follow_the_game() {
+        let mut engine = Engine::with_seed(GridSize::new(9, 9), 10, 1);
+        let time = Duration::from_millis(12_345);
+        assert_eq!(
+            lines(HudElement::Timer, &engine, time, true),
+            vec!["12.345 S", "STARTS ON THE FIRST CLICK"]
+        );
+        engine.reveal(Position(4, 4));
+        assert_eq!(lines(HudElement::Timer, &engine, time, false)[1], "PAUSED");
+        assert_eq!(
+            lines(HudElement::Face, &engine, time, true)[0],
+            "IN PROGRESS"
+        );
+
+        let covered = engine
+            .get_tiles()
+            .iter()
+            .position(|t| !t.is_revealed())
+            .unwrap();
+        engine.flag(Position::from_index(covered, 9));
+        assert_eq!(
+            lines(HudElement::Counter, &engine, time, true)[0],
+            "FLAGS PLACED 1"
+        );
+    }
+}
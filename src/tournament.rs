@@ -0,0 +1,330 @@
+use super::board::BoardSettings;
+use super::crypto::{from_hex, sha256, to_hex};
+use super::game::Engine;
+use super::replay::Replay;
+use super::storage::{self, Format};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+pub const BUNDLE_FORMAT: Format = Format::new("tournament", 1, &[]);
+pub const RESULTS_FORMAT: Format = Format::new("results", 1, &[]);
+const RESULTS_SEPARATOR: &str = "---";
+
+struct SealedRound {
+    encrypted_seed: u64,
+    commitment: [u8; 32],
+}
+
+// The organizer publishes the bundle ahead of time and announces the key at start time.
+// Seeds stay hidden until then, and the commitments stop the organizer from swapping them later.
+pub struct Tournament {
+    pub name: String,
+    // unix timestamp in seconds
+    pub start: u64,
+    pub board: BoardSettings,
+    rounds: Vec<SealedRound>,
+}
+
+fn keystream(key: &[u8], round: usize) -> u64 {
+    let hash = sha256(&[b"seed", key, &(round as u64).to_le_bytes()]);
+    u64::from_le_bytes(hash[..8].try_into().unwrap())
+}
+
+fn commitment(key: &[u8], seed: u64) -> [u8; 32] {
+    sha256(&[b"commit", key, &seed.to_le_bytes()])
+}
+
+impl Tournament {
+    pub fn create(
+        name: &str,
+        start: u64,
+        board_settings: &BoardSettings,
+        seeds: &[u64],
+        key: &[u8],
+    ) -> String {
+        let size = board_settings.size();
+        let mut text = format!(
+            "{}\nname {}\nstart {}\nboard {} {} {}\n",
+            BUNDLE_FORMAT.header(),
+            name,
+            start,
+            size.width,
+            size.height,
+            board_settings.num_bombs()
+        );
+        for (round, seed) in seeds.iter().enumerate() {
+            text.push_str(&format!(
+                "round {:016x} {}\n",
+                seed ^ keystream(key, round),
+                to_hex(&commitment(key, *seed))
+            ));
+        }
+        text
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let body = BUNDLE_FORMAT.read(text)?;
+        let lines = body.lines().filter(|line| !line.trim().is_empty());
+
+        let mut name = None;
+        let mut start = None;
+        let mut board = None;
+        let mut rounds = Vec::new();
+
+        for line in lines {
+            if let Some(rest) = line.strip_prefix("name ") {
+                name = Some(rest.trim().to_string());
+                continue;
+            }
+
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            match fields.as_slice() {
+                ["start", s] => start = s.parse().ok(),
+                ["board", w, h, bombs] => {
+                    board = match (w.parse(), h.parse(), bombs.parse()) {
+                        (Ok(w), Ok(h), Ok(bombs)) => Some((w, h, bombs)),
+                        _ => return Err(format!("invalid board line '{}'", line)),
+                    };
+                }
+                ["round", seed, hash] => {
+                    let encrypted_seed = u64::from_str_radix(seed, 16)
+                        .map_err(|_| format!("invalid round line '{}'", line))?;
+                    let commitment = from_hex(hash)
+                        .and_then(|bytes| bytes.try_into().ok())
+                        .ok_or(format!("invalid round line '{}'", line))?;
+                    rounds.push(SealedRound {
+                        encrypted_seed,
+                        commitment,
+                    });
+                }
+                _ => return Err(format!("unexpected line '{}'", line)),
+            }
+        }
+
+        let (width, height, num_bombs) = board.ok_or("missing board line")?;
+        let board = BoardSettings::builder()
+            .size(width, height)
+            .mines(num_bombs)
+            .build()
+            .map_err(|e| format!("invalid board: {}", e))?;
+        if rounds.is_empty() {
+            return Err("tournament has no rounds".to_string());
+        }
+
+        Ok(Self {
+            name: name.ok_or("missing name line")?,
+            start: start.ok_or("missing start line")?,
+            board,
+            rounds,
+        })
+    }
+
+    // decrypts the seeds, failing if the key does not match the published commitments
+    pub fn unseal(&self, key: &[u8]) -> Result<Vec<u64>, String> {
+        self.rounds
+            .iter()
+            .enumerate()
+            .map(|(round, sealed)| {
+                let seed = sealed.encrypted_seed ^ keystream(key, round);
+                if commitment(key, seed) == sealed.commitment {
+                    Ok(seed)
+                } else {
+                    Err("wrong tournament key".to_string())
+                }
+            })
+            .collect()
+    }
+
+    pub fn board_settings(&self) -> BoardSettings {
+        self.board.clone()
+    }
+}
+
+pub fn results_text(name: &str, replays: &[Replay]) -> String {
+    let mut text = format!("{}\ntournament {}\n", RESULTS_FORMAT.header(), name);
+    for replay in replays {
+        text.push_str(RESULTS_SEPARATOR);
+        text.push('\n');
+        text.push_str(&replay.to_text());
+    }
+    text
+}
+
+pub fn parse_results(text: &str) -> Result<(String, Vec<Replay>), String> {
+    let body = RESULTS_FORMAT.read(text)?;
+    let separator = format!("\n{}\n", RESULTS_SEPARATOR);
+    let mut sections = body.split(&separator);
+    let name = sections
+        .next()
+        .unwrap_or("")
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix("tournament "))
+        .ok_or("missing tournament line")?
+        .to_string();
+
+    let replays = sections.map(Replay::parse).collect::<Result<Vec<_>, _>>()?;
+    Ok((name, replays))
+}
+
+// a tournament being played: settings are locked and every round is recorded
+pub struct TournamentSession {
+    pub tournament: Tournament,
+    key: Vec<u8>,
+    seeds: Vec<u64>,
+    pub round: usize,
+    pub replays: Vec<Replay>,
+    results_path: PathBuf,
+}
+
+impl TournamentSession {
+    // a relaunch continues after the rounds already in the results file, so a finished
+    // round can't be played again by starting over
+    pub fn new(tournament: Tournament, key: &str, results_path: PathBuf) -> Result<Self, String> {
+        let seeds = tournament.unseal(key.as_bytes())?;
+        let replays = match fs::read_to_string(&results_path) {
+            Ok(text) => recorded_rounds(&tournament, &seeds, key.as_bytes(), &text)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(format!("{}: {}", results_path.display(), e)),
+        };
+        if replays.len() == seeds.len() {
+            return Err(format!(
+                "all {} rounds of '{}' are already recorded in {}",
+                seeds.len(),
+                tournament.name,
+                results_path.display()
+            ));
+        }
+        Ok(Self {
+            tournament,
+            key: key.as_bytes().to_vec(),
+            seeds,
+            round: replays.len(),
+            replays,
+            results_path,
+        })
+    }
+
+    pub fn num_rounds(&self) -> usize {
+        self.seeds.len()
+    }
+
+    pub fn current_seed(&self) -> u64 {
+        self.seeds[self.round]
+    }
+
+    pub fn is_round_recorded(&self) -> bool {
+        self.replays.len() > self.round
+    }
+
+    // checksums the finished round and rewrites the results file so partial results survive
+    // a crash; the key is public once the tournament starts, so the checksum only catches
+    // a damaged or hand edited file, not a forged one
+    pub fn finish_round(&mut self, engine: &Engine) -> io::Result<()> {
+        let mut replay = Replay::from_engine(engine);
+        replay.add_checksum(&self.key);
+        self.replays.push(replay);
+
+        storage::write_atomic(
+            &self.results_path,
+            results_text(&self.tournament.name, &self.replays),
+        )
+    }
+
+    pub fn next_round(&mut self) -> Option<u64> {
+        if !self.is_round_recorded() || self.round + 1 >= self.num_rounds() {
+            return None;
+        }
+        self.round += 1;
+        Some(self.current_seed())
+    }
+}
+
+// the rounds of an existing results file, refused unless they belong to this tournament
+fn recorded_rounds(
+    tournament: &Tournament,
+    seeds: &[u64],
+    key: &[u8],
+    text: &str,
+) -> Result<Vec<Replay>, String> {
+    let (name, replays) = parse_results(text)?;
+    if name != tournament.name || replays.len() > seeds.len() {
+        return Err(format!(
+            "the results file belongs to another tournament than '{}'",
+            tournament.name
+        ));
+    }
+    for (round, replay) in replays.iter().enumerate() {
+        if replay.seed != seeds[round] || !replay.has_valid_checksum(key) {
+            return Err(format!(
+                "round {} of the results file does not match",
+                round + 1
+            ));
+        }
+    }
+    Ok(replays)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{GridSize, Position};
+
+    #[test]
+    fn test_unseal_checks_key() {
+        let text = Tournament::create("Cup", 0, &BoardSettings::easy(), &[7, 99], b"secret");
+        let tournament = Tournament::parse(&text).unwrap();
+
+        assert_eq!(tournament.name, "Cup");
+        assert_eq!(tournament.unseal(b"secret"), Ok(vec![7, 99]));
+        assert!(tournament.unseal(b"guess").is_err());
+    }
+
+    #[test]
+    fn test_results_round_trip() {
+        let mut engine = Engine::with_seed(GridSize::new(8, 8), 10, 3);
+        engine.reveal(Position(1, 1));
+        let mut replay = Replay::from_engine(&engine);
+        replay.add_checksum(b"secret");
+
+        let text = results_text("Cup", &[replay.clone(), replay.clone()]);
+        assert_eq!(
+            parse_results(&text),
+            Ok(("Cup".to_string(), vec![replay.clone(), replay]))
+        );
+    }
+
+    #[test]
+    fn test_session_continues_after_recorded_rounds() {
+        let path = std::env::temp_dir().join(format!(
+            "rust_sweeper_tournament_{}.results",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        let bundle = Tournament::create("Cup", 0, &BoardSettings::easy(), &[7, 99], b"secret");
+        let session =
+            || TournamentSession::new(Tournament::parse(&bundle).unwrap(), "secret", path.clone());
+
+        let mut first = session().unwrap();
+        assert_eq!(first.round, 0);
+        let mut engine = Engine::with_seed(GridSize::new(9, 9), 10, first.current_seed());
+        engine.reveal(Position(4, 4));
+        first.finish_round(&engine).unwrap();
+
+        // a relaunch picks up at the second round instead of replaying the first
+        let mut second = session().unwrap();
+        assert_eq!(second.round, 1);
+        assert_eq!(second.current_seed(), 99);
+        let mut engine = Engine::with_seed(GridSize::new(9, 9), 10, 99);
+        engine.reveal(Position(4, 4));
+        second.finish_round(&engine).unwrap();
+        assert!(session().is_err());
+
+        // a results file from another board is refused rather than overwritten
+        fs::write(&path, results_text("Cup", &[Replay::from_engine(&engine)])).unwrap();
+        assert!(session().is_err());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,177 @@
+use super::game::{Engine, Penalties, Position};
+use std::time::Duration;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ClickKind {
+    Reveal,
+    Flag,
+    Chord,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Click {
+    kind: ClickKind,
+    pos: Position,
+    // tiles the click uncovered
+    opened: usize,
+}
+
+// the efficiency trainer: every click the player makes, held against the fewest the
+// board needs once the game is won
+pub struct Trainer {
+    clicks: Vec<Click>,
+    // turned on mid-game, the tally would miss the clicks before it
+    from_first_move: bool,
+}
+
+impl Trainer {
+    pub fn new(engine: &Engine) -> Self {
+        Self {
+            clicks: Vec::new(),
+            from_first_move: engine.moves().is_empty(),
+        }
+    }
+
+    pub fn is_counting(&self) -> bool {
+        self.from_first_move
+    }
+
+    pub fn click(&mut self, kind: ClickKind, pos: Position, opened: usize) {
+        self.clicks.push(Click { kind, pos, opened });
+    }
+
+    // only a won board has its whole solution to compare against
+    pub fn report(&self, engine: &Engine) -> Option<Report> {
+        if !engine.is_won() || !self.from_first_move {
+            return None;
+        }
+        let width = engine.size().width;
+        let tiles = engine.get_tiles();
+        // a chord leans on the flags around it
+        let chorded = |flag: &Click, after: usize| {
+            self.clicks[after..].iter().any(|click| {
+                click.kind == ClickKind::Chord
+                    && click.opened > 0
+                    && engine.neighbours(click.pos).contains(&flag.pos)
+            })
+        };
+        let wasted_flags = self
+            .clicks
+            .iter()
+            .enumerate()
+            .filter(|(i, click)| {
+                click.kind == ClickKind::Flag
+                    && !(tiles[click.pos.to_index(width)].is_bomb() && chorded(click, i + 1))
+            })
+            .count();
+        let wasted_chords = self
+            .clicks
+            .iter()
+            .filter(|click| click.kind == ClickKind::Chord && click.opened < 2)
+            .count();
+        Some(Report {
+            clicks: self.clicks.len(),
+            wasted_flags,
+            wasted_chords,
+            optimal: engine.optimal_clicks(),
+        })
+    }
+}
+
+pub struct Report {
+    pub clicks: usize,
+    // flags no chord went on to use
+    pub wasted_flags: usize,
+    // chords that opened less than a single reveal would have
+    pub wasted_chords: usize,
+    // the board's own shortest solve, as tile indices
+    pub optimal: Vec<usize>,
+}
+
+impl Report {
+    // the board's 3BV over the clicks used, with every wasted click counted twice
+    pub fn efficiency(&self) -> f32 {
+        let penalized = self.clicks + self.wasted_flags + self.wasted_chords;
+        self.optimal.len() as f32 / penalized.max(1) as f32
+    }
+}
+
+// what the end of game panel shows, counted whether or not the trainer was on
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct GameStats {
+    pub won: bool,
+    // with penalties, as the leaderboards count it
+    pub time: Duration,
+    pub three_bv: usize,
+    pub clicks: usize,
+    // as a percentage, only for a win
+    pub efficiency: Option<f32>,
+}
+
+impl GameStats {
+    pub fn new(engine: &Engine, penalties: Penalties) -> Self {
+        let summary = engine.summary(penalties);
+        Self {
+            won: summary.won,
+            time: summary.final_time(),
+            three_bv: engine.board_3bv(),
+            clicks: engine.clicks().total(),
+            efficiency: engine.efficiency(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::proven_mine;
+
+    #[test]
+    fn test_optimal_clicks_solve_the_board() {
+        // "..1*2*": the opening, then the 2
+        let solved = proven_mine();
+        let optimal = solved.optimal_clicks();
+        assert_eq!(optimal, vec![0, 4]);
+
+        let late = Trainer::new(&solved);
+        assert!(!late.is_counting());
+
+        let mut engine = proven_mine();
+        let mut trainer = Trainer::new(&Engine::with_seed(engine.size(), 2, 0));
+        trainer.click(ClickKind::Reveal, Position(0, 0), 3);
+        assert!(trainer.report(&engine).is_none());
+
+        // a flag nothing chords off, a chord that opens nothing, then the last tile
+        engine.flag(Position(3, 0));
+        trainer.click(ClickKind::Flag, Position(3, 0), 0);
+        trainer.click(ClickKind::Chord, Position(2, 0), 0);
+        engine.reveal(Position(4, 0));
+        trainer.click(ClickKind::Reveal, Position(4, 0), 1);
+
+        let report = trainer.report(&engine).unwrap();
+        assert_eq!(report.clicks, 4);
+        assert_eq!((report.wasted_flags, report.wasted_chords), (1, 1));
+        assert_eq!(report.optimal, optimal);
+        assert_eq!(report.efficiency(), 2.0 / 6.0);
+    }
+
+    #[test]
+    fn test_game_stats_hold_clicks_against_3bv() {
+        let penalties = Penalties {
+            per_hint: Duration::ZERO,
+            per_undo: Duration::ZERO,
+        };
+        let mut engine = proven_mine();
+        let stats = GameStats::new(&engine, penalties);
+        assert_eq!((stats.three_bv, stats.clicks), (2, 1));
+        assert_eq!(stats.efficiency, None);
+
+        // a flag that didn't help, then the last tile
+        engine.flag(Position(3, 0));
+        engine.reveal(Position(4, 0));
+        let stats = GameStats::new(&engine, penalties);
+        assert!(stats.won);
+        assert_eq!(stats.clicks, 3);
+        assert_eq!(stats.efficiency, Some(200.0 / 3.0));
+    }
+}
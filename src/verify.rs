@@ -0,0 +1,154 @@
+use super::replay::{Outcome, Replay};
+use super::tournament::{parse_results, Tournament};
+
+// re-simulates a replay and checks that it really produces the claimed result and time
+pub fn verify_replay(replay: &Replay) -> Result<(), String> {
+    let engine = replay.simulate()?;
+
+    // older replays carry no draws, the seed alone has to do for them
+    if !replay.draws.is_empty() && engine.generation_draws() != replay.draws {
+        let first = engine
+            .generation_draws()
+            .iter()
+            .zip(&replay.draws)
+            .position(|(ours, theirs)| ours != theirs)
+            .unwrap_or(engine.generation_draws().len().min(replay.draws.len()));
+        return Err(format!(
+            "recorded mine draws differ from the seed's from draw {}",
+            first + 1
+        ));
+    }
+    if engine.moves().len() != replay.moves.len() {
+        return Err("replay contains moves made after the game ended".to_string());
+    }
+    let outcome = Outcome::of(&engine);
+    if outcome != replay.outcome {
+        return Err(format!(
+            "claimed result {:?} but the moves produce {:?}",
+            replay.outcome, outcome
+        ));
+    }
+    if engine.elapsed() != replay.time {
+        return Err(format!(
+            "claimed time {}ms but the moves end at {}ms",
+            replay.time.as_millis(),
+            engine.elapsed().as_millis()
+        ));
+    }
+    Ok(())
+}
+
+// additionally checks a tournament round against the sealed bundle
+pub fn verify_round(
+    replay: &Replay,
+    round: usize,
+    tournament: &Tournament,
+    seeds: &[u64],
+    key: &[u8],
+) -> Result<(), String> {
+    if !replay.has_valid_checksum(key) {
+        return Err("checksum does not match".to_string());
+    }
+    let size = tournament.board.size();
+    if (replay.width, replay.height, replay.num_bombs)
+        != (size.width, size.height, tournament.board.num_bombs())
+    {
+        return Err("board does not match the tournament settings".to_string());
+    }
+    if seeds.get(round) != Some(&replay.seed) {
+        return Err("seed does not match the tournament round".to_string());
+    }
+    verify_replay(replay)
+}
+
+// prints a verdict per replay and returns whether everything checked out
+pub fn run(file_text: &str, bundle: Option<(&str, &str)>) -> Result<bool, String> {
+    let replays = match Replay::parse(file_text) {
+        Ok(replay) => vec![replay],
+        Err(_) => parse_results(file_text)?.1,
+    };
+
+    let sealed = match bundle {
+        Some((bundle_text, key)) => {
+            let tournament = Tournament::parse(bundle_text)?;
+            let seeds = tournament.unseal(key.as_bytes())?;
+            Some((tournament, seeds, key))
+        }
+        None => None,
+    };
+
+    let mut all_valid = true;
+    for (round, replay) in replays.iter().enumerate() {
+        let verdict = match &sealed {
+            Some((tournament, seeds, key)) => {
+                verify_round(replay, round, tournament, seeds, key.as_bytes())
+            }
+            None => verify_replay(replay),
+        };
+
+        match verdict {
+            Ok(()) => println!(
+                "replay {}: OK, {:?} in {:.3}s (seed {}, {} draws)",
+                round + 1,
+                replay.outcome,
+                replay.time.as_secs_f64(),
+                replay.seed,
+                replay.draws.len()
+            ),
+            Err(reason) => {
+                all_valid = false;
+                println!("replay {}: INVALID, {}", round + 1, reason);
+            }
+        }
+    }
+    Ok(all_valid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Engine, GridSize, Position};
+    use std::time::Duration;
+
+    fn finished_replay() -> Replay {
+        let mut engine = Engine::with_seed(GridSize::new(5, 5), 1, 11);
+        engine.tick(Duration::from_millis(2500));
+        engine.reveal(Position(2, 2));
+        Replay::from_engine(&engine)
+    }
+
+    #[test]
+    fn test_valid_replay() {
+        assert_eq!(verify_replay(&finished_replay()), Ok(()));
+    }
+
+    #[test]
+    fn test_claimed_time_must_match() {
+        let mut replay = finished_replay();
+        replay.time = Duration::from_millis(900);
+        assert!(verify_replay(&replay).is_err());
+    }
+
+    #[test]
+    fn test_recorded_draws_must_match_the_seed() {
+        let mut engine = Engine::with_seed(GridSize::new(9, 9), 10, 5);
+        engine.reveal(Position(4, 4));
+        let mut replay = Replay::from_engine(&engine);
+        assert_eq!(verify_replay(&replay), Ok(()));
+
+        replay.draws[2] += 1;
+        assert_eq!(
+            verify_replay(&replay),
+            Err("recorded mine draws differ from the seed's from draw 3".to_string())
+        );
+        replay.draws.truncate(2);
+        assert!(verify_replay(&replay).is_err());
+    }
+
+    #[test]
+    fn test_claimed_result_must_match() {
+        let mut replay = finished_replay();
+        replay.outcome = Outcome::Lost;
+        assert!(verify_replay(&replay).is_err());
+    }
+}
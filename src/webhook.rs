@@ -0,0 +1,85 @@
+use super::game::Engine;
+use super::trainer::GameStats;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+// the body posted when a game ends; the seed is a string because a u64 doesn't fit in
+// a javascript number
+pub fn payload(engine: &Engine, difficulty: Option<&str>, stats: &GameStats) -> String {
+    let size = engine.size();
+    format!(
+        "{{\"event\":\"game_finished\",\"result\":\"{}\",\"time_ms\":{},\"seed\":\"{}\",\"difficulty\":{},\"width\":{},\"height\":{},\"mines\":{},\"three_bv\":{},\"clicks\":{}}}",
+        if stats.won { "won" } else { "lost" },
+        stats.time.as_millis(),
+        engine.seed(),
+        difficulty.map_or("null".to_string(), |name| format!("\"{}\"", name)),
+        size.width,
+        size.height,
+        engine.num_bombs(),
+        stats.three_bv,
+        stats.clicks
+    )
+}
+
+// posts finished games to the url in the settings from a thread of their own, so a slow
+// endpoint never holds up a frame
+pub struct Webhook {
+    sender: Sender<String>,
+    failures: Receiver<String>,
+}
+
+impl Webhook {
+    pub fn new() -> Self {
+        let (sender, failures) = mpsc::channel();
+        Self { sender, failures }
+    }
+
+    pub fn post(&self, url: &str, body: String) {
+        let url = url.to_string();
+        let sender = self.sender.clone();
+        thread::spawn(move || {
+            let agent = ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build();
+            let posted = agent
+                .post(&url)
+                .set("Content-Type", "application/json")
+                .send_string(&body);
+            if let Err(e) = posted {
+                let _ = sender.send(e.to_string());
+            }
+        });
+    }
+
+    // the posts that failed since the last call
+    pub fn failures(&self) -> Vec<String> {
+        self.failures.try_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{GridSize, Penalties, Position};
+
+    #[test]
+    fn test_payload_describes_the_result() {
+        let mut engine = Engine::with_seed(GridSize::new(9, 9), 10, u64::MAX);
+        engine.tick(Duration::from_millis(1500));
+        engine.reveal(Position(4, 4));
+        let penalties = Penalties {
+            per_hint: Duration::ZERO,
+            per_undo: Duration::ZERO,
+        };
+        let stats = GameStats::new(&engine, penalties);
+        let body = payload(&engine, Some("easy"), &stats);
+        assert!(
+            body.starts_with("{\"event\":\"game_finished\",\"result\":\"lost\",\"time_ms\":1500,")
+        );
+        assert!(body.contains("\"seed\":\"18446744073709551615\",\"difficulty\":\"easy\","));
+        assert!(body.contains(&format!("\"three_bv\":{},", engine.board_3bv())));
+        assert!(body.ends_with("\"clicks\":1}"));
+        assert!(payload(&engine, None, &stats).contains("\"difficulty\":null"));
+    }
+}